@@ -0,0 +1,154 @@
+//! A minimal Linux native AIO context (the interface libaio wraps) over raw syscalls.
+
+use std::io;
+use std::os::fd::RawFd;
+
+const IOCB_CMD_PREAD: u16 = 0;
+const IOCB_CMD_PWRITE: u16 = 1;
+
+/// `struct iocb` for little-endian targets, where `aio_key` precedes `aio_rw_flags`.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct Iocb {
+    aio_data: u64,
+    aio_key: u32,
+    aio_rw_flags: i32,
+    aio_lio_opcode: u16,
+    aio_reqprio: i16,
+    aio_fildes: u32,
+    aio_buf: u64,
+    aio_nbytes: u64,
+    aio_offset: i64,
+    aio_reserved2: u64,
+    aio_flags: u32,
+    aio_resfd: u32,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct IoEvent {
+    data: u64,
+    obj: u64,
+    res: i64,
+    res2: i64,
+}
+
+/// One AIO context, used by a single thread. Operations are identified by
+/// slots below the context size, like the tags of an io_uring.
+pub struct Context {
+    ctx: libc::c_ulong,
+    /// One control block per slot; never reallocated, as the kernel keeps pointers to them
+    iocbs: Box<[Iocb]>,
+    /// Control blocks filled but not yet submitted
+    pending: Vec<*mut Iocb>,
+    /// Completions read by the last `submit_and_wait`
+    events: Vec<IoEvent>,
+    ready: usize,
+}
+
+// The raw pointers refer to the context's own control blocks, which move with it
+unsafe impl Send for Context {}
+
+impl Context {
+    pub fn new(slots: usize) -> io::Result<Self> {
+        let mut ctx: libc::c_ulong = 0;
+        if unsafe { libc::syscall(libc::SYS_io_setup, slots as libc::c_long, &mut ctx as *mut libc::c_ulong) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Context {
+            ctx,
+            iocbs: vec![Iocb::default(); slots].into_boxed_slice(),
+            pending: Vec::with_capacity(slots),
+            events: vec![IoEvent::default(); slots],
+            ready: 0,
+        })
+    }
+
+    /// Queue a read of `len` bytes at `offset` of `fd` into `buf`.
+    ///
+    /// # Safety
+    /// `buf` must stay valid and unaliased until the completion for `slot` is reaped.
+    pub unsafe fn push_read(&mut self, slot: usize, fd: RawFd, buf: *mut u8, len: usize, offset: u64) {
+        self.push(IOCB_CMD_PREAD, slot, fd, buf, len, offset);
+    }
+
+    /// Queue a write of `len` bytes from `buf` at `offset` of `fd`.
+    ///
+    /// # Safety
+    /// `buf` must stay valid and unmodified until the completion for `slot` is reaped.
+    pub unsafe fn push_write(&mut self, slot: usize, fd: RawFd, buf: *const u8, len: usize, offset: u64) {
+        self.push(IOCB_CMD_PWRITE, slot, fd, buf.cast_mut(), len, offset);
+    }
+
+    fn push(&mut self, opcode: u16, slot: usize, fd: RawFd, buf: *mut u8, len: usize, offset: u64) {
+        let iocb = &mut self.iocbs[slot];
+        *iocb = Iocb {
+            aio_data: slot as u64,
+            aio_lio_opcode: opcode,
+            aio_fildes: fd as u32,
+            aio_buf: buf as u64,
+            aio_nbytes: len as u64,
+            aio_offset: offset as i64,
+            ..Iocb::default()
+        };
+        self.pending.push(iocb);
+    }
+
+    /// Submit the queued control blocks and wait until `min_complete` completions are available.
+    pub fn submit_and_wait(&mut self, min_complete: usize) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            let ret = unsafe {
+                libc::syscall(libc::SYS_io_submit, self.ctx, self.pending.len() as libc::c_long, self.pending.as_mut_ptr())
+            };
+            if ret < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+            self.pending.drain(..ret as usize);
+        }
+        let capacity = self.events.len() - self.ready;
+        let min_complete = min_complete.saturating_sub(self.ready).min(capacity);
+        loop {
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_io_getevents,
+                    self.ctx,
+                    min_complete as libc::c_long,
+                    capacity as libc::c_long,
+                    self.events[self.ready..].as_mut_ptr(),
+                    std::ptr::null::<libc::timespec>(),
+                )
+            };
+            if ret >= 0 {
+                self.ready += ret as usize;
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Pop the completions collected so far as `(slot, result)`.
+    pub fn reap(&mut self, mut on_complete: impl FnMut(usize, io::Result<usize>)) {
+        for event in &self.events[..self.ready] {
+            let result = if event.res < 0 {
+                Err(io::Error::from_raw_os_error(-event.res as i32))
+            } else {
+                Ok(event.res as usize)
+            };
+            on_complete(event.data as usize, result);
+        }
+        self.ready = 0;
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { libc::syscall(libc::SYS_io_destroy, self.ctx) };
+    }
+}
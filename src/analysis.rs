@@ -0,0 +1,430 @@
+//! Time-series analysis of per-operation latencies.
+
+use std::time::Duration;
+
+/// Periodic latency spikes found in a run.
+#[derive(Debug, Clone)]
+pub struct Periodicity {
+    pub period: Duration,
+    /// Autocorrelation of the binned spike counts at `period`, in [-1, 1].
+    pub strength: f64,
+    pub spike_count: usize,
+    pub spike_threshold: Duration,
+}
+
+/// Autocorrelation coefficient of `series` at each of `lags`.
+///
+/// Lags at or beyond the series length are skipped.
+pub fn autocorrelation(series: &[f64], lags: &[usize]) -> Vec<(usize, f64)> {
+    lags.iter()
+        .filter(|&&lag| lag < series.len())
+        .map(|&lag| (lag, autocorrelation_at(series, lag)))
+        .collect()
+}
+
+fn autocorrelation_at(series: &[f64], lag: usize) -> f64 {
+    let n = series.len();
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let variance: f64 = series.iter().map(|x| (x - mean) * (x - mean)).sum();
+    if variance == 0.0 {
+        return 0.0;
+    }
+    let covariance: f64 = (0..n - lag)
+        .map(|i| (series[i] - mean) * (series[i + lag] - mean))
+        .sum();
+    covariance / variance
+}
+
+/// Pearson correlation of two equally long series, `None` if either is constant.
+pub fn correlation(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_x = xs[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = ys[..n].iter().sum::<f64>() / n as f64;
+    let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs[..n].iter().zip(&ys[..n]) {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x) * (x - mean_x);
+        var_y += (y - mean_y) * (y - mean_y);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(covariance / (var_x * var_y).sqrt())
+}
+
+/// Gaps between consecutive issue times, which must be sorted.
+pub fn inter_arrival_gaps(issued: &[Duration]) -> Vec<Duration> {
+    issued.windows(2).map(|pair| pair[1].saturating_sub(pair[0])).collect()
+}
+
+/// Standard deviation over mean, `None` for fewer than two values or a zero mean.
+pub fn coefficient_of_variation(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if mean == 0.0 {
+        return None;
+    }
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64;
+    Some(variance.sqrt() / mean)
+}
+
+/// Signed distance in bytes from the end of each operation to the start of the
+/// next, for `(file, offset, len)` operations in issue order: 0 is sequential,
+/// negative seeks backwards. `None` where the next operation is in another file.
+pub fn seek_distances(ops: &[(usize, u64, u64)]) -> Vec<Option<i64>> {
+    ops.windows(2)
+        .map(|pair| {
+            let (file, offset, len) = pair[0];
+            let (next_file, next_offset, _) = pair[1];
+            (file == next_file).then(|| next_offset as i64 - (offset + len) as i64)
+        })
+        .collect()
+}
+
+/// Look for latency spikes that recur with a fixed period.
+///
+/// `samples` are `(timestamp, latency)` pairs in any order. Operations slower
+/// than `spike_threshold` count as spikes; their counts are binned over time
+/// and the bin series' autocorrelation peak gives the period.
+pub fn detect_periodic_spikes(
+    samples: &[(Duration, Duration)],
+    spike_threshold: Duration,
+) -> Option<Periodicity> {
+    let spikes: Vec<Duration> = samples
+        .iter()
+        .filter(|(_, latency)| *latency > spike_threshold)
+        .map(|(timestamp, _)| *timestamp)
+        .collect();
+    if spikes.len() < 3 {
+        return None;
+    }
+
+    let duration = samples.iter().map(|(t, _)| *t).max()?;
+    let bin = (duration / 1000).clamp(Duration::from_millis(1), Duration::from_millis(100));
+    let bins = (duration.as_nanos() / bin.as_nanos()) as usize + 1;
+    if bins < 8 {
+        return None;
+    }
+
+    let mut counts = vec![0.0; bins];
+    for timestamp in &spikes {
+        counts[(timestamp.as_nanos() / bin.as_nanos()) as usize] += 1.0;
+    }
+
+    // A period must repeat at least twice within the run to be credible
+    let acf: Vec<f64> = (0..=bins / 2).map(|lag| autocorrelation_at(&counts, lag)).collect();
+    let (best_lag, best_r) = (2..acf.len().saturating_sub(1))
+        .filter(|&lag| acf[lag] >= acf[lag - 1] && acf[lag] >= acf[lag + 1])
+        .map(|lag| (lag, acf[lag]))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if best_r < 0.2 {
+        return None;
+    }
+
+    Some(Periodicity {
+        period: bin * best_lag as u32,
+        strength: best_r,
+        spike_count: spikes.len(),
+        spike_threshold,
+    })
+}
+
+/// Two-sided Mann-Whitney U test of whether `a` and `b` come from the same
+/// distribution, returning `(u, p)` with `u` counted for `a`. The p-value is
+/// exact for small samples without ties and normal-approximated otherwise.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> Option<(f64, f64)> {
+    let (n1, n2) = (a.len(), b.len());
+    if n1 == 0 || n2 == 0 {
+        return None;
+    }
+    let mut pooled: Vec<(f64, bool)> = a.iter().map(|&x| (x, true)).chain(b.iter().map(|&x| (x, false))).collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Midranks for ties, collecting the tie correction as we go
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i;
+        while j + 1 < pooled.len() && pooled[j + 1].0 == pooled[i].0 {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum_a += rank * pooled[i..=j].iter().filter(|(_, in_a)| *in_a).count() as f64;
+        let tied = (j - i + 1) as f64;
+        tie_term += tied * tied * tied - tied;
+        i = j + 1;
+    }
+    let u = rank_sum_a - (n1 * (n1 + 1)) as f64 / 2.0;
+
+    let p = if tie_term == 0.0 && n1 + n2 <= 40 {
+        exact_mann_whitney_p(n1, n2, u as usize)
+    } else {
+        let n = (n1 + n2) as f64;
+        let mean = (n1 * n2) as f64 / 2.0;
+        let variance = (n1 * n2) as f64 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+        if variance <= 0.0 {
+            return Some((u, 1.0));
+        }
+        // Continuity correction towards the mean
+        let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+        erfc(z / std::f64::consts::SQRT_2)
+    };
+    Some((u, p.min(1.0)))
+}
+
+/// Two-sided p-value of `u` from the exact null distribution of U, counting
+/// the orderings of `n1` and `n2` values that give each U.
+fn exact_mann_whitney_p(n1: usize, n2: usize, u: usize) -> f64 {
+    let max_u = n1 * n2;
+    // counts[i][j][k]: orderings of i and j values with U = k
+    let mut counts = vec![vec![vec![0.0f64; max_u + 1]; n2 + 1]; n1 + 1];
+    for i in 0..=n1 {
+        for j in 0..=n2 {
+            if i == 0 || j == 0 {
+                counts[i][j][0] = 1.0;
+                continue;
+            }
+            for k in 0..=i * j {
+                // The largest value is from the first sample (beating all j) or from the second
+                let from_a = if k >= j { counts[i - 1][j][k - j] } else { 0.0 };
+                counts[i][j][k] = from_a + counts[i][j - 1][k];
+            }
+        }
+    }
+    let total: f64 = counts[n1][n2].iter().sum();
+    let lower: f64 = counts[n1][n2][..=u].iter().sum();
+    let upper: f64 = counts[n1][n2][u..].iter().sum();
+    2.0 * lower.min(upper) / total
+}
+
+/// Smallest two-sided p-value the exact test can reach with `n1` and `n2` values.
+pub fn mann_whitney_min_p(n1: usize, n2: usize) -> f64 {
+    if n1 == 0 || n2 == 0 || n1 + n2 > 40 {
+        return 0.0;
+    }
+    exact_mann_whitney_p(n1, n2, 0)
+}
+
+/// Complementary error function (Numerical Recipes' Chebyshev fit, relative error below 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t * (-z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+        .exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// A lognormal distribution: the log of the values is normal with `mu` and `sigma`.
+#[derive(Debug, Clone, Copy)]
+pub struct LogNormal {
+    pub mu: f64,
+    pub sigma: f64,
+}
+
+impl LogNormal {
+    /// The median, `exp(mu)`.
+    pub fn median(&self) -> f64 {
+        self.mu.exp()
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        normal_cdf((x.ln() - self.mu) / self.sigma)
+    }
+
+    /// Density of `ln x`, which is all the fits below compare.
+    fn log_density(&self, ln_x: f64) -> f64 {
+        let z = (ln_x - self.mu) / self.sigma;
+        (-0.5 * z * z).exp() / (self.sigma * (2.0 * std::f64::consts::PI).sqrt())
+    }
+}
+
+/// Two lognormal components, e.g. cache hits and misses.
+#[derive(Debug, Clone, Copy)]
+pub struct Mixture {
+    /// Share of the faster component, in [0, 1]
+    pub fast_weight: f64,
+    pub fast: LogNormal,
+    pub slow: LogNormal,
+}
+
+impl Mixture {
+    fn cdf(&self, x: f64) -> f64 {
+        self.fast_weight * self.fast.cdf(x) + (1.0 - self.fast_weight) * self.slow.cdf(x)
+    }
+}
+
+/// Goodness of fit of a candidate distribution.
+#[derive(Debug, Clone, Copy)]
+pub struct Fit {
+    /// Kolmogorov-Smirnov distance: the largest gap between the empirical and the fitted CDF
+    pub ks: f64,
+    /// Bayesian information criterion; lower is better, and it penalizes extra parameters
+    pub bic: f64,
+}
+
+/// Smallest sigma a fit may use, so a constant sample does not give an infinite density.
+const MIN_SIGMA: f64 = 1e-3;
+
+/// Maximum-likelihood lognormal fit of positive `values`.
+pub fn fit_lognormal(values: &[f64]) -> Option<(LogNormal, Fit)> {
+    if values.len() < 2 {
+        return None;
+    }
+    let logs: Vec<f64> = values.iter().map(|v| v.max(f64::MIN_POSITIVE).ln()).collect();
+    let n = logs.len() as f64;
+    let mu = logs.iter().sum::<f64>() / n;
+    let sigma = (logs.iter().map(|l| (l - mu) * (l - mu)).sum::<f64>() / n).sqrt().max(MIN_SIGMA);
+    let dist = LogNormal { mu, sigma };
+    let log_likelihood: f64 = logs.iter().map(|&l| dist.log_density(l).max(f64::MIN_POSITIVE).ln()).sum();
+    let fit = Fit { ks: ks_distance(values, |x| dist.cdf(x)), bic: 2.0 * n.ln() - 2.0 * log_likelihood };
+    Some((dist, fit))
+}
+
+/// Fit a two-component lognormal mixture to positive `values` with expectation maximization.
+pub fn fit_mixture(values: &[f64]) -> Option<(Mixture, Fit)> {
+    if values.len() < 10 {
+        return None;
+    }
+    let logs: Vec<f64> = values.iter().map(|v| v.max(f64::MIN_POSITIVE).ln()).collect();
+    let n = logs.len() as f64;
+
+    // Start from the lower and upper halves of the sorted sample
+    let mut sorted = logs.clone();
+    sorted.sort_by(f64::total_cmp);
+    let half = sorted.len() / 2;
+    let start = |part: &[f64]| {
+        let mu = part.iter().sum::<f64>() / part.len() as f64;
+        let sigma = (part.iter().map(|l| (l - mu) * (l - mu)).sum::<f64>() / part.len() as f64).sqrt().max(MIN_SIGMA);
+        LogNormal { mu, sigma }
+    };
+    let mut mixture = Mixture { fast_weight: 0.5, fast: start(&sorted[..half]), slow: start(&sorted[half..]) };
+
+    let mut fast_share = vec![0.0; logs.len()];
+    let mut previous = f64::NEG_INFINITY;
+    let mut log_likelihood = 0.0;
+    for _ in 0..500 {
+        // E step: how likely each value is to come from the fast component
+        log_likelihood = 0.0;
+        for (share, &l) in fast_share.iter_mut().zip(&logs) {
+            let fast = mixture.fast_weight * mixture.fast.log_density(l);
+            let slow = (1.0 - mixture.fast_weight) * mixture.slow.log_density(l);
+            let total = (fast + slow).max(f64::MIN_POSITIVE);
+            *share = fast / total;
+            log_likelihood += total.ln();
+        }
+        // M step: weighted means and deviations
+        let fast_total: f64 = fast_share.iter().sum();
+        let slow_total = n - fast_total;
+        if fast_total < 1.0 || slow_total < 1.0 {
+            return None;
+        }
+        let fast_mu = fast_share.iter().zip(&logs).map(|(w, l)| w * l).sum::<f64>() / fast_total;
+        let slow_mu = fast_share.iter().zip(&logs).map(|(w, l)| (1.0 - w) * l).sum::<f64>() / slow_total;
+        let fast_var = fast_share.iter().zip(&logs).map(|(w, l)| w * (l - fast_mu) * (l - fast_mu)).sum::<f64>() / fast_total;
+        let slow_var =
+            fast_share.iter().zip(&logs).map(|(w, l)| (1.0 - w) * (l - slow_mu) * (l - slow_mu)).sum::<f64>() / slow_total;
+        mixture = Mixture {
+            fast_weight: fast_total / n,
+            fast: LogNormal { mu: fast_mu, sigma: fast_var.sqrt().max(MIN_SIGMA) },
+            slow: LogNormal { mu: slow_mu, sigma: slow_var.sqrt().max(MIN_SIGMA) },
+        };
+        if (log_likelihood - previous).abs() < 1e-6 * log_likelihood.abs().max(1.0) {
+            break;
+        }
+        previous = log_likelihood;
+    }
+    if mixture.fast.mu > mixture.slow.mu {
+        mixture = Mixture { fast_weight: 1.0 - mixture.fast_weight, fast: mixture.slow, slow: mixture.fast };
+    }
+    let fit = Fit { ks: ks_distance(values, |x| mixture.cdf(x)), bic: 5.0 * n.ln() - 2.0 * log_likelihood };
+    Some((mixture, fit))
+}
+
+/// Kolmogorov-Smirnov distance between the empirical CDF of `values` and `cdf`.
+fn ks_distance(values: &[f64], cdf: impl Fn(f64) -> f64) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let n = sorted.len() as f64;
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let fitted = cdf(x.max(f64::MIN_POSITIVE));
+            (fitted - i as f64 / n).abs().max((fitted - (i + 1) as f64 / n).abs())
+        })
+        .fold(0.0, f64::max)
+}
+
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() <= 1e-6 * expected.max(1e-3)
+    }
+
+    #[test]
+    fn separated_small_samples_reach_the_exact_minimum() {
+        assert_eq!(mann_whitney(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), Some((0.0, 0.1)));
+        assert_eq!(mann_whitney(&[4.0, 5.0, 6.0], &[1.0, 2.0, 3.0]), Some((9.0, 0.1)));
+        assert_eq!(mann_whitney_min_p(3, 3), 0.1);
+        assert!(close(mann_whitney_min_p(4, 4), 2.0 / 70.0));
+        assert_eq!(mann_whitney_min_p(30, 30), 0.0);
+    }
+
+    #[test]
+    fn exact_p_counts_orderings_up_to_u() {
+        // Only 5 > 4 of the 16 pairs goes the other way: U = 1, and 2 of 70 orderings reach it
+        let (u, p) = mann_whitney(&[1.0, 2.0, 3.0, 5.0], &[4.0, 6.0, 7.0, 8.0]).unwrap();
+        assert_eq!(u, 1.0);
+        assert!(close(p, 4.0 / 70.0), "{p}");
+        // U = 1 of 4: the 2 orderings at or below it, doubled, out of 6
+        let (u, p) = mann_whitney(&[1.0, 3.0], &[2.0, 4.0]).unwrap();
+        assert_eq!(u, 1.0);
+        assert!(close(p, 4.0 / 6.0), "{p}");
+    }
+
+    #[test]
+    fn ties_use_midranks_and_the_corrected_variance() {
+        let (u, p) = mann_whitney(&[1.0, 2.0, 3.0], &[3.0, 4.0, 5.0]).unwrap();
+        assert_eq!(u, 0.5);
+        assert!(close(p, 0.121_183_27), "{p}");
+        assert_eq!(mann_whitney(&[7.0; 3], &[7.0; 3]), Some((4.5, 1.0)));
+    }
+
+    #[test]
+    fn large_samples_use_the_normal_approximation() {
+        let evens: Vec<f64> = (0..25).map(|i| (2 * i) as f64).collect();
+        let odds: Vec<f64> = (0..25).map(|i| (2 * i + 1) as f64).collect();
+        let (u, p) = mann_whitney(&evens, &odds).unwrap();
+        assert_eq!(u, 300.0);
+        assert!(close(p, 0.815_890_15), "{p}");
+
+        let low: Vec<f64> = (0..25).map(f64::from).collect();
+        let high: Vec<f64> = (25..50).map(f64::from).collect();
+        let (u, p) = mann_whitney(&low, &high).unwrap();
+        assert_eq!(u, 0.0);
+        assert!((p / 1.415_656_2e-9 - 1.0).abs() < 1e-5, "{p}");
+    }
+
+    #[test]
+    fn empty_samples_have_no_test() {
+        assert_eq!(mann_whitney(&[], &[1.0]), None);
+        assert_eq!(mann_whitney(&[1.0], &[]), None);
+    }
+}
@@ -0,0 +1,177 @@
+//! In-memory upper bound: the same random block reads served from anonymous memory.
+
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use memmap2::MmapOptions;
+use rand::Rng;
+
+use crate::timing::Clock;
+use crate::{Args, calculate_statistics, print_statistics};
+
+const SYSCALL_ROUNDS: usize = 100_000;
+
+/// Median and tail of the syscall micro-benchmarks.
+#[derive(Debug, Clone, Copy)]
+pub struct SyscallBaseline {
+    pub noop_median: Duration,
+    pub noop_p99: Duration,
+    pub pread_median: Duration,
+    pub pread_p99: Duration,
+}
+
+/// Time a no-op syscall and a `pread` of `block_size` bytes from a file that is
+/// fully in the page cache. `scratch_path` is created and removed again.
+pub fn measure_syscalls(clock: &Clock, scratch_path: &str, block_size: usize) -> std::io::Result<SyscallBaseline> {
+    let mut noop = Vec::with_capacity(SYSCALL_ROUNDS);
+    for _ in 0..SYSCALL_ROUNDS {
+        let start = clock.now();
+        std::hint::black_box(noop_syscall());
+        noop.push(clock.between(start, clock.now()));
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(scratch_path)?;
+    file.write_all(&vec![0xAB; block_size])?;
+    let mut buffer = vec![0u8; block_size];
+    let mut pread = Vec::with_capacity(SYSCALL_ROUNDS);
+    let measured = (|| {
+        // Warm the page cache before timing
+        read_at(&file, &mut buffer, 0)?;
+        for _ in 0..SYSCALL_ROUNDS {
+            let start = clock.now();
+            read_at(&file, &mut buffer, 0)?;
+            pread.push(clock.between(start, clock.now()));
+        }
+        Ok::<_, std::io::Error>(())
+    })();
+    drop(file);
+    std::fs::remove_file(scratch_path)?;
+    measured?;
+
+    let noop_stats = calculate_statistics(noop.iter().collect());
+    let pread_stats = calculate_statistics(pread.iter().collect());
+    Ok(SyscallBaseline {
+        noop_median: noop_stats.median,
+        noop_p99: noop_stats.p99,
+        pread_median: pread_stats.median,
+        pread_p99: pread_stats.p99,
+    })
+}
+
+pub fn print_syscall_baseline(baseline: &SyscallBaseline, block_size: usize) {
+    println!("  No-op syscall:        median {}ns, 99th %ile {}ns", baseline.noop_median.as_nanos(), baseline.noop_p99.as_nanos());
+    println!(
+        "  Cached pread ({block_size} B): median {}ns, 99th %ile {}ns",
+        baseline.pread_median.as_nanos(),
+        baseline.pread_p99.as_nanos()
+    );
+    println!(
+        "  Copy and page-cache lookup ≈ {}ns of each warm read",
+        baseline.pread_median.saturating_sub(baseline.noop_median).as_nanos()
+    );
+}
+
+#[cfg(unix)]
+fn noop_syscall() -> libc::c_long {
+    unsafe { libc::syscall(libc::SYS_getppid) }
+}
+
+#[cfg(not(unix))]
+fn noop_syscall() -> u32 {
+    std::process::id()
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buffer, offset)
+}
+
+#[cfg(not(unix))]
+fn read_at(file: &File, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = file;
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buffer)
+}
+
+/// Copy random blocks out of an anonymous mapping as large as the dataset and
+/// report the latencies in the same format as the file tests.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let region_size = usize::try_from(args.dataset_bytes().unwrap_or(u64::MAX))?;
+    let block_size = args.block_size;
+    if region_size < block_size {
+        return Err("baseline region (--num-files × --file-size) is smaller than one block".into());
+    }
+
+    println!("🧠 Memory Baseline");
+    println!("  Region: {region_size} bytes of anonymous memory");
+    println!("  Block size: {block_size} bytes");
+    println!("  Operations: {}", args.num_operations);
+    println!("  Threads: {}", args.num_threads);
+
+    let mut region = MmapOptions::new().len(region_size).map_anon()?;
+    // Populate every page so the measurement sees resident memory, not first-touch faults
+    region.fill(0xAB);
+    let region = region.make_read_only()?;
+
+    let clock = Clock::new(args.clock);
+    let max_blocks = region_size / block_size;
+    let operations_per_thread = args.num_operations / args.num_threads;
+    let remainder = args.num_operations % args.num_threads;
+
+    let wall_start = Instant::now();
+    let latencies: Vec<Duration> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..args.num_threads)
+            .map(|thread_id| {
+                let region = &region;
+                let thread_operations = operations_per_thread + usize::from(thread_id < remainder);
+                scope.spawn(move || {
+                    let mut rng = crate::rng::StreamRng::new(args.rng, args.seed, thread_id);
+                    let mut buffer = vec![0u8; block_size];
+                    let mut latencies = Vec::with_capacity(thread_operations);
+                    for _ in 0..thread_operations {
+                        let offset = rng.random_range(0..max_blocks) * block_size;
+                        let start = clock.now();
+                        buffer.copy_from_slice(&region[offset..offset + block_size]);
+                        std::hint::black_box(&buffer);
+                        latencies.push(clock.between(start, clock.now()));
+                    }
+                    latencies
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+    });
+    let wall = wall_start.elapsed();
+
+    println!("\n📈 Random Block Copies ({} operations):", latencies.len());
+    print_statistics(calculate_statistics(latencies.iter().collect()));
+    let copied = latencies.len() as f64 * block_size as f64;
+    println!("  Bandwidth: {:.2} GB/s", copied / wall.as_secs_f64() / 1e9);
+
+    // Single-threaded streaming copy of the whole region for a bandwidth ceiling
+    let mut buffer = vec![0u8; block_size];
+    let start = Instant::now();
+    for chunk in region.chunks_exact(block_size) {
+        buffer.copy_from_slice(chunk);
+        std::hint::black_box(&buffer);
+    }
+    let sequential = start.elapsed();
+    println!("\n➡️  Sequential Copy ({region_size} bytes, 1 thread):");
+    println!(
+        "  Bandwidth: {:.2} GB/s",
+        (max_blocks * block_size) as f64 / sequential.as_secs_f64() / 1e9
+    );
+
+    println!("\n🔧 Syscall Baseline:");
+    let scratch_path = format!("{}_syscall.dat", args.file_prefix);
+    print_syscall_baseline(&measure_syscalls(&clock, &scratch_path, block_size)?, block_size);
+
+    Ok(())
+}
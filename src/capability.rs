@@ -0,0 +1,222 @@
+//! Probe what the platform and the target file system support before a run,
+//! so unsupported combinations fail with a clear report instead of an EINVAL
+//! halfway through, or fall back to something close.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{Args, CreateMode, engine};
+
+/// Whether one feature works here, and why not.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Capability {
+    fn from_result(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Capability { available: true, reason: None },
+            Err(reason) => Capability { available: false, reason: Some(reason) },
+        }
+    }
+}
+
+/// The capability matrix recorded in the run metadata.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    /// Engines registered for `--engine`
+    pub engines: BTreeMap<&'static str, Capability>,
+    /// Features of the file system holding the test files
+    pub o_direct: Capability,
+    /// An O_DIRECT read of `--block-size` at a `--block-size` offset, as the reads of the run
+    pub o_direct_block_size: Capability,
+    pub rwf_nowait: Capability,
+    pub fallocate: Capability,
+    pub fallocate_keep_size: Capability,
+    pub fallocate_punch_hole: Capability,
+    pub fallocate_zero_range: Capability,
+}
+
+/// Probe the engines, and the file system features on a scratch file in `dir`
+/// with reads of `block_size`.
+pub fn probe(dir: &Path, block_size: usize) -> Capabilities {
+    let engines = engine::availability()
+        .into_iter()
+        .map(|(name, available)| (name, Capability::from_result(available)))
+        .collect();
+    let path = dir.join(format!(".random_io_tester_probe_{}", std::process::id()));
+    let features = probe_file(&path, block_size);
+    let _ = std::fs::remove_file(&path);
+    let [o_direct, o_direct_block_size, rwf_nowait, fallocate, fallocate_keep_size, fallocate_punch_hole, fallocate_zero_range] =
+        features.map(Capability::from_result);
+    Capabilities { engines, o_direct, o_direct_block_size, rwf_nowait, fallocate, fallocate_keep_size, fallocate_punch_hole, fallocate_zero_range }
+}
+
+/// O_DIRECT, at 4 KiB and at `block_size`, RWF_NOWAIT and the fallocate modes, in the order of [`Capabilities`].
+#[cfg(target_os = "linux")]
+fn probe_file(path: &Path, block_size: usize) -> [Result<(), String>; 7] {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    const LEN: usize = 4096;
+    let file = match std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path) {
+        Ok(file) => file,
+        Err(e) => return std::array::from_fn(|_| Err(format!("cannot create a probe file in {}: {e}", path.display()))),
+    };
+    let errno = |ret: libc::c_long| {
+        if ret < 0 { Err(std::io::Error::last_os_error().to_string()) } else { Ok(()) }
+    };
+    let fallocate = |mode: libc::c_int, offset: libc::off_t| {
+        errno(unsafe { libc::fallocate(file.as_raw_fd(), mode, offset, LEN as libc::off_t) } as libc::c_long)
+    };
+    // Written data for the reads and the hole punching, then allocation past it
+    if let Err(e) = std::os::unix::fs::FileExt::write_all_at(&file, &[0; LEN], 0) {
+        return std::array::from_fn(|_| Err(format!("cannot write a probe file in {}: {e}", path.display())));
+    }
+    let plain = fallocate(0, LEN as libc::off_t);
+    let keep_size = fallocate(libc::FALLOC_FL_KEEP_SIZE, 2 * LEN as libc::off_t);
+    let punch_hole = fallocate(libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE, 0);
+    let zero_range = fallocate(libc::FALLOC_FL_ZERO_RANGE, 0);
+
+    // Data where the read at `block_size` starts, since reads of holes skip the alignment checks
+    let extended = std::os::unix::fs::FileExt::write_all_at(&file, &[0; LEN], block_size as u64)
+        .and_then(|()| file.set_len((2 * block_size).max(3 * LEN) as u64));
+    if let Err(e) = extended {
+        return std::array::from_fn(|_| Err(format!("cannot extend a probe file in {}: {e}", path.display())));
+    }
+    let direct_read = |len: usize, offset: usize| {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|direct| {
+                let layout = std::alloc::Layout::from_size_align(len.next_multiple_of(LEN), LEN).expect("valid probe layout");
+                let buf = unsafe { std::alloc::alloc_zeroed(layout) };
+                if buf.is_null() {
+                    std::alloc::handle_alloc_error(layout);
+                }
+                let ret = unsafe { libc::pread(direct.as_raw_fd(), buf.cast(), len, offset as libc::off_t) };
+                let result = errno(ret as libc::c_long);
+                unsafe { std::alloc::dealloc(buf, layout) };
+                result
+            })
+    };
+    let o_direct = direct_read(LEN, 0);
+    let o_direct_block_size = match &o_direct {
+        Ok(()) => direct_read(block_size.max(1), block_size),
+        Err(_) => Err("O_DIRECT is not supported".to_string()),
+    };
+
+    let mut buf = [0u8; LEN];
+    let iov = libc::iovec { iov_base: buf.as_mut_ptr().cast(), iov_len: LEN };
+    // EAGAIN only means the block is not cached; the flag itself is supported
+    let rwf_nowait = match unsafe { libc::preadv2(file.as_raw_fd(), &iov, 1, 0, libc::RWF_NOWAIT) } {
+        ret if ret >= 0 => Ok(()),
+        _ => match std::io::Error::last_os_error() {
+            e if e.raw_os_error() == Some(libc::EAGAIN) => Ok(()),
+            e => Err(e.to_string()),
+        },
+    };
+    [o_direct, o_direct_block_size, rwf_nowait, plain, keep_size, punch_hole, zero_range]
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe_file(_path: &Path, _block_size: usize) -> [Result<(), String>; 7] {
+    std::array::from_fn(|_| Err("only probed on Linux".to_string()))
+}
+
+impl Capabilities {
+    /// Every probed feature with its display name, engines first.
+    fn matrix(&self) -> Vec<(String, &Capability)> {
+        let features = [
+            ("O_DIRECT", &self.o_direct),
+            ("O_DIRECT at --block-size", &self.o_direct_block_size),
+            ("RWF_NOWAIT", &self.rwf_nowait),
+            ("fallocate", &self.fallocate),
+            ("fallocate KEEP_SIZE", &self.fallocate_keep_size),
+            ("fallocate PUNCH_HOLE", &self.fallocate_punch_hole),
+            ("fallocate ZERO_RANGE", &self.fallocate_zero_range),
+        ];
+        self.engines
+            .iter()
+            .map(|(name, capability)| (format!("engine {name}"), capability))
+            .chain(features.into_iter().map(|(name, capability)| (name.to_string(), capability)))
+            .collect()
+    }
+
+    /// The features that are not available, with the reason.
+    pub fn unavailable(&self) -> Vec<String> {
+        self.matrix()
+            .into_iter()
+            .filter_map(|(name, capability)| capability.reason.as_ref().map(|reason| format!("{name} ({reason})")))
+            .collect()
+    }
+
+    pub fn report(&self, dir: &Path) {
+        println!("\n🔍 Platform Capabilities ({}):", dir.display());
+        for (name, capability) in self.matrix() {
+            match &capability.reason {
+                None => println!("  ✅ {name}"),
+                Some(reason) => println!("  ❌ {name}: {reason}"),
+            }
+        }
+    }
+
+    /// Check `args` against the capabilities, switching unsupported choices to
+    /// the closest supported one. Returns the fallbacks taken, or why the run
+    /// cannot go ahead; with `--strict` every fallback is an error.
+    pub fn apply(&self, args: &mut Args) -> Result<Vec<String>, String> {
+        let mut fallbacks = Vec::new();
+        let available = |name: &str| self.engines.get(name).is_some_and(|c| c.available);
+        if let Some(Capability { available: false, reason }) = self.engines.get(args.engine.as_str()) {
+            let reason = reason.as_deref().unwrap_or("unavailable");
+            // Only the async engines stand in for each other, so --queue-depth keeps its meaning
+            let alternative = match args.engine.as_str() {
+                "io_uring" => Some("libaio"),
+                "libaio" => Some("io_uring"),
+                _ => None,
+            };
+            match alternative.filter(|name| available(name)) {
+                Some(name) if !args.poll => {
+                    fallbacks.push(format!("the {} engine is not available ({reason}); using {name}", args.engine));
+                    args.engine = name.to_string();
+                }
+                _ => return Err(format!("the {} engine is not available here: {reason}", args.engine)),
+            }
+        }
+        if args.poll && !self.o_direct.available {
+            return Err(format!(
+                "--poll reads with O_DIRECT, which the file system under --file-prefix does not support: {}",
+                self.o_direct.reason.as_deref().unwrap_or("unavailable")
+            ));
+        }
+        // The case that otherwise fails every read with EINVAL
+        let direct_reader = if args.poll { Some("--poll") } else { (args.engine == "libaio").then_some("the libaio engine") };
+        if let Some(reader) = direct_reader
+            && self.o_direct.available
+            && let Some(reason) = &self.o_direct_block_size.reason
+        {
+            return Err(format!(
+                "{reader} reads with O_DIRECT, which rejects reads of --block-size {} here ({reason}); use a multiple of the logical block size",
+                args.block_size
+            ));
+        }
+        if args.create_mode == CreateMode::Fallocate && !self.fallocate.available {
+            fallbacks.push(format!(
+                "fallocate is not supported ({}); creating files with --create-mode write",
+                self.fallocate.reason.as_deref().unwrap_or("unavailable")
+            ));
+            args.create_mode = CreateMode::Write;
+        }
+        if args.strict && !fallbacks.is_empty() {
+            return Err(format!("{} and --strict is set", fallbacks.join("; ")));
+        }
+        Ok(fallbacks)
+    }
+}
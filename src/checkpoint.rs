@@ -0,0 +1,209 @@
+//! Periodic checkpoints of aggregated results, so an interrupted run can be resumed.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Args, BlockCounts};
+use crate::histogram::LatencyHistogram;
+
+/// Reader threads publish their progress after this many operations.
+pub const PROGRESS_BATCH: u64 = 1024;
+
+/// The parameters that must match for a checkpoint to be resumed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunIdentity {
+    pub num_files: usize,
+    pub file_size: u64,
+    pub block_size: usize,
+    pub num_operations: usize,
+    pub seed: u64,
+    pub engine: String,
+    pub file_prefix: String,
+    #[serde(default)]
+    pub rng: crate::rng::RngKind,
+    /// Per-file sizes from `--file-sizes`, empty when all files have `file_size` bytes
+    #[serde(default)]
+    pub file_sizes: Vec<u64>,
+    /// Directory tree layout from `--dirs-depth`/`--dirs-fanout`
+    #[serde(default)]
+    pub dirs_depth: usize,
+    #[serde(default)]
+    pub dirs_fanout: usize,
+    /// Directories the files are spread over (`--target-dir`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_dirs: Vec<std::path::PathBuf>,
+    /// Fraction of the blocks readers touch (`--coverage`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<f64>,
+}
+
+impl From<&Args> for RunIdentity {
+    fn from(args: &Args) -> Self {
+        RunIdentity {
+            num_files: args.num_files,
+            file_size: args.file_size,
+            block_size: args.block_size,
+            num_operations: args.num_operations,
+            seed: args.seed,
+            engine: args.engine.clone(),
+            file_prefix: args.file_prefix.clone(),
+            rng: args.rng,
+            file_sizes: args.sizes.clone(),
+            dirs_depth: args.dirs_depth,
+            dirs_fanout: if args.dirs_depth > 0 { args.dirs_fanout } else { 0 },
+            target_dirs: args.target_dirs.clone(),
+            coverage: args.coverage,
+        }
+    }
+}
+
+/// Aggregated results of the operations completed so far.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub completed_operations: u64,
+    pub all: LatencyHistogram,
+    pub first: LatencyHistogram,
+    pub repeated: LatencyHistogram,
+}
+
+impl Progress {
+    /// Count one operation; `read` is `None` for operations that produced no result.
+    pub fn record(&mut self, read: Option<(Duration, bool)>) {
+        self.completed_operations += 1;
+        if let Some((latency, is_first_read)) = read {
+            self.all.record(latency);
+            if is_first_read {
+                self.first.record(latency);
+            } else {
+                self.repeated.record(latency);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &Progress) {
+        self.completed_operations += other.completed_operations;
+        self.all.merge(&other.all);
+        self.first.merge(&other.first);
+        self.repeated.merge(&other.repeated);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Run ID, kept when the run is resumed
+    #[serde(default)]
+    pub run_id: Option<String>,
+    pub identity: RunIdentity,
+    /// How many times the run has been resumed
+    pub generation: u32,
+    pub progress: Progress,
+    /// Blocks read so far as `(file, block, read count)`, for first-read tracking
+    pub read_blocks: Vec<(usize, u64, u64)>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    /// Write the checkpoint atomically: a crash leaves either the old or the new one.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp_path)?;
+        serde_json::to_writer(&mut file, self)?;
+        file.flush()?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    }
+}
+
+/// Background thread writing a checkpoint every `interval`.
+pub struct Checkpointer {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+    writer: CheckpointWriter,
+}
+
+#[derive(Clone)]
+struct CheckpointWriter {
+    path: PathBuf,
+    run_id: String,
+    identity: RunIdentity,
+    generation: u32,
+    progress: Arc<Mutex<Progress>>,
+    read_blocks: Arc<Mutex<BlockCounts>>,
+}
+
+impl CheckpointWriter {
+    fn write(&self) -> std::io::Result<()> {
+        let progress = self.progress.lock().unwrap().clone();
+        let read_blocks = self
+            .read_blocks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&(file, block), &count)| (file, block, count))
+            .collect();
+        Checkpoint {
+            run_id: Some(self.run_id.clone()),
+            identity: self.identity.clone(),
+            generation: self.generation,
+            progress,
+            read_blocks,
+        }
+        .save(&self.path)
+    }
+}
+
+impl Checkpointer {
+    pub fn start(
+        path: PathBuf,
+        interval: Duration,
+        run_id: String,
+        identity: RunIdentity,
+        generation: u32,
+        progress: Arc<Mutex<Progress>>,
+        read_blocks: Arc<Mutex<BlockCounts>>,
+    ) -> Self {
+        let writer = CheckpointWriter {
+            path,
+            run_id,
+            identity,
+            generation,
+            progress,
+            read_blocks,
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = Arc::clone(&stop);
+        let thread_writer = writer.clone();
+
+        let handle = std::thread::spawn(move || {
+            let mut last = Instant::now();
+            while !stop_clone.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(100));
+                if last.elapsed() >= interval {
+                    if let Err(e) = thread_writer.write() {
+                        eprintln!("⚠️  Checkpoint failed: {e}");
+                    }
+                    last = Instant::now();
+                }
+            }
+        });
+
+        Checkpointer { stop, handle, writer }
+    }
+
+    /// Stop the thread and write a final checkpoint.
+    pub fn finish(self) -> std::io::Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+        self.writer.write()
+    }
+}
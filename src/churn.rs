@@ -0,0 +1,105 @@
+//! `--cache-churn`: stream a sparse file through the page cache at a fixed rate
+//! while the readers run. Holes read as zeros without touching the device, so
+//! the readers see page-cache eviction without device contention.
+
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Bytes read per step.
+const CHUNK: usize = 1024 * 1024;
+
+/// Parse a rate such as `256M/s` into bytes per second.
+pub fn parse_rate(spec: &str) -> Result<u64, String> {
+    let size = spec
+        .strip_suffix("/s")
+        .ok_or_else(|| format!("'{spec}': expected a rate such as 256M/s"))?;
+    let rate = crate::workload::parse_size(size)?;
+    if rate == 0 {
+        return Err(format!("'{spec}': rate must be positive"));
+    }
+    Ok(rate)
+}
+
+/// What the churn thread streamed.
+pub struct Summary {
+    pub bytes: u64,
+    pub elapsed: Duration,
+    pub file_size: u64,
+}
+
+/// The running churn thread and its file.
+pub struct Churner {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    /// `None` once finished
+    handle: Option<JoinHandle<std::io::Result<Summary>>>,
+}
+
+/// Create a sparse file of `size` bytes at `path` and read through it, wrapping
+/// at the end, at `rate` bytes per second.
+pub fn start(path: PathBuf, size: u64, rate: u64) -> std::io::Result<Churner> {
+    let file = File::options().read(true).write(true).create(true).truncate(true).open(&path)?;
+    if let Err(e) = file.set_len(size) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stop = Arc::clone(&stopped);
+    let handle = std::thread::spawn(move || {
+        let mut buffer = vec![0u8; CHUNK];
+        let start = Instant::now();
+        let (mut bytes, mut offset) = (0u64, 0u64);
+        while !stop.load(Ordering::Relaxed) {
+            let len = (CHUNK as u64).min(size - offset) as usize;
+            file.read_exact_at(&mut buffer[..len], offset)?;
+            bytes += len as u64;
+            offset = if offset + len as u64 >= size { 0 } else { offset + len as u64 };
+            // Sleep off any lead over the target rate
+            let due = Duration::from_secs_f64(bytes as f64 / rate as f64);
+            if let Some(ahead) = due.checked_sub(start.elapsed()) {
+                std::thread::sleep(ahead);
+            }
+        }
+        Ok(Summary { bytes, elapsed: start.elapsed(), file_size: size })
+    });
+    Ok(Churner { path, stopped, handle: Some(handle) })
+}
+
+impl Churner {
+    /// Stop streaming, remove the file and return what was read.
+    pub fn finish(mut self) -> std::io::Result<Summary> {
+        self.stopped.store(true, Ordering::Relaxed);
+        let handle = self.handle.take().expect("cache churn finished twice");
+        let summary = handle.join().expect("cache churn thread panicked");
+        std::fs::remove_file(&self.path)?;
+        summary
+    }
+}
+
+/// Stops the thread and removes the file when the run ends before `finish`.
+impl Drop for Churner {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stopped.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+pub fn report(summary: &Summary) {
+    let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    println!(
+        "\n🌪️  Cache churn: streamed {:.1} MiB through the page cache in {:.1}s ({:.1} MiB/s), {:.1} passes over a {:.0} MiB sparse file",
+        mib(summary.bytes),
+        summary.elapsed.as_secs_f64(),
+        mib(summary.bytes) / summary.elapsed.as_secs_f64().max(f64::EPSILON),
+        summary.bytes as f64 / summary.file_size.max(1) as f64,
+        mib(summary.file_size)
+    );
+}
@@ -0,0 +1,89 @@
+//! Compare two sets of `--json` results files, e.g. repeated runs before and
+//! after a change, and tell real differences from run-to-run noise.
+
+use std::path::{Path, PathBuf};
+
+use crate::analysis;
+
+/// Reads one metric from a parsed results file.
+type Metric = fn(&serde_json::Value) -> Option<f64>;
+
+/// Metrics compared between the sets: name, whether higher is better, and how
+/// to read it from a results file.
+const METRICS: &[(&str, bool, Metric)] = &[
+    ("IOPS", true, |run| Some(run["all"]["count"].as_f64()? / run["elapsed_secs"].as_f64()?.max(f64::EPSILON))),
+    ("Average", false, |run| run["all"]["avg_ns"].as_f64()),
+    ("Median", false, |run| run["all"]["median_ns"].as_f64()),
+    ("99th %ile", false, |run| run["all"]["p99_ns"].as_f64()),
+];
+
+fn load(path: &Path) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path).map_err(|e| format!("cannot open {}: {e}", path.display()))?;
+    let run: serde_json::Value = serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| format!("cannot parse {}: {e}", path.display()))?;
+    if run["all"].is_null() {
+        return Err(format!("{} has no read statistics", path.display()).into());
+    }
+    Ok(run)
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] }
+}
+
+fn format_metric(name: &str, value: f64) -> String {
+    if name == "IOPS" { format!("{value:.0}") } else { crate::units::format_ns(value) }
+}
+
+/// Print the median of each metric per set, the change from `baseline` to
+/// `candidate`, and a Mann-Whitney U test of whether the change is significant.
+pub fn run(baseline: &[PathBuf], candidate: &[PathBuf], alpha: f64) -> Result<(), Box<dyn std::error::Error>> {
+    let baseline_runs = baseline.iter().map(|path| load(path)).collect::<Result<Vec<_>, _>>()?;
+    let candidate_runs = candidate.iter().map(|path| load(path)).collect::<Result<Vec<_>, _>>()?;
+
+    println!("⚖️  Comparing {} baseline run(s) with {} candidate run(s)", baseline_runs.len(), candidate_runs.len());
+    println!(
+        "  {:>10} {:>14} {:>14} {:>9} {:>9}  Verdict",
+        "Metric", "Baseline", "Candidate", "Change", "p-value"
+    );
+    for &(name, higher_is_better, read) in METRICS {
+        let a: Vec<f64> = baseline_runs.iter().filter_map(read).collect();
+        let b: Vec<f64> = candidate_runs.iter().filter_map(read).collect();
+        if a.is_empty() || b.is_empty() {
+            continue;
+        }
+        let (before, after) = (median(&a), median(&b));
+        let change = if before != 0.0 { 100.0 * (after - before) / before } else { 0.0 };
+        let p = analysis::mann_whitney(&a, &b).map(|(_, p)| p);
+        let verdict = match p {
+            _ if a.len() < 2 || b.len() < 2 => "needs repeated runs".to_string(),
+            Some(p) if p < alpha => {
+                let better = (after > before) == higher_is_better;
+                format!("significant, {}", if better { "better" } else { "worse" })
+            }
+            _ => "not significant".to_string(),
+        };
+        println!(
+            "  {:>10} {:>14} {:>14} {:>+8.1}% {:>9}  {}",
+            name,
+            format_metric(name, before),
+            format_metric(name, after),
+            change,
+            p.map_or("-".to_string(), |p| format!("{p:.4}")),
+            verdict
+        );
+    }
+
+    let min_p = analysis::mann_whitney_min_p(baseline_runs.len(), candidate_runs.len());
+    if min_p >= alpha {
+        println!(
+            "⚠️  With {} and {} runs no difference can reach p < {alpha} (smallest possible p is {min_p:.3}); repeat each side more often",
+            baseline_runs.len(),
+            candidate_runs.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! Read-after-write checks: read a written block back and measure how long it
+//! takes until the new contents are visible, from the writing thread or another.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use crossbeam::channel::{Receiver, Sender};
+
+use crate::engine::{IoEngine, WRITE_PATTERN};
+use crate::workload::{OpKind, Operation};
+
+/// Marks a block written by a checked write.
+const MAGIC: &[u8; 8] = b"RIORAW\0\0";
+/// Magic and stamp: the smallest block a checked write fits in.
+pub const HEADER_LEN: usize = 16;
+/// A write not visible after this long is counted as never visible.
+const VISIBILITY_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawMode {
+    /// The writing thread reads the block back right after the write returns
+    SameThread,
+    /// A separate checker thread reads the block back, retrying until it sees the write
+    OtherThread,
+}
+
+thread_local! {
+    /// Floor of the last write issued by this thread, see [`Pending::floor`]
+    static LAST_FLOOR: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A write waiting to be read back.
+struct Pending {
+    file: usize,
+    offset: u64,
+    len: usize,
+    /// Newest stamp of the block whose write had completed when this write
+    /// started. Reading that stamp or an older one means the read missed this
+    /// write; any newer stamp is this write or one racing with it.
+    floor: u64,
+    written: Instant,
+}
+
+/// Outcome of checking one write.
+enum Check {
+    /// Visible this long after the write returned
+    Visible(Duration),
+    /// The read right after the write returned older contents
+    Stale,
+    /// Reads from another thread kept returning older contents until the timeout
+    Timeout,
+    Failed,
+}
+
+#[derive(Debug, Default)]
+struct RawStats {
+    checks: u64,
+    stale_reads: u64,
+    never_visible: u64,
+    errors: u64,
+    /// Time from the write returning until a read returned the new contents
+    delays: Vec<Duration>,
+}
+
+impl RawStats {
+    /// Count a check that saw older contents `stale_reads` times before its outcome.
+    fn add(&mut self, check: Check, stale_reads: u64) {
+        self.checks += 1;
+        self.stale_reads += stale_reads;
+        match check {
+            Check::Visible(delay) => self.delays.push(delay),
+            Check::Stale => self.stale_reads += 1,
+            Check::Timeout => self.never_visible += 1,
+            Check::Failed => self.errors += 1,
+        }
+    }
+}
+
+/// Stamps every write with a unique, increasing number and checks that reads
+/// issued after it do not return contents older than the write.
+pub struct RawChecker {
+    engine: Arc<dyn IoEngine>,
+    mode: RawMode,
+    next_stamp: AtomicU64,
+    /// Newest stamp of a completed write to each `(file, offset)`
+    latest: Mutex<HashMap<(usize, u64), u64>>,
+    stats: Arc<Mutex<RawStats>>,
+    sender: Mutex<Option<Sender<Pending>>>,
+    checker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl RawChecker {
+    pub fn start(engine: Arc<dyn IoEngine>, mode: RawMode) -> Arc<Self> {
+        let stats = Arc::new(Mutex::new(RawStats::default()));
+        let (sender, checker) = if mode == RawMode::OtherThread {
+            let (sender, receiver) = crossbeam::channel::unbounded();
+            let engine = Arc::clone(&engine);
+            let stats = Arc::clone(&stats);
+            let handle = std::thread::spawn(move || check_pending(engine.as_ref(), &receiver, &stats));
+            (Some(sender), Some(handle))
+        } else {
+            (None, None)
+        };
+        Arc::new(RawChecker {
+            engine,
+            mode,
+            next_stamp: AtomicU64::new(1),
+            latest: Mutex::new(HashMap::new()),
+            stats,
+            sender: Mutex::new(sender),
+            checker: Mutex::new(checker),
+        })
+    }
+
+    /// Write a stamped block for `op`.
+    pub fn write(&self, op: &Operation) -> std::io::Result<()> {
+        let floor = self.latest.lock().unwrap().get(&(op.file, op.offset)).copied().unwrap_or(0);
+        LAST_FLOOR.set(floor);
+        let stamp = self.next_stamp.fetch_add(1, Ordering::Relaxed);
+        let mut data = vec![WRITE_PATTERN; op.len as usize];
+        data[..8].copy_from_slice(MAGIC);
+        data[8..16].copy_from_slice(&stamp.to_le_bytes());
+        self.engine.write(op.file, op.offset, &data)?;
+        let mut latest = self.latest.lock().unwrap();
+        let newest = latest.entry((op.file, op.offset)).or_insert(stamp);
+        *newest = (*newest).max(stamp);
+        Ok(())
+    }
+
+    /// Check a completed write: read it back now, or hand it to the checker thread.
+    pub fn after(&self, op: &Operation) {
+        if op.kind != OpKind::Write {
+            return;
+        }
+        let written = Instant::now();
+        let pending = Pending { file: op.file, offset: op.offset, len: op.len as usize, floor: LAST_FLOOR.get(), written };
+        match self.mode {
+            RawMode::SameThread => {
+                let mut buffer = vec![0u8; pending.len];
+                let check = match read_stamp(self.engine.as_ref(), &pending, &mut buffer) {
+                    Ok(true) => Check::Visible(written.elapsed()),
+                    Ok(false) => Check::Stale,
+                    Err(_) => Check::Failed,
+                };
+                self.stats.lock().unwrap().add(check, 0);
+            }
+            RawMode::OtherThread => {
+                if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+                    let _ = sender.send(pending);
+                }
+            }
+        }
+    }
+
+    /// Wait for the checker thread to drain its queue and print the results.
+    pub fn finish(&self) {
+        self.sender.lock().unwrap().take();
+        if let Some(handle) = self.checker.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+        let stats = self.stats.lock().unwrap();
+        let mode = match self.mode {
+            RawMode::SameThread => "same thread",
+            RawMode::OtherThread => "other thread",
+        };
+        println!("\n👀 Read-after-write ({mode}): {} writes checked", stats.checks);
+        println!("  Stale reads: {}", stats.stale_reads);
+        if self.mode == RawMode::OtherThread {
+            println!("  Not visible within {}s: {}", VISIBILITY_TIMEOUT.as_secs(), stats.never_visible);
+        }
+        if stats.errors > 0 {
+            println!("  Read-back errors: {}", stats.errors);
+        }
+        if !stats.delays.is_empty() {
+            println!("  Time from write to visible read:");
+            crate::print_statistics(crate::calculate_statistics(stats.delays.iter().collect()));
+        }
+        if stats.stale_reads > 0 && self.mode == RawMode::SameThread {
+            println!("⚠️  A thread did not see its own completed write");
+        }
+    }
+}
+
+/// Read the block of `pending` and tell whether the write is visible.
+fn read_stamp(engine: &dyn IoEngine, pending: &Pending, buffer: &mut [u8]) -> std::io::Result<bool> {
+    engine.read_into(pending.file, pending.offset, buffer)?;
+    let stamp = u64::from_le_bytes(buffer[8..16].try_into().unwrap());
+    Ok(&buffer[..8] == MAGIC && stamp > pending.floor)
+}
+
+fn check_pending(engine: &dyn IoEngine, receiver: &Receiver<Pending>, stats: &Mutex<RawStats>) {
+    let mut buffer = Vec::new();
+    for pending in receiver {
+        buffer.resize(pending.len, 0);
+        let mut stale_reads = 0;
+        let check = loop {
+            match read_stamp(engine, &pending, &mut buffer) {
+                Ok(true) => break Check::Visible(pending.written.elapsed()),
+                Ok(false) if pending.written.elapsed() < VISIBILITY_TIMEOUT => stale_reads += 1,
+                Ok(false) => break Check::Timeout,
+                Err(_) => break Check::Failed,
+            }
+        };
+        stats.lock().unwrap().add(check, stale_reads);
+    }
+}
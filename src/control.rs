@@ -0,0 +1,242 @@
+//! `--rate`, `--control-socket` and `--keys`: pace the readers to a target
+//! operation rate, and let an operator pause, resume, re-rate, skip to the next
+//! phase or close a soak interval while the run goes on, so a long run keeps
+//! its warmup. Time spent paused does not count towards the phase's throughput.
+//!
+//! The socket takes one command per line and answers each with one line:
+//! `pause`, `resume`, `rate <ops/s>` (`rate off` lifts the cap), `skip`,
+//! `report` (close the current `--soak` interval now) and `status`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often paused readers and the listener check for an interrupt.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Pause state and rate cap shared by the readers and the socket.
+pub struct Control {
+    /// When the current pause began, if paused
+    paused: Mutex<Option<Instant>>,
+    resumed: Condvar,
+    /// Time spent paused in the current phase, excluding the current pause
+    paused_nanos: AtomicU64,
+    /// Set to end the current phase early
+    skipping: AtomicBool,
+    /// Operations per second over all readers; 0 is unlimited
+    rate: AtomicU64,
+    threads: usize,
+    /// Whether `report` has a soak interval to close
+    soak: bool,
+    /// Keep every thread's schedule fixed, catching up after falling behind
+    open_loop: bool,
+}
+
+impl Control {
+    pub fn new(rate: Option<u64>, threads: usize, soak: bool, open_loop: bool) -> Self {
+        Control {
+            paused: Mutex::new(None),
+            resumed: Condvar::new(),
+            paused_nanos: AtomicU64::new(0),
+            skipping: AtomicBool::new(false),
+            rate: AtomicU64::new(rate.unwrap_or(0)),
+            threads: threads.max(1),
+            soak,
+            open_loop,
+        }
+    }
+
+    /// Block while the run is paused, then until the thread's next operation is
+    /// due under the rate cap. `due` is the thread's own schedule; a thread
+    /// that falls behind does not catch up with a burst, unless `--open-loop`
+    /// keeps the schedule fixed. Returns when the operation was due, or `None`
+    /// once the phase should end, on an interrupt or a skip.
+    pub fn wait(&self, due: &mut Option<Instant>) -> Option<Instant> {
+        let mut paused = self.paused.lock().unwrap();
+        if paused.is_some() {
+            while paused.is_some() && !self.ending() {
+                paused = self.resumed.wait_timeout(paused, TICK).unwrap().0;
+            }
+            *due = None;
+        }
+        drop(paused);
+        if self.ending() {
+            return None;
+        }
+        let now = Instant::now();
+        let rate = self.rate.load(Ordering::Relaxed);
+        if rate == 0 {
+            *due = None;
+            return Some(now);
+        }
+        let at = due.unwrap_or(now);
+        if let Some(ahead) = at.checked_duration_since(now) {
+            std::thread::sleep(ahead);
+        }
+        let from = if self.open_loop { at } else { at.max(now) };
+        *due = Some(from + Duration::from_secs_f64(self.threads as f64 / rate as f64));
+        Some(at)
+    }
+
+    fn ending(&self) -> bool {
+        crate::status::interrupted() || self.skipping.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume; pausing twice keeps the first pause's start.
+    pub fn set_paused(&self, pause: bool) {
+        let mut paused = self.paused.lock().unwrap();
+        match (*paused, pause) {
+            (None, true) => *paused = Some(Instant::now()),
+            (Some(since), false) => {
+                self.paused_nanos.fetch_add(since.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                *paused = None;
+            }
+            _ => {}
+        }
+        self.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.lock().unwrap().is_some()
+    }
+
+    /// End the current phase: the readers stop and the run reports what they did.
+    pub fn skip_phase(&self) {
+        self.skipping.store(true, Ordering::Relaxed);
+    }
+
+    /// Start a phase: clear a skip and the paused time of the phase before.
+    /// A pause carries over into the new phase.
+    pub fn begin_phase(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        if paused.is_some() {
+            *paused = Some(Instant::now());
+        }
+        self.paused_nanos.store(0, Ordering::Relaxed);
+        self.skipping.store(false, Ordering::Relaxed);
+    }
+
+    /// The part of `elapsed`, measured since the phase began, that was not spent paused.
+    pub fn unpaused(&self, elapsed: Duration) -> Duration {
+        let current = self.paused.lock().unwrap().map_or(Duration::ZERO, |since| since.elapsed());
+        elapsed.saturating_sub(Duration::from_nanos(self.paused_nanos.load(Ordering::Relaxed)) + current)
+    }
+
+    /// Carry out one socket command and return the answer.
+    fn command(&self, line: &str) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["pause"] => {
+                self.set_paused(true);
+                println!("⏸️  Paused from the control socket");
+                "ok paused".to_string()
+            }
+            ["resume"] => {
+                self.set_paused(false);
+                println!("▶️  Resumed from the control socket");
+                "ok resumed".to_string()
+            }
+            ["rate", "off"] => {
+                self.rate.store(0, Ordering::Relaxed);
+                println!("🎚️  Rate cap lifted from the control socket");
+                "ok rate unlimited".to_string()
+            }
+            ["rate", rate] => match rate.parse::<u64>() {
+                Ok(rate) if rate > 0 => {
+                    self.rate.store(rate, Ordering::Relaxed);
+                    println!("🎚️  Rate set to {rate} operations/s from the control socket");
+                    format!("ok rate {rate}")
+                }
+                _ => format!("error: '{rate}' is not a positive number of operations per second"),
+            },
+            ["skip"] => {
+                self.skip_phase();
+                println!("⏭️  Skipping to the next phase from the control socket");
+                "ok skip".to_string()
+            }
+            ["report"] if self.soak => {
+                crate::status::request_report();
+                "ok report".to_string()
+            }
+            ["report"] => "error: report closes a --soak interval; this run has none".to_string(),
+            ["status"] => {
+                let rate = self.rate.load(Ordering::Relaxed);
+                format!(
+                    "ok {}, rate {}",
+                    if self.is_paused() { "paused" } else { "running" },
+                    if rate == 0 { "unlimited".to_string() } else { format!("{rate} operations/s") }
+                )
+            }
+            _ => "error: unknown command; expected pause, resume, rate <ops/s|off>, skip, report or status".to_string(),
+        }
+    }
+}
+
+/// The thread answering the control socket.
+pub struct Server {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Listen on a new Unix socket at `path`, which must not exist yet.
+pub fn serve(path: PathBuf, control: Arc<Control>) -> std::io::Result<Server> {
+    let listener = UnixListener::bind(&path)?;
+    listener.set_nonblocking(true)?;
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stop = Arc::clone(&stopped);
+    let handle = std::thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = session(stream, &control, &stop) {
+                        eprintln!("⚠️  Control socket connection failed: {e}");
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(TICK),
+                Err(e) => {
+                    eprintln!("⚠️  Control socket stopped: {e}");
+                    return;
+                }
+            }
+        }
+    });
+    Ok(Server { path, stopped, handle })
+}
+
+/// Answer the commands of one connection until the client hangs up or the run ends.
+fn session(stream: UnixStream, control: &Control, stop: &AtomicBool) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(TICK))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while !stop.load(Ordering::Relaxed) {
+        // A timed-out read keeps what it got of the line in `line`
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {
+                if !line.trim().is_empty() {
+                    writeln!(writer, "{}", control.command(&line))?;
+                }
+                line.clear();
+            }
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+impl Server {
+    /// Stop listening and remove the socket.
+    pub fn finish(self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
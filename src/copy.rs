@@ -0,0 +1,171 @@
+//! Engines measuring kernel-side copies: the data never reaches user space.
+
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::AsRawFd;
+use std::thread::JoinHandle;
+
+use crate::engine::{Engine, IoEngine};
+use crate::{Args, CopySink};
+
+/// Where `sendfile` sends the data.
+enum Sink {
+    Null(File),
+    /// Write end of a Unix socket pair whose read end is drained by a thread
+    Socket {
+        writer: Option<std::os::unix::net::UnixStream>,
+        drain: Option<JoinHandle<()>>,
+    },
+}
+
+impl Sink {
+    fn open(kind: CopySink) -> std::io::Result<Self> {
+        match kind {
+            CopySink::Null => Ok(Sink::Null(std::fs::OpenOptions::new().write(true).open("/dev/null")?)),
+            CopySink::Socket => {
+                let (writer, mut reader) = std::os::unix::net::UnixStream::pair()?;
+                let drain = std::thread::spawn(move || {
+                    let mut buffer = vec![0u8; 1 << 20];
+                    while matches!(reader.read(&mut buffer), Ok(n) if n > 0) {}
+                });
+                Ok(Sink::Socket {
+                    writer: Some(writer),
+                    drain: Some(drain),
+                })
+            }
+        }
+    }
+
+    fn fd(&self) -> std::os::fd::RawFd {
+        match self {
+            Sink::Null(file) => file.as_raw_fd(),
+            Sink::Socket { writer, .. } => writer.as_ref().expect("sink is open").as_raw_fd(),
+        }
+    }
+}
+
+impl Drop for Sink {
+    fn drop(&mut self) {
+        if let Sink::Socket { writer, drain } = self {
+            // Closing the write end lets the drain thread see end-of-file
+            writer.take();
+            if let Some(drain) = drain.take() {
+                let _ = drain.join();
+            }
+        }
+    }
+}
+
+/// `sendfile` from the test files to `/dev/null` or a socket.
+pub struct SendfileEngine {
+    files: Vec<File>,
+    sink: Sink,
+}
+
+impl SendfileEngine {
+    pub fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        if args.poll {
+            return Err("--poll does not apply to the sendfile engine".into());
+        }
+        Ok(Engine::Sync(Box::new(SendfileEngine {
+            files: open_all(args, paths)?,
+            sink: Sink::open(args.copy_sink)?,
+        })))
+    }
+}
+
+impl IoEngine for SendfileEngine {
+    fn read(&self, file: usize, offset: u64, len: usize) -> std::io::Result<()> {
+        let source = self.files[file].as_raw_fd();
+        let mut position = offset as libc::off_t;
+        let mut remaining = len;
+        while remaining > 0 {
+            let sent = unsafe { libc::sendfile(self.sink.fd(), source, &mut position, remaining) };
+            match sent {
+                n if n < 0 => {
+                    let err = std::io::Error::last_os_error();
+                    if err.kind() != std::io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+                0 => return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short sendfile")),
+                n => remaining -= n as usize,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `copy_file_range` from the test files into a scratch file at the same offsets.
+pub struct CopyFileRangeEngine {
+    files: Vec<File>,
+    destination: File,
+    destination_path: String,
+}
+
+impl CopyFileRangeEngine {
+    pub fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        if args.poll {
+            return Err("--poll does not apply to the copy_file_range engine".into());
+        }
+        let destination_path = format!("{}_copy_destination", args.file_prefix);
+        let destination = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&destination_path)?;
+        Ok(Engine::Sync(Box::new(CopyFileRangeEngine {
+            files: open_all(args, paths)?,
+            destination,
+            destination_path,
+        })))
+    }
+}
+
+impl IoEngine for CopyFileRangeEngine {
+    fn read(&self, file: usize, offset: u64, len: usize) -> std::io::Result<()> {
+        let source = self.files[file].as_raw_fd();
+        let mut source_offset = offset as libc::loff_t;
+        let mut destination_offset = offset as libc::loff_t;
+        let mut remaining = len;
+        while remaining > 0 {
+            let copied = unsafe {
+                libc::copy_file_range(
+                    source,
+                    &mut source_offset,
+                    self.destination.as_raw_fd(),
+                    &mut destination_offset,
+                    remaining,
+                    0,
+                )
+            };
+            match copied {
+                n if n < 0 => {
+                    let err = std::io::Error::last_os_error();
+                    if err.kind() != std::io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+                0 => return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short copy_file_range")),
+                n => remaining -= n as usize,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CopyFileRangeEngine {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.destination_path);
+    }
+}
+
+fn open_all(args: &Args, paths: &[String]) -> std::io::Result<Vec<File>> {
+    let files = paths.iter().map(File::open).collect::<Result<Vec<_>, _>>()?;
+    if args.readahead == Some(0) {
+        for file in &files {
+            crate::fsutil::advise_random(file)?;
+        }
+    }
+    Ok(files)
+}
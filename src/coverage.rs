@@ -0,0 +1,83 @@
+//! `--coverage`: read only a seeded random subset of each file's blocks, so
+//! the working set is independent of the dataset size on disk.
+
+use crate::rng::splitmix64;
+
+/// Feistel rounds of the block permutation.
+const ROUNDS: usize = 4;
+
+/// Parse a fraction of the dataset like `25%` or `0.25`.
+pub fn parse_coverage(spec: &str) -> Result<f64, String> {
+    let fraction = match spec.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => spec.trim().parse::<f64>(),
+    }
+    .map_err(|_| format!("'{spec}': expected a percentage such as 25% or a fraction such as 0.25"))?;
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err(format!("'{spec}': coverage must be above 0% and at most 100%"));
+    }
+    Ok(fraction)
+}
+
+/// Blocks of one file that readers may touch: the first `covered` positions of
+/// a seeded permutation of its blocks.
+#[derive(Debug, Clone)]
+pub struct FileCoverage {
+    blocks: u64,
+    covered: u64,
+    half_bits: u32,
+    keys: [u64; ROUNDS],
+}
+
+impl FileCoverage {
+    /// Cover `fraction` of `blocks` (at least one), chosen by `seed`.
+    pub fn new(blocks: u64, fraction: f64, seed: u64) -> Self {
+        let covered = ((blocks as f64 * fraction).ceil() as u64).clamp(blocks.min(1), blocks);
+        // Permute over the smallest even power of two holding the blocks and walk
+        // the cycle back into range, so every block is equally likely to be chosen
+        let bits = u64::BITS - blocks.saturating_sub(1).leading_zeros();
+        let mut state = seed;
+        FileCoverage {
+            blocks,
+            covered,
+            half_bits: bits.div_ceil(2).max(1),
+            keys: std::array::from_fn(|_| splitmix64(&mut state)),
+        }
+    }
+
+    pub fn covered(&self) -> u64 {
+        self.covered
+    }
+
+    /// The covered block for a block `picked` out of the whole file, keeping its
+    /// relative position so skewed distributions stay skewed.
+    pub fn block(&self, picked: u64) -> u64 {
+        if self.blocks == 0 {
+            return 0;
+        }
+        let index = (picked as u128 * self.covered as u128 / self.blocks.max(1) as u128) as u64;
+        let mut block = self.permute(index.min(self.covered.saturating_sub(1)));
+        while block >= self.blocks {
+            block = self.permute(block);
+        }
+        block
+    }
+
+    fn permute(&self, value: u64) -> u64 {
+        let mask = (1u64 << self.half_bits) - 1;
+        let (mut left, mut right) = (value >> self.half_bits, value & mask);
+        for key in self.keys {
+            let mut state = right ^ key;
+            (left, right) = (right, left ^ (splitmix64(&mut state) & mask));
+        }
+        (left << self.half_bits) | right
+    }
+}
+
+/// One `FileCoverage` per file, each with its own stream of `seed`.
+pub fn per_file(blocks: impl Iterator<Item = u64>, fraction: f64, seed: u64) -> Vec<FileCoverage> {
+    blocks
+        .enumerate()
+        .map(|(file, blocks)| FileCoverage::new(blocks, fraction, crate::rng::thread_seed(seed ^ 0xC0FE_4A6E, file)))
+        .collect()
+}
@@ -0,0 +1,195 @@
+//! Crash-consistency torture: a child process writes blocks synchronously and
+//! acknowledges each one after `fdatasync` returns; the parent kills it with
+//! SIGKILL at a random moment and checks that every acknowledged write survived.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileExt;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::{Args, dataset, status};
+
+/// Marks a block written by the crash writer.
+const MAGIC: &[u8; 8] = b"RIOCRASH";
+/// Magic, round, sequence number, file and block: the smallest usable block size.
+pub const HEADER_LEN: usize = 8 + 4 + 8 + 8 + 8;
+
+/// The newest acknowledged write of each `(file, block)` not yet found lost, as `(round, seq)`.
+type Acknowledged = HashMap<(usize, u64), (u32, u64)>;
+
+/// Why an acknowledged block failed verification.
+enum Loss {
+    /// The block holds an older write (or none of ours) than the acknowledged one
+    Stale,
+    /// The header is newer or equal, but the rest of the block does not match it
+    Torn,
+}
+
+/// Contents of a crash-writer block: the header followed by a fill derived from it,
+/// so a block that was only partly written is told apart from a complete one.
+fn block_contents(len: usize, round: u32, seq: u64, file: usize, block: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(len);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&round.to_le_bytes());
+    data.extend_from_slice(&seq.to_le_bytes());
+    data.extend_from_slice(&(file as u64).to_le_bytes());
+    data.extend_from_slice(&block.to_le_bytes());
+    let mut state = seq ^ ((round as u64) << 32);
+    while data.len() < len {
+        data.extend_from_slice(&crate::rng::splitmix64(&mut state).to_le_bytes());
+    }
+    data.truncate(len);
+    data
+}
+
+/// Child side: write random blocks of the dataset forever, printing
+/// `file block round seq` for each one once it is durable.
+pub fn run_writer(args: &Args, round: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = dataset::Manifest::load(&dataset::manifest_path(args))?;
+    let files = manifest
+        .files
+        .iter()
+        .map(|entry| std::fs::OpenOptions::new().write(true).open(&entry.path))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let block_size = args.block_size as u64;
+    let mut rng = StdRng::seed_from_u64(crate::rng::thread_seed(args.seed, round as usize));
+    let mut out = std::io::stdout().lock();
+    for seq in 0u64.. {
+        let file = rng.random_range(0..files.len());
+        let block = rng.random_range(0..manifest.files[file].size / block_size);
+        let data = block_contents(args.block_size, round, seq, file, block);
+        files[file].write_all_at(&data, block * block_size)?;
+        files[file].sync_data()?;
+        writeln!(out, "{file} {block} {round} {seq}")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Parent side: run `rounds` writer children, kill each after a random delay of
+/// up to `max_kill_ms`, and verify all writes acknowledged so far.
+pub fn run(args: &Args, rounds: u32, max_kill_ms: u64) -> Result<status::Outcome, Box<dyn std::error::Error>> {
+    let exe = std::env::current_exe()?;
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let mut acknowledged = Acknowledged::new();
+    let mut total_acks = 0u64;
+    let mut lost = 0usize;
+    let mut torn = 0usize;
+
+    println!("💣 Crash-consistency test: {rounds} rounds, SIGKILL within {max_kill_ms}ms");
+    for round in 0..rounds {
+        let mut child = Command::new(&exe)
+            .arg("--file-prefix")
+            .arg(&args.file_prefix)
+            .arg("--block-size")
+            .arg(args.block_size.to_string())
+            .arg("--seed")
+            .arg(args.seed.to_string())
+            .arg("crash-writer")
+            .arg("--round")
+            .arg(round.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        // Drain acknowledgements while the writer runs so it never blocks on a full pipe
+        let stdout = child.stdout.take().ok_or("crash writer has no stdout")?;
+        let reader = std::thread::spawn(move || {
+            BufReader::new(stdout)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| {
+                    let fields: Vec<u64> = line.split_whitespace().filter_map(|f| f.parse().ok()).collect();
+                    match fields[..] {
+                        [file, block, round, seq] => Some(((file as usize, block), (round as u32, seq))),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        let delay = rng.random_range(1..=max_kill_ms.max(1));
+        std::thread::sleep(Duration::from_millis(delay));
+        // SIGKILL: the writer gets no chance to flush or clean up
+        child.kill()?;
+        child.wait()?;
+
+        let acks = reader.join().map_err(|_| "acknowledgement reader panicked")?;
+        let round_acks = acks.len() as u64;
+        acknowledged.extend(acks);
+        total_acks += round_acks;
+
+        let losses = verify(args, &acknowledged)?;
+        let round_torn = losses.iter().filter(|(_, loss)| matches!(loss, Loss::Torn)).count();
+        println!(
+            "  Round {round}: killed after {delay}ms, {round_acks} acknowledged writes, {} blocks checked, {} lost ({round_torn} torn)",
+            acknowledged.len(),
+            losses.len()
+        );
+        for ((file, block), loss) in losses.iter().take(5) {
+            let kind = match loss {
+                Loss::Stale => "older contents",
+                Loss::Torn => "torn block",
+            };
+            println!("    file {file} block {block}: {kind}");
+        }
+        // Report each loss once; later rounds check the blocks' new writes only
+        for (key, _) in &losses {
+            acknowledged.remove(key);
+        }
+        lost += losses.len();
+        torn += round_torn;
+    }
+
+    println!("\n📊 Crash-Consistency Summary");
+    println!("  Acknowledged writes: {total_acks}");
+    println!("  Lost acknowledged writes: {lost} ({torn} torn)");
+    if lost == 0 {
+        println!("✅ Every acknowledged write survived");
+    } else {
+        println!("❌ Acknowledged writes were lost");
+    }
+    println!("ℹ️  SIGKILL ends the process, not the machine: the page cache survives it, so this checks the");
+    println!("   write and acknowledgement path; pair it with a device-level fault to test power loss");
+
+    Ok(status::Outcome {
+        status: if lost == 0 { status::Status::Success } else { status::Status::IoErrors },
+        details: vec![
+            ("acknowledged_writes", total_acks.to_string()),
+            ("lost_writes", lost.to_string()),
+        ],
+    })
+}
+
+/// Read back every acknowledged block and list those not holding that write or a later one.
+fn verify(args: &Args, acknowledged: &Acknowledged) -> std::io::Result<Vec<((usize, u64), Loss)>> {
+    let manifest = dataset::Manifest::load(&dataset::manifest_path(args))
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    let files = manifest
+        .files
+        .iter()
+        .map(|entry| std::fs::File::open(&entry.path))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let mut buffer = vec![0u8; args.block_size];
+    let mut losses = Vec::new();
+    let mut keys: Vec<_> = acknowledged.keys().copied().collect();
+    keys.sort_unstable();
+    for (file, block) in keys {
+        let expected = acknowledged[&(file, block)];
+        files[file].read_exact_at(&mut buffer, block * args.block_size as u64)?;
+        if &buffer[..8] != MAGIC {
+            losses.push(((file, block), Loss::Stale));
+            continue;
+        }
+        let round = u32::from_le_bytes(buffer[8..12].try_into().unwrap());
+        let seq = u64::from_le_bytes(buffer[12..20].try_into().unwrap());
+        if (round, seq) < expected {
+            losses.push(((file, block), Loss::Stale));
+        } else if buffer != block_contents(args.block_size, round, seq, file, block) {
+            losses.push(((file, block), Loss::Torn));
+        }
+    }
+    Ok(losses)
+}
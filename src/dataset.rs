@@ -0,0 +1,398 @@
+//! Layout of the test files on disk: a flat set of `<prefix>_<i>.dat` files,
+//! the same spread round-robin over the `--target-dir` directories, or a
+//! directory tree below `<prefix>_tree` when `--dirs-depth` is set. A manifest
+//! written at creation time lets `--reuse-existing` check a dataset before using it.
+
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Args;
+
+/// Number of evenly spaced samples hashed into a file's fingerprint.
+const FINGERPRINT_SAMPLES: u64 = 16;
+/// Bytes read at each fingerprint sample.
+const FINGERPRINT_SAMPLE_SIZE: u64 = 4096;
+/// Bytes covered by each checksum of a `--checksums` manifest.
+pub const CHECKSUM_CHUNK: u64 = 1024 * 1024;
+
+/// Path of test file `index`, or the `--device` that stands in for the files.
+///
+/// With a tree, the base-`fanout` digits of `index` pick one directory per
+/// level, least significant first, so consecutive files land in different
+/// top-level directories and every leaf holds an equal share.
+pub fn file_path(args: &Args, index: usize) -> String {
+    if let Some(device) = &args.device {
+        return device.to_string_lossy().into_owned();
+    }
+    if !args.target_dirs.is_empty() {
+        let dir = &args.target_dirs[index % args.target_dirs.len()];
+        return dir.join(format!("{}_{}.dat", base_name(args), index)).to_string_lossy().into_owned();
+    }
+    let Some(root) = tree_root(args) else {
+        return format!("{}_{}.dat", args.file_prefix, index);
+    };
+    let width = (args.dirs_fanout - 1).max(1).to_string().len();
+    let mut path = root;
+    let mut rest = index;
+    for _ in 0..args.dirs_depth {
+        path.push(format!("{:0width$}", rest % args.dirs_fanout));
+        rest /= args.dirs_fanout;
+    }
+    path.push(format!("{}_{}.dat", base_name(args), index));
+    path.to_string_lossy().into_owned()
+}
+
+/// Directories holding the test files: the `--target-dir`s, or else the
+/// directory of `--file-prefix`.
+pub fn data_dirs(args: &Args) -> Vec<PathBuf> {
+    if args.target_dirs.is_empty() {
+        vec![crate::fsutil::target_dir(&args.file_prefix)]
+    } else {
+        args.target_dirs.clone()
+    }
+}
+
+/// Root of the directory tree, `None` for the flat layout.
+pub fn tree_root(args: &Args) -> Option<PathBuf> {
+    (args.dirs_depth > 0).then(|| PathBuf::from(format!("{}_tree", args.file_prefix)))
+}
+
+/// Number of leaf directories the files are spread across (1 for the flat layout).
+pub fn leaf_dirs(args: &Args) -> usize {
+    let leaves = (0..args.dirs_depth).try_fold(1usize, |n, _| n.checked_mul(args.dirs_fanout));
+    leaves.unwrap_or(usize::MAX).min(args.num_files.max(1))
+}
+
+/// Create the directory that will hold `file_path`.
+pub fn create_parent(file_path: &str) -> std::io::Result<()> {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Remove the directory tree once its files are gone. Directories that still
+/// hold something else are left in place.
+pub fn remove_tree(args: &Args) -> std::io::Result<()> {
+    match tree_root(args) {
+        Some(root) if root.is_dir() => remove_empty_dirs(&root),
+        _ => Ok(()),
+    }
+}
+
+fn remove_empty_dirs(dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_empty_dirs(&entry.path())?;
+        }
+    }
+    match std::fs::remove_dir(dir) {
+        Err(e) if e.kind() == std::io::ErrorKind::DirectoryNotEmpty => Ok(()),
+        result => result,
+    }
+}
+
+/// File name part of the prefix, used for the files inside the tree or a target directory.
+fn base_name(args: &Args) -> String {
+    Path::new(&args.file_prefix)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "testfile".to_string())
+}
+
+/// What was created: enough to tell whether files found on disk are the same dataset.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub create_mode: String,
+    /// Bytes per checksum when the manifest was written with `--checksums`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum_chunk: Option<u64>,
+    /// How the files were ordered at creation, unless created 0 to N-1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_order: Option<CreationOrder>,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// `--create-order` of a dataset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreationOrder {
+    pub mode: String,
+    pub seed: u64,
+    /// File indices in the order their creation started
+    pub files: Vec<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    /// FNV-1a hash of sampled file contents, see [`fingerprint`]
+    pub fingerprint: String,
+    /// FNV-1a hash of every `checksum_chunk` bytes of the file, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checksums: Vec<String>,
+}
+
+impl Manifest {
+    /// Describe `file_paths`, with a checksum of every `checksum_chunk` bytes if given.
+    pub fn build(file_paths: &[String], create_mode: &str, checksum_chunk: Option<u64>) -> std::io::Result<Self> {
+        let files = file_paths
+            .iter()
+            .map(|path| {
+                let size = std::fs::metadata(path)?.len();
+                Ok(ManifestEntry {
+                    path: path.clone(),
+                    size,
+                    fingerprint: fingerprint(path, size)?,
+                    checksums: match checksum_chunk {
+                        Some(chunk) => checksums(path, chunk, false)?,
+                        None => Vec::new(),
+                    },
+                })
+            })
+            .collect::<std::io::Result<_>>()?;
+        Ok(Manifest { create_mode: create_mode.to_string(), checksum_chunk, creation_order: None, files })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Where the manifest of the dataset with this prefix is kept.
+pub fn manifest_path(args: &Args) -> PathBuf {
+    PathBuf::from(format!("{}_manifest.json", args.file_prefix))
+}
+
+/// Hash of the file size and `FINGERPRINT_SAMPLES` blocks spread across the file.
+/// Cheap enough to run on every reuse, and catches files that were truncated,
+/// rewritten or created in a different mode.
+pub fn fingerprint(path: &str, size: u64) -> std::io::Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut hash = fnv1a(FNV_OFFSET, &size.to_le_bytes());
+    let mut buffer = vec![0u8; FINGERPRINT_SAMPLE_SIZE as usize];
+    for sample in 0..FINGERPRINT_SAMPLES {
+        let offset = (size * sample / FINGERPRINT_SAMPLES) / FINGERPRINT_SAMPLE_SIZE * FINGERPRINT_SAMPLE_SIZE;
+        let len = FINGERPRINT_SAMPLE_SIZE.min(size.saturating_sub(offset)) as usize;
+        file.read_exact_at(&mut buffer[..len], offset)?;
+        hash = fnv1a(hash, &buffer[..len]);
+    }
+    Ok(format!("{hash:016x}"))
+}
+
+/// Checksums of consecutive `chunk`-byte pieces of the file. With `uncached`,
+/// the file's pages are dropped from the page cache first so the data is read
+/// back from the device rather than from memory.
+pub fn checksums(path: &str, chunk: u64, uncached: bool) -> std::io::Result<Vec<String>> {
+    let file = std::fs::File::open(path)?;
+    if uncached {
+        crate::fsutil::advise_dontneed(&file)?;
+    }
+    let size = file.metadata()?.len();
+    let mut buffer = vec![0u8; chunk.min(size) as usize];
+    let mut sums = Vec::new();
+    let mut offset = 0;
+    while offset < size {
+        let len = chunk.min(size - offset) as usize;
+        file.read_exact_at(&mut buffer[..len], offset)?;
+        sums.push(format!("{:016x}", fnv1a(FNV_OFFSET, &buffer[..len])));
+        offset += len as u64;
+    }
+    Ok(sums)
+}
+
+/// Result of checking one file of the dataset against the manifest.
+pub struct FileCheck {
+    pub path: String,
+    /// Byte ranges whose checksum no longer matches
+    pub bad_chunks: Vec<(u64, u64)>,
+    /// Why the file could not be checked chunk by chunk
+    pub error: Option<String>,
+}
+
+/// Re-read every file listed in the manifest and compare it chunk by chunk.
+/// Manifests without checksums are compared by fingerprint only.
+pub fn verify(manifest: &Manifest) -> Vec<FileCheck> {
+    manifest
+        .files
+        .iter()
+        .map(|entry| {
+            let mut check = FileCheck { path: entry.path.clone(), bad_chunks: Vec::new(), error: None };
+            match std::fs::metadata(&entry.path) {
+                Ok(meta) if meta.len() != entry.size => {
+                    check.error = Some(format!("{} bytes, expected {}", meta.len(), entry.size));
+                    return check;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    check.error = Some(e.to_string());
+                    return check;
+                }
+            }
+            let Some(chunk) = manifest.checksum_chunk else {
+                match fingerprint(&entry.path, entry.size) {
+                    Ok(current) if current == entry.fingerprint => {}
+                    Ok(_) => check.error = Some("fingerprint differs".to_string()),
+                    Err(e) => check.error = Some(e.to_string()),
+                }
+                return check;
+            };
+            match checksums(&entry.path, chunk, true) {
+                Ok(current) => {
+                    check.bad_chunks = current
+                        .iter()
+                        .zip(&entry.checksums)
+                        .enumerate()
+                        .filter(|(_, (current, expected))| current != expected)
+                        .map(|(i, _)| (i as u64 * chunk, ((i as u64 + 1) * chunk).min(entry.size)))
+                        .collect();
+                }
+                Err(e) => check.error = Some(e.to_string()),
+            }
+            check
+        })
+        .collect()
+}
+
+/// One bit flipped by [`inject_corruption`].
+pub struct Corruption {
+    pub path: String,
+    pub offset: u64,
+    pub bit: u8,
+}
+
+/// Flip one random bit in each of a `fraction` of the `block_size` blocks of
+/// every file, chosen from `seed`, so the verifier can be checked against
+/// damage it must find. Returns the flipped bits and the number of blocks.
+pub fn inject_corruption(
+    file_paths: &[String],
+    block_size: u64,
+    fraction: f64,
+    seed: u64,
+) -> std::io::Result<(Vec<Corruption>, u64)> {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut flipped = Vec::new();
+    let mut blocks = 0;
+    for path in file_paths {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+        let size = file.metadata()?.len();
+        for start in (0..size).step_by(block_size as usize) {
+            blocks += 1;
+            if !rng.random_bool(fraction) {
+                continue;
+            }
+            let offset = start + rng.random_range(0..block_size.min(size - start));
+            let bit = rng.random_range(0..8u8);
+            let mut byte = [0u8];
+            file.read_exact_at(&mut byte, offset)?;
+            byte[0] ^= 1 << bit;
+            file.write_all_at(&byte, offset)?;
+            flipped.push(Corruption { path: path.clone(), offset, bit });
+        }
+        file.sync_data()?;
+    }
+    Ok((flipped, blocks))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// How an existing dataset was checked before reuse.
+pub enum Reuse {
+    /// No test files were found; they have to be created
+    Missing,
+    /// The files match the manifest, including their fingerprints
+    Verified(Vec<String>),
+    /// Count and sizes match, but there is no manifest to compare contents against
+    Unverified(Vec<String>),
+}
+
+/// Check the test files found on disk against what this run expects: every file
+/// present with its size, and when a manifest exists, the same files, creation
+/// mode and content fingerprints. Any mismatch is an error rather than a
+/// benchmark of a stale or truncated dataset.
+pub fn check_existing(args: &Args, create_mode: &str) -> Result<Reuse, String> {
+    let paths: Vec<String> = (0..args.num_files).map(|i| file_path(args, i)).collect();
+    let mut present = 0;
+    let mut problems = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        match std::fs::metadata(path) {
+            Ok(meta) => {
+                present += 1;
+                if meta.len() != args.file_size_of(i) {
+                    problems.push(format!("{path} has {} bytes, expected {}", meta.len(), args.file_size_of(i)));
+                }
+            }
+            Err(_) => problems.push(format!("{path} is missing")),
+        }
+    }
+    let manifest_path = manifest_path(args);
+    if present == 0 && !manifest_path.exists() {
+        return Ok(Reuse::Missing);
+    }
+    if !problems.is_empty() {
+        return Err(mismatch(&problems));
+    }
+
+    let manifest = match Manifest::load(&manifest_path) {
+        Ok(manifest) => manifest,
+        Err(_) if !manifest_path.exists() => return Ok(Reuse::Unverified(paths)),
+        Err(e) => return Err(format!("cannot read {}: {e}", manifest_path.display())),
+    };
+    if manifest.create_mode != create_mode {
+        problems.push(format!("files were created with --create-mode {}, this run uses {create_mode}", manifest.create_mode));
+    }
+    if manifest.files.len() != paths.len() {
+        problems.push(format!("manifest lists {} files, this run uses {}", manifest.files.len(), paths.len()));
+    }
+    for (i, (entry, path)) in manifest.files.iter().zip(&paths).enumerate() {
+        if entry.path != *path {
+            problems.push(format!("manifest lists {}, this run expects {path}", entry.path));
+            continue;
+        }
+        let size = args.file_size_of(i);
+        if entry.size != size {
+            problems.push(format!("{path} was created with {} bytes, this run expects {size}", entry.size));
+            continue;
+        }
+        match fingerprint(path, size) {
+            Ok(current) if current == entry.fingerprint => {}
+            Ok(_) => problems.push(format!("{path} content changed since it was created")),
+            Err(e) => problems.push(format!("cannot read {path}: {e}")),
+        }
+    }
+    if problems.is_empty() { Ok(Reuse::Verified(paths)) } else { Err(mismatch(&problems)) }
+}
+
+fn mismatch(problems: &[String]) -> String {
+    const SHOWN: usize = 5;
+    let mut message = String::from("existing dataset does not match this run (--reuse-existing):");
+    for problem in problems.iter().take(SHOWN) {
+        message.push_str("\n  ");
+        message.push_str(problem);
+    }
+    if problems.len() > SHOWN {
+        message.push_str(&format!("\n  ... and {} more", problems.len() - SHOWN));
+    }
+    message.push_str("\nRemove the files or drop --reuse-existing to recreate them");
+    message
+}
@@ -0,0 +1,122 @@
+//! `--metadata-churn`: grow a directory tree of empty files next to the test
+//! files and keep looking its entries up while the readers run, pressuring the
+//! dentry and inode caches. The churn runs in one-second bursts with one-second
+//! pauses, so reads during bursts can be compared with reads between them.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{ReadResult, calculate_statistics, print_statistics};
+
+/// Length of each burst and of each pause between bursts.
+const PERIOD: Duration = Duration::from_secs(1);
+/// Entries per directory of the tree.
+const FANOUT: u64 = 1024;
+
+/// What the churn thread did.
+pub struct Summary {
+    pub created: u64,
+    pub lookups: u64,
+    /// Bursts as (start, end), relative to the start of the measured phase
+    pub bursts: Vec<(Duration, Duration)>,
+}
+
+/// The running churn thread and its tree.
+pub struct MetadataChurner {
+    root: PathBuf,
+    stopped: Arc<AtomicBool>,
+    /// `None` once finished
+    handle: Option<JoinHandle<std::io::Result<Summary>>>,
+}
+
+fn entry(root: &std::path::Path, index: u64) -> PathBuf {
+    root.join(format!("d{}", index / FANOUT)).join(format!("f{}", index % FANOUT))
+}
+
+/// Create the tree under `root`, a new directory, up to `entries` files, then
+/// stat them round-robin. `run_start` is the start of the measured phase.
+pub fn start(root: PathBuf, entries: u64, run_start: Instant) -> std::io::Result<MetadataChurner> {
+    std::fs::create_dir(&root)?;
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stop = Arc::clone(&stopped);
+    let tree = root.clone();
+    let handle = std::thread::spawn(move || {
+        let (mut created, mut lookups) = (0u64, 0u64);
+        let mut bursts = Vec::new();
+        while !stop.load(Ordering::Relaxed) {
+            let begin = Instant::now();
+            while begin.elapsed() < PERIOD && !stop.load(Ordering::Relaxed) {
+                if created < entries {
+                    if created % FANOUT == 0 {
+                        std::fs::create_dir(tree.join(format!("d{}", created / FANOUT)))?;
+                    }
+                    File::create(entry(&tree, created))?;
+                    created += 1;
+                } else {
+                    std::fs::symlink_metadata(entry(&tree, lookups % entries))?;
+                    lookups += 1;
+                }
+            }
+            bursts.push((begin.saturating_duration_since(run_start), run_start.elapsed()));
+            let pause = Instant::now();
+            while pause.elapsed() < PERIOD && !stop.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        Ok(Summary { created, lookups, bursts })
+    });
+    Ok(MetadataChurner { root, stopped, handle: Some(handle) })
+}
+
+impl MetadataChurner {
+    /// Stop, remove the tree and return what was done.
+    pub fn finish(mut self) -> std::io::Result<Summary> {
+        self.stopped.store(true, Ordering::Relaxed);
+        let handle = self.handle.take().expect("metadata churn finished twice");
+        let summary = handle.join().expect("metadata churn thread panicked");
+        std::fs::remove_dir_all(&self.root)?;
+        summary
+    }
+}
+
+/// Stops the thread and removes the tree when the run ends before `finish`.
+impl Drop for MetadataChurner {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stopped.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+}
+
+pub fn report(summary: &Summary, results: &[ReadResult]) {
+    let busy: f64 = summary.bursts.iter().map(|(begin, end)| (*end - *begin).as_secs_f64()).sum();
+    println!(
+        "\n🗃️  Metadata churn: created {} files in {} directories and looked up {} ({:.0} operations/s over {} bursts)",
+        summary.created,
+        summary.created.div_ceil(FANOUT),
+        summary.lookups,
+        (summary.created + summary.lookups) as f64 / busy.max(f64::EPSILON),
+        summary.bursts.len()
+    );
+    let in_burst = |r: &&ReadResult| summary.bursts.iter().any(|&(begin, end)| r.timestamp >= begin && r.timestamp < end);
+    let (during, between): (Vec<&ReadResult>, Vec<&ReadResult>) = results.iter().partition(in_burst);
+    let mut p99 = Vec::new();
+    for (label, reads) in [("Reads during bursts", during), ("Reads between bursts", between)] {
+        if reads.is_empty() {
+            continue;
+        }
+        println!("\n  {label}:");
+        let stats = calculate_statistics(reads.iter().map(|r| &r.latency).collect());
+        p99.push(stats.p99);
+        print_statistics(stats);
+    }
+    if let [during, between] = p99[..] {
+        println!("\n  p99 during bursts: {:+.1}% against between bursts", 100.0 * (during.as_secs_f64() / between.as_secs_f64().max(f64::EPSILON) - 1.0));
+    }
+}
@@ -0,0 +1,304 @@
+//! Discovery of the block device backing the test files, via sysfs.
+
+use std::path::{Path, PathBuf};
+
+use crate::fsutil::{self, Extent};
+
+/// Zone model reported by the kernel for a zoned block device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneModel {
+    HostAware,
+    HostManaged,
+}
+
+impl std::fmt::Display for ZoneModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZoneModel::HostAware => write!(f, "host-aware"),
+            ZoneModel::HostManaged => write!(f, "host-managed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ZonedInfo {
+    pub model: ZoneModel,
+    pub zone_size: u64,
+    pub nr_zones: u64,
+}
+
+/// A block device (or partition) as seen through `/sys/dev/block`.
+#[derive(Debug, Clone)]
+pub struct BlockDevice {
+    /// Kernel name, e.g. `nvme0n1p2`.
+    pub name: String,
+    /// sysfs directory of the whole disk, which holds the `queue/` attributes.
+    pub disk_sysfs: PathBuf,
+    /// Byte offset of the partition on the disk, 0 for whole disks.
+    pub start_bytes: u64,
+}
+
+impl BlockDevice {
+    /// Read a `queue/<name>` attribute of the underlying disk.
+    pub fn queue_attr(&self, name: &str) -> Option<String> {
+        read_attr(&self.disk_sysfs.join("queue").join(name))
+    }
+
+    /// Overwrite a `queue/<name>` attribute of the underlying disk (usually needs root).
+    pub fn set_queue_attr(&self, name: &str, value: &str) -> std::io::Result<()> {
+        std::fs::write(self.disk_sysfs.join("queue").join(name), value)
+    }
+
+    /// Smallest unit the device addresses, in bytes (512 if unknown).
+    pub fn logical_block_size(&self) -> u64 {
+        self.queue_attr("logical_block_size").and_then(|v| v.parse().ok()).unwrap_or(512)
+    }
+
+    /// Smallest unit the device writes without a read-modify-write, in bytes.
+    pub fn physical_block_size(&self) -> u64 {
+        self.queue_attr("physical_block_size").and_then(|v| v.parse().ok()).unwrap_or_else(|| self.logical_block_size())
+    }
+
+    /// Device readahead window in KiB.
+    pub fn read_ahead_kb(&self) -> Option<u64> {
+        self.queue_attr("read_ahead_kb")?.parse().ok()
+    }
+
+    /// Temperature of the disk in °C from its hwmon sensor (NVMe, or SATA with
+    /// the drivetemp module), if it has one.
+    pub fn temperature(&self) -> Option<f64> {
+        let device = self.disk_sysfs.join("device");
+        // NVMe controllers hold `hwmonN` directly, drivetemp under `hwmon/`
+        [device.clone(), device.join("hwmon")]
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("hwmon"))
+            .find_map(|entry| read_attr(&entry.path().join("temp1_input"))?.parse::<f64>().ok())
+            .map(|millidegrees| millidegrees / 1000.0)
+    }
+
+    /// Sectors of 512 bytes the kernel has written to the disk since boot, from its `stat`.
+    pub fn sectors_written(&self) -> Option<u64> {
+        read_attr(&self.disk_sysfs.join("stat"))?.split_whitespace().nth(6)?.parse().ok()
+    }
+
+    /// Bytes the NVMe controller has received from hosts over its lifetime: the
+    /// SMART log's "data units written", in units of 512,000 bytes. Usually needs root.
+    #[cfg(target_os = "linux")]
+    pub fn nvme_bytes_written(&self) -> std::io::Result<u128> {
+        use std::os::unix::io::AsRawFd;
+
+        #[repr(C)]
+        #[derive(Default)]
+        struct AdminCommand {
+            opcode: u8,
+            flags: u8,
+            rsvd1: u16,
+            nsid: u32,
+            cdw2: u32,
+            cdw3: u32,
+            metadata: u64,
+            addr: u64,
+            metadata_len: u32,
+            data_len: u32,
+            cdw10: u32,
+            cdw11: u32,
+            cdw12: u32,
+            cdw13: u32,
+            cdw14: u32,
+            cdw15: u32,
+            timeout_ms: u32,
+            result: u32,
+        }
+        // _IOWR('N', 0x41, struct nvme_admin_cmd)
+        const NVME_IOCTL_ADMIN_CMD: libc::c_ulong = 0xC048_4E41;
+        const GET_LOG_PAGE: u8 = 0x02;
+        const SMART_LOG: u32 = 0x02;
+        const DATA_UNIT: u128 = 512_000;
+
+        let disk = self.disk_sysfs.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if !disk.starts_with("nvme") {
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, format!("{disk} is not an NVMe namespace")));
+        }
+        let node = std::fs::File::open(format!("/dev/{disk}"))?;
+        let mut log = [0u8; 512];
+        let mut command = AdminCommand {
+            opcode: GET_LOG_PAGE,
+            nsid: u32::MAX,
+            addr: log.as_mut_ptr() as u64,
+            data_len: log.len() as u32,
+            // Number of dwords minus one, and the log page
+            cdw10: ((log.len() as u32 / 4 - 1) << 16) | SMART_LOG,
+            ..Default::default()
+        };
+        let ret = unsafe { libc::ioctl(node.as_raw_fd(), NVME_IOCTL_ADMIN_CMD as _, &mut command) };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if ret > 0 {
+            return Err(std::io::Error::other(format!("NVMe status {ret:#x}")));
+        }
+        let units = u128::from_le_bytes(log[48..64].try_into().expect("16 bytes"));
+        Ok(units * DATA_UNIT)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn nvme_bytes_written(&self) -> std::io::Result<u128> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "NVMe admin commands need Linux"))
+    }
+
+    /// Zone information if the disk is a zoned (SMR/ZNS) device.
+    pub fn zoned(&self) -> Option<ZonedInfo> {
+        let model = match self.queue_attr("zoned")?.as_str() {
+            "host-aware" => ZoneModel::HostAware,
+            "host-managed" => ZoneModel::HostManaged,
+            _ => return None,
+        };
+        let zone_sectors: u64 = self.queue_attr("chunk_sectors")?.parse().ok()?;
+        let nr_zones = self
+            .queue_attr("nr_zones")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        Some(ZonedInfo {
+            model,
+            zone_size: zone_sectors * 512,
+            nr_zones,
+        })
+    }
+}
+
+/// Find the block device holding `path`, if it lives on a real block device.
+#[cfg(target_os = "linux")]
+pub fn block_device_for(path: &Path) -> std::io::Result<BlockDevice> {
+    use std::os::unix::fs::MetadataExt;
+
+    from_dev(std::fs::metadata(path)?.dev())
+}
+
+/// The block device `path` is the device node of, e.g. `/dev/nvme0n1`.
+#[cfg(target_os = "linux")]
+pub fn block_device_node(path: &Path) -> std::io::Result<BlockDevice> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.file_type().is_block_device() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a block device"));
+    }
+    from_dev(metadata.rdev())
+}
+
+#[cfg(target_os = "linux")]
+fn from_dev(dev: u64) -> std::io::Result<BlockDevice> {
+    let (major, minor) = (libc::major(dev), libc::minor(dev));
+    let sysfs = std::fs::canonicalize(format!("/sys/dev/block/{major}:{minor}"))?;
+    let name = sysfs
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let is_partition = sysfs.join("partition").exists();
+    let (disk_sysfs, start_bytes) = if is_partition {
+        let start_sectors: u64 = read_attr(&sysfs.join("start"))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let disk = sysfs.parent().map(Path::to_path_buf).unwrap_or(sysfs.clone());
+        (disk, start_sectors * 512)
+    } else {
+        (sysfs, 0)
+    };
+
+    Ok(BlockDevice {
+        name,
+        disk_sysfs,
+        start_bytes,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn block_device_for(_path: &Path) -> std::io::Result<BlockDevice> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "block device discovery requires sysfs",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn block_device_node(_path: &Path) -> std::io::Result<BlockDevice> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "block device discovery requires sysfs",
+    ))
+}
+
+/// Restores a device's readahead window when dropped.
+pub struct ReadaheadGuard {
+    device: BlockDevice,
+    original_kb: u64,
+}
+
+impl ReadaheadGuard {
+    /// Set the device readahead to `kb`, remembering the current value.
+    pub fn set(device: &BlockDevice, kb: u64) -> std::io::Result<Self> {
+        let original_kb = device.read_ahead_kb().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "device has no read_ahead_kb attribute")
+        })?;
+        device.set_queue_attr("read_ahead_kb", &kb.to_string())?;
+        Ok(ReadaheadGuard {
+            device: device.clone(),
+            original_kb,
+        })
+    }
+}
+
+impl Drop for ReadaheadGuard {
+    fn drop(&mut self) {
+        let _ = self
+            .device
+            .set_queue_attr("read_ahead_kb", &self.original_kb.to_string());
+    }
+}
+
+fn read_attr(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Maps logical file offsets to the device zone they are stored in.
+#[derive(Debug)]
+pub struct ZoneMap {
+    zone_size: u64,
+    partition_start: u64,
+    files: Vec<Vec<Extent>>,
+}
+
+impl ZoneMap {
+    /// Build the map from the physical extents of every test file.
+    pub fn build(
+        file_paths: &[String],
+        zone_size: u64,
+        partition_start: u64,
+    ) -> std::io::Result<Self> {
+        let files = file_paths
+            .iter()
+            .map(|p| fsutil::file_extents(Path::new(p)))
+            .collect::<Result<_, _>>()?;
+        Ok(ZoneMap {
+            zone_size,
+            partition_start,
+            files,
+        })
+    }
+
+    /// Zone index holding byte `offset` of file `file_index`, if it is mapped.
+    pub fn zone_of(&self, file_index: usize, offset: u64) -> Option<u64> {
+        let extents = self.files.get(file_index)?;
+        let idx = extents.partition_point(|e| e.logical <= offset);
+        let extent = extents.get(idx.checked_sub(1)?)?;
+        if offset >= extent.logical + extent.length {
+            return None;
+        }
+        let physical = self.partition_start + extent.physical + (offset - extent.logical);
+        Some(physical / self.zone_size)
+    }
+}
@@ -0,0 +1,531 @@
+//! Distributions of block indices within a file.
+
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use rand::{Rng, RngCore};
+
+/// Picks the block a read lands on within a file. Implementations are shared by
+/// all reader threads; each passes its own random stream.
+pub trait Distribution: std::fmt::Debug + std::fmt::Display + Send + Sync {
+    /// A block index in `0..max_blocks`; `max_blocks` is positive.
+    fn next_block(&self, rng: &mut dyn RngCore, max_blocks: u64) -> u64;
+
+    /// Whether every block is equally likely, so there is no hot spot.
+    fn is_uniform(&self) -> bool {
+        false
+    }
+}
+
+/// Builds a distribution from its full `--distribution` spec, e.g. `pareto:1.2`.
+pub type Factory = fn(&str) -> Result<Arc<dyn Distribution>, String>;
+
+/// Distributions by the name before the first `:` of their spec.
+static REGISTRY: RwLock<Vec<(String, Factory)>> = RwLock::new(Vec::new());
+
+/// Make `--distribution <name>[:<params>]` build its distribution with `factory`,
+/// replacing an earlier registration of `name`. Register before parsing the options.
+pub fn register(name: &str, factory: Factory) {
+    let mut registry = REGISTRY.write().unwrap();
+    registry.retain(|(registered, _)| registered != name);
+    registry.push((name.to_string(), factory));
+}
+
+/// Register the distributions of [`AccessDistribution`].
+pub fn register_builtins() {
+    for name in ["uniform", "normal", "pareto", "file"] {
+        register(name, |spec| AccessDistribution::parse(spec).map(|d| Arc::new(d) as Arc<dyn Distribution>));
+    }
+}
+
+/// The `--distribution` of a run, shared by its reader threads.
+#[derive(Debug, Clone)]
+pub struct SharedDistribution(pub Arc<dyn Distribution>);
+
+impl SharedDistribution {
+    /// Build the registered distribution named by `spec`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let name = spec.split(':').next().unwrap_or_default();
+        let registry = REGISTRY.read().unwrap();
+        match registry.iter().find(|(registered, _)| registered == name) {
+            Some((_, factory)) => factory(spec).map(SharedDistribution),
+            None => Err(format!(
+                "unknown distribution '{spec}' (registered: {})",
+                registry.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+impl std::ops::Deref for SharedDistribution {
+    type Target = dyn Distribution;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl std::fmt::Display for SharedDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Where reads land within a file: the built-in distributions.
+#[derive(Debug, Clone)]
+pub enum AccessDistribution {
+    /// Every block equally likely
+    Uniform,
+    /// Weighted buckets loaded from a histogram or CDF file
+    Empirical(EmpiricalDistribution),
+    /// Gaussian around block `mean`, truncated to the file
+    Normal { mean: f64, stddev: f64 },
+    /// Pareto with shape `alpha`: block 0 is hottest, heavier tail for smaller `alpha`
+    Pareto { alpha: f64 },
+}
+
+/// How reads are spread over the files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileWeights {
+    /// Every file equally likely
+    Uniform,
+    /// File i has weight 1 / (i + 1)^exponent: file 0 is the hottest
+    Zipf { exponent: f64, cumulative: Vec<f64> },
+    /// One weight per file
+    Explicit { weights: Vec<f64>, cumulative: Vec<f64> },
+}
+
+impl FileWeights {
+    /// Parse `uniform`, `zipf[:<exponent>]` (default exponent 1) or `explicit:<w0>,<w1>,...`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if spec == "uniform" {
+            return Ok(FileWeights::Uniform);
+        }
+        if let Some(rest) = spec.strip_prefix("zipf") {
+            let exponent = match rest.strip_prefix(':') {
+                Some(exponent) => parse_param(spec, exponent)?,
+                None if rest.is_empty() => 1.0,
+                None => return Err(format!("'{spec}': expected zipf or zipf:<exponent>")),
+            };
+            if exponent < 0.0 {
+                return Err(format!("'{spec}': the exponent must be >= 0"));
+            }
+            return Ok(FileWeights::Zipf {
+                exponent,
+                cumulative: Vec::new(),
+            });
+        }
+        if let Some(list) = spec.strip_prefix("explicit:") {
+            let weights = list.split(',').map(|w| parse_param(spec, w)).collect::<Result<Vec<_>, _>>()?;
+            if weights.iter().any(|&w| w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+                return Err(format!("'{spec}': weights must be >= 0 and not all 0"));
+            }
+            return Ok(FileWeights::Explicit {
+                weights,
+                cumulative: Vec::new(),
+            });
+        }
+        Err(format!("unknown file weights '{spec}' (expected uniform, zipf[:<exponent>] or explicit:<w0>,<w1>,...)"))
+    }
+
+    /// Precompute the cumulative weights for `num_files` files.
+    pub fn resolve(&mut self, num_files: usize) -> Result<(), String> {
+        let weights: Vec<f64> = match self {
+            FileWeights::Uniform => return Ok(()),
+            FileWeights::Zipf { exponent, .. } => (0..num_files).map(|i| 1.0 / ((i + 1) as f64).powf(*exponent)).collect(),
+            FileWeights::Explicit { weights, .. } => {
+                if weights.len() != num_files {
+                    return Err(format!("--file-weights lists {} weights for {num_files} files", weights.len()));
+                }
+                weights.clone()
+            }
+        };
+        let resolved: Vec<f64> = weights
+            .iter()
+            .scan(0.0, |sum, w| {
+                *sum += w;
+                Some(*sum)
+            })
+            .collect();
+        match self {
+            FileWeights::Zipf { cumulative, .. } | FileWeights::Explicit { cumulative, .. } => *cumulative = resolved,
+            FileWeights::Uniform => {}
+        }
+        Ok(())
+    }
+
+    /// Draw a file index in `0..num_files`.
+    pub fn pick(&self, rng: &mut impl Rng, num_files: usize) -> usize {
+        match self {
+            FileWeights::Zipf { cumulative, .. } | FileWeights::Explicit { cumulative, .. } if !cumulative.is_empty() => {
+                let target = rng.random::<f64>() * cumulative[cumulative.len() - 1];
+                cumulative.partition_point(|&c| c <= target).min(cumulative.len() - 1)
+            }
+            _ => rng.random_range(0..num_files),
+        }
+    }
+
+    /// Fraction of reads expected to go to `file`.
+    pub fn share(&self, file: usize, num_files: usize) -> f64 {
+        match self {
+            FileWeights::Zipf { cumulative, .. } | FileWeights::Explicit { cumulative, .. } if file < cumulative.len() => {
+                let below = if file == 0 { 0.0 } else { cumulative[file - 1] };
+                (cumulative[file] - below) / cumulative[cumulative.len() - 1]
+            }
+            _ => 1.0 / num_files as f64,
+        }
+    }
+}
+
+impl std::fmt::Display for FileWeights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileWeights::Uniform => write!(f, "uniform"),
+            FileWeights::Zipf { exponent, .. } => write!(f, "zipf (exponent {exponent})"),
+            FileWeights::Explicit { weights, .. } => write!(f, "explicit ({} weights)", weights.len()),
+        }
+    }
+}
+
+/// Order in which a thread walks the blocks of the dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPattern {
+    /// Blocks drawn from `--distribution`
+    Random,
+    /// Fixed step in blocks through the concatenated files: 1 is forward
+    /// sequential, -1 reverse sequential
+    Stride(i64),
+}
+
+impl AccessPattern {
+    /// Parse `random`, `sequential`, `reverse` or `stride:<blocks>` (negative strides go backwards).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec {
+            "random" => Ok(AccessPattern::Random),
+            "sequential" => Ok(AccessPattern::Stride(1)),
+            "reverse" => Ok(AccessPattern::Stride(-1)),
+            _ => {
+                let stride = spec
+                    .strip_prefix("stride:")
+                    .and_then(|n| n.trim().parse::<i64>().ok())
+                    .ok_or_else(|| format!("unknown pattern '{spec}' (expected random, sequential, reverse or stride:<blocks>)"))?;
+                if stride == 0 {
+                    return Err(format!("'{spec}': the stride must not be 0"));
+                }
+                Ok(AccessPattern::Stride(stride))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AccessPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessPattern::Random => write!(f, "random"),
+            AccessPattern::Stride(1) => write!(f, "sequential"),
+            AccessPattern::Stride(-1) => write!(f, "reverse sequential"),
+            AccessPattern::Stride(n) => write!(f, "stride of {n} blocks"),
+        }
+    }
+}
+
+/// A thread's position in a strided walk over `total_blocks` blocks.
+#[derive(Debug, Clone, Copy)]
+pub struct StrideCursor {
+    position: u64,
+    stride: i64,
+    total_blocks: u64,
+}
+
+impl StrideCursor {
+    /// Start thread `thread_id` of `num_threads` at the beginning of its share of the
+    /// dataset, or at the end of it when walking backwards.
+    pub fn new(stride: i64, thread_id: usize, num_threads: usize, total_blocks: u64) -> Self {
+        let share = |t: usize| (total_blocks as u128 * t as u128 / num_threads as u128) as u64;
+        let position = if stride > 0 {
+            share(thread_id)
+        } else {
+            share(thread_id + 1).saturating_sub(1)
+        };
+        StrideCursor {
+            position: position.min(total_blocks - 1),
+            stride,
+            total_blocks,
+        }
+    }
+
+    /// The current block index, then step, wrapping around the dataset.
+    pub fn advance(&mut self) -> u64 {
+        let current = self.position;
+        let step = (self.stride.unsigned_abs() % self.total_blocks) as u128;
+        let total = self.total_blocks as u128;
+        let next = if self.stride > 0 {
+            (current as u128 + step) % total
+        } else {
+            (current as u128 + total - step) % total
+        };
+        self.position = next as u64;
+        current
+    }
+}
+
+/// Redraws before a truncated distribution falls back to clamping.
+const MAX_REDRAWS: usize = 64;
+
+impl AccessDistribution {
+    /// Parse `uniform`, `normal:<mean>,<stddev>`, `pareto:<alpha>` or `file:<path>`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if spec == "uniform" {
+            return Ok(AccessDistribution::Uniform);
+        }
+        if let Some(params) = spec.strip_prefix("normal:") {
+            let (mean, stddev) = params
+                .split_once(',')
+                .ok_or_else(|| format!("'{spec}': expected normal:<mean>,<stddev> in blocks"))?;
+            let mean = parse_param(spec, mean)?;
+            let stddev = parse_param(spec, stddev)?;
+            if mean < 0.0 || stddev <= 0.0 {
+                return Err(format!("'{spec}': mean must be >= 0 and stddev > 0"));
+            }
+            return Ok(AccessDistribution::Normal { mean, stddev });
+        }
+        if let Some(alpha) = spec.strip_prefix("pareto:") {
+            let alpha = parse_param(spec, alpha)?;
+            if alpha <= 0.0 {
+                return Err(format!("'{spec}': alpha must be > 0"));
+            }
+            return Ok(AccessDistribution::Pareto { alpha });
+        }
+        if let Some(path) = spec.strip_prefix("file:") {
+            return EmpiricalDistribution::load(Path::new(path)).map(AccessDistribution::Empirical);
+        }
+        Err(format!(
+            "unknown distribution '{spec}' (expected uniform, normal:<mean>,<stddev>, pareto:<alpha> or file:<path>)"
+        ))
+    }
+}
+
+impl Distribution for AccessDistribution {
+    fn next_block(&self, rng: &mut dyn RngCore, max_blocks: u64) -> u64 {
+        match self {
+            AccessDistribution::Uniform => rng.random_range(0..max_blocks),
+            AccessDistribution::Empirical(empirical) => empirical.sample(rng, max_blocks),
+            AccessDistribution::Normal { mean, stddev } => truncated(max_blocks, || {
+                // Box-Muller; 1 - u keeps the logarithm finite
+                let u: f64 = rng.random();
+                let v: f64 = rng.random();
+                mean + stddev * (-2.0 * (1.0 - u).ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+            }),
+            AccessDistribution::Pareto { alpha } => truncated(max_blocks, || {
+                // Inverse CDF with scale 1, shifted so the smallest value lands on block 0
+                let u: f64 = rng.random();
+                (1.0 - u).powf(-1.0 / alpha) - 1.0
+            }),
+        }
+    }
+
+    fn is_uniform(&self) -> bool {
+        matches!(self, AccessDistribution::Uniform)
+    }
+}
+
+/// Parse a drift rate such as `1%/s` or `5%/min` into a fraction of the file per second.
+pub fn parse_rate(spec: &str) -> Result<f64, String> {
+    let (percent, per) = spec
+        .split_once("%/")
+        .ok_or_else(|| format!("'{spec}': expected <percent>%/s or <percent>%/min"))?;
+    let seconds = match per {
+        "s" => 1.0,
+        "min" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("'{spec}': unknown time unit '{per}' (expected s, min or h)")),
+    };
+    let percent = parse_param(spec, percent)?;
+    if percent <= 0.0 {
+        return Err(format!("'{spec}': rate must be positive"));
+    }
+    Ok(percent / 100.0 / seconds)
+}
+
+fn parse_param(spec: &str, value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("'{spec}': '{value}' is not a number"))
+}
+
+/// Redraw until a value falls within `0..max_blocks`, clamping the last draw if none does.
+fn truncated(max_blocks: u64, mut draw: impl FnMut() -> f64) -> u64 {
+    let mut value = draw();
+    for _ in 0..MAX_REDRAWS {
+        if value >= 0.0 && value < max_blocks as f64 {
+            break;
+        }
+        value = draw();
+    }
+    (value.max(0.0) as u64).min(max_blocks - 1)
+}
+
+impl std::fmt::Display for AccessDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessDistribution::Uniform => write!(f, "uniform"),
+            AccessDistribution::Empirical(e) => write!(f, "file:{} ({} buckets)", e.source, e.starts.len()),
+            AccessDistribution::Normal { mean, stddev } => write!(f, "normal (mean block {mean}, stddev {stddev} blocks)"),
+            AccessDistribution::Pareto { alpha } => write!(f, "pareto (alpha {alpha})"),
+        }
+    }
+}
+
+/// Positions of bucket boundaries in an empirical distribution file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    /// Fractions of the file, 0.0..1.0
+    Relative,
+    /// Absolute block indices
+    Block,
+}
+
+/// A piecewise-uniform distribution: buckets with weights, uniform within each bucket.
+///
+/// File format, one bucket per line, `#` starts a comment:
+///
+/// ```text
+/// # unit: relative      (default; positions are fractions of the file)
+/// # unit: block         (positions are block indices)
+/// # type: histogram     (default; second column is the bucket's weight)
+/// # type: cdf           (second column is the cumulative probability)
+/// 0.00 10
+/// 0.25 1
+/// ```
+///
+/// Each bucket extends to the next line's position, the last one to the end of
+/// the file. A single column of weights describes equal-width buckets.
+#[derive(Debug, Clone)]
+pub struct EmpiricalDistribution {
+    source: String,
+    unit: Unit,
+    /// Bucket start positions, ascending
+    starts: Vec<f64>,
+    /// Running sum of bucket weights, same length as `starts`
+    cumulative: Vec<f64>,
+}
+
+impl EmpiricalDistribution {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Self::parse(&text, &path.display().to_string())
+    }
+
+    fn parse(text: &str, source: &str) -> Result<Self, String> {
+        let mut unit = Unit::Relative;
+        let mut is_cdf = false;
+        let mut rows: Vec<(Option<f64>, f64)> = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                match comment.trim().split_once(':').map(|(k, v)| (k.trim(), v.trim())) {
+                    Some(("unit", "relative")) => unit = Unit::Relative,
+                    Some(("unit", "block")) => unit = Unit::Block,
+                    Some(("type", "histogram")) => is_cdf = false,
+                    Some(("type", "cdf")) => is_cdf = true,
+                    _ => {}
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let values: Vec<f64> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<f64>().map_err(|e| format!("{source}:{}: {e}", number + 1)))
+                .collect::<Result<_, _>>()?;
+            match values.as_slice() {
+                [weight] => rows.push((None, *weight)),
+                [position, weight] => rows.push((Some(*position), *weight)),
+                _ => return Err(format!("{source}:{}: expected 1 or 2 columns", number + 1)),
+            }
+        }
+
+        if rows.is_empty() {
+            return Err(format!("{source}: no buckets"));
+        }
+        let positioned = rows.iter().filter(|(p, _)| p.is_some()).count();
+        if positioned != 0 && positioned != rows.len() {
+            return Err(format!("{source}: either all lines or none must have a position"));
+        }
+
+        let starts: Vec<f64> = if positioned == 0 {
+            if unit == Unit::Block {
+                (0..rows.len()).map(|i| i as f64).collect()
+            } else {
+                (0..rows.len()).map(|i| i as f64 / rows.len() as f64).collect()
+            }
+        } else {
+            rows.iter().map(|(p, _)| p.unwrap_or_default()).collect()
+        };
+        if starts.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(format!("{source}: positions must be strictly increasing"));
+        }
+        if unit == Unit::Relative && starts.iter().any(|&p| !(0.0..1.0).contains(&p)) {
+            return Err(format!("{source}: relative positions must lie in [0, 1)"));
+        }
+
+        let mut weights: Vec<f64> = rows.iter().map(|(_, w)| *w).collect();
+        if is_cdf {
+            let mut previous = 0.0;
+            for value in &mut weights {
+                let cumulative = *value;
+                *value = cumulative - previous;
+                previous = cumulative;
+            }
+        }
+        if weights.iter().any(|&w| w < 0.0 || !w.is_finite()) {
+            return Err(format!("{source}: weights must be non-negative (CDFs non-decreasing)"));
+        }
+        let cumulative: Vec<f64> = weights
+            .iter()
+            .scan(0.0, |sum, w| {
+                *sum += w;
+                Some(*sum)
+            })
+            .collect();
+        if cumulative.last().copied().unwrap_or(0.0) <= 0.0 {
+            return Err(format!("{source}: total weight is zero"));
+        }
+
+        Ok(EmpiricalDistribution {
+            source: source.to_string(),
+            unit,
+            starts,
+            cumulative,
+        })
+    }
+
+    fn sample(&self, rng: &mut (impl Rng + ?Sized), max_blocks: u64) -> u64 {
+        let total = *self.cumulative.last().expect("at least one bucket");
+        let target = rng.random::<f64>() * total;
+        let bucket = self
+            .cumulative
+            .partition_point(|&c| c <= target)
+            .min(self.cumulative.len() - 1);
+
+        let end_of_file = match self.unit {
+            Unit::Relative => 1.0,
+            Unit::Block => max_blocks as f64,
+        };
+        let start = self.starts[bucket];
+        let end = self.starts.get(bucket + 1).copied().unwrap_or(end_of_file).max(start);
+        let position = start + rng.random::<f64>() * (end - start);
+
+        let block = match self.unit {
+            Unit::Relative => (position * max_blocks as f64) as u64,
+            Unit::Block => position as u64,
+        };
+        block.min(max_blocks - 1)
+    }
+}
@@ -0,0 +1,783 @@
+//! The scheduling core shared by all engines: reader threads, block selection,
+//! first-read tracking and result collection.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crossbeam::sync::WaitGroup;
+use rand::Rng;
+
+use crate::{Args, BlockCounts, WorkloadKind, rng, ReadResult, RunContext, checkpoint, coverage, device, distribution, engine, faults, precision, prefetch, soak, timing, workload};
+
+/// Run `args.num_threads` readers over `num_files` files, issuing every operation
+/// as `issue(op)`, and collect the successful ones. With `--scatter k` an
+/// operation is k reads or writes one after the other. With `--fsync-writes`
+/// every write is followed by `sync(op)`, timed separately.
+pub fn run_readers<R, S, A>(
+    args: &Args,
+    ctx: &RunContext,
+    num_files: usize,
+    prefetch: Option<prefetch::PrefetchHandle>,
+    issue: R,
+    sync: S,
+    after: A,
+) -> Vec<ReadResult>
+where
+    R: Fn(&workload::Operation) -> std::io::Result<()> + Send + Sync + 'static,
+    S: Fn(&workload::Operation) -> std::io::Result<()> + Send + Sync + 'static,
+    A: Fn(&workload::Operation) + Send + Sync + 'static,
+{
+    let fsync_writes = args.fsync_writes;
+    let clock = ctx.clock;
+    let latency_correction = ctx.latency_correction;
+    let faults = ctx.faults.clone();
+    let fault_batch = args.fault_batch as u64;
+    spawn_readers(args, ctx, num_files, prefetch, move |thread_operations, mut picker, recorder| {
+        let mut thread_faults = faults::FaultStats::default();
+        let mut batch = faults.is_some().then(faults::Batch::start);
+        for _ in 0..thread_operations {
+            let ops = picker.next_scatter();
+            let Some(op) = ops.first() else { break };
+            let is_first_read = recorder.first_scatter_read(&ops);
+
+            // Perform the read operation
+            let start = clock.now();
+            let mut result = ops.iter().try_for_each(&issue);
+            let latency = clock.between(start, clock.now()).saturating_sub(latency_correction);
+            let mut sync_latency = None;
+            if fsync_writes && result.is_ok() && ops.iter().any(|op| op.kind == workload::OpKind::Write) {
+                let sync_start = clock.now();
+                result = ops.iter().filter(|op| op.kind == workload::OpKind::Write).try_for_each(&sync);
+                sync_latency = Some(clock.between(sync_start, clock.now()).saturating_sub(latency_correction));
+            }
+            recorder.record(op, is_first_read, start, latency, None, result.as_ref().err());
+            if let (Some(sync_latency), Ok(())) = (sync_latency, &result) {
+                recorder.record_sync(sync_latency);
+            }
+            if let Some(current) = &mut batch {
+                current.add(is_first_read, latency);
+                if current.ops >= fault_batch {
+                    thread_faults.add(std::mem::replace(current, faults::Batch::start()));
+                }
+            }
+            // Follow-up work such as read-after-write checks stays out of the measured latency
+            if result.is_ok() {
+                ops.iter().for_each(&after);
+            }
+        }
+        if let (Some(faults), Some(last)) = (&faults, batch) {
+            thread_faults.add(last);
+            faults.lock().unwrap().merge(&thread_faults);
+        }
+    })
+}
+
+/// A read handed to an async engine and not yet completed.
+struct InFlight {
+    op: workload::Operation,
+    is_first_read: bool,
+    issued: u64,
+    submitted: u64,
+}
+
+/// Run readers that each keep up to `queue_depth` reads in flight on `engine`.
+pub fn run_async_readers(
+    args: &Args,
+    ctx: &RunContext,
+    num_files: usize,
+    prefetch: Option<prefetch::PrefetchHandle>,
+    engine: Arc<dyn engine::AsyncEngine>,
+    queue_depth: usize,
+) -> Vec<ReadResult> {
+    let clock = ctx.clock;
+    let latency_correction = ctx.latency_correction;
+    let submit_batch = args.submit_batch.unwrap_or(queue_depth).min(queue_depth);
+    spawn_readers(args, ctx, num_files, prefetch, move |thread_operations, mut picker, recorder| {
+        let mut queue = match engine.queue(queue_depth) {
+            Ok(queue) => queue,
+            Err(e) => {
+                eprintln!("⚠️  Cannot create an I/O queue on thread {}: {e}", picker.thread_id);
+                return;
+            }
+        };
+        let mut slots: Vec<Option<InFlight>> = (0..queue_depth).map(|_| None).collect();
+        let mut in_flight = 0;
+        let mut remaining = thread_operations;
+        let mut exhausted = false;
+        let mut newly_queued = Vec::with_capacity(queue_depth);
+
+        loop {
+            // Top the queue up, submitting every `submit_batch` reads
+            while !exhausted && remaining > 0 && in_flight < queue_depth {
+                let Some(op) = picker.next() else {
+                    exhausted = true;
+                    break;
+                };
+                remaining -= 1;
+                let is_first_read = recorder.first_read(&op);
+                let tag = slots.iter().position(Option::is_none).expect("a free slot below the queue depth");
+                let issued = clock.now();
+                if let Err(e) = queue.push(tag, &op) {
+                    recorder.record(&op, is_first_read, issued, Duration::ZERO, None, Some(&e));
+                    continue;
+                }
+                slots[tag] = Some(InFlight {
+                    op,
+                    is_first_read,
+                    issued,
+                    submitted: issued,
+                });
+                in_flight += 1;
+                newly_queued.push(tag);
+
+                // A full queue or the last read is submitted below together with the wait
+                if newly_queued.len() >= submit_batch
+                    && in_flight < queue_depth
+                    && remaining > 0
+                    && let Err(e) = submit(queue.as_mut(), &mut slots, &mut newly_queued, clock, 0)
+                {
+                    eprintln!("⚠️  Submitting reads failed on thread {}: {e}", picker.thread_id);
+                    exhausted = true;
+                }
+            }
+            if in_flight == 0 {
+                break;
+            }
+
+            if let Err(e) = submit(queue.as_mut(), &mut slots, &mut newly_queued, clock, 1) {
+                eprintln!("⚠️  Submitting reads failed on thread {}: {e}", picker.thread_id);
+                break;
+            }
+
+            queue.reap(&mut |tag, result| {
+                let completed = clock.now();
+                let Some(read) = slots.get_mut(tag).and_then(Option::take) else { return };
+                in_flight -= 1;
+                let latency = clock.between(read.issued, completed).saturating_sub(latency_correction);
+                let submit_delay = clock.between(read.issued, read.submitted);
+                recorder.record(&read.op, read.is_first_read, read.issued, latency, Some(submit_delay), result.as_ref().err());
+            });
+        }
+    })
+}
+
+/// Run readers that submit the `scatter` blocks of every operation to `engine` in one
+/// batch and wait for all of them.
+pub fn run_async_scatter_readers(
+    args: &Args,
+    ctx: &RunContext,
+    num_files: usize,
+    prefetch: Option<prefetch::PrefetchHandle>,
+    engine: Arc<dyn engine::AsyncEngine>,
+    scatter: usize,
+) -> Vec<ReadResult> {
+    let clock = ctx.clock;
+    let latency_correction = ctx.latency_correction;
+    spawn_readers(args, ctx, num_files, prefetch, move |thread_operations, mut picker, recorder| {
+        let mut queue = match engine.queue(scatter) {
+            Ok(queue) => queue,
+            Err(e) => {
+                eprintln!("⚠️  Cannot create an I/O queue on thread {}: {e}", picker.thread_id);
+                return;
+            }
+        };
+        for _ in 0..thread_operations {
+            let ops = picker.next_scatter();
+            let Some(op) = ops.first() else { break };
+            let is_first_read = recorder.first_scatter_read(&ops);
+
+            let start = clock.now();
+            let mut error = ops.iter().enumerate().find_map(|(tag, op)| queue.push(tag, op).err());
+            let mut pending = ops.len();
+            while error.is_none() && pending > 0 {
+                if let Err(e) = queue.submit_and_wait(pending) {
+                    error = Some(e);
+                    break;
+                }
+                queue.reap(&mut |_, result| {
+                    pending -= 1;
+                    if let Err(e) = result {
+                        error.get_or_insert(e);
+                    }
+                });
+            }
+            let latency = clock.between(start, clock.now()).saturating_sub(latency_correction);
+            recorder.record(op, is_first_read, start, latency, None, error.as_ref());
+            if pending > 0 {
+                // Reads may still be in flight after a failed submission
+                eprintln!("⚠️  Abandoning thread {} after a failed batch", picker.thread_id);
+                break;
+            }
+        }
+    })
+}
+
+/// Submit the reads in `newly_queued`, stamping their submission time, and wait for
+/// `min_complete` completions.
+fn submit(
+    queue: &mut dyn engine::IoQueue,
+    slots: &mut [Option<InFlight>],
+    newly_queued: &mut Vec<usize>,
+    clock: timing::Clock,
+    min_complete: usize,
+) -> std::io::Result<()> {
+    let submitted = clock.now();
+    for tag in newly_queued.drain(..) {
+        if let Some(read) = &mut slots[tag] {
+            read.submitted = submitted;
+        }
+    }
+    queue.submit_and_wait(min_complete)
+}
+
+/// Spawn the reader threads, hand each its operation count, picker and
+/// recorder, and gather the recorded results once all are done.
+fn spawn_readers<F>(
+    args: &Args,
+    ctx: &RunContext,
+    num_files: usize,
+    prefetch: Option<prefetch::PrefetchHandle>,
+    body: F,
+) -> Vec<ReadResult>
+where
+    F: Fn(usize, Picker, &mut Recorder) + Send + Sync + 'static,
+{
+    let body = Arc::new(body);
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let failures = Arc::new(Mutex::new(Failures::default()));
+
+    let wg = WaitGroup::new();
+
+    for thread_id in 0..args.num_threads {
+        let thread_operations = thread_operations(args, thread_id);
+        let results_clone = Arc::clone(&results);
+        let failures_clone = Arc::clone(&failures);
+        let body_clone = Arc::clone(&body);
+        let args_clone = args.clone();
+        let wg_clone = wg.clone();
+        let working_set_blocks = ctx.working_set_blocks;
+        let prefetch_clone = prefetch.clone();
+        let workload_clone = ctx.workload.clone();
+        let run_start = ctx.run_start;
+        let precision = ctx.precision.clone();
+        let soak = ctx.soak.clone();
+        let ingest = ctx.ingest.clone();
+        let schedules = ctx.schedules.clone();
+        let control = ctx.control.clone();
+        let mut recorder = Recorder {
+            thread_id,
+            precision: ctx.precision.clone(),
+            precision_batch: Vec::new(),
+            soak: ctx.soak.clone(),
+            soak_batch: soak::Batch::default(),
+            clock: ctx.clock,
+            start_ticks: ctx.start_ticks,
+            read_blocks: Arc::clone(&ctx.read_blocks),
+            zone_map: ctx.zone_map.clone(),
+            progress: ctx.progress.clone(),
+            progress_batch: checkpoint::Progress::default(),
+            block_size: args.block_size as u64,
+            results: Vec::new(),
+            failures: Failures::default(),
+        };
+
+        std::thread::spawn(move || {
+            let _guard = wg_clone;
+
+            let mut picker = match new_picker(args_clone, thread_id, num_files, working_set_blocks, prefetch_clone, workload_clone, run_start) {
+                Ok(picker) => picker,
+                Err(e) => {
+                    eprintln!("⚠️  Workload generator failed to start on thread {thread_id}: {e}");
+                    return;
+                }
+            };
+            picker.precision = precision;
+            picker.soak = soak;
+            picker.ingest = ingest;
+            picker.control = control;
+            picker.schedule = schedules.map(|schedules| std::mem::take(&mut schedules.lock().unwrap()[thread_id]).into_iter());
+
+            body_clone(thread_operations, picker, &mut recorder);
+            recorder.flush_progress();
+
+            // Add thread results to global results
+            {
+                let mut global_results = results_clone.lock().unwrap();
+                global_results.extend(recorder.results);
+            }
+            failures_clone.lock().unwrap().merge(recorder.failures);
+        });
+    }
+
+    // Wait for all threads to complete
+    wg.wait();
+
+    let failures = failures.lock().unwrap();
+    ctx.failed_reads.fetch_add(failures.count, std::sync::atomic::Ordering::Relaxed);
+    if let Some(first_error) = &failures.first_error {
+        println!("⚠️  {} reads failed; first error: {first_error}", failures.count);
+        ctx.first_error.lock().unwrap().get_or_insert_with(|| first_error.clone());
+    }
+
+    let results = results.lock().unwrap();
+    results.clone()
+}
+
+/// Operations of reader `thread_id`: an even share of `--num-operations`.
+fn thread_operations(args: &Args, thread_id: usize) -> usize {
+    args.num_operations / args.num_threads + usize::from(thread_id < args.num_operations % args.num_threads)
+}
+
+/// The picker of reader `thread_id`, without the run's stop conditions.
+fn new_picker(
+    args: Args,
+    thread_id: usize,
+    num_files: usize,
+    working_set_blocks: Option<u64>,
+    prefetch: Option<prefetch::PrefetchHandle>,
+    workload: Option<Arc<dyn workload::WorkloadSource>>,
+    run_start: Instant,
+) -> Result<Picker, String> {
+    // Each thread draws from its own stream of the run's seed
+    let seed = rng::thread_seed(args.seed, thread_id);
+    let rng = rng::StreamRng::new(args.rng, args.seed, thread_id);
+    let blocks = FileBlocks::new(&args, num_files, working_set_blocks);
+    let coverage = args
+        .coverage
+        .map(|fraction| coverage::per_file(blocks.blocks.iter().copied(), fraction, args.seed))
+        .unwrap_or_default();
+    let lookahead = lookahead_rng(&rng, prefetch.as_ref(), &args, &blocks);
+    let cursor = match args.pattern {
+        distribution::AccessPattern::Stride(stride) if blocks.total() > 0 => {
+            Some(distribution::StrideCursor::new(stride, thread_id, args.num_threads, blocks.total()))
+        }
+        _ => None,
+    };
+    let generator = workload.map(|w| w.generator(thread_id, seed)).transpose()?;
+    Ok(Picker {
+        thread_id,
+        args,
+        num_files,
+        blocks,
+        run_start,
+        rng,
+        cursor,
+        lookahead,
+        prefetch,
+        generator,
+        precision: None,
+        soak: None,
+        ingest: None,
+        coverage,
+        schedule: None,
+        control: None,
+        due: None,
+        passed_classes: Vec::new(),
+    })
+}
+
+/// Draw every reader's operations before the measured phase (`--pregenerate`),
+/// so the readers only replay them; one thread per reader.
+pub fn pregenerate(
+    args: &Args,
+    num_files: usize,
+    workload: Option<Arc<dyn workload::WorkloadSource>>,
+) -> Result<Vec<Vec<workload::Operation>>, String> {
+    let run_start = Instant::now();
+    std::thread::scope(|scope| {
+        let generators: Vec<_> = (0..args.num_threads)
+            .map(|thread_id| {
+                let workload = workload.clone();
+                scope.spawn(move || {
+                    let mut picker = new_picker(args.clone(), thread_id, num_files, None, None, workload, run_start)?;
+                    let count = thread_operations(args, thread_id).saturating_mul(args.scatter);
+                    Ok(std::iter::from_fn(|| picker.next()).take(count).collect())
+                })
+            })
+            .collect();
+        generators.into_iter().map(|generator| generator.join().expect("schedule generator panicked")).collect()
+    })
+}
+
+/// Chooses a thread's reads and sends prefetch hints ahead of them.
+struct Picker {
+    thread_id: usize,
+    args: Args,
+    num_files: usize,
+    blocks: FileBlocks,
+    run_start: Instant,
+    rng: rng::StreamRng,
+    /// Position of a `--pattern` other than random
+    cursor: Option<distribution::StrideCursor>,
+    lookahead: Option<rng::StreamRng>,
+    prefetch: Option<prefetch::PrefetchHandle>,
+    generator: Option<Box<dyn workload::WorkloadGenerator>>,
+    /// Stops the thread once `--target-precision` is reached
+    precision: Option<Arc<precision::PrecisionTarget>>,
+    /// Stops the thread at the end of a `--soak`
+    soak: Option<Arc<soak::Soak>>,
+    /// Limits operations to the part of each file `--ingest` has written
+    ingest: Option<Arc<crate::ingest::Ingest>>,
+    /// The blocks of each file `--coverage` lets readers touch; empty without it
+    coverage: Vec<coverage::FileCoverage>,
+    /// Operations drawn up front by `--pregenerate`, replayed in order
+    schedule: Option<std::vec::IntoIter<workload::Operation>>,
+    /// Pauses, paces and stops the thread (`--rate`, `--control-socket`, `--keys`)
+    control: Option<Arc<crate::control::Control>>,
+    /// When the next operation is due under the rate cap
+    due: Option<Instant>,
+    /// Scratch of the job class draws
+    passed_classes: Vec<bool>,
+}
+
+impl Picker {
+    /// The next read, or `None` when the thread should stop.
+    fn next(&mut self) -> Option<workload::Operation> {
+        if self.stopping() {
+            return None;
+        }
+        let mut intended = None;
+        if let Some(control) = &self.control {
+            let slot = control.wait(&mut self.due)?;
+            if self.args.open_loop {
+                intended = Some(slot.saturating_duration_since(self.run_start));
+            }
+        }
+        let mut op = self.draw()?;
+        op.intended = intended;
+        Some(op)
+    }
+
+    /// Whether the run is over for this thread: interrupted, precise enough or at the end of the soak.
+    fn stopping(&self) -> bool {
+        crate::status::interrupted()
+            || self.precision.as_ref().is_some_and(|p| p.reached())
+            || self.soak.as_ref().is_some_and(|s| s.finished())
+    }
+
+    /// Draw the next operation without waiting.
+    fn draw(&mut self) -> Option<workload::Operation> {
+        if let Some(schedule) = &mut self.schedule {
+            return schedule.next();
+        }
+        let block_size = self.args.block_size as u64;
+        if let (Some(prefetch), Some(ahead)) = (&self.prefetch, &mut self.lookahead)
+            && let Some((file, block)) = pick_block(ahead, &self.args, &self.blocks)
+        {
+            prefetch.hint(file, block * block_size, block_size);
+        }
+
+        // Select file and block position
+        let picked = match &mut self.cursor {
+            Some(cursor) => {
+                let (file, block) = self.blocks.locate(cursor.advance());
+                Ok(Some(workload::Operation {
+                    kind: workload::OpKind::Read,
+                    file,
+                    offset: block * block_size,
+                    len: block_size,
+                    tag: None,
+                    intended: None,
+                }))
+            }
+            None => next_read(self.generator.as_mut(), &mut self.rng, &self.args, self.num_files, &self.blocks),
+        };
+        match picked {
+            Ok(Some(mut op)) if self.generator.is_none() => {
+                if let Some(rate) = self.args.hotspot_move {
+                    op.offset = hotspot_offset(op.offset, rate, self.run_start.elapsed(), block_size, self.blocks.of(op.file));
+                }
+                if let Some(coverage) = self.coverage.get(op.file) {
+                    op.offset = coverage.block(op.offset / block_size) * block_size;
+                }
+                let mut readable = self.blocks.of(op.file);
+                if let Some(ingest) = &self.ingest {
+                    // Scale the block into the written part, keeping its relative position
+                    let written = ingest.readable_blocks(op.file, block_size).min(readable);
+                    op.offset = (op.offset / block_size * written / readable.max(1)).min(written.saturating_sub(1)) * block_size;
+                    readable = written;
+                }
+                if self.args.write_percent > 0 && self.rng.random_range(0..100) < self.args.write_percent {
+                    op.kind = workload::OpKind::Write;
+                }
+                if let Some(job) = &self.args.job {
+                    // The class decides the kind and length; it stops at the end of the file (or working set) like a scan
+                    let class = job.pick(&mut self.rng, &mut self.passed_classes)?;
+                    op.tag = Some(class as u16);
+                    op.kind = job.classes[class].kind;
+                    op.len = job.classes[class].len.min(readable * block_size - op.offset);
+                }
+                if self.args.workload == WorkloadKind::Scan {
+                    // Scans stop at the end of the file (or working set)
+                    let len = self.args.scan_length.sample(&mut self.rng, block_size);
+                    op.len = len.min(readable * block_size - op.offset);
+                }
+                Some(op)
+            }
+            Ok(op) => op,
+            Err(e) => {
+                eprintln!("⚠️  Workload generator failed on thread {}: {e}", self.thread_id);
+                None
+            }
+        }
+    }
+
+    /// The reads of one `--scatter` operation; empty when the thread should stop.
+    /// `--rate` paces the operation, not each of its blocks.
+    fn next_scatter(&mut self) -> Vec<workload::Operation> {
+        let Some(first) = self.next() else { return Vec::new() };
+        let intended = first.intended;
+        let mut ops = vec![first];
+        while ops.len() < self.args.scatter && !self.stopping() {
+            let Some(mut op) = self.draw() else { break };
+            op.intended = intended;
+            ops.push(op);
+        }
+        ops
+    }
+}
+
+/// Reads that returned an error, which are left out of the results.
+#[derive(Default)]
+struct Failures {
+    count: u64,
+    first_error: Option<String>,
+}
+
+impl Failures {
+    fn merge(&mut self, other: Failures) {
+        self.count += other.count;
+        if self.first_error.is_none() {
+            self.first_error = other.first_error;
+        }
+    }
+}
+
+/// Tracks first reads and collects one thread's results and progress.
+struct Recorder {
+    thread_id: usize,
+    precision: Option<Arc<precision::PrecisionTarget>>,
+    precision_batch: Vec<Duration>,
+    soak: Option<Arc<soak::Soak>>,
+    soak_batch: soak::Batch,
+    clock: timing::Clock,
+    start_ticks: u64,
+    read_blocks: Arc<Mutex<BlockCounts>>,
+    zone_map: Option<Arc<device::ZoneMap>>,
+    progress: Option<Arc<Mutex<checkpoint::Progress>>>,
+    progress_batch: checkpoint::Progress,
+    block_size: u64,
+    results: Vec<ReadResult>,
+    failures: Failures,
+}
+
+impl Recorder {
+    /// Count a read of the blocks `op` covers, returning whether any is read for the first time.
+    fn first_read(&self, op: &workload::Operation) -> bool {
+        if op.kind == workload::OpKind::Write {
+            return false;
+        }
+        let mut blocks = self.read_blocks.lock().unwrap();
+        let first_block = op.offset / self.block_size;
+        let end_block = (op.offset + op.len).div_ceil(self.block_size).max(first_block + 1);
+        let mut is_first = false;
+        for block in first_block..end_block {
+            let count = blocks.entry((op.file, block)).or_insert(0);
+            *count += 1;
+            is_first |= *count == 1;
+        }
+        is_first
+    }
+
+    /// Count the blocks of a scatter operation; it is a first read if any block is.
+    fn first_scatter_read(&self, ops: &[workload::Operation]) -> bool {
+        ops.iter().filter(|op| self.first_read(op)).count() > 0
+    }
+
+    /// Attach the latency of the sync that followed the operation recorded last.
+    fn record_sync(&mut self, latency: Duration) {
+        if let Some(result) = self.results.last_mut() {
+            result.sync_latency = Some(latency);
+        }
+    }
+
+    fn record(
+        &mut self,
+        op: &workload::Operation,
+        is_first_read: bool,
+        start: u64,
+        latency: Duration,
+        submit_delay: Option<Duration>,
+        error: Option<&std::io::Error>,
+    ) {
+        let ok = error.is_none();
+        if let Some(e) = error {
+            self.failures.count += 1;
+            self.failures.first_error.get_or_insert_with(|| e.to_string());
+            if let Some(soak) = &self.soak {
+                self.soak_batch.record(None, soak.raw_log());
+            }
+        } else {
+            let zone = self.zone_map.as_ref().and_then(|m| m.zone_of(op.file, op.offset));
+            let result = ReadResult {
+                timestamp: self.clock.between(self.start_ticks, start),
+                latency,
+                is_first_read,
+                zone,
+                thread: self.thread_id,
+                file: op.file,
+                offset: op.offset,
+                bytes: op.len,
+                is_write: op.kind == workload::OpKind::Write,
+                submit_delay,
+                sync_latency: None,
+                tag: op.tag,
+                lateness: op.intended.map(|intended| self.clock.between(self.start_ticks, start).saturating_sub(intended)),
+            };
+            // A soak keeps histograms only, so memory does not grow with the run
+            match &self.soak {
+                Some(soak) => self.soak_batch.record(Some(&result), soak.raw_log()),
+                None => self.results.push(result),
+            }
+            if let Some(precision) = &self.precision
+                && op.kind == workload::OpKind::Read
+            {
+                self.precision_batch.push(latency);
+                if self.precision_batch.len() >= precision::BATCH {
+                    precision.add(&mut self.precision_batch);
+                }
+            }
+        }
+
+        if let Some(soak) = &self.soak
+            && self.soak_batch.ops() >= soak::BATCH
+        {
+            soak.add(&mut self.soak_batch);
+        }
+        if let Some(progress) = &self.progress {
+            self.progress_batch.record(ok.then_some((latency, is_first_read)));
+            if self.progress_batch.completed_operations >= checkpoint::PROGRESS_BATCH {
+                progress.lock().unwrap().merge(&std::mem::take(&mut self.progress_batch));
+            }
+        }
+    }
+
+    fn flush_progress(&mut self) {
+        if let Some(soak) = &self.soak {
+            soak.add(&mut self.soak_batch);
+        }
+        if let Some(precision) = &self.precision {
+            precision.add(&mut self.precision_batch);
+        }
+        if let Some(progress) = &self.progress {
+            progress.lock().unwrap().merge(&std::mem::take(&mut self.progress_batch));
+        }
+    }
+}
+
+/// Next read for a thread: from the workload plugin or script if one is loaded, else a
+/// block picked by `--distribution`. `None` once there is nothing left to read.
+fn next_read(
+    generator: Option<&mut Box<dyn workload::WorkloadGenerator>>,
+    rng: &mut rng::StreamRng,
+    args: &Args,
+    num_files: usize,
+    blocks: &FileBlocks,
+) -> Result<Option<workload::Operation>, String> {
+    let Some(generator) = generator else {
+        return Ok(pick_block(rng, args, blocks).map(|(file, block)| workload::Operation {
+            kind: workload::OpKind::Read,
+            file,
+            offset: block * args.block_size as u64,
+            len: args.block_size as u64,
+            tag: None,
+            intended: None,
+        }));
+    };
+    let Some(op) = generator.next_op()? else { return Ok(None) };
+    op.check(num_files, args.file_size)?;
+    if op.kind != workload::OpKind::Read {
+        return Err(format!("unsupported operation {:?}", op.kind));
+    }
+    Ok(Some(op))
+}
+
+/// Rotate a block offset by the distance the hot spot has drifted: `rate` of the
+/// file per second, wrapping around at the end.
+fn hotspot_offset(offset: u64, rate: f64, elapsed: Duration, block_size: u64, max_blocks: u64) -> u64 {
+    let shift = (rate * elapsed.as_secs_f64() * max_blocks as f64) as u64 % max_blocks;
+    (offset / block_size + shift) % max_blocks * block_size
+}
+
+/// A copy of `rng` advanced `distance` picks ahead, so it yields the blocks
+/// the reader will access that many operations from now.
+fn lookahead_rng(
+    rng: &rng::StreamRng,
+    prefetch: Option<&prefetch::PrefetchHandle>,
+    args: &Args,
+    blocks: &FileBlocks,
+) -> Option<rng::StreamRng> {
+    let prefetch = prefetch?;
+    let mut ahead = rng.clone();
+    for _ in 0..prefetch.distance() {
+        pick_block(&mut ahead, args, blocks);
+    }
+    Some(ahead)
+}
+
+/// Draw the next (file, block) pair; `None` if files hold no complete block.
+fn pick_block(rng: &mut rng::StreamRng, args: &Args, blocks: &FileBlocks) -> Option<(usize, u64)> {
+    let file_index = args.file_weights.pick(rng, blocks.num_files());
+    let max_blocks = blocks.of(file_index);
+    if max_blocks == 0 {
+        return None;
+    }
+    Some((file_index, args.distribution.next_block(rng, max_blocks)))
+}
+
+/// Readable blocks of every file, after any working-set limit.
+#[derive(Debug, Clone)]
+struct FileBlocks {
+    blocks: Vec<u64>,
+    /// Index of each file's first block in the concatenated files
+    starts: Vec<u64>,
+}
+
+impl FileBlocks {
+    fn new(args: &Args, num_files: usize, working_set_blocks: Option<u64>) -> Self {
+        let block_size = args.block_size as u64;
+        let blocks: Vec<u64> = (0..num_files)
+            .map(|file| {
+                let blocks = args.file_size_of(file) / block_size;
+                working_set_blocks.map_or(blocks, |limit| limit.min(blocks))
+            })
+            .collect();
+        let starts = blocks
+            .iter()
+            .scan(0, |next, &n| {
+                let start = *next;
+                *next += n;
+                Some(start)
+            })
+            .collect();
+        FileBlocks { blocks, starts }
+    }
+
+    fn num_files(&self) -> usize {
+        self.blocks.len()
+    }
+
+    fn of(&self, file: usize) -> u64 {
+        self.blocks[file]
+    }
+
+    fn total(&self) -> u64 {
+        self.starts.last().map_or(0, |start| start + self.blocks[self.blocks.len() - 1])
+    }
+
+    /// The file and block of a block index in the concatenated files.
+    fn locate(&self, position: u64) -> (usize, u64) {
+        let file = self.starts.partition_point(|&start| start <= position) - 1;
+        (file, position - self.starts[file])
+    }
+}
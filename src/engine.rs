@@ -0,0 +1,1270 @@
+//! I/O engines: how a single read is issued against the test files.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::histogram::LatencyHistogram;
+use crate::stats::Statistics;
+use crate::{Args, BufferKind, MmapTouch, fsutil, mapping, msync, page_size, units, workload};
+
+/// Issues reads against the test files; shared by all reader threads.
+pub trait IoEngine: Send + Sync {
+    /// Read `len` bytes at `offset` of file `file`.
+    fn read(&self, file: usize, offset: u64, len: usize) -> std::io::Result<()>;
+
+    /// Write `data` at `offset` of file `file`.
+    fn write(&self, _file: usize, _offset: u64, _data: &[u8]) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "engine does not write"))
+    }
+
+    /// Make the writes to file `file` durable (`--fsync-writes`).
+    fn sync(&self, _file: usize) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "engine does not sync"))
+    }
+
+    /// Bookkeeping for the calling thread's last operation `op`, run once its latency is taken.
+    fn after(&self, _op: &workload::Operation) {}
+
+    /// Pass the data of the calling thread's last read `op` to `check`, once its latency is taken.
+    fn last_read(&self, _op: &workload::Operation, _check: &mut dyn FnMut(&[u8])) {}
+
+    /// Read `buf.len()` bytes at `offset` of file `file` into `buf`, to check their contents.
+    fn read_into(&self, _file: usize, _offset: u64, _buf: &mut [u8]) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "engine does not return data"))
+    }
+
+    /// Print engine statistics once all readers are done.
+    fn report(&self) {}
+}
+
+/// Keeps several reads in flight per thread.
+pub trait AsyncEngine: Send + Sync {
+    /// Create a thread's queue holding up to `depth` reads in flight.
+    fn queue(&self, depth: usize) -> std::io::Result<Box<dyn IoQueue + '_>>;
+
+    /// Print engine statistics once all readers are done.
+    fn report(&self) {}
+}
+
+/// One thread's submission queue. Operations are identified by tags below the queue depth.
+pub trait IoQueue {
+    /// Queue a read or write without submitting it; `tag` must not be in flight.
+    fn push(&mut self, tag: usize, op: &workload::Operation) -> std::io::Result<()>;
+
+    /// Submit the queued operations and block until at least `min_complete` have completed.
+    fn submit_and_wait(&mut self, min_complete: usize) -> std::io::Result<()>;
+
+    /// Pass every available completion to `on_complete`.
+    fn reap(&mut self, on_complete: &mut dyn FnMut(usize, std::io::Result<()>));
+}
+
+/// An opened engine of either kind.
+pub enum Engine {
+    Sync(Box<dyn IoEngine>),
+    Async(Box<dyn AsyncEngine>),
+}
+
+type Constructor = fn(&Args, &[String]) -> Result<Engine, Box<dyn std::error::Error>>;
+
+/// A registered engine.
+pub struct EngineEntry {
+    name: &'static str,
+    description: &'static str,
+    open: Constructor,
+    /// Checks that the running kernel supports the engine
+    probe: fn() -> Result<(), String>,
+    /// Opens as an [`AsyncEngine`] keeping several operations in flight
+    asynchronous: bool,
+    /// Can issue the workload's writes
+    writes: bool,
+    /// Leaves the data of each read where [`IoEngine::last_read`] finds it (`--verify`)
+    data: bool,
+}
+
+fn always_available() -> Result<(), String> {
+    Ok(())
+}
+
+/// All engines selectable with `--engine`.
+pub const ENGINES: &[EngineEntry] = &[
+    EngineEntry {
+        name: "standard",
+        description: "open, seek and read per operation",
+        open: StandardEngine::open,
+        probe: always_available,
+        asynchronous: false,
+        writes: true,
+        data: true,
+    },
+    EngineEntry {
+        name: "mmap",
+        description: "memory-mapped files",
+        open: MmapEngine::open,
+        probe: always_available,
+        asynchronous: false,
+        writes: true,
+        data: true,
+    },
+    #[cfg(target_os = "linux")]
+    EngineEntry {
+        name: "io_uring",
+        description: "io_uring reads and writes with --queue-depth in flight per thread",
+        open: UringEngine::open,
+        probe: UringEngine::probe,
+        asynchronous: true,
+        writes: true,
+        data: false,
+    },
+    #[cfg(target_os = "linux")]
+    EngineEntry {
+        name: "libaio",
+        description: "Linux native AIO (io_submit) with --queue-depth in flight per thread, O_DIRECT where supported",
+        open: AioEngine::open,
+        probe: AioEngine::probe,
+        asynchronous: true,
+        writes: true,
+        data: false,
+    },
+    #[cfg(target_os = "linux")]
+    EngineEntry {
+        name: "sendfile",
+        description: "sendfile to --copy-sink, never copying to user space",
+        open: crate::copy::SendfileEngine::open,
+        probe: always_available,
+        asynchronous: false,
+        writes: false,
+        data: false,
+    },
+    #[cfg(target_os = "linux")]
+    EngineEntry {
+        name: "copy_file_range",
+        description: "copy_file_range into a scratch file next to the test files",
+        open: crate::copy::CopyFileRangeEngine::open,
+        probe: always_available,
+        asynchronous: false,
+        writes: false,
+        data: false,
+    },
+    EngineEntry {
+        name: "threadpool",
+        description: "blocking reads and writes by a shared pool of --inflight workers, fed by every thread's --queue-depth",
+        open: PoolEngine::open,
+        probe: always_available,
+        asynchronous: true,
+        writes: true,
+        data: false,
+    },
+    EngineEntry {
+        name: "null",
+        description: "no I/O at all, to measure the most operations per second the harness itself drives",
+        open: NullEngine::open,
+        probe: always_available,
+        asynchronous: false,
+        writes: true,
+        data: false,
+    },
+];
+
+/// The `--engine` choices with their descriptions for `--help`.
+pub fn possible_values() -> impl Iterator<Item = clap::builder::PossibleValue> {
+    ENGINES
+        .iter()
+        .map(|e| clap::builder::PossibleValue::new(e.name).help(e.description))
+}
+
+/// Every registered engine with whether it works on this system, and why not.
+pub fn availability() -> Vec<(&'static str, Result<(), String>)> {
+    ENGINES.iter().map(|e| (e.name, (e.probe)())).collect()
+}
+
+fn entry(name: &str) -> Option<&'static EngineEntry> {
+    ENGINES.iter().find(|e| e.name == name)
+}
+
+/// Whether the engine registered as `name` is an [`AsyncEngine`].
+pub fn is_async(name: &str) -> bool {
+    entry(name).is_some_and(|e| e.asynchronous)
+}
+
+/// Whether the engine registered as `name` can write.
+pub fn can_write(name: &str) -> bool {
+    entry(name).is_some_and(|e| e.writes)
+}
+
+/// Whether the engine registered as `name` keeps the data it reads for `--verify`.
+pub fn keeps_data(name: &str) -> bool {
+    entry(name).is_some_and(|e| e.data)
+}
+
+/// Construct the engine registered as `name` over `paths`.
+pub fn open(name: &str, args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+    let entry = entry(name).ok_or_else(|| format!("unknown engine '{name}'"))?;
+    if let Err(e) = (entry.probe)() {
+        let available: Vec<_> = availability().into_iter().filter(|(_, a)| a.is_ok()).map(|(name, _)| name).collect();
+        return Err(format!("the {name} engine is not available here: {e} (available: {})", available.join(", ")).into());
+    }
+    (entry.open)(args, paths)
+}
+
+/// Opens the file for every read, like a cold request path; `--poll` reads
+/// files opened once instead, so only the completion path is measured.
+struct StandardEngine {
+    paths: Vec<String>,
+    random_advice: bool,
+    /// Files opened with O_DIRECT and read with preadv2(RWF_HIPRI), polling for the completion
+    direct: Option<Vec<File>>,
+    block_size: usize,
+    buffers: BufferKind,
+    /// Step times, one shard per reader thread, merged for the report
+    steps: Vec<Mutex<StepTimes>>,
+    next_shard: AtomicUsize,
+}
+
+thread_local! {
+    /// open, read and close of the calling thread's last read, recorded by `after`
+    static LAST_STEPS: Cell<Option<[Duration; 3]>> = const { Cell::new(None) };
+    /// The calling thread's shard of [`StandardEngine::steps`]
+    static STEP_SHARD: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Where the time of a read goes when every read opens its file.
+#[derive(Default)]
+struct StepTimes {
+    /// open() and the readahead advice
+    open: LatencyHistogram,
+    /// seek and read
+    read: LatencyHistogram,
+    close: LatencyHistogram,
+}
+
+impl StandardEngine {
+    fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        Ok(Engine::Sync(Box::new(StandardEngine {
+            paths: paths.to_vec(),
+            random_advice: args.readahead == Some(0),
+            direct: if args.poll { Some(open_direct(paths)?) } else { None },
+            block_size: args.block_size,
+            buffers: args.buffers,
+            steps: (0..args.num_threads.max(1)).map(|_| Mutex::default()).collect(),
+            next_shard: AtomicUsize::new(0),
+        })))
+    }
+}
+
+impl IoEngine for StandardEngine {
+    fn read(&self, file: usize, offset: u64, len: usize) -> std::io::Result<()> {
+        if let Some(direct) = &self.direct {
+            return with_buffer(self.buffers, len, |buffer| direct_read(&direct[file], offset, buffer, true));
+        }
+        let start = Instant::now();
+        let mut file = File::open(&self.paths[file])?;
+        if self.random_advice {
+            fsutil::advise_random(&file)?;
+        }
+        let opened = Instant::now();
+        file.seek(SeekFrom::Start(offset))?;
+
+        with_buffer(self.buffers, len, |buffer| file.read_exact(buffer))?;
+        let read = Instant::now();
+        drop(file);
+        let closed = Instant::now();
+        LAST_STEPS.set(Some([opened - start, read - opened, closed - read]));
+        Ok(())
+    }
+
+    fn after(&self, _op: &workload::Operation) {
+        let Some([open, read, close]) = LAST_STEPS.take() else { return };
+        let shard = STEP_SHARD.get().unwrap_or_else(|| {
+            let shard = self.next_shard.fetch_add(1, Ordering::Relaxed);
+            STEP_SHARD.set(Some(shard));
+            shard
+        });
+        let mut steps = self.steps[shard % self.steps.len()].lock().unwrap();
+        steps.open.record(open);
+        steps.read.record(read);
+        steps.close.record(close);
+    }
+
+    fn last_read(&self, op: &workload::Operation, check: &mut dyn FnMut(&[u8])) {
+        with_buffer(self.buffers, op.len as usize, |buffer| check(buffer));
+    }
+
+    fn write(&self, file: usize, offset: u64, data: &[u8]) -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new().write(true).open(&self.paths[file])?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(data)
+    }
+
+    fn sync(&self, file: usize) -> std::io::Result<()> {
+        std::fs::OpenOptions::new().write(true).open(&self.paths[file])?.sync_data()
+    }
+
+    fn read_into(&self, file: usize, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        let mut file = File::open(&self.paths[file])?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+
+    fn report(&self) {
+        let mut steps = StepTimes::default();
+        for shard in &self.steps {
+            let shard = shard.lock().unwrap();
+            steps.open.merge(&shard.open);
+            steps.read.merge(&shard.read);
+            steps.close.merge(&shard.close);
+        }
+        if let Some(direct) = &self.direct {
+            self.report_poll_baseline(direct);
+        }
+        if steps.read.is_empty() {
+            return;
+        }
+        let total: f64 = [&steps.open, &steps.read, &steps.close].iter().map(|h| h.mean().as_secs_f64()).sum();
+        println!("\n📂 Per-Read Open, Read and Close ({} reads):", steps.read.count());
+        println!("  {:<8} {:>10} {:>10} {:>10} {:>10} {:>7}", "Step", "Average", "Median", "99th %ile", "Max", "Share");
+        for (name, histogram) in [("open()", &steps.open), ("read()", &steps.read), ("close()", &steps.close)] {
+            let stats = Statistics::from_histogram(histogram);
+            println!(
+                "  {name:<8} {:>10} {:>10} {:>10} {:>10} {:>6.1}%",
+                units::format_latency(stats.avg),
+                units::format_latency(stats.median),
+                units::format_latency(stats.p99),
+                units::format_latency(stats.max),
+                100.0 * histogram.mean().as_secs_f64() / total.max(f64::EPSILON)
+            );
+        }
+    }
+}
+
+/// Byte written by write operations, distinct from the creation pattern.
+pub const WRITE_PATTERN: u8 = 0xCD;
+
+/// Reads of each kind in the polled vs plain O_DIRECT comparison of `--poll`.
+const POLL_BASELINE_READS: usize = 1000;
+
+impl StandardEngine {
+    /// Read random blocks with and without RWF_HIPRI, alternating, through the
+    /// same files and buffer as the run, so the benefit of polling can be read off.
+    fn report_poll_baseline(&self, files: &[File]) {
+        let sizes: Vec<u64> = files.iter().map(|file| fsutil::file_len(file).unwrap_or(0) / self.block_size as u64).collect();
+        if sizes.iter().all(|&blocks| blocks == 0) {
+            return;
+        }
+        let (mut polled, mut plain) = (LatencyHistogram::default(), LatencyHistogram::default());
+        let mut state = 0x9E37_79B9_7F4A_7C15;
+        for i in 0..2 * POLL_BASELINE_READS {
+            let file = (crate::rng::splitmix64(&mut state) % files.len() as u64) as usize;
+            if sizes[file] == 0 {
+                continue;
+            }
+            let offset = crate::rng::splitmix64(&mut state) % sizes[file] * self.block_size as u64;
+            let hipri = i % 2 == 0;
+            let start = Instant::now();
+            if with_buffer(self.buffers, self.block_size, |buffer| direct_read(&files[file], offset, buffer, hipri)).is_err() {
+                continue;
+            }
+            if hipri { &mut polled } else { &mut plain }.record(start.elapsed());
+        }
+        if polled.is_empty() || plain.is_empty() {
+            return;
+        }
+        println!("\n⚡ Polled vs Plain O_DIRECT ({} + {} reads after the run, same files and buffers):", polled.count(), plain.count());
+        println!("  {:<10} {:>10} {:>10} {:>10} {:>10}", "Reads", "Average", "Median", "99th %ile", "Max");
+        for (name, histogram) in [("RWF_HIPRI", &polled), ("plain", &plain)] {
+            let stats = Statistics::from_histogram(histogram);
+            println!(
+                "  {name:<10} {:>10} {:>10} {:>10} {:>10}",
+                units::format_latency(stats.avg),
+                units::format_latency(stats.median),
+                units::format_latency(stats.p99),
+                units::format_latency(stats.max)
+            );
+        }
+        println!(
+            "  Polling changes the median by {:+.1}%",
+            100.0 * (polled.quantile(0.5).as_secs_f64() / plain.quantile(0.5).as_secs_f64().max(f64::EPSILON) - 1.0)
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_direct(paths: &[String]) -> std::io::Result<Vec<File>> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    paths.iter().map(|path| std::fs::OpenOptions::new().read(true).custom_flags(libc::O_DIRECT).open(path)).collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_direct(_paths: &[String]) -> std::io::Result<Vec<File>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "polled reads need Linux"))
+}
+
+/// Fill `buffer` from `offset` of an O_DIRECT file, polling for the completion if `hipri`.
+#[cfg(target_os = "linux")]
+fn direct_read(file: &File, offset: u64, buffer: &mut [u8], hipri: bool) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let iov = libc::iovec {
+        iov_base: buffer.as_mut_ptr().cast(),
+        iov_len: buffer.len(),
+    };
+    let flags = if hipri { libc::RWF_HIPRI } else { 0 };
+    let read = unsafe { libc::preadv2(file.as_raw_fd(), &iov, 1, offset as libc::off_t, flags) };
+    if read < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if read as usize != buffer.len() {
+        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short read"));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn direct_read(_file: &File, _offset: u64, _buffer: &mut [u8], _hipri: bool) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "polled reads need Linux"))
+}
+
+/// Alignment satisfying O_DIRECT on every common logical block size.
+const DIRECT_IO_ALIGN: usize = 4096;
+
+/// Size of a huge page, the allocation unit of pinned buffers.
+const HUGE_PAGE: usize = 2 * 1024 * 1024;
+
+/// How the pinned buffers of a run ended up backed.
+pub struct PinnedStats {
+    pub buffers: AtomicU64,
+    /// Backed by hugetlbfs pages rather than transparent huge pages
+    pub hugetlb: AtomicU64,
+    /// mlock failed, usually for lack of RLIMIT_MEMLOCK; the buffer stays pageable
+    pub unlocked: AtomicU64,
+}
+
+pub static PINNED_STATS: PinnedStats = PinnedStats {
+    buffers: AtomicU64::new(0),
+    hugetlb: AtomicU64::new(0),
+    unlocked: AtomicU64::new(0),
+};
+
+/// Print how the pinned buffers were backed.
+pub fn report_pinned() {
+    let buffers = PINNED_STATS.buffers.load(Ordering::Relaxed);
+    let hugetlb = PINNED_STATS.hugetlb.load(Ordering::Relaxed);
+    let unlocked = PINNED_STATS.unlocked.load(Ordering::Relaxed);
+    println!(
+        "📌 Pinned buffers: {buffers} of {} MiB or more, {hugetlb} on hugetlbfs pages and {} on transparent huge pages",
+        HUGE_PAGE / (1024 * 1024),
+        buffers - hugetlb
+    );
+    if unlocked > 0 {
+        println!("⚠️  {unlocked} pinned buffers could not be mlocked and stayed pageable; raise the memlock limit (ulimit -l)");
+    }
+}
+
+/// Map `len` bytes of anonymous memory on huge pages, falling back to
+/// transparent huge pages, and lock it in memory.
+#[cfg(target_os = "linux")]
+fn map_pinned(len: usize) -> *mut u8 {
+    let map = |flags: libc::c_int| unsafe {
+        libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags, -1, 0)
+    };
+    let mut ptr = map(libc::MAP_HUGETLB);
+    if ptr == libc::MAP_FAILED {
+        ptr = map(0);
+        if ptr == libc::MAP_FAILED {
+            std::alloc::handle_alloc_error(std::alloc::Layout::from_size_align(len, HUGE_PAGE).expect("valid buffer layout"));
+        }
+        unsafe { libc::madvise(ptr, len, libc::MADV_HUGEPAGE) };
+    } else {
+        PINNED_STATS.hugetlb.fetch_add(1, Ordering::Relaxed);
+    }
+    if unsafe { libc::mlock(ptr, len) } != 0 {
+        PINNED_STATS.unlocked.fetch_add(1, Ordering::Relaxed);
+    }
+    PINNED_STATS.buffers.fetch_add(1, Ordering::Relaxed);
+    ptr.cast()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn map_pinned(len: usize) -> *mut u8 {
+    let layout = std::alloc::Layout::from_size_align(len, HUGE_PAGE).expect("valid buffer layout");
+    PINNED_STATS.buffers.fetch_add(1, Ordering::Relaxed);
+    PINNED_STATS.unlocked.fetch_add(1, Ordering::Relaxed);
+    unsafe { std::alloc::alloc_zeroed(layout) }
+}
+
+#[cfg(target_os = "linux")]
+fn unmap_pinned(ptr: *mut u8, len: usize) {
+    unsafe { libc::munmap(ptr.cast(), len) };
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unmap_pinned(ptr: *mut u8, len: usize) {
+    unsafe { std::alloc::dealloc(ptr, std::alloc::Layout::from_size_align(len, HUGE_PAGE).expect("valid buffer layout")) };
+}
+
+thread_local! {
+    /// The reused read buffers of a synchronous engine's thread, one of each kind,
+    /// so neither side of a pinned vs pageable comparison pays for allocation
+    static PAGEABLE_BUFFER: std::cell::RefCell<AlignedBuffer> = std::cell::RefCell::new(AlignedBuffer::new(BufferKind::Pageable));
+    static PINNED_BUFFER: std::cell::RefCell<AlignedBuffer> = std::cell::RefCell::new(AlignedBuffer::new(BufferKind::Pinned));
+}
+
+/// Run `f` on the first `len` bytes of the calling thread's buffer of `kind`.
+fn with_buffer<R>(kind: BufferKind, len: usize, f: impl FnOnce(&mut [u8]) -> R) -> R {
+    let buffer = match kind {
+        BufferKind::Pageable => &PAGEABLE_BUFFER,
+        BufferKind::Pinned => &PINNED_BUFFER,
+    };
+    buffer.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.ensure(len);
+        f(unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr(), len) })
+    })
+}
+
+/// A buffer aligned for O_DIRECT: on the heap, or pinned (`--buffers pinned`).
+struct AlignedBuffer {
+    ptr: Option<std::ptr::NonNull<u8>>,
+    capacity: usize,
+    kind: BufferKind,
+}
+
+impl Default for AlignedBuffer {
+    fn default() -> Self {
+        AlignedBuffer::new(BufferKind::Pageable)
+    }
+}
+
+// The buffer owns its allocation exclusively
+unsafe impl Send for AlignedBuffer {}
+
+impl AlignedBuffer {
+    fn new(kind: BufferKind) -> Self {
+        AlignedBuffer { ptr: None, capacity: 0, kind }
+    }
+
+    /// Grow to hold at least `len` bytes; the contents are not preserved.
+    fn ensure(&mut self, len: usize) {
+        if len <= self.capacity {
+            return;
+        }
+        self.release();
+        if self.kind == BufferKind::Pinned {
+            let capacity = len.next_multiple_of(HUGE_PAGE);
+            self.ptr = std::ptr::NonNull::new(map_pinned(capacity));
+            self.capacity = capacity;
+            return;
+        }
+        let capacity = len.next_multiple_of(DIRECT_IO_ALIGN);
+        let layout = std::alloc::Layout::from_size_align(capacity, DIRECT_IO_ALIGN).expect("valid buffer layout");
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        self.ptr = Some(std::ptr::NonNull::new(ptr).unwrap_or_else(|| std::alloc::handle_alloc_error(layout)));
+        self.capacity = capacity;
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.map_or(std::ptr::null_mut(), |p| p.as_ptr())
+    }
+
+    fn release(&mut self) {
+        if let Some(ptr) = self.ptr.take() {
+            if self.kind == BufferKind::Pinned {
+                unmap_pinned(ptr.as_ptr(), self.capacity);
+                self.capacity = 0;
+                return;
+            }
+            let layout = std::alloc::Layout::from_size_align(self.capacity, DIRECT_IO_ALIGN).expect("valid buffer layout");
+            unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) };
+        }
+        self.capacity = 0;
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Reads by touching mapped memory.
+struct MmapEngine {
+    mappings: Arc<mapping::SegmentedMappings>,
+    segment_size: u64,
+    touch: MmapTouch,
+    /// Mapped shared and writable, because the workload writes
+    writable: bool,
+    /// Files are mapped directly (`--dax`)
+    dax: bool,
+    /// Flushes stores according to `--msync`
+    syncer: Option<Arc<msync::Syncer>>,
+}
+
+impl MmapEngine {
+    fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        let segment_size = args.mmap_segment_size;
+        if segment_size > 0
+            && (!segment_size.is_multiple_of(page_size() as u64) || !segment_size.is_multiple_of(args.block_size as u64))
+        {
+            return Err(format!(
+                "--mmap-segment-size must be a multiple of the page size ({}) and of the block size ({})",
+                page_size(),
+                args.block_size
+            )
+            .into());
+        }
+        if args.mmap_max_mapped > 0 && segment_size == 0 {
+            return Err("--mmap-max-mapped requires --mmap-segment-size".into());
+        }
+        if args.poll {
+            return Err("--poll does not apply to the mmap engine".into());
+        }
+
+        // Whole files are mapped here; segments are mapped lazily by the readers
+        let writable = args.writes();
+        let mappings = Arc::new(mapping::SegmentedMappings::new(
+            paths,
+            segment_size,
+            args.mmap_max_mapped,
+            args.readahead == Some(0),
+            writable,
+            args.dax,
+        )?);
+        let syncer = (writable && args.msync != msync::MsyncPolicy::None)
+            .then(|| msync::Syncer::start(args.msync, Arc::clone(&mappings)));
+        Ok(Engine::Sync(Box::new(MmapEngine {
+            mappings,
+            segment_size,
+            touch: args.mmap_touch,
+            writable,
+            dax: args.dax,
+            syncer,
+        })))
+    }
+}
+
+impl MmapEngine {
+    /// The mapped bytes `offset..offset + len` of file `file`.
+    fn range(&self, file: usize, offset: u64, len: usize) -> std::io::Result<(Arc<mapping::Region>, usize)> {
+        let (mmap, local) = self.mappings.locate(file, offset)?;
+        if local + len > mmap.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Read beyond file bounds",
+            ));
+        }
+        Ok((mmap, local))
+    }
+}
+
+impl IoEngine for MmapEngine {
+    fn read(&self, file: usize, offset: u64, len: usize) -> std::io::Result<()> {
+        let (mmap, local) = self.range(file, offset, len)?;
+        // Other threads may store to the mapping concurrently; the benchmark does not care
+        let data = unsafe { std::slice::from_raw_parts(mmap.as_ptr().add(local), len) };
+        match self.touch {
+            // Force memory access by copying the data
+            MmapTouch::FullBlock => {
+                std::hint::black_box(data.to_vec());
+            }
+            // Fault in every page without paying for the copy
+            MmapTouch::PerPage => {
+                for page in data.chunks(page_size()) {
+                    std::hint::black_box(unsafe { std::ptr::read_volatile(page.as_ptr()) });
+                }
+            }
+            MmapTouch::FirstByte => {
+                std::hint::black_box(unsafe { std::ptr::read_volatile(data.as_ptr()) });
+            }
+        }
+        Ok(())
+    }
+
+    /// A store into the shared mapping: the page is dirtied in the page cache
+    /// and written back by the kernel later, or with `--dax` goes to the media directly.
+    fn write(&self, file: usize, offset: u64, data: &[u8]) -> std::io::Result<()> {
+        let (mmap, local) = self.range(file, offset, data.len())?;
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), mmap.as_mut_ptr().add(local), data.len()) };
+        match &self.syncer {
+            Some(syncer) => syncer.written(file, offset, data.len()),
+            None => Ok(()),
+        }
+    }
+
+    /// The mapping itself is what the read touched.
+    fn last_read(&self, op: &workload::Operation, check: &mut dyn FnMut(&[u8])) {
+        if let Ok((mmap, local)) = self.range(op.file, op.offset, op.len as usize) {
+            check(unsafe { std::slice::from_raw_parts(mmap.as_ptr().add(local), op.len as usize) });
+        }
+    }
+
+    fn read_into(&self, file: usize, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        let (mmap, local) = self.range(file, offset, buf.len())?;
+        unsafe { std::ptr::copy_nonoverlapping(mmap.as_ptr().add(local), buf.as_mut_ptr(), buf.len()) };
+        Ok(())
+    }
+
+    fn report(&self) {
+        if let Some(syncer) = &self.syncer {
+            syncer.finish();
+        }
+        if self.dax {
+            println!(
+                "💎 DAX: loads{} went straight to the media, bypassing the page cache; latencies are memory accesses, not block I/O",
+                if self.writable { " and MAP_SYNC stores" } else { "" }
+            );
+        }
+        if self.segment_size > 0 {
+            let stats = self.mappings.stats();
+            println!(
+                "🗺️  Mapped {} segments of {} bytes, unmapped {}, peak {} bytes mapped",
+                stats.created, self.segment_size, stats.evicted, stats.peak_mapped_bytes
+            );
+        }
+    }
+}
+
+/// Reads and writes through one io_uring per thread over files opened once.
+#[cfg(target_os = "linux")]
+struct UringEngine {
+    files: Vec<File>,
+    buffers: BufferKind,
+    /// Files are opened with O_DIRECT and rings set up with IORING_SETUP_IOPOLL
+    poll: bool,
+    /// Summed over finished queues: `io_uring_enter` calls, operations pushed,
+    /// and reads and writes in flight at each submission
+    enters: AtomicU64,
+    reads: AtomicU64,
+    writes: AtomicU64,
+    reads_in_flight: AtomicU64,
+    writes_in_flight: AtomicU64,
+}
+
+#[cfg(target_os = "linux")]
+impl UringEngine {
+    fn probe() -> Result<(), String> {
+        crate::uring::Ring::new(1, 0).map(drop).map_err(|e| format!("io_uring_setup failed: {e}"))
+    }
+
+    fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let flags = if args.poll { libc::O_DIRECT } else { 0 };
+        let writable = args.writes();
+        let files = paths
+            .iter()
+            .map(|path| std::fs::OpenOptions::new().read(true).write(writable).custom_flags(flags).open(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        if args.readahead == Some(0) {
+            for file in &files {
+                fsutil::advise_random(file)?;
+            }
+        }
+        Ok(Engine::Async(Box::new(UringEngine {
+            files,
+            buffers: args.buffers,
+            poll: args.poll,
+            enters: AtomicU64::new(0),
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            reads_in_flight: AtomicU64::new(0),
+            writes_in_flight: AtomicU64::new(0),
+        })))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl AsyncEngine for UringEngine {
+    fn queue(&self, depth: usize) -> std::io::Result<Box<dyn IoQueue + '_>> {
+        use std::os::fd::AsRawFd;
+
+        let setup_flags = if self.poll { crate::uring::IORING_SETUP_IOPOLL } else { 0 };
+        Ok(Box::new(UringQueue {
+            ring: crate::uring::Ring::new(depth.next_power_of_two() as u32, setup_flags)?,
+            fds: self.files.iter().map(|f| f.as_raw_fd()).collect(),
+            buffers: (0..depth).map(|_| AlignedBuffer::new(self.buffers)).collect(),
+            expected: vec![0; depth],
+            is_write: vec![false; depth],
+            engine: self,
+            enters: 0,
+            reads: 0,
+            writes: 0,
+            in_flight: (0, 0),
+            in_flight_sums: (0, 0),
+        }))
+    }
+
+    fn report(&self) {
+        let enters = self.enters.load(Ordering::Relaxed);
+        let reads = self.reads.load(Ordering::Relaxed);
+        let writes = self.writes.load(Ordering::Relaxed);
+        if writes == 0 {
+            println!(
+                "💍 io_uring: {enters} io_uring_enter calls for {reads} reads ({:.2} syscalls per read)",
+                enters as f64 / reads.max(1) as f64
+            );
+            return;
+        }
+        println!(
+            "💍 io_uring: {enters} io_uring_enter calls for {reads} reads and {writes} writes ({:.2} syscalls per operation)",
+            enters as f64 / (reads + writes) as f64
+        );
+        println!(
+            "  In flight at submission: {:.2} reads, {:.2} writes on average",
+            self.reads_in_flight.load(Ordering::Relaxed) as f64 / enters.max(1) as f64,
+            self.writes_in_flight.load(Ordering::Relaxed) as f64 / enters.max(1) as f64
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct UringQueue<'a> {
+    ring: crate::uring::Ring,
+    fds: Vec<std::os::fd::RawFd>,
+    /// One buffer per tag, only resized while its tag is not in flight
+    buffers: Vec<AlignedBuffer>,
+    /// Length requested for each tag, to detect short reads and writes
+    expected: Vec<u32>,
+    /// Whether each tag in flight is a write, for the per-kind accounting
+    is_write: Vec<bool>,
+    engine: &'a UringEngine,
+    enters: u64,
+    reads: u64,
+    writes: u64,
+    /// Reads and writes in flight now, and summed at every submission
+    in_flight: (u64, u64),
+    in_flight_sums: (u64, u64),
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for UringQueue<'_> {
+    fn drop(&mut self) {
+        self.engine.enters.fetch_add(self.enters, Ordering::Relaxed);
+        self.engine.reads.fetch_add(self.reads, Ordering::Relaxed);
+        self.engine.writes.fetch_add(self.writes, Ordering::Relaxed);
+        self.engine.reads_in_flight.fetch_add(self.in_flight_sums.0, Ordering::Relaxed);
+        self.engine.writes_in_flight.fetch_add(self.in_flight_sums.1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl IoQueue for UringQueue<'_> {
+    fn push(&mut self, tag: usize, op: &workload::Operation) -> std::io::Result<()> {
+        let len = op.len as usize;
+        let write = op.kind == workload::OpKind::Write;
+        let buffer = &mut self.buffers[tag];
+        buffer.ensure(len);
+        let fd = self.fds[op.file];
+        // The buffer is neither touched nor resized until the tag's completion is reaped
+        let pushed = if write {
+            unsafe {
+                std::ptr::write_bytes(buffer.as_mut_ptr(), WRITE_PATTERN, len);
+                self.ring.push_write(fd, buffer.as_mut_ptr(), len as u32, op.offset, tag as u64)
+            }
+        } else {
+            unsafe { self.ring.push_read(fd, buffer.as_mut_ptr(), len as u32, op.offset, tag as u64) }
+        };
+        pushed?;
+        self.expected[tag] = len as u32;
+        self.is_write[tag] = write;
+        if write {
+            self.writes += 1;
+            self.in_flight.1 += 1;
+        } else {
+            self.reads += 1;
+            self.in_flight.0 += 1;
+        }
+        Ok(())
+    }
+
+    fn submit_and_wait(&mut self, min_complete: usize) -> std::io::Result<()> {
+        self.enters += 1;
+        self.in_flight_sums.0 += self.in_flight.0;
+        self.in_flight_sums.1 += self.in_flight.1;
+        self.ring.submit_and_wait(min_complete as u32).map(|_| ())
+    }
+
+    fn reap(&mut self, on_complete: &mut dyn FnMut(usize, std::io::Result<()>)) {
+        let expected = &self.expected;
+        let is_write = &self.is_write;
+        let in_flight = &mut self.in_flight;
+        self.ring.reap(|tag, result| {
+            let tag = tag as usize;
+            if is_write[tag] {
+                in_flight.1 -= 1;
+            } else {
+                in_flight.0 -= 1;
+            }
+            let result = result.and_then(|done| {
+                if done == expected[tag] {
+                    Ok(())
+                } else if is_write[tag] {
+                    Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "short write"))
+                } else {
+                    Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short read"))
+                }
+            });
+            on_complete(tag, result);
+        });
+    }
+}
+
+/// Reads and writes through one native AIO context per thread, the legacy
+/// asynchronous path older kernels offer instead of io_uring.
+#[cfg(target_os = "linux")]
+struct AioEngine {
+    files: Vec<File>,
+    buffers: BufferKind,
+    /// Files are opened with O_DIRECT; without it io_submit completes buffered reads before returning
+    direct: bool,
+    /// Summed over finished queues: io_submit and io_getevents calls, operations pushed
+    submits: AtomicU64,
+    reads: AtomicU64,
+    writes: AtomicU64,
+}
+
+#[cfg(target_os = "linux")]
+impl AioEngine {
+    fn probe() -> Result<(), String> {
+        crate::aio::Context::new(1).map(drop).map_err(|e| format!("io_setup failed: {e}"))
+    }
+
+    fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let writable = args.writes();
+        let open = |path: &String, flags: i32| {
+            std::fs::OpenOptions::new().read(true).write(writable).custom_flags(flags).open(path)
+        };
+        // Some file systems (e.g. tmpfs) reject O_DIRECT; fall back to buffered files there
+        let (files, direct) = match paths.iter().map(|path| open(path, libc::O_DIRECT)).collect::<Result<Vec<_>, _>>() {
+            Ok(files) => (files, true),
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+                println!("⚠️  O_DIRECT is not supported here; libaio reads are buffered and io_submit blocks until they complete");
+                (paths.iter().map(|path| open(path, 0)).collect::<Result<Vec<_>, _>>()?, false)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if args.readahead == Some(0) {
+            for file in &files {
+                fsutil::advise_random(file)?;
+            }
+        }
+        Ok(Engine::Async(Box::new(AioEngine {
+            files,
+            buffers: args.buffers,
+            direct,
+            submits: AtomicU64::new(0),
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+        })))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl AsyncEngine for AioEngine {
+    fn queue(&self, depth: usize) -> std::io::Result<Box<dyn IoQueue + '_>> {
+        use std::os::fd::AsRawFd;
+
+        Ok(Box::new(AioQueue {
+            context: crate::aio::Context::new(depth)?,
+            fds: self.files.iter().map(|f| f.as_raw_fd()).collect(),
+            buffers: (0..depth).map(|_| AlignedBuffer::new(self.buffers)).collect(),
+            expected: vec![0; depth],
+            is_write: vec![false; depth],
+            engine: self,
+            submits: 0,
+            reads: 0,
+            writes: 0,
+        }))
+    }
+
+    fn report(&self) {
+        let submits = self.submits.load(Ordering::Relaxed);
+        let reads = self.reads.load(Ordering::Relaxed);
+        let writes = self.writes.load(Ordering::Relaxed);
+        println!(
+            "📮 libaio: {submits} submissions for {reads} reads and {writes} writes ({:.2} per operation), {}",
+            submits as f64 / (reads + writes).max(1) as f64,
+            if self.direct { "O_DIRECT" } else { "buffered" }
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct AioQueue<'a> {
+    context: crate::aio::Context,
+    fds: Vec<std::os::fd::RawFd>,
+    /// One buffer per slot, only resized while its slot is not in flight
+    buffers: Vec<AlignedBuffer>,
+    /// Length requested for each slot, to detect short reads and writes
+    expected: Vec<usize>,
+    is_write: Vec<bool>,
+    engine: &'a AioEngine,
+    submits: u64,
+    reads: u64,
+    writes: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for AioQueue<'_> {
+    fn drop(&mut self) {
+        self.engine.submits.fetch_add(self.submits, Ordering::Relaxed);
+        self.engine.reads.fetch_add(self.reads, Ordering::Relaxed);
+        self.engine.writes.fetch_add(self.writes, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl IoQueue for AioQueue<'_> {
+    fn push(&mut self, tag: usize, op: &workload::Operation) -> std::io::Result<()> {
+        let len = op.len as usize;
+        let write = op.kind == workload::OpKind::Write;
+        let buffer = &mut self.buffers[tag];
+        buffer.ensure(len);
+        let fd = self.fds[op.file];
+        // The buffer is neither touched nor resized until the slot's completion is reaped
+        unsafe {
+            if write {
+                std::ptr::write_bytes(buffer.as_mut_ptr(), WRITE_PATTERN, len);
+                self.context.push_write(tag, fd, buffer.as_mut_ptr(), len, op.offset);
+            } else {
+                self.context.push_read(tag, fd, buffer.as_mut_ptr(), len, op.offset);
+            }
+        }
+        self.expected[tag] = len;
+        self.is_write[tag] = write;
+        if write {
+            self.writes += 1;
+        } else {
+            self.reads += 1;
+        }
+        Ok(())
+    }
+
+    fn submit_and_wait(&mut self, min_complete: usize) -> std::io::Result<()> {
+        self.submits += 1;
+        self.context.submit_and_wait(min_complete)
+    }
+
+    fn reap(&mut self, on_complete: &mut dyn FnMut(usize, std::io::Result<()>)) {
+        let expected = &self.expected;
+        let is_write = &self.is_write;
+        self.context.reap(|tag, result| {
+            let result = result.and_then(|done| {
+                if done == expected[tag] {
+                    Ok(())
+                } else if is_write[tag] {
+                    Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "short write"))
+                } else {
+                    Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short read"))
+                }
+            });
+            on_complete(tag, result);
+        });
+    }
+}
+
+/// Completes every operation at once without touching the files, so a run
+/// measures the scheduling, recording and statistics around the I/O.
+struct NullEngine {
+    ops: AtomicU64,
+    opened: Instant,
+}
+
+impl NullEngine {
+    fn open(_args: &Args, _paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        Ok(Engine::Sync(Box::new(NullEngine { ops: AtomicU64::new(0), opened: Instant::now() })))
+    }
+}
+
+impl IoEngine for NullEngine {
+    fn read(&self, _file: usize, _offset: u64, _len: usize) -> std::io::Result<()> {
+        self.ops.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn write(&self, _file: usize, _offset: u64, _data: &[u8]) -> std::io::Result<()> {
+        self.ops.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn sync(&self, _file: usize) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn report(&self) {
+        let ops = self.ops.load(Ordering::Relaxed);
+        println!(
+            "🕳️  null engine: {ops} operations without I/O, {:.0} operations/s: the most this harness drives with these options; a storage run near it is limited by the harness, not the device",
+            ops as f64 / self.opened.elapsed().as_secs_f64().max(f64::EPSILON)
+        );
+    }
+}
+
+/// An operation handed to the `threadpool` workers.
+struct PoolJob {
+    tag: usize,
+    op: workload::Operation,
+    queued: Instant,
+    completions: std::sync::mpsc::Sender<(usize, std::io::Result<()>)>,
+}
+
+/// Blocking reads and writes by a fixed pool of `--inflight` workers shared by
+/// all reader threads, like a database I/O scheduler: the threads' queues feed
+/// one bounded queue, so concurrency at the device follows the pool size, not
+/// the thread count.
+struct PoolEngine {
+    /// Dropped on shutdown, which ends the workers
+    jobs: Mutex<Option<std::sync::mpsc::SyncSender<PoolJob>>>,
+    workers: Mutex<Vec<std::thread::JoinHandle<()>>>,
+    size: usize,
+    stats: Arc<PoolStats>,
+}
+
+#[derive(Default)]
+struct PoolStats {
+    reads: AtomicU64,
+    writes: AtomicU64,
+    /// Time operations spent queued until a worker took them
+    queued_nanos: AtomicU64,
+    /// Operations the workers are running now, and at most
+    running: AtomicU64,
+    peak_running: AtomicU64,
+}
+
+impl PoolEngine {
+    fn open(args: &Args, paths: &[String]) -> Result<Engine, Box<dyn std::error::Error>> {
+        let writable = args.writes();
+        let files = paths
+            .iter()
+            .map(|path| std::fs::OpenOptions::new().read(true).write(writable).open(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        if args.readahead == Some(0) {
+            for file in &files {
+                fsutil::advise_random(file)?;
+            }
+        }
+        let files = Arc::new(files);
+        let size = args.inflight.unwrap_or(DEFAULT_INFLIGHT);
+        let (jobs, queue) = std::sync::mpsc::sync_channel::<PoolJob>(size);
+        let queue = Arc::new(Mutex::new(queue));
+        let stats = Arc::new(PoolStats::default());
+        let workers = (0..size)
+            .map(|worker| {
+                let (files, queue, stats, buffers) = (Arc::clone(&files), Arc::clone(&queue), Arc::clone(&stats), args.buffers);
+                std::thread::Builder::new()
+                    .name(format!("io-pool-{worker}"))
+                    .spawn(move || pool_worker(&files, &queue, &stats, buffers))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Engine::Async(Box::new(PoolEngine {
+            jobs: Mutex::new(Some(jobs)),
+            workers: Mutex::new(workers),
+            size,
+            stats,
+        })))
+    }
+}
+
+/// Default `--inflight`: workers of the `threadpool` engine.
+pub const DEFAULT_INFLIGHT: usize = 64;
+
+fn pool_worker(files: &[File], queue: &Mutex<std::sync::mpsc::Receiver<PoolJob>>, stats: &PoolStats, buffers: BufferKind) {
+    use std::os::unix::fs::FileExt;
+
+    let mut buffer = AlignedBuffer::new(buffers);
+    loop {
+        // Only the wait for a job holds the lock
+        let Ok(job) = queue.lock().expect("pool queue lock").recv() else { return };
+        stats.queued_nanos.fetch_add(job.queued.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        let running = stats.running.fetch_add(1, Ordering::Relaxed) + 1;
+        stats.peak_running.fetch_max(running, Ordering::Relaxed);
+        let len = job.op.len as usize;
+        buffer.ensure(len);
+        let data = unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr(), len) };
+        let file = &files[job.op.file];
+        let result = if job.op.kind == workload::OpKind::Write {
+            data.fill(WRITE_PATTERN);
+            file.write_all_at(data, job.op.offset)
+        } else {
+            file.read_exact_at(data, job.op.offset)
+        };
+        stats.running.fetch_sub(1, Ordering::Relaxed);
+        // The submitting thread may have given up; its results no longer matter
+        let _ = job.completions.send((job.tag, result));
+    }
+}
+
+impl Drop for PoolEngine {
+    fn drop(&mut self) {
+        self.jobs.lock().expect("pool sender lock").take();
+        for worker in self.workers.lock().expect("pool workers lock").drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl AsyncEngine for PoolEngine {
+    fn queue(&self, _depth: usize) -> std::io::Result<Box<dyn IoQueue + '_>> {
+        let jobs = self.jobs.lock().expect("pool sender lock").clone().ok_or_else(|| std::io::Error::other("the I/O pool has shut down"))?;
+        let (completions, completed) = std::sync::mpsc::channel();
+        Ok(Box::new(PoolQueue { jobs, completions, completed, pushed: Vec::new(), ready: Vec::new(), stats: &self.stats }))
+    }
+
+    fn report(&self) {
+        let reads = self.stats.reads.load(Ordering::Relaxed);
+        let writes = self.stats.writes.load(Ordering::Relaxed);
+        let queued = Duration::from_nanos(self.stats.queued_nanos.load(Ordering::Relaxed) / (reads + writes).max(1));
+        println!(
+            "🧵 threadpool: {} workers ran {reads} reads and {writes} writes, at most {} at once; operations queued {} on average before a worker took them",
+            self.size,
+            self.stats.peak_running.load(Ordering::Relaxed),
+            crate::units::format_latency(queued)
+        );
+    }
+}
+
+/// One reader thread's operations, handed to the shared pool on submission.
+struct PoolQueue<'a> {
+    jobs: std::sync::mpsc::SyncSender<PoolJob>,
+    completions: std::sync::mpsc::Sender<(usize, std::io::Result<()>)>,
+    completed: std::sync::mpsc::Receiver<(usize, std::io::Result<()>)>,
+    /// Pushed and not yet submitted
+    pushed: Vec<(usize, workload::Operation)>,
+    /// Completed and not yet reaped
+    ready: Vec<(usize, std::io::Result<()>)>,
+    stats: &'a PoolStats,
+}
+
+impl IoQueue for PoolQueue<'_> {
+    fn push(&mut self, tag: usize, op: &workload::Operation) -> std::io::Result<()> {
+        self.pushed.push((tag, *op));
+        Ok(())
+    }
+
+    fn submit_and_wait(&mut self, min_complete: usize) -> std::io::Result<()> {
+        for (tag, op) in self.pushed.drain(..) {
+            let counter = if op.kind == workload::OpKind::Write { &self.stats.writes } else { &self.stats.reads };
+            counter.fetch_add(1, Ordering::Relaxed);
+            // Blocks while the pool's queue is full
+            let job = PoolJob { tag, op, queued: Instant::now(), completions: self.completions.clone() };
+            self.jobs.send(job).map_err(|_| std::io::Error::other("the I/O pool has shut down"))?;
+        }
+        while self.ready.len() < min_complete {
+            let completion = self.completed.recv().map_err(|_| std::io::Error::other("the I/O pool has shut down"))?;
+            self.ready.push(completion);
+        }
+        Ok(())
+    }
+
+    fn reap(&mut self, on_complete: &mut dyn FnMut(usize, std::io::Result<()>)) {
+        self.ready.extend(self.completed.try_iter());
+        for (tag, result) in self.ready.drain(..) {
+            on_complete(tag, result);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Attribute page faults to mmap reads by sampling the reader thread's fault
+//! counters around small batches of operations.
+
+use std::time::Duration;
+
+/// Major and minor faults of the calling thread so far.
+#[cfg(target_os = "linux")]
+pub fn thread_faults() -> (u64, u64) {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) } != 0 {
+        return (0, 0);
+    }
+    (usage.ru_majflt as u64, usage.ru_minflt as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn thread_faults() -> (u64, u64) {
+    (0, 0)
+}
+
+/// Operations since the last fault-counter sample.
+pub struct Batch {
+    start: (u64, u64),
+    pub ops: u64,
+    first_reads: u64,
+    latency: Duration,
+}
+
+impl Batch {
+    pub fn start() -> Self {
+        Batch { start: thread_faults(), ops: 0, first_reads: 0, latency: Duration::ZERO }
+    }
+
+    pub fn add(&mut self, is_first_read: bool, latency: Duration) {
+        self.ops += 1;
+        self.first_reads += u64::from(is_first_read);
+        self.latency += latency;
+    }
+}
+
+/// Fault counts of all batches of a run.
+#[derive(Debug, Default)]
+pub struct FaultStats {
+    batches: u64,
+    ops: u64,
+    first_reads: u64,
+    major_faults: u64,
+    minor_faults: u64,
+    /// Batches with at least one major fault, and their operations
+    major_batches: u64,
+    ops_in_major_batches: u64,
+    first_reads_in_major_batches: u64,
+    /// Upper bound of reads with a major fault: a batch's majors, at most one per read
+    faulting_reads_upper: u64,
+    latency_with_major: Duration,
+    latency_without_major: Duration,
+}
+
+impl FaultStats {
+    /// Take a final sample for `batch` and add it.
+    pub fn add(&mut self, batch: Batch) {
+        if batch.ops == 0 {
+            return;
+        }
+        let (major, minor) = thread_faults();
+        let major = major.saturating_sub(batch.start.0);
+        self.batches += 1;
+        self.ops += batch.ops;
+        self.first_reads += batch.first_reads;
+        self.major_faults += major;
+        self.minor_faults += minor.saturating_sub(batch.start.1);
+        if major > 0 {
+            self.major_batches += 1;
+            self.ops_in_major_batches += batch.ops;
+            self.first_reads_in_major_batches += batch.first_reads;
+            self.faulting_reads_upper += major.min(batch.ops);
+            self.latency_with_major += batch.latency;
+        } else {
+            self.latency_without_major += batch.latency;
+        }
+    }
+
+    pub fn merge(&mut self, other: &FaultStats) {
+        self.batches += other.batches;
+        self.ops += other.ops;
+        self.first_reads += other.first_reads;
+        self.major_faults += other.major_faults;
+        self.minor_faults += other.minor_faults;
+        self.major_batches += other.major_batches;
+        self.ops_in_major_batches += other.ops_in_major_batches;
+        self.first_reads_in_major_batches += other.first_reads_in_major_batches;
+        self.faulting_reads_upper += other.faulting_reads_upper;
+        self.latency_with_major += other.latency_with_major;
+        self.latency_without_major += other.latency_without_major;
+    }
+
+    pub fn report(&self, batch_size: usize) {
+        println!("\n🧩 Page Fault Attribution (fault counters sampled every {batch_size} operations):");
+        if self.ops == 0 {
+            println!("  No operations sampled");
+            return;
+        }
+        let share = |n: u64| 100.0 * n as f64 / self.ops as f64;
+        println!("  Major faults: {} ({:.3} per operation)", self.major_faults, self.major_faults as f64 / self.ops as f64);
+        println!("  Minor faults: {} ({:.3} per operation)", self.minor_faults, self.minor_faults as f64 / self.ops as f64);
+        if self.major_batches == self.faulting_reads_upper {
+            println!(
+                "  Operations with a major fault: {} ({:.2}%)",
+                self.major_batches,
+                share(self.major_batches)
+            );
+        } else {
+            println!(
+                "  Operations with a major fault: {} to {} ({:.2}% to {:.2}%)",
+                self.major_batches,
+                self.faulting_reads_upper,
+                share(self.major_batches),
+                share(self.faulting_reads_upper)
+            );
+        }
+        println!("  Flagged as first reads:       {} ({:.2}%)", self.first_reads, share(self.first_reads));
+        let repeated_in_major = self.ops_in_major_batches - self.first_reads_in_major_batches;
+        if batch_size == 1 {
+            println!("  Repeated reads with a major fault (evicted and read again): {repeated_in_major}");
+            println!(
+                "  First reads without a major fault (already cached): {}",
+                self.first_reads - self.first_reads_in_major_batches
+            );
+        } else {
+            println!("  Repeated reads in batches with a major fault: {repeated_in_major} (use --fault-batch 1 for exact counts)");
+        }
+        let mean = |latency: Duration, ops: u64| {
+            if ops == 0 { "-".to_string() } else { crate::units::format_latency(latency / ops.max(1) as u32) }
+        };
+        println!(
+            "  Mean latency: {} in batches with a major fault, {} without",
+            mean(self.latency_with_major, self.ops_in_major_batches),
+            mean(self.latency_without_major, self.ops - self.ops_in_major_batches)
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Helpers for inspecting and preparing the filesystem that holds the test files.
+
+use std::path::{Path, PathBuf};
+
+/// How free space on the target filesystem was trimmed before the run.
+#[derive(Debug, Clone)]
+pub enum TrimOutcome {
+    /// FITRIM ioctl succeeded; the kernel reports how many bytes were trimmed.
+    Ioctl { trimmed_bytes: u64 },
+    /// The `fstrim` utility succeeded; its summary line is kept verbatim.
+    Fstrim { summary: String },
+    /// Neither method worked.
+    Failed { reason: String },
+}
+
+impl std::fmt::Display for TrimOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrimOutcome::Ioctl { trimmed_bytes } => {
+                write!(f, "done via FITRIM ({trimmed_bytes} bytes trimmed)")
+            }
+            TrimOutcome::Fstrim { summary } => write!(f, "done via fstrim ({summary})"),
+            TrimOutcome::Failed { reason } => write!(f, "failed ({reason})"),
+        }
+    }
+}
+
+/// A contiguous run of a file's data on the underlying device.
+#[derive(Debug, Clone, Copy)]
+pub struct Extent {
+    pub logical: u64,
+    pub physical: u64,
+    pub length: u64,
+}
+
+/// Directory in which the test files are created, derived from the file prefix.
+pub fn target_dir(file_prefix: &str) -> PathBuf {
+    match Path::new(file_prefix).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Discard free space on the filesystem containing `dir`.
+///
+/// Tries the FITRIM ioctl on the directory first and falls back to running
+/// `fstrim` on the directory, which usually needs fewer privileges to set up.
+pub fn trim_free_space(dir: &Path) -> TrimOutcome {
+    let ioctl_error = match fitrim(dir) {
+        Ok(trimmed_bytes) => return TrimOutcome::Ioctl { trimmed_bytes },
+        Err(e) => e,
+    };
+
+    match std::process::Command::new("fstrim").arg("-v").arg(dir).output() {
+        Ok(output) if output.status.success() => TrimOutcome::Fstrim {
+            summary: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        },
+        Ok(output) => TrimOutcome::Failed {
+            reason: format!(
+                "FITRIM: {ioctl_error}; fstrim: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        },
+        Err(e) => TrimOutcome::Failed {
+            reason: format!("FITRIM: {ioctl_error}; fstrim: {e}"),
+        },
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn fitrim(dir: &Path) -> std::io::Result<u64> {
+    use std::os::unix::io::AsRawFd;
+
+    #[repr(C)]
+    struct FstrimRange {
+        start: u64,
+        len: u64,
+        minlen: u64,
+    }
+    // _IOWR('X', 121, struct fstrim_range)
+    const FITRIM: libc::c_ulong = 0xC018_5879;
+
+    let dir_file = std::fs::File::open(dir)?;
+    let mut range = FstrimRange {
+        start: 0,
+        len: u64::MAX,
+        minlen: 0,
+    };
+    let ret = unsafe { libc::ioctl(dir_file.as_raw_fd(), FITRIM as _, &mut range) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // The kernel writes the number of trimmed bytes back into `len`.
+    Ok(range.len)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn fitrim(_dir: &Path) -> std::io::Result<u64> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "FITRIM is only available on Linux",
+    ))
+}
+
+/// Size of an open file; unlike `metadata().len()` also right for block devices.
+pub fn file_len(file: &std::fs::File) -> std::io::Result<u64> {
+    use std::io::Seek;
+
+    let mut file = file;
+    file.seek(std::io::SeekFrom::End(0))
+}
+
+/// Tell the kernel that `file` is accessed randomly, which disables readahead for it.
+#[cfg(unix)]
+pub fn advise_random(file: &std::fs::File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let ret = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_RANDOM) };
+    if ret != 0 {
+        return Err(std::io::Error::from_raw_os_error(ret));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn advise_random(_file: &std::fs::File) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Ask the kernel to start reading a range of `file` into the page cache.
+#[cfg(unix)]
+pub fn advise_willneed(file: &std::fs::File, offset: u64, len: u64) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let ret = unsafe {
+        libc::posix_fadvise(
+            file.as_raw_fd(),
+            offset as libc::off_t,
+            len as libc::off_t,
+            libc::POSIX_FADV_WILLNEED,
+        )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::from_raw_os_error(ret));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn advise_willneed(_file: &std::fs::File, _offset: u64, _len: u64) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Ask the kernel to drop `file`'s clean pages from the page cache, so the next
+/// reads come from the device.
+pub fn advise_dontneed(file: &std::fs::File) -> std::io::Result<()> {
+    advise_dontneed_range(file, 0, 0)
+}
+
+/// Like [`advise_dontneed`], for `len` bytes at `offset` only (0 = to the end).
+#[cfg(unix)]
+pub fn advise_dontneed_range(file: &std::fs::File, offset: u64, len: u64) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let ret = unsafe {
+        libc::posix_fadvise(file.as_raw_fd(), offset as libc::off_t, len as libc::off_t, libc::POSIX_FADV_DONTNEED)
+    };
+    if ret != 0 {
+        return Err(std::io::Error::from_raw_os_error(ret));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn advise_dontneed_range(_file: &std::fs::File, _offset: u64, _len: u64) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Allocate `len` bytes for `file` without writing data.
+#[cfg(target_os = "linux")]
+pub fn fallocate(file: &std::fs::File, len: u64) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let len = libc::off_t::try_from(len)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "file too large for fallocate"))?;
+    let ret = unsafe { libc::fallocate(file.as_raw_fd(), 0, 0, len) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn fallocate(_file: &std::fs::File, _len: u64) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "fallocate is only available on Linux",
+    ))
+}
+
+/// Physical extents of `path`, sorted by logical offset (FIEMAP).
+#[cfg(target_os = "linux")]
+pub fn file_extents(path: &Path) -> std::io::Result<Vec<Extent>> {
+    use std::os::unix::io::AsRawFd;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct FiemapExtent {
+        fe_logical: u64,
+        fe_physical: u64,
+        fe_length: u64,
+        fe_reserved64: [u64; 2],
+        fe_flags: u32,
+        fe_reserved: [u32; 3],
+    }
+    #[repr(C)]
+    struct Fiemap {
+        fm_start: u64,
+        fm_length: u64,
+        fm_flags: u32,
+        fm_mapped_extents: u32,
+        fm_extent_count: u32,
+        fm_reserved: u32,
+        fm_extents: [FiemapExtent; BATCH],
+    }
+    // _IOWR('f', 11, struct fiemap)
+    const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+    const FIEMAP_FLAG_SYNC: u32 = 0x1;
+    const FIEMAP_EXTENT_LAST: u32 = 0x1;
+    const BATCH: usize = 128;
+
+    let file = std::fs::File::open(path)?;
+    let mut extents = Vec::new();
+    let mut start = 0u64;
+    loop {
+        let mut map = Fiemap {
+            fm_start: start,
+            fm_length: u64::MAX - start,
+            fm_flags: FIEMAP_FLAG_SYNC,
+            fm_mapped_extents: 0,
+            fm_extent_count: BATCH as u32,
+            fm_reserved: 0,
+            fm_extents: [FiemapExtent::default(); BATCH],
+        };
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as _, &mut map) };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mapped = &map.fm_extents[..map.fm_mapped_extents as usize];
+        let Some(last) = mapped.last() else { break };
+        extents.extend(mapped.iter().map(|e| Extent {
+            logical: e.fe_logical,
+            physical: e.fe_physical,
+            length: e.fe_length,
+        }));
+        if last.fe_flags & FIEMAP_EXTENT_LAST != 0 {
+            break;
+        }
+        start = last.fe_logical + last.fe_length;
+    }
+    Ok(extents)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn file_extents(_path: &Path) -> std::io::Result<Vec<Extent>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "FIEMAP is only available on Linux",
+    ))
+}
+
+/// One entry of `/proc/self/mountinfo`.
+#[derive(Debug, Clone)]
+pub struct MountInfo {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    /// Per-mount and superblock options combined.
+    pub options: Vec<String>,
+}
+
+impl MountInfo {
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options
+            .iter()
+            .any(|o| o == name || o.split_once('=').is_some_and(|(k, _)| k == name))
+    }
+
+    /// The `dax` mount option of ext4 and xfs: `always`, `inode` (per-file
+    /// attribute) or `never`; a bare `dax` means `always`.
+    pub fn dax(&self) -> Option<&str> {
+        self.options.iter().find_map(|o| match o.split_once('=') {
+            Some(("dax", mode)) => Some(mode),
+            None if o == "dax" => Some("always"),
+            _ => None,
+        })
+    }
+}
+
+/// Whether the kernel accesses `path` through DAX, bypassing the page cache (`STATX_ATTR_DAX`).
+#[cfg(target_os = "linux")]
+pub fn is_dax(path: &Path) -> std::io::Result<bool> {
+    use std::os::unix::ffi::OsStrExt;
+
+    const STATX_ATTR_DAX: u64 = 0x0020_0000;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statx = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statx(libc::AT_FDCWD, path.as_ptr(), 0, 0, &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(stat.stx_attributes_mask & STATX_ATTR_DAX != 0 && stat.stx_attributes & STATX_ATTR_DAX != 0)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_dax(_path: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// The mount that `path` lives on, chosen as the longest matching mount point.
+pub fn mount_info_for(path: &Path) -> std::io::Result<MountInfo> {
+    let path = std::fs::canonicalize(path)?;
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+
+    mountinfo
+        .lines()
+        .filter_map(parse_mountinfo_line)
+        .filter(|m| path.starts_with(&m.mount_point))
+        .max_by_key(|m| m.mount_point.as_os_str().len())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no mount found for {}", path.display()),
+            )
+        })
+}
+
+fn parse_mountinfo_line(line: &str) -> Option<MountInfo> {
+    // 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+    let (left, right) = line.split_once(" - ")?;
+    let left: Vec<&str> = left.split(' ').collect();
+    let right: Vec<&str> = right.split(' ').collect();
+    let mount_point = unescape_mount_field(left.get(4)?);
+    let mut options: Vec<String> = left.get(5)?.split(',').map(str::to_string).collect();
+    if let Some(super_options) = right.get(2) {
+        options.extend(super_options.split(',').map(str::to_string));
+    }
+    Some(MountInfo {
+        mount_point: PathBuf::from(mount_point),
+        fs_type: right.first()?.to_string(),
+        options,
+    })
+}
+
+/// Undo the octal escaping (`\040` for space etc.) used in mountinfo fields.
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 4 <= bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).ok();
+            if let Some(c) = digits.and_then(|d| u8::from_str_radix(d, 8).ok()) {
+                out.push(c);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,142 @@
+//! `--ingest`: keep writing the test files in the background while the
+//! readers run, as a database reads while it is still loading. Readers only
+//! see the part of each file that is already written.
+
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{ReadResult, calculate_statistics, print_statistics};
+
+/// Bytes appended to one file before moving on to the next.
+const CHUNK: u64 = 1024 * 1024;
+/// Byte the files are filled with, the same as at regular creation.
+const PATTERN: u8 = 0xAB;
+
+/// Progress of the ingest, shared with the readers.
+pub struct Ingest {
+    /// Bytes written at the start of every file
+    written: Vec<AtomicU64>,
+    stopped: AtomicBool,
+}
+
+impl Ingest {
+    /// Whole blocks readers may access in `file`.
+    pub fn readable_blocks(&self, file: usize, block_size: u64) -> u64 {
+        self.written[file].load(Ordering::Acquire) / block_size
+    }
+}
+
+/// Create the files with the first `fraction` of each written, and at least one block.
+pub fn prepare(file_paths: &[String], sizes: &[u64], fraction: f64, block_size: u64) -> std::io::Result<Vec<u64>> {
+    file_paths
+        .iter()
+        .zip(sizes)
+        .map(|(path, &size)| {
+            crate::dataset::create_parent(path)?;
+            let file = File::create(path)?;
+            let initial = ((size as f64 * fraction) as u64 / block_size).max(1) * block_size;
+            let initial = initial.min(size);
+            write_pattern(&file, 0, initial)?;
+            file.sync_all()?;
+            Ok(initial)
+        })
+        .collect()
+}
+
+fn write_pattern(file: &File, from: u64, to: u64) -> std::io::Result<()> {
+    let chunk = vec![PATTERN; CHUNK.min(to - from) as usize];
+    let mut offset = from;
+    while offset < to {
+        let len = (chunk.len() as u64).min(to - offset);
+        file.write_all_at(&chunk[..len as usize], offset)?;
+        offset += len;
+    }
+    Ok(())
+}
+
+/// What the background writer did.
+pub struct Summary {
+    pub bytes: u64,
+    pub elapsed: Duration,
+    /// Whether every file reached its full size before the readers finished
+    pub complete: bool,
+}
+
+/// The background writer of a running ingest.
+pub struct Ingester {
+    ingest: Arc<Ingest>,
+    handle: JoinHandle<std::io::Result<Summary>>,
+}
+
+/// Start appending to the files, one chunk per file in turn, from `written` up to `sizes`.
+pub fn start(file_paths: &[String], sizes: Vec<u64>, written: Vec<u64>) -> std::io::Result<(Arc<Ingest>, Ingester)> {
+    let files = file_paths
+        .iter()
+        .map(|path| File::options().write(true).open(path))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let ingest = Arc::new(Ingest {
+        written: written.into_iter().map(AtomicU64::new).collect(),
+        stopped: AtomicBool::new(false),
+    });
+    let writer = Arc::clone(&ingest);
+    let handle = std::thread::spawn(move || {
+        let start = Instant::now();
+        let mut bytes = 0;
+        loop {
+            let mut progressed = false;
+            for (file_index, file) in files.iter().enumerate() {
+                if writer.stopped.load(Ordering::Relaxed) {
+                    return Ok(Summary { bytes, elapsed: start.elapsed(), complete: false });
+                }
+                let written = writer.written[file_index].load(Ordering::Relaxed);
+                let end = (written + CHUNK).min(sizes[file_index]);
+                if end == written {
+                    continue;
+                }
+                write_pattern(file, written, end)?;
+                writer.written[file_index].store(end, Ordering::Release);
+                bytes += end - written;
+                progressed = true;
+            }
+            if !progressed {
+                break;
+            }
+        }
+        for file in &files {
+            file.sync_all()?;
+        }
+        Ok(Summary { bytes, elapsed: start.elapsed(), complete: true })
+    });
+    Ok((Arc::clone(&ingest), Ingester { ingest, handle }))
+}
+
+impl Ingester {
+    /// Stop writing once the readers are done and return what was written.
+    pub fn finish(self) -> std::io::Result<Summary> {
+        self.ingest.stopped.store(true, Ordering::Relaxed);
+        self.handle.join().expect("ingest writer panicked")
+    }
+}
+
+/// Print the ingest throughput and the read latency while it ran and after.
+pub fn report(summary: &Summary, results: &[ReadResult]) {
+    let secs = summary.elapsed.as_secs_f64();
+    println!(
+        "\n🚚 Ingest: wrote {:.1} MiB in {secs:.2}s ({:.1} MiB/s){}",
+        summary.bytes as f64 / (1024.0 * 1024.0),
+        summary.bytes as f64 / (1024.0 * 1024.0) / secs.max(f64::EPSILON),
+        if summary.complete { "" } else { ", stopped with the readers before the files were complete" }
+    );
+    let (during, after): (Vec<&ReadResult>, Vec<&ReadResult>) = results.iter().partition(|r| r.timestamp < summary.elapsed);
+    for (label, reads) in [("Reads during ingest", during), ("Reads after ingest", after)] {
+        if reads.is_empty() {
+            continue;
+        }
+        println!("\n  {label}:");
+        print_statistics(calculate_statistics(reads.iter().map(|r| &r.latency).collect()));
+    }
+}
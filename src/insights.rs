@@ -0,0 +1,221 @@
+//! Tuning suggestions derived from the measurements, printed after the
+//! results and recorded in the results file, for readers who would rather not
+//! interpret raw percentiles themselves.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::results::ResultsFile;
+use crate::units;
+
+/// One finding with what to try about it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Insight {
+    /// Area the finding is about, e.g. `queue_depth` or `tail_latency`
+    pub topic: &'static str,
+    /// What the measurements show
+    pub finding: String,
+    pub suggestion: String,
+}
+
+/// One step of a sweep: the swept value with its throughput and read latency.
+pub struct SweepPoint {
+    pub value: f64,
+    pub iops: f64,
+    pub median: Duration,
+}
+
+/// p99 this many times the median counts as a long tail.
+const TAIL_RATIO: f64 = 10.0;
+/// A sweep step that gains less IOPS than this fraction has saturated.
+const SATURATION_GAIN: f64 = 0.1;
+/// Median latency growth that makes a saturated step a bad trade.
+const LATENCY_GROWTH: f64 = 0.2;
+/// Medians below this are served from memory, not from a device.
+const MEMORY_SPEED: Duration = Duration::from_micros(5);
+/// A fairness index this far below what chance gives means skewed scheduling.
+const FAIRNESS_SLACK: f64 = 0.05;
+/// Share of open-loop operations a slot late from which the generator is the bottleneck.
+const LATE_PERCENT: f64 = 1.0;
+/// Block size from which transfer time dominates a read.
+const LARGE_BLOCK: usize = 64 * 1024;
+
+fn ns(nanos: u128) -> String {
+    units::format_ns(nanos as f64)
+}
+
+/// Insights from a single measured run.
+pub fn from_results(results: &ResultsFile, block_size: usize) -> Vec<Insight> {
+    let mut insights = Vec::new();
+    if results.failed_reads > 0 {
+        insights.push(Insight {
+            topic: "errors",
+            finding: format!("{} reads failed and are missing from the statistics", results.failed_reads),
+            suggestion: "Fix the cause first (see the first error); latencies of a run with failures are not comparable".to_string(),
+        });
+    }
+    if let Some(all) = &results.all {
+        let ratio = all.p99_ns as f64 / all.median_ns.max(1) as f64;
+        if ratio >= TAIL_RATIO {
+            insights.push(Insight {
+                topic: "tail_latency",
+                finding: format!("p99 ({}) is {ratio:.0}× the median ({})", ns(all.p99_ns), ns(all.median_ns)),
+                suggestion: "Correlate the slow reads with writeback and other load using --interval 1 --sample-system, or wait with --stabilize before measuring".to_string(),
+            });
+        }
+    }
+    match (&results.first, &results.repeated) {
+        (Some(first), Some(repeated)) if repeated.median_ns * 10 < first.median_ns => insights.push(Insight {
+            topic: "page_cache",
+            finding: format!(
+                "repeated reads (median {}) are {:.0}× faster than first reads (median {}): they come from the page cache",
+                ns(repeated.median_ns),
+                first.median_ns as f64 / repeated.median_ns.max(1) as f64,
+                ns(first.median_ns)
+            ),
+            suggestion: "To measure the device rather than RAM, make the dataset larger than memory or read with O_DIRECT (--poll)".to_string(),
+        }),
+        _ => {
+            if let Some(all) = results.all.as_ref().filter(|all| all.median_ns < MEMORY_SPEED.as_nanos()) {
+                insights.push(Insight {
+                    topic: "page_cache",
+                    finding: format!("the median read takes {}, which is memory speed", ns(all.median_ns)),
+                    suggestion: "Reads are served from the page cache; grow the dataset beyond RAM or read with O_DIRECT (--poll) to reach the device".to_string(),
+                });
+            }
+        }
+    }
+    if let Some(iops) = &results.iops_per_second
+        && (iops.p5 as f64) < iops.median as f64 * 0.5
+    {
+        insights.push(Insight {
+            topic: "throughput_stability",
+            finding: format!("in at least 5% of the seconds IOPS fell below {}, under half the median of {}", iops.p5, iops.median),
+            suggestion: "Look for periodic stalls with --interval 1 --sample-system; a longer run or --stabilize keeps them out of short benchmarks".to_string(),
+        });
+    }
+    let thread_medians: Vec<u128> = results.threads.iter().filter_map(|t| t.latency.as_ref()).map(|l| l.median_ns).collect();
+    if let (Some(fastest), Some(slowest)) = (thread_medians.iter().min(), thread_medians.iter().max())
+        && thread_medians.len() > 1
+        && *slowest > fastest * 2
+    {
+        insights.push(Insight {
+            topic: "thread_balance",
+            finding: format!("the slowest thread's median ({}) is over twice the fastest's ({})", ns(*slowest), ns(*fastest)),
+            suggestion: "Threads compete unevenly; check CPU placement and whether some threads read mostly cached blocks (per-thread table)".to_string(),
+        });
+    }
+    if let Some(fairness) = &results.fairness {
+        if let (Some(files), Some(chance)) = (fairness.files, fairness.files_by_chance)
+            && files < chance - FAIRNESS_SLACK
+        {
+            insights.push(Insight {
+                topic: "fairness",
+                finding: format!("operations per file have a fairness index of {files:.3} where random selection alone gives {chance:.3}"),
+                suggestion: "Files are not read as --file-weights intends; check the per-file table for files that are skipped or too small to hold a block".to_string(),
+            });
+        }
+        if fairness.threads < 1.0 - FAIRNESS_SLACK {
+            insights.push(Insight {
+                topic: "fairness",
+                finding: format!("operations per thread have a fairness index of {:.3}: some threads issued far fewer than others", fairness.threads),
+                suggestion: "Threads are starved; check CPU placement, lock contention and whether some threads wait on slower files".to_string(),
+            });
+        }
+    }
+    if let Some(lateness) = results.lateness.as_ref().filter(|l| l.late_percent >= LATE_PERCENT) {
+        insights.push(Insight {
+            topic: "generator",
+            finding: format!(
+                "{:.1}% of operations started a slot ({}) or more behind the open-loop schedule (p99 lateness {})",
+                lateness.late_percent,
+                ns(lateness.slot_ns),
+                ns(lateness.lateness.p99_ns)
+            ),
+            suggestion: "The threads, not the device, limit the rate; add --threads or --queue-depth, or lower --rate, before reading the latencies as the device's".to_string(),
+        });
+    }
+    if let (Some(reads), Some(writes)) = (&results.all, &results.writes)
+        && writes.p99_ns as f64 >= reads.p99_ns as f64 * TAIL_RATIO
+    {
+        insights.push(Insight {
+            topic: "writes",
+            finding: format!("write p99 ({}) is {:.0}× the read p99 ({})", ns(writes.p99_ns), writes.p99_ns as f64 / reads.p99_ns.max(1) as f64, ns(reads.p99_ns)),
+            suggestion: "Writes stall on writeback; measure reads without --write-percent to see their own latency".to_string(),
+        });
+    }
+    if block_size >= LARGE_BLOCK
+        && let Some(iops) = &results.iops_per_second
+    {
+        insights.push(Insight {
+            topic: "block_size",
+            finding: format!(
+                "reads of {block_size} bytes moved {:.1} MiB/s; at this size a read's latency is mostly transfer time",
+                iops.mean * block_size as f64 / (1024.0 * 1024.0)
+            ),
+            suggestion: "Block sizes of 64 KiB and more are bandwidth-bound; compare IOPS and latency at --block-size 4096 for the per-operation cost".to_string(),
+        });
+    }
+    insights
+}
+
+/// Where more queue depth stopped paying off in a `--qd-sweep`.
+pub fn from_queue_depths(points: &[SweepPoint]) -> Vec<Insight> {
+    let mut insights = Vec::new();
+    let knee = points.windows(2).find(|pair| {
+        pair[1].iops < pair[0].iops * (1.0 + SATURATION_GAIN) && pair[1].median.as_secs_f64() > pair[0].median.as_secs_f64() * (1.0 + LATENCY_GROWTH)
+    });
+    match (knee, points) {
+        (Some([before, after]), _) => insights.push(Insight {
+            topic: "queue_depth",
+            finding: format!(
+                "QD beyond {} adds latency without IOPS: at QD {} IOPS changed by {:+.0}% while the median grew {:.0}%",
+                before.value,
+                after.value,
+                100.0 * (after.iops / before.iops.max(f64::MIN_POSITIVE) - 1.0),
+                100.0 * (after.median.as_secs_f64() / before.median.as_secs_f64().max(f64::MIN_POSITIVE) - 1.0)
+            ),
+            suggestion: format!("Run at --queue-depth {} or lower; deeper queues only wait longer", before.value),
+        }),
+        (_, [.., before, last]) if last.iops >= before.iops * (1.0 + SATURATION_GAIN) => insights.push(Insight {
+            topic: "queue_depth",
+            finding: format!("IOPS still grew {:.0}% from QD {} to QD {}", 100.0 * (last.iops / before.iops.max(f64::MIN_POSITIVE) - 1.0), before.value, last.value),
+            suggestion: format!("The device is not saturated at QD {}; extend --qd-sweep to deeper queues", last.value),
+        }),
+        _ => {}
+    }
+    insights
+}
+
+/// Where the working set stopped fitting in the page cache in a `--ws-sweep`.
+pub fn from_working_sets(points: &[SweepPoint]) -> Vec<Insight> {
+    points
+        .windows(2)
+        .find(|pair| pair[1].median.as_secs_f64() >= pair[0].median.as_secs_f64() * 3.0)
+        .map(|pair| Insight {
+            topic: "working_set",
+            finding: format!(
+                "the median read grows {:.0}× from {} to {} between working sets of {}% and {}% of RAM",
+                pair[1].median.as_secs_f64() / pair[0].median.as_secs_f64().max(f64::MIN_POSITIVE),
+                units::format_latency(pair[0].median),
+                units::format_latency(pair[1].median),
+                pair[0].value,
+                pair[1].value
+            ),
+            suggestion: format!("Working sets beyond {}% of RAM no longer fit in the page cache; size caches and hot data below that", pair[0].value),
+        })
+        .into_iter()
+        .collect()
+}
+
+pub fn report(insights: &[Insight]) {
+    if insights.is_empty() {
+        return;
+    }
+    println!("\n💡 Insights:");
+    for insight in insights {
+        println!("  • [{}] {}", insight.topic, insight.finding);
+        println!("    → {}", insight.suggestion);
+    }
+}
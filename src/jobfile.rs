@@ -0,0 +1,235 @@
+//! `--job-file`: a composite workload described as tagged operation classes,
+//! e.g. index lookups, document fetches and WAL writes, each drawn by weight
+//! and reported with its own latency statistics. A class may be capped at an
+//! operation rate over all threads, leaving its share to the other classes,
+//! and given a p99 latency objective to be checked against.
+//!
+//! ```json
+//! {
+//!   "classes": [
+//!     { "tag": "index-lookup", "weight": 70, "size": "4K", "slo_p99_us": 500 },
+//!     { "tag": "doc-fetch", "weight": 25, "size": "64K" },
+//!     { "tag": "wal", "weight": 5, "op": "write", "rate": 200 }
+//!   ]
+//! }
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::workload::{OpKind, parse_size};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobSpec {
+    classes: Vec<ClassSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClassSpec {
+    tag: String,
+    weight: u32,
+    #[serde(default)]
+    op: Option<OpKind>,
+    /// Bytes per operation, e.g. `64K`; defaults to --block-size
+    #[serde(default)]
+    size: Option<String>,
+    /// Operations per second over all threads
+    #[serde(default)]
+    rate: Option<f64>,
+    #[serde(default)]
+    slo_p99_us: Option<f64>,
+}
+
+/// JSON schema of a job file, kept in step with `JobSpec` and `ClassSpec`
+/// and the checks `Job::load` applies on top of them.
+pub fn schema() -> serde_json::Value {
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "random_io_tester job file",
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["classes"],
+        "properties": {
+            "classes": {
+                "description": "Tagged operation classes, each drawn by weight",
+                "type": "array",
+                "minItems": 1,
+                "maxItems": u16::MAX,
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["tag", "weight"],
+                    "properties": {
+                        "tag": { "description": "Unique name the class is reported under", "type": "string", "minLength": 1 },
+                        "weight": { "description": "Relative share of operations; the weights must not all be zero", "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                        "op": { "description": "Kind of operation", "type": "string", "enum": ["read", "write"], "default": "read" },
+                        "size": {
+                            "description": "Bytes per operation, e.g. 64K, a positive multiple of --block-size; defaults to --block-size",
+                            "type": "string",
+                            "pattern": "^\\s*[0-9]+\\s*([bB]|[kKmMgGtT]([bB]|[iI][bB])?)?\\s*$"
+                        },
+                        "rate": { "description": "Cap in operations per second over all threads", "type": "number", "exclusiveMinimum": 0 },
+                        "slo_p99_us": { "description": "Objective for the class's p99 latency, in microseconds", "type": "number", "exclusiveMinimum": 0 }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// One tagged class of operations.
+#[derive(Debug, Clone)]
+pub struct OpClass {
+    pub tag: String,
+    pub weight: u32,
+    pub kind: OpKind,
+    pub len: u64,
+    /// Cap in operations per second over all threads
+    pub rate: Option<f64>,
+    /// Objective for the class's p99 latency, in microseconds
+    pub slo_p99_us: Option<f64>,
+}
+
+/// Longest sleep between checks for an interrupt while every class is over its rate.
+const INTERRUPT_CHECK: Duration = Duration::from_millis(50);
+
+/// The operation classes of a job file.
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub classes: Vec<OpClass>,
+    total_weight: u64,
+    /// When each rate-capped class may issue next, in nanoseconds since `epoch`; shared by all threads
+    due: Arc<Vec<AtomicU64>>,
+    epoch: Instant,
+}
+
+impl Job {
+    /// Load a job file; every operation size must be a multiple of `block_size`.
+    pub fn load(path: &Path, block_size: u64) -> Result<Job, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        let spec: JobSpec = serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        if spec.classes.is_empty() || spec.classes.len() > usize::from(u16::MAX) {
+            return Err(format!("{}: expected 1 to {} operation classes", path.display(), u16::MAX));
+        }
+        let mut classes = Vec::with_capacity(spec.classes.len());
+        for class in spec.classes {
+            if class.tag.is_empty() || classes.iter().any(|c: &OpClass| c.tag == class.tag) {
+                return Err(format!("{}: tags must be non-empty and unique, got '{}' twice or empty", path.display(), class.tag));
+            }
+            let len = match &class.size {
+                Some(size) => parse_size(size).map_err(|e| format!("{}: class '{}': {e}", path.display(), class.tag))?,
+                None => block_size,
+            };
+            if len == 0 || len % block_size != 0 {
+                return Err(format!("{}: class '{}': size {len} is not a positive multiple of --block-size {block_size}", path.display(), class.tag));
+            }
+            for (name, value) in [("rate", class.rate), ("slo_p99_us", class.slo_p99_us)] {
+                if value.is_some_and(|v| !(v.is_finite() && v > 0.0)) {
+                    return Err(format!("{}: class '{}': {name} must be a positive number", path.display(), class.tag));
+                }
+            }
+            classes.push(OpClass {
+                tag: class.tag,
+                weight: class.weight,
+                kind: class.op.unwrap_or(OpKind::Read),
+                len,
+                rate: class.rate,
+                slo_p99_us: class.slo_p99_us,
+            });
+        }
+        let total_weight = classes.iter().map(|c| u64::from(c.weight)).sum();
+        if total_weight == 0 {
+            return Err(format!("{}: the class weights add up to zero", path.display()));
+        }
+        let due = Arc::new(classes.iter().map(|_| AtomicU64::new(0)).collect());
+        Ok(Job { classes, total_weight, due, epoch: Instant::now() })
+    }
+
+    /// Index of a class drawn by weight, `None` once the run is interrupted.
+    /// A class over its `rate` is passed over for the others; when every class
+    /// left is over its rate, the thread waits for the first to become due.
+    /// `passed` is the caller's scratch space, reused across draws.
+    pub fn pick(&self, rng: &mut impl Rng, passed: &mut Vec<bool>) -> Option<usize> {
+        if !self.is_paced() {
+            return Some(self.draw(rng, &[]).unwrap_or(0));
+        }
+        passed.resize(self.classes.len(), false);
+        loop {
+            if crate::status::interrupted() {
+                return None;
+            }
+            let mut wait = Duration::MAX;
+            passed.fill(false);
+            while let Some(index) = self.draw(rng, passed) {
+                match self.claim(index) {
+                    Ok(()) => return Some(index),
+                    Err(ahead) => {
+                        passed[index] = true;
+                        wait = wait.min(ahead);
+                    }
+                }
+            }
+            std::thread::sleep(wait.min(INTERRUPT_CHECK));
+        }
+    }
+
+    /// Whether any class is capped at a rate.
+    pub fn is_paced(&self) -> bool {
+        self.classes.iter().any(|class| class.rate.is_some())
+    }
+
+    /// Draw by weight among the classes not `passed`; `None` if none is left.
+    fn draw(&self, rng: &mut impl Rng, passed: &[bool]) -> Option<usize> {
+        let open = |index: usize| passed.get(index) != Some(&true);
+        let total: u64 = if passed.contains(&true) {
+            self.classes.iter().enumerate().filter(|&(index, _)| open(index)).map(|(_, c)| u64::from(c.weight)).sum()
+        } else {
+            self.total_weight
+        };
+        if total == 0 {
+            return None;
+        }
+        let mut ticket = rng.random_range(0..total);
+        for (index, class) in self.classes.iter().enumerate().filter(|&(index, _)| open(index)) {
+            match ticket.checked_sub(u64::from(class.weight)) {
+                Some(rest) => ticket = rest,
+                None => return Some(index),
+            }
+        }
+        None
+    }
+
+    /// Take the next operation slot of a rate-capped class, or say how long
+    /// until it is due. A class that fell behind does not catch up in a burst.
+    fn claim(&self, index: usize) -> Result<(), Duration> {
+        let Some(rate) = self.classes[index].rate else { return Ok(()) };
+        let interval = (1e9 / rate) as u64;
+        let now = self.epoch.elapsed().as_nanos() as u64;
+        let due = &self.due[index];
+        let mut next = due.load(Ordering::Relaxed);
+        loop {
+            if next > now {
+                return Err(Duration::from_nanos(next - now));
+            }
+            match due.compare_exchange_weak(next, now + interval, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return Ok(()),
+                Err(current) => next = current,
+            }
+        }
+    }
+
+    pub fn has_writes(&self) -> bool {
+        self.classes.iter().any(|c| c.kind == OpKind::Write)
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.classes.iter().map(|c| c.tag.clone()).collect()
+    }
+}
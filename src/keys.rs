@@ -0,0 +1,72 @@
+//! `--keys`: single-key controls on the terminal while the test runs: `p`
+//! pauses and resumes the load, `n` skips to the next phase (the next step of
+//! a sweep, or the report of a plain run). Time spent paused is kept out of
+//! the throughput, and operations are not timed while paused.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+
+use crate::control::Control;
+
+/// The thread reading keys; restores the terminal when dropped, also on an early return.
+pub struct Keys {
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    saved: libc::termios,
+}
+
+/// Read keys from stdin if it is a terminal, switched to unbuffered input
+/// without echo; Ctrl-C keeps working.
+pub fn listen(control: Arc<Control>) -> Option<Keys> {
+    let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 0 || unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut saved) } != 0 {
+        return None;
+    }
+    let mut raw = saved;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stop = Arc::clone(&stopped);
+    let handle = std::thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            let mut poll = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+            if unsafe { libc::poll(&mut poll, 1, 100) } <= 0 {
+                continue;
+            }
+            let mut key = 0u8;
+            if unsafe { libc::read(libc::STDIN_FILENO, (&mut key as *mut u8).cast(), 1) } != 1 {
+                return;
+            }
+            match key {
+                b'p' | b' ' => {
+                    let pause = !control.is_paused();
+                    control.set_paused(pause);
+                    println!("{}", if pause { "⏸️  Paused (p resumes)" } else { "▶️  Resumed" });
+                }
+                b'n' => {
+                    control.skip_phase();
+                    println!("⏭️  Skipping to the next phase");
+                }
+                _ => {}
+            }
+        }
+    });
+    Some(Keys { stopped, handle: Some(handle), saved })
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Library side of `random_io_tester`: the block distributions behind
+//! `--distribution`, for embedders that bring their own selection logic.
+//!
+//! A custom distribution implements [`Distribution`] and is registered under
+//! the name `--distribution` refers to it by:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use rand::RngCore;
+//! use random_io_tester::{Distribution, SharedDistribution, register};
+//!
+//! /// Every read lands on the last `tail` blocks of the file.
+//! #[derive(Debug)]
+//! struct Tail(u64);
+//!
+//! impl std::fmt::Display for Tail {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         write!(f, "tail:{}", self.0)
+//!     }
+//! }
+//!
+//! impl Distribution for Tail {
+//!     fn next_block(&self, rng: &mut dyn RngCore, max_blocks: u64) -> u64 {
+//!         let tail = self.0.clamp(1, max_blocks);
+//!         max_blocks - 1 - rng.next_u64() % tail
+//!     }
+//! }
+//!
+//! register("tail", |spec| {
+//!     let tail = spec.strip_prefix("tail:").and_then(|n| n.parse().ok()).ok_or("expected tail:<blocks>")?;
+//!     Ok(Arc::new(Tail(tail)))
+//! });
+//!
+//! let distribution = SharedDistribution::parse("tail:8").unwrap();
+//! let block = distribution.next_block(&mut rand::rng(), 100);
+//! assert!((92..100).contains(&block));
+//! ```
+
+pub mod distribution;
+
+pub use distribution::{Distribution, Factory, SharedDistribution, register, register_builtins};
@@ -1,384 +1,3499 @@
-use clap::Parser;
-use crossbeam::sync::WaitGroup;
-use memmap2::MmapOptions;
-use rand::{Rng, SeedableRng};
+#[cfg(target_os = "linux")]
+mod aio;
+mod analysis;
+mod baseline;
+mod capability;
+mod checkpoint;
+mod churn;
+mod compare;
+#[cfg(target_os = "linux")]
+mod consistency;
+mod control;
+mod coverage;
+mod copy;
+mod crash;
+mod dataset;
+mod dentries;
+mod device;
+mod driver;
+mod engine;
+mod faults;
+mod fsutil;
+mod histogram;
+mod ingest;
+mod insights;
+mod jobfile;
+mod keys;
+mod mapping;
+mod msync;
+mod output;
+mod paired;
+mod precision;
+mod prefetch;
+mod presets;
+mod procfs;
+mod report;
+mod results;
+mod rng;
+mod runlock;
+mod sampler;
+mod sanity;
+mod settle;
+mod shards;
+mod soak;
+mod stabilize;
+mod schema;
+mod stats;
+mod status;
+mod timing;
+mod units;
+#[cfg(target_os = "linux")]
+mod uring;
+mod verify;
+mod workload;
+mod writeamp;
+mod writeverify;
+
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use rand::rngs::StdRng;
-use std::collections::HashSet;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use random_io_tester::distribution;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use stats::{Statistics, calculate_statistics, print_statistics};
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Number of files to create
-    #[arg(short = 'f', long, default_value_t = 10)]
+    #[arg(short = 'f', long, default_value_t = 10, global = true)]
     num_files: usize,
 
     /// Size of each file in bytes
-    #[arg(short = 's', long, default_value_t = 1024 * 1024)]
-    file_size: usize,
+    #[arg(short = 's', long, default_value_t = 1024 * 1024, global = true)]
+    file_size: u64,
 
     /// Waiting time after file creation in seconds
     #[arg(short = 'w', long, default_value_t = 1)]
     wait_time: u64,
 
+    /// Instead of --wait-time, run these steps after file creation, e.g. `syncfs,drop-dirty,sleep=5s`:
+    /// syncfs on the target file system, wait for dirty and writeback memory to fall to SIZE
+    /// (`drop-dirty=16M`, default 1M), sleep
+    #[arg(long, value_delimiter = ',', value_parser = settle::parse_step)]
+    settle: Vec<settle::Step>,
+
+    /// Instead of --wait-time, probe the device until its read latency, temperature and writeback settle
+    #[arg(long)]
+    stabilize: bool,
+
+    /// Longest --stabilize wait in seconds before measuring anyway
+    #[arg(long, default_value_t = 300, requires = "stabilize")]
+    stabilize_timeout: u64,
+
     /// Number of threads for read operations
-    #[arg(short = 't', long, default_value_t = 4)]
+    #[arg(short = 't', long, default_value_t = 4, global = true)]
     num_threads: usize,
 
     /// Random seed for reproducible experiments
-    #[arg(long, default_value_t = 42)]
+    #[arg(long, default_value_t = 42, global = true)]
     seed: u64,
 
+    /// Generator of the per-thread random streams
+    #[arg(long, value_enum, default_value_t = rng::RngKind::Stdrng, global = true)]
+    rng: rng::RngKind,
+
+    /// Pick a random seed and print it, so the run can be reproduced with --seed
+    #[arg(long, conflicts_with = "seed", global = true)]
+    randomize_seed: bool,
+
     /// Size of blocks to read in bytes
-    #[arg(short = 'b', long, default_value_t = 4096)]
+    #[arg(short = 'b', long, default_value_t = 4096, global = true)]
     block_size: usize,
 
     /// Number of read operations to perform
-    #[arg(short = 'n', long, default_value_t = 1000)]
+    #[arg(short = 'n', long, default_value_t = 1000, global = true)]
     num_operations: usize,
 
-    /// Use memory-mapped files instead of standard I/O
-    #[arg(short = 'm', long)]
+    /// I/O engine issuing the reads
+    #[arg(short = 'e', long, default_value = "standard", value_parser = clap::builder::PossibleValuesParser::new(engine::possible_values()))]
+    engine: String,
+
+    /// Reads each thread keeps in flight with an async engine
+    #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=4096))]
+    queue_depth: usize,
+
+    /// Workers of the threadpool engine, and so its operations in flight at once, whatever the thread count (default 64)
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=4096))]
+    inflight: Option<usize>,
+
+    /// Reads queued before each submission with an async engine (default: the queue depth)
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=4096))]
+    submit_batch: Option<usize>,
+
+    /// Blocks each operation reads from random places; latency covers all of them
+    #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=1024))]
+    scatter: usize,
+
+    /// Destination of the sendfile engine
+    #[arg(long, value_enum, default_value_t = CopySink::Null)]
+    copy_sink: CopySink,
+
+    /// After the random reads, read every file whole with the engine and report throughput
+    #[arg(long)]
+    whole_file_copies: bool,
+
+    /// Poll for completions instead of waiting for interrupts: O_DIRECT with IORING_SETUP_IOPOLL or preadv2(RWF_HIPRI)
+    #[arg(long)]
+    poll: bool,
+
+    /// Use memory-mapped files instead of standard I/O (same as --engine mmap)
+    #[arg(short = 'm', long, conflicts_with = "engine")]
     use_mmap: bool,
 
-    /// Prefix for test files
+    /// Map DAX files (persistent memory, CXL memory) directly with the mmap engine, MAP_SYNC for stores
+    #[arg(long, conflicts_with_all = ["engine", "device"])]
+    dax: bool,
+
+    /// Distribution of reads within a file: uniform, normal:<mean>,<stddev>, pareto:<alpha>, or file:<path> with a histogram/CDF
+    #[arg(long, default_value = "uniform", value_parser = distribution::SharedDistribution::parse)]
+    distribution: distribution::SharedDistribution,
+
+    /// Sizes of the files when they differ: a list such as 64M,256M,1G (repeated over the files) or uniform:<min>..<max>
+    #[arg(long, value_parser = workload::FileSizes::parse, conflicts_with_all = ["workload_plugin", "workload_script"])]
+    file_sizes: Option<workload::FileSizes>,
+
+    /// Size of every file, resolved from --file-sizes; empty when all are --file-size bytes
+    #[arg(skip)]
+    sizes: Vec<u64>,
+
+    /// How reads are spread over files: uniform, zipf[:<exponent>] or explicit:<w0>,<w1>,...
+    #[arg(long, default_value = "uniform", value_parser = distribution::FileWeights::parse, conflicts_with_all = ["workload_plugin", "workload_script"])]
+    file_weights: distribution::FileWeights,
+
+    /// Order of reads: random, sequential, reverse, or stride:<blocks> (negative goes backwards)
+    #[arg(long, default_value = "random", value_parser = distribution::AccessPattern::parse, conflicts_with_all = ["workload_plugin", "workload_script", "prefetch_distance"])]
+    pattern: distribution::AccessPattern,
+
+    /// Percentage of operations that write instead of read
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100), conflicts_with_all = ["workload_plugin", "workload_script"])]
+    write_percent: u8,
+
+    /// fio-style operation mix: read, write, rw[NN], randread, randwrite or randrw[NN] (NN% reads)
+    #[arg(long, value_parser = workload::RwMix::parse, conflicts_with_all = ["pattern", "write_percent", "workload_plugin", "workload_script"])]
+    rw: Option<workload::RwMix>,
+
+    /// JSON file of tagged operation classes (tag, weight, op, size) drawn instead of --write-percent, with statistics per tag
+    #[arg(long, conflicts_with_all = ["write_percent", "workload_plugin", "workload_script", "soak", "scatter"])]
+    job_file: Option<std::path::PathBuf>,
+
+    /// Operation classes loaded from --job-file
+    #[arg(skip)]
+    job: Option<jobfile::Job>,
+
+    /// When mmap writes are flushed with msync: none, block (each write), every:<n> writes, or background:<ms>
+    #[arg(long, default_value = "none", value_parser = msync::MsyncPolicy::parse)]
+    msync: msync::MsyncPolicy,
+
+    /// fdatasync after every write and report the sync latency separately from the write latency
+    #[arg(long, conflicts_with = "soak")]
+    fsync_writes: bool,
+
+    /// Compare the bytes written by the benchmark with the disk's write counters (kernel, and NVMe SMART if readable) before and after the run
+    #[arg(long, conflicts_with = "soak")]
+    write_amplification: bool,
+
+    /// Read every written block back, from the writing thread or a separate one, and report stale reads and the time until the write is visible
+    #[arg(long, value_enum)]
+    read_after_write: Option<consistency::RawMode>,
+
+    /// Check that reads return what the test files hold (the creation fill or the write pattern), on the data each read left in memory once its latency is taken
+    #[arg(long, conflicts_with_all = ["device", "read_after_write", "scatter"])]
+    verify: bool,
+
+    /// Check only this share of the reads with --verify, e.g. 1% or 0.01, chosen from the seed
+    #[arg(long, value_parser = verify::parse_sample, requires = "verify")]
+    verify_sample: Option<f64>,
+
+    /// Shape of each operation: a point read of one block or a range scan
+    #[arg(long, value_enum, default_value_t = WorkloadKind::Point, conflicts_with_all = ["workload_plugin", "workload_script", "prefetch_distance"])]
+    workload: WorkloadKind,
+
+    /// Seconds to repeat write-verify cycles with --workload write-verify
+    #[arg(long, default_value_t = 60)]
+    write_verify_secs: u64,
+
+    /// Distinct random blocks written and read back per write-verify cycle
+    #[arg(long, default_value_t = 1024)]
+    write_verify_blocks: usize,
+
+    /// Length of each scan with --workload scan: a size or a range, e.g. 1MB..16MB
+    #[arg(long, default_value = "1MB..16MB", value_parser = workload::ScanLength::parse)]
+    scan_length: workload::ScanLength,
+
+    /// Drift the distribution's hot region across the file at this rate, e.g. 1%/s or 5%/min
+    #[arg(long, value_parser = distribution::parse_rate, conflicts_with_all = ["workload_plugin", "workload_script", "prefetch_distance"])]
+    hotspot_move: Option<f64>,
+
+    /// Prefix for test files; the run ID is appended so concurrent runs in one directory stay apart
     #[arg(long, default_value = "testfile")]
     file_prefix: String,
+
+    /// Use --file-prefix exactly as given, without appending the run ID
+    #[arg(long)]
+    no_namespace: bool,
+
+    /// Benchmark this block device (e.g. /dev/nvme0n1) directly instead of test files; nothing is created or deleted
+    #[arg(long, conflicts_with_all = ["reuse_existing", "file_sizes", "file_weights", "dirs_depth", "zone_aware", "inject_corruption", "checksums"])]
+    device: Option<std::path::PathBuf>,
+
+    /// Allow writes to the --device, destroying whatever it holds
+    #[arg(long, requires = "device")]
+    allow_destructive: bool,
+
+    /// Reuse test files left by an earlier run after checking them against its manifest, and keep them afterwards
+    #[arg(long)]
+    reuse_existing: bool,
+
+    /// Record a checksum of every MiB of each file in the dataset manifest, for `verify-dataset`
+    #[arg(long)]
+    checksums: bool,
+
+    /// Flip one bit in this fraction of blocks (e.g. 0.01) after creation, to test that `verify-dataset` finds them
+    #[arg(long, requires = "reuse_existing")]
+    inject_corruption: Option<f64>,
+
+    /// Spread the files over a directory tree this many levels deep below `<prefix>_tree` (0 = all in one directory)
+    #[arg(long, default_value_t = 0)]
+    dirs_depth: usize,
+
+    /// Number of subdirectories per level of the --dirs-depth tree
+    #[arg(long, default_value_t = 16, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=65536))]
+    dirs_fanout: usize,
+
+    /// Trim free space on the target filesystem (FITRIM/fstrim) at the given point
+    #[arg(long, value_enum)]
+    trim: Option<TrimWhen>,
+
+    /// Write files zone-by-zone and annotate reads with the device zone they hit
+    #[arg(long)]
+    zone_aware: bool,
+
+    /// Abort instead of warning when the environment would invalidate the results
+    #[arg(long)]
+    strict: bool,
+
+    /// Reporting interval in seconds for per-interval statistics (0 = disabled)
+    #[arg(short = 'i', long, default_value_t = 0)]
+    interval: u64,
+
+    /// Also print the median and p99 read latency of every thread in every interval
+    #[arg(long)]
+    thread_matrix: bool,
+
+    /// Cap the operations per second over all threads; each thread paces itself to an equal share
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    rate: Option<u64>,
+
+    /// With --rate, keep each thread's schedule fixed instead of waiting for the previous operation: a thread that falls behind issues at once to catch up, and how late each operation started is reported
+    #[arg(long, requires = "rate")]
+    open_loop: bool,
+
+    /// With --rate, find the thread count first: probe for a second at 1, 2, 4, ... threads up to --threads and run with the first that reaches the rate
+    #[arg(long, requires = "rate", conflicts_with_all = ["ws_sweep", "qd_sweep", "resume"])]
+    auto_threads: bool,
+
+    /// Listen on this Unix socket for pause, resume, rate <ops/s|off>, report (close the soak interval now) and status
+    #[arg(long, conflicts_with_all = ["ws_sweep", "qd_sweep"])]
+    control_socket: Option<std::path::PathBuf>,
+
+    /// Single-key controls on the terminal: p pauses and resumes the load, n skips to the next phase; paused time is kept out of the throughput
+    #[arg(long)]
+    keys: bool,
+
+    /// Soak test for this many seconds (0 = until interrupted): operations are folded into
+    /// per-interval histograms and summaries appended to --soak-log, so memory stays bounded
+    #[arg(long, conflicts_with_all = ["ws_sweep", "qd_sweep", "checkpoint", "resume"])]
+    soak: Option<u64>,
+
+    /// File the soak interval summaries are appended to as JSON lines
+    #[arg(long, default_value = "soak-{host}-{ts}-{id}.jsonl")]
+    soak_log: String,
+
+    /// Also log every soak operation as a CSV row to this file
+    #[arg(long)]
+    soak_raw_log: Option<String>,
+
+    /// Rotate the soak logs when a file exceeds this many MiB, keeping the last four
+    #[arg(long, default_value_t = 256)]
+    soak_rotate_mb: u64,
+
+    /// Sample CPU iowait, dirty pages and load average at each reporting interval
+    #[arg(long)]
+    sample_system: bool,
+
+    /// Subtract the calibrated harness overhead from every measured latency
+    #[arg(long)]
+    subtract_overhead: bool,
+
+    /// Clock used to time operations (tsc falls back to monotonic off x86_64)
+    #[arg(long, value_enum, default_value_t = timing::ClockSource::Monotonic, global = true)]
+    clock: timing::ClockSource,
+
+    /// How the mmap engine touches each block: copy it, or only fault in its pages
+    #[arg(long, value_enum, default_value_t = MmapTouch::FullBlock)]
+    mmap_touch: MmapTouch,
+
+    /// Memory that reads land in: pageable heap buffers or pinned (mlocked, huge page) buffers
+    #[arg(long, value_enum, default_value_t = BufferKind::Pageable)]
+    buffers: BufferKind,
+
+    /// Map files in segments of this many bytes, created on first access (0 = whole files up front)
+    #[arg(long, default_value_t = 0)]
+    mmap_segment_size: u64,
+
+    /// Cap on the total bytes mapped at once; least recently used segments are unmapped (0 = no cap)
+    #[arg(long, default_value_t = 0)]
+    mmap_max_mapped: u64,
+
+    /// How test files are created: written with data, sparse, or fallocated
+    #[arg(long, value_enum, default_value_t = CreateMode::Write)]
+    create_mode: CreateMode,
+
+    /// Order in which files are created, so physical placement varies between datasets (shuffled and interleaved follow --seed)
+    #[arg(long, value_enum, default_value_t = CreateOrder::Sequential)]
+    create_order: CreateOrder,
+
+    /// Create the test files from this many processes, each writing a share of the files balanced by size
+    #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=1024))]
+    create_procs: usize,
+
+    /// Spread the test files round-robin over these directories (repeat the option), named after --file-prefix; each --create-procs process writes into one of them, and run markers, probes, checks, readahead, trim and syncfs cover them all
+    #[arg(long = "target-dir", value_name = "DIR", conflicts_with_all = ["device", "dirs_depth"])]
+    target_dirs: Vec<std::path::PathBuf>,
+
+    /// Write only this fraction of each file up front (e.g. 0.1) and the rest in the background while the readers run, reading only written blocks
+    #[arg(long, conflicts_with_all = ["reuse_existing", "device", "ws_sweep", "qd_sweep", "checkpoint", "resume", "zone_aware", "prefetch_distance", "workload_plugin", "workload_script"])]
+    ingest: Option<f64>,
+
+    /// Touch only a seeded random subset of each file's blocks of this size (e.g. 25%), whatever the dataset size
+    #[arg(long, value_parser = coverage::parse_coverage, conflicts_with_all = ["ingest", "ws_sweep", "prefetch_distance", "workload_plugin", "workload_script"])]
+    coverage: Option<f64>,
+
+    /// Draw every thread's operations before the measured phase, so the readers only replay them (costs memory per operation)
+    #[arg(long, conflicts_with_all = ["soak", "ingest", "hotspot_move", "prefetch_distance", "ws_sweep", "qd_sweep"])]
+    pregenerate: bool,
+
+    /// Stream a sparse file through the page cache at this rate (e.g. 256M/s) while the readers run, evicting cached blocks without device I/O
+    #[arg(long, value_parser = churn::parse_rate, conflicts_with_all = ["device", "ws_sweep", "qd_sweep"])]
+    cache_churn: Option<u64>,
+
+    /// Size of the --cache-churn file (default twice the RAM)
+    #[arg(long, value_parser = workload::parse_size, requires = "cache_churn")]
+    cache_churn_size: Option<u64>,
+
+    /// Grow a directory tree of this many empty files next to the test files and keep looking them up, in one-second bursts, to pressure the dentry and inode caches
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["device", "ws_sweep", "qd_sweep"])]
+    metadata_churn: Option<u64>,
+
+    /// Repeat the measurement with working sets of these percentages of RAM (e.g. 10,25,50,100,200)
+    #[arg(long, value_delimiter = ',')]
+    ws_sweep: Vec<f64>,
+
+    /// Repeat the measurement at these queue depths with an async engine (e.g. 1,2,4,8,16,32,64)
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["ws_sweep", "queue_depth", "scatter"])]
+    qd_sweep: Vec<usize>,
+
+    /// Readahead in KiB: 0 disables it per file (fadvise/madvise RANDOM), N > 0 sets the device's read_ahead_kb
+    #[arg(long)]
+    readahead: Option<u64>,
+
+    /// Run a prefetch thread that issues WILLNEED hints this many operations ahead of each reader
+    #[arg(long)]
+    prefetch_distance: Option<usize>,
+
+    /// Generate operations with a WASM module instead of random blocks (needs the wasm-plugins feature)
+    #[arg(long, conflicts_with_all = ["ws_sweep", "prefetch_distance"])]
+    workload_plugin: Option<std::path::PathBuf>,
+
+    /// Compute read offsets with a Rhai script instead of random blocks (needs the scripting feature)
+    #[arg(long, conflicts_with_all = ["ws_sweep", "prefetch_distance", "workload_plugin"])]
+    workload_script: Option<std::path::PathBuf>,
+
+    /// Also measure a no-op syscall and a cached pread and report them with the results
+    #[arg(long)]
+    syscall_baseline: bool,
+
+    /// Report how often blocks were read and what fraction of the dataset was touched
+    #[arg(long)]
+    access_counts: bool,
+
+    /// Report the distances between consecutive operations of each thread and of all threads merged
+    #[arg(long)]
+    seek_distances: bool,
+
+    /// Report the gaps between the issue times of consecutive operations of each thread, separately from latency
+    #[arg(long)]
+    inter_arrival: bool,
+
+    /// Fit the read latencies to a lognormal and a two-component lognormal mixture and report parameters and goodness of fit
+    #[arg(long)]
+    fit_distributions: bool,
+
+    /// Sample the reader threads' page-fault counters every this many operations and estimate how many mmap reads took a major fault (0 = off)
+    #[arg(long, default_value_t = 0)]
+    fault_batch: usize,
+
+    /// After the run, evict this many random blocks one at a time and read each twice, for matched cold/warm latency pairs
+    #[arg(long, default_value_t = 0)]
+    paired_samples: usize,
+
+    /// Keep reading until the 95% confidence interval of --target-percentile is within this bound (e.g. 1%); --num-operations becomes the upper limit
+    #[arg(long, value_parser = precision::parse_precision)]
+    target_precision: Option<f64>,
+
+    /// Percentile whose confidence interval --target-precision tracks
+    #[arg(long, default_value_t = 50.0, requires = "target_precision")]
+    target_percentile: f64,
+
+    /// Add a trimmed statistics block that leaves out reads started in the first this-many seconds
+    #[arg(long, default_value_t = 0.0)]
+    trim_warmup: f64,
+
+    /// Add a trimmed statistics block that leaves out this percentage of the fastest and of the slowest reads
+    #[arg(long, default_value_t = 0.0)]
+    trim_percent: f64,
+
+    /// Unit of the printed latencies
+    #[arg(long, value_enum, default_value_t = units::TimeUnit::Auto, global = true)]
+    time_unit: units::TimeUnit,
+
+    /// Write a JSON summary of the results to --output
+    #[arg(long)]
+    json: bool,
+
+    /// Exit with status 3 if more reads than this fail (default: failed reads are only reported)
+    #[arg(long)]
+    max_read_errors: Option<u64>,
+
+    /// Exit with status 4 if the 99th percentile latency exceeds this many microseconds
+    #[arg(long)]
+    sla_p99_us: Option<f64>,
+
+    /// Write the full report to this file and print only a short summary
+    #[arg(long, global = true)]
+    report_file: Option<std::path::PathBuf>,
+
+    /// Print only a plain one-line summary (the full report goes to --report-file, if given)
+    #[arg(short = 'q', long, global = true)]
+    quiet: bool,
+
+    /// Results file name; {host}, {ts} and {id} become the host name, UTC start time and run ID
+    #[arg(long, default_value = "results-{host}-{ts}-{id}.json")]
+    output: String,
+
+    /// Also write the run as FORMAT=TARGET, repeatable: text (the report), json (the results summary) or csv (one row per operation); TARGET is a file name or - for standard output
+    #[arg(long, value_parser = report::parse_sink)]
+    out: Vec<report::SinkSpec>,
+
+    /// Periodically save aggregated results and progress to this file
+    #[arg(long, conflicts_with_all = ["ws_sweep", "qd_sweep"])]
+    checkpoint: Option<std::path::PathBuf>,
+
+    /// Seconds between checkpoints
+    #[arg(long, default_value_t = 60)]
+    checkpoint_interval: u64,
+
+    /// Continue an interrupted run from a checkpoint written with --checkpoint
+    #[arg(long, conflicts_with_all = ["ws_sweep", "qd_sweep"])]
+    resume: Option<std::path::PathBuf>,
 }
 
-#[derive(Debug, Clone)]
-struct ReadResult {
-    latency: Duration,
-    is_first_read: bool,
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum CreateMode {
+    /// Write the test pattern to every byte
+    Write,
+    /// Only set the file length; no blocks are allocated
+    Sparse,
+    /// Allocate blocks with fallocate without writing data
+    Fallocate,
 }
 
-#[derive(Debug)]
-struct Statistics {
-    count: usize,
-    avg: Duration,
-    median: Duration,
-    p90: Duration,
-    p95: Duration,
-    p99: Duration,
-    min: Duration,
-    max: Duration,
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum CreateOrder {
+    /// File 0 to N-1, one after the other
+    Sequential,
+    /// One file after the other, in a seeded random order
+    Shuffled,
+    /// Chunks of all files in a seeded random order, so their extents interleave (--create-mode write)
+    Interleaved,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+/// Soak interval when --interval is not given
+const SOAK_INTERVAL_SECS: u64 = 60;
 
-    println!("🚀 Random I/O Tester Starting...");
-    println!("Configuration:");
-    println!("  Files: {} × {} bytes", args.num_files, args.file_size);
-    println!("  Threads: {}", args.num_threads);
-    println!("  Block size: {} bytes", args.block_size);
-    println!("  Operations: {}", args.num_operations);
-    println!("  Mode: {}", if args.use_mmap { "Memory-mapped" } else { "Standard I/O" });
-    println!("  Seed: {}", args.seed);
-    println!();
+/// How often dirty and writeback page counts are sampled during mmap writes
+const WRITEBACK_SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
 
-    // Phase 1: Create test files
-    println!("📝 Creating test files...");
-    let file_paths = create_test_files(&args)?;
-    println!("✅ Created {} files", file_paths.len());
+/// Size of the buffer used to stream the test pattern into files
+const CREATE_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
 
-    // Phase 2: Wait
-    println!("⏳ Waiting {} seconds...", args.wait_time);
-    std::thread::sleep(Duration::from_secs(args.wait_time));
+/// Operations from this size on also get latency per MiB transferred
+const LARGE_TRANSFER: u64 = 64 * 1024;
 
-    // Phase 3: Run performance tests
-    println!("🔬 Running performance tests...");
-    let results = if args.use_mmap {
-        run_mmap_tests(&args, &file_paths)?
-    } else {
-        run_standard_io_tests(&args, &file_paths)?
-    };
+impl Args {
+    /// Whether any operation may write: --write-percent or a write class in the --job-file.
+    fn writes(&self) -> bool {
+        self.write_percent > 0 || self.job.as_ref().is_some_and(jobfile::Job::has_writes)
+    }
 
-    // Phase 4: Analyze and report results
-    println!("\n📊 Performance Results:");
-    analyze_and_report_results(results);
+    /// Size of test file `file` in bytes.
+    fn file_size_of(&self, file: usize) -> u64 {
+        self.sizes.get(file).copied().unwrap_or(self.file_size)
+    }
 
-    // Cleanup
-    cleanup_test_files(&file_paths)?;
-    println!("\n🧹 Cleaned up test files");
+    /// Size of all test files together, `None` on overflow.
+    fn dataset_bytes(&self) -> Option<u64> {
+        (0..self.num_files).try_fold(0u64, |total, file| total.checked_add(self.file_size_of(file)))
+    }
+}
 
-    Ok(())
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Measure random block copies from anonymous memory as an in-memory upper bound
+    Baseline,
+    /// Print a shell completion script, e.g. `random_io_tester completions bash > /etc/bash_completion.d/random_io_tester`
+    Completions { shell: clap_complete::Shell },
+    /// Print the JSON schema of the --job-file format
+    Schema {
+        /// Print the schema of the command-line options instead, keyed by long name
+        #[arg(long)]
+        options: bool,
+    },
+    /// Kill a synchronous writer process with SIGKILL at random points and check that every write it acknowledged survived
+    CrashTest {
+        /// Number of writer processes to start and kill
+        #[arg(long, default_value_t = 10)]
+        rounds: u32,
+        /// Kill each writer after a random delay of up to this many milliseconds
+        #[arg(long, default_value_t = 500)]
+        max_kill_ms: u64,
+    },
+    /// Creator process of `--create-procs`, creating the files listed on stdin
+    #[command(hide = true)]
+    CreateShard {
+        #[arg(long, value_enum)]
+        mode: CreateMode,
+    },
+    /// Writer process of `crash-test`
+    #[command(hide = true)]
+    CrashWriter {
+        #[arg(long)]
+        round: u32,
+    },
+    /// Re-read the dataset kept at --file-prefix and compare it with its manifest, e.g. to detect bit rot
+    VerifyDataset,
+    /// Compare repeated runs of two configurations (their --json results files) and test whether the differences are significant
+    Compare {
+        /// Results files of the baseline runs
+        #[arg(long, num_args = 1.., required = true)]
+        baseline: Vec<std::path::PathBuf>,
+        /// Results files of the candidate runs
+        #[arg(long, num_args = 1.., required = true)]
+        candidate: Vec<std::path::PathBuf>,
+        /// Significance level of the Mann-Whitney U test
+        #[arg(long, default_value_t = 0.05)]
+        alpha: f64,
+    },
+    /// List, print or run the bundled workload presets (cold-random-read, oltp-mixed, wal-commit, compaction, full-scan)
+    Presets {
+        #[command(subcommand)]
+        action: PresetAction,
+    },
 }
 
-fn create_test_files(args: &Args) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let mut file_paths = Vec::new();
-    
-    // Create test data buffer
-    let test_data = vec![0xAB; args.file_size];
-    
-    for i in 0..args.num_files {
-        let file_path = format!("{}_{}.dat", args.file_prefix, i);
-        let mut file = File::create(&file_path)?;
-        file.write_all(&test_data)?;
-        file.sync_all()?;
-        file_paths.push(file_path);
-    }
-    
-    Ok(file_paths)
+#[derive(Subcommand, Debug, Clone)]
+enum PresetAction {
+    /// List the presets with a description of each
+    List,
+    /// Print the options of a preset as a command line to copy and edit
+    Show { name: String },
+    /// Run a preset; options after the name override the preset's, e.g. `presets run oltp-mixed -t 32 -n 1000000`
+    Run {
+        name: String,
+        /// Options overriding the preset's
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        overrides: Vec<std::ffi::OsString>,
+    },
 }
 
-fn run_standard_io_tests(args: &Args, file_paths: &[String]) -> Result<Vec<ReadResult>, Box<dyn std::error::Error>> {
-    let results = Arc::new(Mutex::new(Vec::new()));
-    let read_blocks = Arc::new(Mutex::new(HashSet::new()));
-    
-    // Prepare random operations for each thread
-    let operations_per_thread = args.num_operations / args.num_threads;
-    let remainder = args.num_operations % args.num_threads;
-    
-    let wg = WaitGroup::new();
-    
-    for thread_id in 0..args.num_threads {
-        let thread_operations = operations_per_thread + if thread_id < remainder { 1 } else { 0 };
-        let results_clone = Arc::clone(&results);
-        let read_blocks_clone = Arc::clone(&read_blocks);
-        let file_paths_clone = file_paths.to_vec();
-        let args_clone = args.clone();
-        let wg_clone = wg.clone();
-        
-        std::thread::spawn(move || {
-            let _guard = wg_clone;
-            
-            // Create thread-specific RNG with derived seed
-            let mut rng = StdRng::seed_from_u64(args_clone.seed + thread_id as u64);
-            let mut thread_results = Vec::new();
-            
-            for _ in 0..thread_operations {
-                // Select random file
-                let file_index = rng.random_range(0..file_paths_clone.len());
-                let file_path = &file_paths_clone[file_index];
-                
-                // Calculate random block position
-                let max_blocks = args_clone.file_size / args_clone.block_size;
-                if max_blocks == 0 { continue; }
-                
-                let block_index = rng.random_range(0..max_blocks);
-                let offset = block_index * args_clone.block_size;
-                
-                // Check if this block has been read before
-                let is_first_read = {
-                    let mut blocks = read_blocks_clone.lock().unwrap();
-                    blocks.insert(format!("{file_index}:{block_index}"))
-                };
-                
-                // Perform the read operation
-                let start = Instant::now();
-                let result = perform_standard_read(file_path, offset, args_clone.block_size);
-                let latency = start.elapsed();
-                
-                if result.is_ok() {
-                    thread_results.push(ReadResult {
-                        latency,
-                        is_first_read,
-                    });
-                }
-            }
-            
-            // Add thread results to global results
-            {
-                let mut global_results = results_clone.lock().unwrap();
-                global_results.extend(thread_results);
-            }
-        });
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum TrimWhen {
+    /// Before the test files are created
+    Before,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum MmapTouch {
+    /// Read a single byte at the start of the block
+    FirstByte,
+    /// Read one byte in every page of the block
+    PerPage,
+    /// Copy the whole block out of the mapping
+    FullBlock,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum BufferKind {
+    /// Ordinary heap memory, reused by every read of a thread
+    Pageable,
+    /// mlocked memory on huge pages, reused by every read of a thread, as for GPU or RDMA transfers
+    Pinned,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum WorkloadKind {
+    /// One block per operation
+    Point,
+    /// A contiguous range of --scan-length bytes per operation
+    Scan,
+    /// Cycles of writing random blocks, syncing, dropping the cache and reading them back to compare
+    WriteVerify,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum CopySink {
+    /// /dev/null
+    Null,
+    /// A Unix socket drained by a helper thread
+    Socket,
+}
+
+#[derive(Debug, Clone)]
+struct ReadResult {
+    /// Start of the operation, relative to the start of the measured phase
+    timestamp: Duration,
+    latency: Duration,
+    is_first_read: bool,
+    zone: Option<u64>,
+    /// Reader thread that issued the operation
+    thread: usize,
+    /// Index of the file the operation went to
+    file: usize,
+    /// Byte offset of the operation in its file
+    offset: u64,
+    /// Bytes the operation read or wrote
+    bytes: u64,
+    /// A write rather than a read
+    is_write: bool,
+    /// Time from issuing the read to submitting it, for async engines
+    submit_delay: Option<Duration>,
+    /// Time of the fdatasync after a write (`--fsync-writes`)
+    sync_latency: Option<Duration>,
+    /// Index of the operation's class in the --job-file
+    tag: Option<u16>,
+    /// How far behind its `--open-loop` schedule the operation started
+    lateness: Option<Duration>,
+}
+
+/// How often each `(file index, block index)` has been read.
+type BlockCounts = HashMap<(usize, u64), u64>;
+
+/// State shared by all reader threads of one measured phase.
+struct RunContext {
+    run_start: Instant,
+    clock: timing::Clock,
+    /// Reading of `clock` taken at `run_start`
+    start_ticks: u64,
+    zone_map: Option<Arc<device::ZoneMap>>,
+    /// Restrict reads to the first this-many blocks of every file
+    working_set_blocks: Option<u64>,
+    /// Blocks read so far with their read counts, for first-read tracking
+    read_blocks: Arc<Mutex<BlockCounts>>,
+    /// Aggregated results that readers publish for checkpointing
+    progress: Option<Arc<Mutex<checkpoint::Progress>>>,
+    /// Subtracted from every measured latency
+    latency_correction: Duration,
+    /// Source of operations replacing the random block picker
+    workload: Option<Arc<dyn workload::WorkloadSource>>,
+    /// Reads that returned an error, left out of the results
+    failed_reads: Arc<AtomicU64>,
+    /// Error of the first failed read
+    first_error: Arc<Mutex<Option<String>>>,
+    /// Reads whose contents `--verify` checked, and found wrong
+    verified_reads: Arc<AtomicU64>,
+    mismatched_reads: Arc<AtomicU64>,
+    /// Page faults sampled around batches of operations (`--fault-batch`)
+    faults: Option<Arc<Mutex<faults::FaultStats>>>,
+    /// Stops the readers early once `--target-precision` is reached
+    precision: Option<Arc<precision::PrecisionTarget>>,
+    /// Collects `--soak` operations into interval histograms instead of results
+    soak: Option<Arc<soak::Soak>>,
+    /// How much of each file `--ingest` has written so far
+    ingest: Option<Arc<ingest::Ingest>>,
+    /// Every reader's operations, drawn before the run by `--pregenerate`
+    schedules: Option<Arc<Mutex<Vec<Vec<workload::Operation>>>>>,
+    /// Pause state, rate cap and phase skip (`--rate`, `--control-socket`, `--keys`)
+    control: Option<Arc<control::Control>>,
+}
+
+impl RunContext {
+    /// Time since the phase began, less the time it spent paused.
+    fn elapsed(&self) -> Duration {
+        let elapsed = self.run_start.elapsed();
+        self.control.as_ref().map_or(elapsed, |control| control.unpaused(elapsed))
     }
-    
-    // Wait for all threads to complete
-    wg.wait();
-    
-    let results = results.lock().unwrap();
-    Ok(results.clone())
 }
 
-fn perform_standard_read(file_path: &str, offset: usize, block_size: usize) -> Result<Vec<u8>, std::io::Error> {
-    let mut file = File::open(file_path)?;
-    file.seek(SeekFrom::Start(offset as u64))?;
-    
-    let mut buffer = vec![0u8; block_size];
-    file.read_exact(&mut buffer)?;
-    
-    Ok(buffer)
+/// Zone size assumed for `--zone-aware` when the device does not report zones.
+const NOMINAL_ZONE_SIZE: u64 = 256 * 1024 * 1024;
+
+fn main() {
+    let outcome = run().unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        let status = if e.is::<status::ConfigError>() { status::Status::ConfigError } else { status::Status::Error };
+        status::Outcome {
+            status,
+            details: vec![("error", format!("{:?}", e.to_string()))],
+        }
+    });
+    outcome.emit();
+    std::process::exit(outcome.status.code());
 }
 
-fn run_mmap_tests(args: &Args, file_paths: &[String]) -> Result<Vec<ReadResult>, Box<dyn std::error::Error>> {
-    // Memory map all files first
-    let mut mmaps = Vec::new();
-    for file_path in file_paths {
-        let file = File::open(file_path)?;
-        let mmap = unsafe { MmapOptions::new().map(&file)? };
-        mmaps.push(mmap);
+fn run() -> Result<status::Outcome, Box<dyn std::error::Error>> {
+    distribution::register_builtins();
+    let mut args = Args::parse();
+    if let Some(Command::Presets { action: PresetAction::Run { ref name, .. } }) = args.command {
+        let preset = presets::find(name).map_err(status::ConfigError)?;
+        let argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+        let matches = Args::command().args_override_self(true).get_matches_from(presets::command_line(preset, &argv));
+        args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        println!("📦 Preset {}: {}", preset.name, preset.description);
     }
-    
-    let results = Arc::new(Mutex::new(Vec::new()));
-    let read_blocks = Arc::new(Mutex::new(HashSet::new()));
-    let mmaps = Arc::new(mmaps);
-    
-    // Prepare random operations for each thread
-    let operations_per_thread = args.num_operations / args.num_threads;
-    let remainder = args.num_operations % args.num_threads;
-    
-    let wg = WaitGroup::new();
-    
-    for thread_id in 0..args.num_threads {
-        let thread_operations = operations_per_thread + if thread_id < remainder { 1 } else { 0 };
-        let results_clone = Arc::clone(&results);
-        let read_blocks_clone = Arc::clone(&read_blocks);
-        let mmaps_clone = Arc::clone(&mmaps);
-        let args_clone = args.clone();
-        let wg_clone = wg.clone();
-        
-        std::thread::spawn(move || {
-            let _guard = wg_clone;
-            
-            // Create thread-specific RNG with derived seed
-            let mut rng = StdRng::seed_from_u64(args_clone.seed + thread_id as u64);
-            let mut thread_results = Vec::new();
-            
-            for _ in 0..thread_operations {
-                // Select random file
-                let file_index = rng.random_range(0..mmaps_clone.len());
-                
-                // Calculate random block position
-                let max_blocks = args_clone.file_size / args_clone.block_size;
-                if max_blocks == 0 { continue; }
-                
-                let block_index = rng.random_range(0..max_blocks);
-                let offset = block_index * args_clone.block_size;
-                
-                // Check if this block has been read before
-                let is_first_read = {
-                    let mut blocks = read_blocks_clone.lock().unwrap();
-                    blocks.insert(format!("{file_index}:{block_index}"))
-                };
-                
-                // Perform the memory access
-                let start = Instant::now();
-                let result = perform_mmap_read(&mmaps_clone[file_index], offset, args_clone.block_size);
-                let latency = start.elapsed();
-                
-                if result.is_ok() {
-                    thread_results.push(ReadResult {
-                        latency,
-                        is_first_read,
-                    });
+    units::set_time_unit(args.time_unit);
+    match args.command {
+        Some(Command::Completions { shell }) => {
+            let mut cmd = Args::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+            return Ok(status::Outcome::success());
+        }
+        Some(Command::Presets { action: PresetAction::List }) => {
+            presets::list();
+            return Ok(status::Outcome::success());
+        }
+        Some(Command::Presets { action: PresetAction::Show { ref name } }) => {
+            presets::show(presets::find(name).map_err(status::ConfigError)?);
+            return Ok(status::Outcome::success());
+        }
+        Some(Command::Schema { options }) => {
+            let schema = if options { schema::options_schema(&Args::command()) } else { jobfile::schema() };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+            return Ok(status::Outcome::success());
+        }
+        Some(Command::VerifyDataset) => {
+            return verify_dataset(&args).map(|()| status::Outcome::success());
+        }
+        Some(Command::Compare { ref baseline, ref candidate, alpha }) => {
+            return compare::run(baseline, candidate, alpha).map(|()| status::Outcome::success());
+        }
+        Some(Command::CreateShard { mode }) => {
+            return shards::run_shard(mode).map(|()| status::Outcome::success());
+        }
+        Some(Command::CrashWriter { round }) => {
+            return crash::run_writer(&args, round).map(|()| status::Outcome::success());
+        }
+        _ => {}
+    }
+    // Before anything divides by the block size
+    reject_zero_counts(&args).map_err(status::ConfigError)?;
+    if args.use_mmap || args.dax {
+        args.engine = "mmap".to_string();
+    }
+    args.file_weights.resolve(args.num_files).map_err(status::ConfigError)?;
+    if let Some(mix) = args.rw {
+        args.pattern = mix.pattern;
+        args.write_percent = mix.write_percent;
+    }
+    if let Some(path) = report::text_file(&args.out).map_err(status::ConfigError)? {
+        if args.report_file.as_ref().is_some_and(|report_file| *report_file != path) {
+            return Err(status::ConfigError("--out text=FILE and --report-file name different files".to_string()).into());
+        }
+        args.report_file = Some(path);
+    }
+    let mut redirect = if args.report_file.is_some() || args.quiet {
+        Some(output::ReportRedirect::start(args.report_file.as_deref()).map_err(|e| format!("cannot redirect the report: {e}"))?)
+    } else {
+        None
+    };
+    if args.randomize_seed {
+        args.seed = rand::random();
+        println!("🎲 Random seed {} (pass --seed {} to reproduce this run)", args.seed, args.seed);
+    }
+    if let Some(sizes) = &args.file_sizes {
+        args.sizes = sizes.resolve(args.num_files, args.seed, args.block_size as u64);
+    }
+    if let Some(path) = &args.job_file {
+        args.job = Some(jobfile::Job::load(path, args.block_size as u64).map_err(status::ConfigError)?);
+    }
+
+    if let Some(Command::Baseline) = args.command {
+        return baseline::run(&args).map(|()| status::Outcome::success());
+    }
+    let raw_device = args.device.clone().map(|path| open_raw_device(&mut args, &path)).transpose().map_err(status::ConfigError)?;
+    validate_config(&args).map_err(status::ConfigError)?;
+    let data_dirs = dataset::data_dirs(&args);
+    let mut probed = Vec::with_capacity(data_dirs.len());
+    for dir in &data_dirs {
+        let capabilities = capability::probe(dir, args.block_size);
+        match capabilities.apply(&mut args) {
+            Ok(fallbacks) => {
+                for fallback in &fallbacks {
+                    println!("↪️  Fallback: {fallback}");
                 }
             }
-            
-            // Add thread results to global results
-            {
-                let mut global_results = results_clone.lock().unwrap();
-                global_results.extend(thread_results);
+            Err(e) => {
+                capabilities.report(dir);
+                return Err(status::ConfigError(e).into());
             }
-        });
+        }
+        probed.push(capabilities);
+    }
+    // A fallback taken for a later directory has to work in the earlier ones too
+    if data_dirs.len() > 1 {
+        for (dir, capabilities) in data_dirs.iter().zip(&probed) {
+            if let Err(e) = capabilities.apply(&mut args) {
+                capabilities.report(dir);
+                return Err(status::ConfigError(e).into());
+            }
+        }
+    }
+    // The results record the capabilities of the first directory
+    let capabilities = probed.swap_remove(0);
+    if let Some(Command::CrashTest { rounds, max_kill_ms }) = args.command {
+        if args.block_size < crash::HEADER_LEN {
+            return Err(status::ConfigError(format!("crash-test needs --block-size of at least {} bytes", crash::HEADER_LEN)).into());
+        }
+        println!("📝 Creating test files...");
+        let file_paths = create_test_files(&args, None)?;
+        let outcome = crash::run(&args, rounds, max_kill_ms);
+        cleanup_test_files(&args, &file_paths)?;
+        return outcome;
     }
-    
-    // Wait for all threads to complete
-    wg.wait();
-    
-    let results = results.lock().unwrap();
-    Ok(results.clone())
-}
 
-fn perform_mmap_read(mmap: &memmap2::Mmap, offset: usize, block_size: usize) -> Result<Vec<u8>, std::io::Error> {
-    if offset + block_size > mmap.len() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "Read beyond file bounds",
-        ));
+    // A kept dataset lives at the plain prefix across runs
+    let namespace = !args.no_namespace && !args.reuse_existing && raw_device.is_none();
+    let resumed = match args.resume.clone() {
+        Some(path) => {
+            let checkpoint = checkpoint::Checkpoint::load(&path)
+                .map_err(|e| format!("cannot load checkpoint {}: {e}", path.display()))?;
+            // The resumed run keeps its run ID, and so its namespaced files
+            if let Some(run_id) = checkpoint.run_id.as_deref().filter(|_| namespace) {
+                args.file_prefix = runlock::namespaced_prefix(&args.file_prefix, run_id);
+            }
+            if checkpoint.identity != checkpoint::RunIdentity::from(&args) {
+                return Err(status::ConfigError(format!(
+                    "checkpoint {} was written by a run with different parameters: {:?}",
+                    path.display(),
+                    checkpoint.identity
+                ))
+                .into());
+            }
+            Some(checkpoint)
+        }
+        None => None,
+    };
+
+    let run = results::RunInfo::new(resumed.as_ref().and_then(|c| c.run_id.clone()));
+    if namespace && resumed.as_ref().is_none_or(|c| c.run_id.is_none()) {
+        args.file_prefix = runlock::namespaced_prefix(&args.file_prefix, &run.id);
     }
-    
-    // Force memory access by copying the data
-    let data = &mmap[offset..offset + block_size];
-    Ok(data.to_vec())
-}
+    // A marker in a directory stands for files named after the file name of its run's prefix
+    let file_name = |prefix: &str| Path::new(prefix).file_name().map(|name| name.to_os_string());
+    for dir in &data_dirs {
+        let other_runs = runlock::live_runs(dir, &run.host);
+        for other in &other_runs {
+            println!(
+                "👥 Run {} (pid {} on {}) is also using {}, with files {}_*",
+                other.run_id,
+                other.pid,
+                other.host,
+                dir.display(),
+                other.file_prefix
+            );
+        }
+        if let Some(other) = other_runs.iter().find(|other| file_name(&other.file_prefix) == file_name(&args.file_prefix)) {
+            return Err(status::ConfigError(format!(
+                "run {} (pid {} on {}) is using --file-prefix {} in {}; refusing to overwrite or delete its files",
+                other.run_id,
+                other.pid,
+                other.host,
+                other.file_prefix,
+                dir.display()
+            ))
+            .into());
+        }
+    }
+    let owner = runlock::RunOwner { run_id: run.id.clone(), host: run.host.clone(), pid: std::process::id(), file_prefix: args.file_prefix.clone() };
+    let _markers = data_dirs
+        .iter()
+        .map(|dir| runlock::claim(dir, &owner).map_err(|e| format!("cannot create the run marker in {}: {e}", dir.display())))
+        .collect::<Result<Vec<_>, _>>()?;
 
-fn analyze_and_report_results(results: Vec<ReadResult>) {
-    if results.is_empty() {
-        println!("❌ No results to analyze");
-        return;
+    println!("🚀 Random I/O Tester Starting...");
+    println!("Configuration:");
+    println!("  Run ID: {}", run.id);
+    println!("  File prefix: {}", args.file_prefix);
+    let dataset_bytes = args
+        .dataset_bytes()
+        .ok_or("dataset size (--num-files × --file-size) overflows 64 bits")?;
+    match (&raw_device, &args.file_sizes) {
+        (Some(device), _) => println!(
+            "  Device: {} ({} sectors of {} bytes, physical block {} bytes)",
+            dataset::file_path(&args, 0),
+            dataset_bytes / device.logical_block_size(),
+            device.logical_block_size(),
+            device.physical_block_size()
+        ),
+        (None, Some(sizes)) => println!("  Files: {} of {sizes} ({dataset_bytes} bytes total)", args.num_files),
+        (None, None) => println!("  Files: {} × {} bytes ({} bytes total)", args.num_files, args.file_size, dataset_bytes),
     }
-    
-    let all_results = &results;
-    let first_reads: Vec<_> = results.iter().filter(|r| r.is_first_read).collect();
-    let repeated_reads: Vec<_> = results.iter().filter(|r| !r.is_first_read).collect();
-    
-    println!("\n📈 All Reads ({} operations):", all_results.len());
-    print_statistics(calculate_statistics(all_results.iter().map(|r| &r.latency).collect()));
-    
-    if !first_reads.is_empty() {
-        println!("\n🆕 First Reads ({} operations):", first_reads.len());
-        print_statistics(calculate_statistics(first_reads.iter().map(|r| &r.latency).collect()));
+    match args.create_order {
+        CreateOrder::Sequential if args.create_procs > 1 => println!("  Creation: {} from {} processes", value_name(&args.create_mode), args.create_procs),
+        CreateOrder::Sequential => println!("  Creation: {}", value_name(&args.create_mode)),
+        order => println!("  Creation: {}, {} order", value_name(&args.create_mode), value_name(&order)),
     }
-    
-    if !repeated_reads.is_empty() {
-        println!("\n🔄 Repeated Reads ({} operations):", repeated_reads.len());
-        print_statistics(calculate_statistics(repeated_reads.iter().map(|r| &r.latency).collect()));
+    if !args.target_dirs.is_empty() {
+        let dirs: Vec<String> = args.target_dirs.iter().map(|dir| dir.display().to_string()).collect();
+        println!("  Target directories: {}", dirs.join(", "));
     }
-}
-
-fn calculate_statistics(latencies: Vec<&Duration>) -> Statistics {
-    if latencies.is_empty() {
-        return Statistics {
-            count: 0,
-            avg: Duration::ZERO,
-            median: Duration::ZERO,
-            p90: Duration::ZERO,
-            p95: Duration::ZERO,
-            p99: Duration::ZERO,
-            min: Duration::ZERO,
-            max: Duration::ZERO,
-        };
+    if let Some(root) = dataset::tree_root(&args) {
+        println!(
+            "  Directory tree: {} ({} levels × {} fanout, {} leaf directories)",
+            root.display(),
+            args.dirs_depth,
+            args.dirs_fanout,
+            dataset::leaf_dirs(&args)
+        );
     }
-    
-    let mut sorted_latencies = latencies.clone();
-    sorted_latencies.sort();
-    
-    let count = sorted_latencies.len();
-    let sum: Duration = sorted_latencies.iter().map(|&d| *d).sum();
-    let avg = sum / count as u32;
-    
-    let median = *sorted_latencies[count / 2];
-    let p90 = *sorted_latencies[((count as f64) * 0.90) as usize];
-    let p95 = *sorted_latencies[((count as f64) * 0.95) as usize];
-    let p99 = *sorted_latencies[((count as f64) * 0.99) as usize];
-    let min = **sorted_latencies.first().unwrap();
-    let max = **sorted_latencies.last().unwrap();
-    
-    Statistics {
-        count,
-        avg,
-        median,
-        p90,
-        p95,
-        p99,
-        min,
-        max,
-    }
-}
-
-fn print_statistics(stats: Statistics) {
-    println!("  Count:     {}", stats.count);
-    println!("  Average:   {:.2}μs", stats.avg.as_micros());
-    println!("  Median:    {:.2}μs", stats.median.as_micros());
-    println!("  90th %ile: {:.2}μs", stats.p90.as_micros());
-    println!("  95th %ile: {:.2}μs", stats.p95.as_micros());
-    println!("  99th %ile: {:.2}μs", stats.p99.as_micros());
-    println!("  Min:       {:.2}μs", stats.min.as_micros());
-    println!("  Max:       {:.2}μs", stats.max.as_micros());
-}
-
-fn cleanup_test_files(file_paths: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    for file_path in file_paths {
-        if Path::new(file_path).exists() {
-            std::fs::remove_file(file_path)?;
-        }
+    println!("  Threads: {}", args.num_threads);
+    println!("  Block size: {} bytes", args.block_size);
+    match args.soak {
+        Some(0) => println!("  Operations: soak until interrupted"),
+        Some(secs) => println!("  Operations: soak for {secs}s"),
+        None => println!("  Operations: {}", args.num_operations),
+    }
+    println!("  Pattern: {}", args.pattern);
+    if args.write_percent > 0 {
+        println!("  Writes: {}% of operations", args.write_percent);
+    }
+    if let Some(job) = &args.job {
+        let total: u32 = job.classes.iter().map(|c| c.weight).sum();
+        let classes: Vec<String> = job
+            .classes
+            .iter()
+            .map(|c| format!("{} {:.0}% ({:?} {} bytes)", c.tag, 100.0 * f64::from(c.weight) / f64::from(total), c.kind, c.len))
+            .collect();
+        println!("  Operation classes: {}", classes.join(", "));
+    }
+    if args.msync != msync::MsyncPolicy::None {
+        println!("  msync: {}", args.msync);
+    }
+    println!("  Distribution: {}", args.distribution);
+    if args.file_weights != distribution::FileWeights::Uniform {
+        println!("  File weights: {}", args.file_weights);
+    }
+    if let Some(fraction) = args.coverage {
+        let block_size = args.block_size as u64;
+        let covered: u64 = coverage::per_file((0..args.num_files).map(|file| args.file_size_of(file) / block_size), fraction, args.seed)
+            .iter()
+            .map(coverage::FileCoverage::covered)
+            .sum();
+        println!("  Coverage: {}% of the blocks ({covered} blocks, {} bytes)", fraction * 100.0, covered * block_size);
+    }
+    if let Some(rate) = args.hotspot_move {
+        println!("  Hot spot: moves {}% of the file per second", rate * 100.0);
+    }
+    if let Some(path) = &args.workload_plugin {
+        println!("  Workload plugin: {}", path.display());
+    }
+    if let Some(path) = &args.workload_script {
+        println!("  Workload script: {}", path.display());
+    }
+    println!("  Engine: {}", args.engine);
+    let unavailable = capabilities.unavailable();
+    if !unavailable.is_empty() {
+        println!("  Not available here: {}", unavailable.join(", "));
+    }
+    if args.workload == WorkloadKind::Scan {
+        println!("  Workload: range scans of {}", args.scan_length);
+    }
+    if args.workload == WorkloadKind::WriteVerify {
+        println!("  Workload: write-verify cycles of {} blocks for {}s", args.write_verify_blocks, args.write_verify_secs);
     }
+    if args.scatter > 1 {
+        println!("  Scatter: {} blocks per operation", args.scatter);
+    }
+    if args.queue_depth > 1 {
+        println!("  Queue depth: {}", args.queue_depth);
+    }
+    if let Some(batch) = args.submit_batch {
+        println!("  Submit batch: {batch}");
+    }
+    if let Some(rate) = args.rate {
+        println!("  Rate cap: {rate} operations/s ({:.1} per thread)", rate as f64 / args.num_threads as f64);
+    }
+    if args.engine == "threadpool" {
+        println!("  I/O pool: {} workers", args.inflight.unwrap_or(engine::DEFAULT_INFLIGHT));
+    }
+    if args.poll {
+        println!("  Completions: polled (O_DIRECT, page cache bypassed)");
+    }
+    if args.engine == "sendfile" {
+        println!("  Copy sink: {}", value_name(&args.copy_sink));
+    }
+    if args.engine == "mmap" {
+        println!("  Mmap touch: {}", value_name(&args.mmap_touch));
+    }
+    if args.buffers != BufferKind::Pageable {
+        println!("  Buffers: {}", value_name(&args.buffers));
+    }
+    println!("  Seed: {}", args.seed);
+    println!("  Thread seeds: {}", format_thread_seeds(args.rng, args.seed, args.num_threads));
+    println!("  Clock: {}", args.clock);
+    println!("  Trim: {}", if args.trim.is_some() { "before" } else { "no" });
+    println!("  Zone-aware: {}", if args.zone_aware { "yes" } else { "no" });
+    if args.verify {
+        println!("  Verify: {:.1}% of reads", 100.0 * args.verify_sample.unwrap_or(1.0));
+    }
+    if args.interval > 0 {
+        println!("  Reporting interval: {}s", args.interval);
+    }
+    println!();
+
+    let block_devices: Vec<Option<device::BlockDevice>> = data_dirs.iter().map(|dir| device::block_device_for(dir).ok()).collect();
+    // Zones, write amplification and stability are followed on the first directory's device
+    let block_device = block_devices[0].clone();
+    let zoned = block_device.as_ref().and_then(|d| d.zoned());
+    let mut devices: Vec<&device::BlockDevice> = block_devices.iter().flatten().collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices.dedup_by(|a, b| a.name == b.name);
+    // libaio falls back to buffered files where O_DIRECT is not supported at all
+    let direct_reader = if args.poll { Some("--poll") } else { (args.engine == "libaio" && capabilities.o_direct.available).then_some("the libaio engine") };
+    if let Some(reader) = direct_reader {
+        // O_DIRECT needs offsets and lengths aligned to the logical block size; every read is whole blocks at block offsets
+        for device in &block_devices {
+            let logical_block_size = device
+                .as_ref()
+                .and_then(|d| d.queue_attr("logical_block_size"))
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(512);
+            if !args.block_size.is_multiple_of(logical_block_size) {
+                return Err(status::ConfigError(format!(
+                    "{reader} reads with O_DIRECT, which needs --block-size to be a multiple of the device's logical block size ({logical_block_size}); got {}",
+                    args.block_size
+                ))
+                .into());
+            }
+        }
+    }
+    for dev in &devices {
+        let Some(zoned) = dev.zoned() else { continue };
+        println!(
+            "🧱 {} is a {} zoned device ({} zones of {} bytes)",
+            dev.name, zoned.model, zoned.nr_zones, zoned.zone_size
+        );
+        if zoned.model == device::ZoneModel::HostManaged && (args.writes() || args.workload == WorkloadKind::WriteVerify) {
+            return Err(status::ConfigError(format!(
+                "{} has host-managed zones, which reject random writes (--write-percent, --job-file writes, write-verify); measure reads only",
+                dev.name
+            ))
+            .into());
+        }
+        if zoned.model == device::ZoneModel::HostManaged && !args.zone_aware {
+            println!("⚠️  Host-managed zones only accept sequential writes; consider --zone-aware");
+        }
+    }
+    let warnings = sanity::check_environment(&data_dirs, dataset_bytes);
+    for warning in &warnings {
+        println!("⚠️  WARNING: {warning}");
+    }
+    if args.strict && !warnings.is_empty() {
+        return Err(format!("{} environment warning(s) and --strict is set", warnings.len()).into());
+    }
+
+    // Keep the guards alive for the whole run so the device settings are restored afterwards
+    let mut readahead_guards = Vec::new();
+    if let Some(kb) = args.readahead.filter(|&kb| kb > 0) {
+        for dev in &devices {
+            match device::ReadaheadGuard::set(dev, kb) {
+                Ok(guard) => readahead_guards.push(guard),
+                Err(e) => println!("⚠️  Cannot set read_ahead_kb on {}: {e}", dev.name),
+            }
+        }
+        for (dir, _) in data_dirs.iter().zip(&block_devices).filter(|(_, dev)| dev.is_none()) {
+            println!("⚠️  No block device found for {}; readahead left unchanged", dir.display());
+        }
+    }
+    let device_readahead = match &devices[..] {
+        [] => "unknown".to_string(),
+        [dev] => dev.read_ahead_kb().map_or("unknown".to_string(), |kb| format!("{kb} KiB")),
+        devices => devices
+            .iter()
+            .map(|dev| format!("{} {}", dev.name, dev.read_ahead_kb().map_or("unknown".to_string(), |kb| format!("{kb} KiB"))))
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    let per_file_advice = if args.readahead == Some(0) { "random (readahead off)" } else { "none" };
+    println!("📖 Readahead: device {device_readahead}, per-file advice {per_file_advice}");
+
+    let zone_size = zoned.as_ref().map_or(NOMINAL_ZONE_SIZE, |z| z.zone_size);
+    if args.zone_aware && zoned.is_none() {
+        println!("⚠️  Target is not on a zoned device; assuming {NOMINAL_ZONE_SIZE}-byte zones");
+    }
+
+    // Load the plugin or script before creating files so a bad module fails fast
+    let workload = match (&args.workload_plugin, &args.workload_script) {
+        (Some(path), _) => Some(workload::load_plugin(path, args.num_files, args.file_size)?),
+        (None, Some(path)) => Some(workload::load_script(path, args.num_files, args.file_size, args.block_size as u64)?),
+        (None, None) => None,
+    };
+
+    // Phase 0: Trim free space so the device starts from a known FTL state
+    let mut trim_outcomes = Vec::new();
+    if args.trim == Some(TrimWhen::Before) {
+        for dir in &data_dirs {
+            println!("✂️  Trimming free space on {}...", dir.display());
+            let outcome = fsutil::trim_free_space(dir);
+            println!("  Trim {outcome}");
+            trim_outcomes.push((dir.clone(), outcome));
+        }
+    }
+    let trim: Vec<results::TrimSummary> = data_dirs
+        .iter()
+        .map(|dir| results::TrimSummary::new(dir, trim_outcomes.iter().find(|(trimmed, _)| trimmed == dir).map(|(_, outcome)| outcome)))
+        .collect();
+
+    // Phase 1: Create test files (an interrupted run or --reuse-existing left them behind)
+    let reuse = if args.reuse_existing {
+        dataset::check_existing(&args, &value_name(&args.create_mode)).map_err(status::ConfigError)?
+    } else {
+        dataset::Reuse::Missing
+    };
+    let mut ingest_written = None;
+    let file_paths = match (reuse, existing_test_files(&args)) {
+        _ if args.ingest.is_some() => {
+            let fraction = args.ingest.unwrap_or_default();
+            println!("📝 Creating test files, {:.0}% written up front for --ingest...", fraction * 100.0);
+            let paths: Vec<String> = (0..args.num_files).map(|i| dataset::file_path(&args, i)).collect();
+            let sizes: Vec<u64> = (0..args.num_files).map(|i| args.file_size_of(i)).collect();
+            ingest_written = Some(ingest::prepare(&paths, &sizes, fraction, args.block_size as u64)?);
+            paths
+        }
+        _ if raw_device.is_some() => {
+            println!("💽 Using {} directly; no test files are created", dataset::file_path(&args, 0));
+            vec![dataset::file_path(&args, 0)]
+        }
+        (dataset::Reuse::Verified(paths), _) => {
+            println!("♻️  Reusing {} existing test files (manifest and fingerprints match)", paths.len());
+            paths
+        }
+        (dataset::Reuse::Unverified(paths), _) => {
+            println!("♻️  Reusing {} existing test files", paths.len());
+            println!(
+                "⚠️  No manifest at {}; only file count and sizes were checked",
+                dataset::manifest_path(&args).display()
+            );
+            paths
+        }
+        (dataset::Reuse::Missing, Some(paths)) if resumed.is_some() => {
+            println!("♻️  Reusing {} existing test files", paths.len());
+            paths
+        }
+        _ => {
+            println!("📝 Creating test files...");
+            let paths = create_test_files(&args, args.zone_aware.then_some(zone_size))?;
+            println!("✅ Created {} files", paths.len());
+            paths
+        }
+    };
+    // File i lies in directory i mod n, so the first files cover every directory
+    let dax: Vec<(&PathBuf, bool)> = data_dirs
+        .iter()
+        .zip(&file_paths)
+        .map(|(dir, path)| (dir, raw_device.is_none() && fsutil::is_dax(Path::new(path)).unwrap_or(false)))
+        .collect();
+    let not_dax = dax.iter().find(|(_, is_dax)| !is_dax).map(|(dir, _)| *dir);
+    match (args.dax, not_dax, dax.iter().any(|(_, is_dax)| *is_dax)) {
+        (true, Some(target_dir), _) => {
+            let mount = fsutil::mount_info_for(target_dir).ok();
+            cleanup_test_files(&args, &file_paths)?;
+            return Err(status::ConfigError(format!(
+                "--dax needs DAX files, but {} is on {} {}; mount a pmem device with -o dax=always",
+                target_dir.display(),
+                mount.as_ref().map_or("a file system", |m| m.fs_type.as_str()),
+                mount.as_ref().and_then(|m| m.dax()).map_or("without the dax option".to_string(), |mode| format!("with dax={mode}"))
+            ))
+            .into());
+        }
+        (true, None, _) => println!("💎 DAX: files are mapped directly; loads bypass the page cache, so cold and warm reads look alike"),
+        (false, _, true) => println!("⚠️  The test files are DAX: reads bypass the page cache and cache effects do not apply; use --dax to map them directly"),
+        (false, _, false) => {}
+    }
+    if args.create_mode != CreateMode::Write && raw_device.is_none() {
+        println!("⚠️  Unwritten blocks read back as zeros, usually without any device I/O");
+    }
+    if let Some(fraction) = args.inject_corruption {
+        let injected = dataset::inject_corruption(&file_paths, args.block_size as u64, fraction, args.seed);
+        let (flipped, blocks) = cleanup_on_error(&args, &file_paths, injected)?;
+        println!("💥 Flipped one bit in {} of {blocks} blocks; the manifest still describes the original data", flipped.len());
+        for corruption in &flipped {
+            println!("    {} byte {} bit {}", corruption.path, corruption.offset, corruption.bit);
+        }
+    }
+
+    let zone_map = if args.zone_aware {
+        let partition_start = block_device.as_ref().map_or(0, |d| d.start_bytes);
+        match device::ZoneMap::build(&file_paths, zone_size, partition_start) {
+            Ok(map) => Some(Arc::new(map)),
+            Err(e) => {
+                println!("⚠️  Cannot map file extents to zones: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Phase 2: Wait
+    if !args.settle.is_empty() {
+        let settled = settle::run(&args.settle, &data_dirs).map_err(|e| format!("cannot settle the file system: {e}"));
+        cleanup_on_error(&args, &file_paths, settled)?;
+    }
+    let stabilization = if args.stabilize {
+        let outcome = stabilize::wait(&file_paths, block_device.as_ref(), args.seed, Duration::from_secs(args.stabilize_timeout))
+            .map_err(|e| format!("cannot probe the test files: {e}"));
+        let outcome = cleanup_on_error(&args, &file_paths, outcome)?;
+        if outcome.stable {
+            println!("✅ Stable after {:.0}s", outcome.waited_secs);
+        } else if args.strict {
+            cleanup_test_files(&args, &file_paths)?;
+            return Err(format!("device not stable after {:.0}s and --strict is set", outcome.waited_secs).into());
+        } else {
+            println!("⚠️  Device not stable after {:.0}s; measuring anyway", outcome.waited_secs);
+        }
+        Some(outcome)
+    } else {
+        if args.settle.is_empty() {
+            println!("⏳ Waiting {} seconds...", args.wait_time);
+            std::thread::sleep(Duration::from_secs(args.wait_time));
+        }
+        None
+    };
+
+    // Phase 3: Run performance tests
+    let clock = timing::Clock::new(args.clock);
+    match clock.source() {
+        timing::ClockSource::Tsc => println!("⏲️  Using TSC clock ({:.4} ns/tick)", clock.ns_per_tick()),
+        timing::ClockSource::Monotonic if args.clock == timing::ClockSource::Tsc => {
+            println!("⚠️  No invariant TSC available; falling back to the monotonic clock")
+        }
+        timing::ClockSource::Monotonic => {}
+    }
+    let calibration = timing::calibrate(&clock);
+    println!(
+        "⏲️  Timer calibration: clock read {}ns, empty operation {}ns",
+        calibration.clock_overhead.as_nanos(),
+        calibration.empty_op.as_nanos()
+    );
+
+    if let (true, Some(rate)) = (args.auto_threads, args.rate) {
+        let latency_correction = if args.subtract_overhead { calibration.empty_op } else { Duration::ZERO };
+        println!("🧮 Finding the threads that reach {rate} operations/s (up to {})...", args.num_threads);
+        let threads = find_thread_count(&args, &file_paths, clock, zone_map.clone(), latency_correction, rate)?;
+        println!("🧮 Running with {threads} threads");
+        args.num_threads = threads;
+    }
+
+    let control = (args.rate.is_some() || args.control_socket.is_some() || args.keys)
+        .then(|| Arc::new(control::Control::new(args.rate, args.num_threads, args.soak.is_some(), args.open_loop)));
+    // Restores the terminal when dropped, on every return below
+    let _keys = match &control {
+        Some(control) if args.keys => {
+            let keys = keys::listen(Arc::clone(control));
+            match keys {
+                Some(_) => println!("⌨️  Keys: p pauses and resumes, n skips to the next phase"),
+                None => println!("⚠️  --keys needs a terminal on stdin; keyboard controls are off"),
+            }
+            keys
+        }
+        _ => None,
+    };
+
+    if !args.ws_sweep.is_empty() {
+        let latency_correction = if args.subtract_overhead { calibration.empty_op } else { Duration::ZERO };
+        let started = Instant::now();
+        let sweep = run_working_set_sweep(&args, &file_paths, clock, zone_map, dataset_bytes, latency_correction, control)
+            .map(|(phases, insights)| report_phases(&args, &run, phases, insights, &trim, started.elapsed()));
+        cleanup_test_files(&args, &file_paths)?;
+        return sweep.map(|()| status::Outcome::success());
+    }
+
+    if !args.qd_sweep.is_empty() {
+        let latency_correction = if args.subtract_overhead { calibration.empty_op } else { Duration::ZERO };
+        let started = Instant::now();
+        let sweep = run_queue_depth_sweep(&args, &file_paths, clock, zone_map, latency_correction, control)
+            .map(|(phases, insights)| report_phases(&args, &run, phases, insights, &trim, started.elapsed()));
+        cleanup_test_files(&args, &file_paths)?;
+        return sweep.map(|()| status::Outcome::success());
+    }
+
+    if args.workload == WorkloadKind::WriteVerify {
+        println!("🔁 Writing, syncing and reading back random blocks for {}s...", args.write_verify_secs);
+        status::install_interrupt_handler();
+        let run = writeverify::run(&args, &file_paths, &clock, Duration::from_secs(args.write_verify_secs), args.write_verify_blocks);
+        cleanup_test_files(&args, &file_paths)?;
+        let summary = run.map_err(|e| format!("write-verify failed: {e}"))?;
+        writeverify::report(&summary, &file_paths);
+        return Ok(status::Outcome {
+            status: if summary.mismatches == 0 { status::Status::Success } else { status::Status::IoErrors },
+            details: vec![("cycles", summary.cycles.to_string()), ("mismatches", summary.mismatches.to_string())],
+        });
+    }
+
+    let latency_correction = if args.subtract_overhead { calibration.empty_op } else { Duration::ZERO };
+
+    // A resumed run continues with the remaining operations on a fresh RNG stream
+    let mut run_args = args.clone();
+    let (generation, prior_progress, read_blocks) = match resumed {
+        Some(checkpoint) => {
+            let generation = checkpoint.generation + 1;
+            let done = checkpoint.progress.completed_operations as usize;
+            run_args.num_operations = args.num_operations.saturating_sub(done);
+            run_args.seed = args.seed.wrapping_add(generation as u64 * 0x9E37_79B9_7F4A_7C15);
+            println!(
+                "⏯️  Resuming: {done} of {} operations already done, continuing with seed {}",
+                args.num_operations, run_args.seed
+            );
+            println!("  Thread seeds: {}", format_thread_seeds(run_args.rng, run_args.seed, run_args.num_threads));
+            let read_blocks = checkpoint
+                .read_blocks
+                .into_iter()
+                .map(|(file, block, count)| ((file, block), count))
+                .collect();
+            (generation, Some(checkpoint.progress), read_blocks)
+        }
+        None => (0, None, BlockCounts::new()),
+    };
+    let checkpoint_path = args.checkpoint.clone().or(args.resume.clone());
+
+    let soak = match args.soak {
+        Some(secs) => {
+            // Readers run until the soak ends
+            run_args.num_operations = usize::MAX;
+            let interval = Duration::from_secs(if args.interval > 0 { args.interval } else { SOAK_INTERVAL_SECS });
+            let log_path = run.output_path(&args.soak_log);
+            println!("🛁 Soak: interval summaries every {}s to {} (SIGHUP closes the current interval now)", interval.as_secs(), log_path.display());
+            status::install_report_handler();
+            let started = soak::start(
+                (secs > 0).then(|| Duration::from_secs(secs)),
+                interval,
+                log_path,
+                args.soak_raw_log.as_deref().map(|pattern| run.output_path(pattern)),
+                args.soak_rotate_mb.saturating_mul(1024 * 1024),
+            );
+            Some(cleanup_on_error(&args, &file_paths, started.map_err(|e| format!("cannot create the soak logs: {e}")))?)
+        }
+        None => None,
+    };
+
+    let ingester = match ingest_written {
+        Some(written) => {
+            let sizes = (0..args.num_files).map(|i| args.file_size_of(i)).collect();
+            let started = ingest::start(&file_paths, sizes, written).map_err(|e| format!("cannot start the ingest: {e}"));
+            Some(cleanup_on_error(&args, &file_paths, started)?)
+        }
+        None => None,
+    };
+
+    let write_amplification = match raw_device.clone().or_else(|| block_device.clone()) {
+        Some(device) if args.write_amplification => {
+            let (before, error) = writeamp::read(&device);
+            if let Some(e) = error {
+                println!("⚠️  Cannot read the NVMe write counter of {}: {e}; using the kernel's only", device.name);
+            }
+            Some((device, before))
+        }
+        None if args.write_amplification => {
+            println!("⚠️  No block device found under {}; skipping write amplification", data_dirs[0].display());
+            None
+        }
+        _ => None,
+    };
+
+    let churner = match args.cache_churn {
+        Some(rate) => {
+            let size = match args.cache_churn_size {
+                Some(size) => Ok(size),
+                None => procfs::MemInfo::read()
+                    .map_err(|e| format!("cannot read /proc/meminfo: {e}"))
+                    .and_then(|meminfo| meminfo.total().ok_or_else(|| "MemTotal missing from /proc/meminfo".to_string()))
+                    .map(|total| 2 * total),
+            };
+            let size = cleanup_on_error(&args, &file_paths, size)?;
+            let path = data_dirs[0].join(format!("random_io_churn_{}", run.id));
+            println!("🌪️  Streaming a {} MiB sparse file through the page cache at {} MiB/s", size >> 20, rate >> 20);
+            let started = churn::start(path, size, rate).map_err(|e| format!("cannot start the cache churn: {e}"));
+            Some(cleanup_on_error(&args, &file_paths, started)?)
+        }
+        None => None,
+    };
+
+    let schedules = if args.pregenerate {
+        let started = Instant::now();
+        let schedules = cleanup_on_error(&args, &file_paths, driver::pregenerate(&run_args, file_paths.len(), workload.clone()))?;
+        let operations: usize = schedules.iter().map(Vec::len).sum();
+        println!(
+            "🗓️  Pre-generated {operations} operations ({:.1} MiB) in {:.2}s",
+            (operations * std::mem::size_of::<workload::Operation>()) as f64 / (1024.0 * 1024.0),
+            started.elapsed().as_secs_f64()
+        );
+        Some(Arc::new(Mutex::new(schedules)))
+    } else {
+        None
+    };
+
+    println!("🔬 Running performance tests...");
+    status::install_interrupt_handler();
+    if let Some(control) = &control {
+        control.begin_phase();
+    }
+    let ctx = RunContext {
+        run_start: Instant::now(),
+        start_ticks: clock.now(),
+        clock,
+        zone_map,
+        working_set_blocks: None,
+        read_blocks: Arc::new(Mutex::new(read_blocks)),
+        progress: checkpoint_path
+            .is_some()
+            .then(|| Arc::new(Mutex::new(prior_progress.clone().unwrap_or_default()))),
+        latency_correction,
+        workload,
+        failed_reads: Arc::new(AtomicU64::new(0)),
+        first_error: Arc::default(),
+        verified_reads: Arc::new(AtomicU64::new(0)),
+        mismatched_reads: Arc::new(AtomicU64::new(0)),
+        faults: (args.fault_batch > 0).then(|| Arc::new(Mutex::new(faults::FaultStats::default()))),
+        precision: args
+            .target_precision
+            .map(|bound| Arc::new(precision::PrecisionTarget::new(bound, args.target_percentile / 100.0))),
+        soak: soak.as_ref().map(|(soak, _)| Arc::clone(soak)),
+        ingest: ingester.as_ref().map(|(ingest, _)| Arc::clone(ingest)),
+        schedules,
+        control,
+    };
+    let control_server = match (&args.control_socket, &ctx.control) {
+        (Some(path), Some(control)) => {
+            let server = control::serve(path.clone(), Arc::clone(control)).map_err(|e| format!("cannot listen on {}: {e}", path.display()));
+            let server = cleanup_on_error(&args, &file_paths, server)?;
+            println!("🎛️  Control socket listening on {}", path.display());
+            Some(server)
+        }
+        _ => None,
+    };
+    let metadata_churner = match args.metadata_churn {
+        Some(entries) => {
+            let root = data_dirs[0].join(format!("random_io_dentries_{}", run.id));
+            println!("🗃️  Growing a tree of {entries} files in {} and looking them up in one-second bursts", root.display());
+            let started = dentries::start(root, entries, ctx.run_start).map_err(|e| format!("cannot start the metadata churn: {e}"));
+            Some(cleanup_on_error(&args, &file_paths, started)?)
+        }
+        None => None,
+    };
+    let checkpointer = match (&checkpoint_path, &ctx.progress) {
+        (Some(path), Some(progress)) => Some(checkpoint::Checkpointer::start(
+            path.clone(),
+            Duration::from_secs(args.checkpoint_interval),
+            run.id.clone(),
+            checkpoint::RunIdentity::from(&args),
+            generation,
+            Arc::clone(progress),
+            Arc::clone(&ctx.read_blocks),
+        )),
+        _ => None,
+    };
+    let interval = Duration::from_secs(args.interval);
+    let sampler = args
+        .sample_system
+        .then(|| sampler::SystemSampler::start(ctx.run_start, interval));
+    // Stores to a shared mapping only dirty pages; the cost shows up later, as writeback
+    let writeback_sampler = (args.engine == "mmap" && args.writes())
+        .then(|| sampler::SystemSampler::start(ctx.run_start, WRITEBACK_SAMPLE_INTERVAL));
+    let (writes, results): (Vec<ReadResult>, Vec<ReadResult>) =
+        run_tests(&run_args, &file_paths, &ctx)?.into_iter().partition(|r| r.is_write);
+    if let Some(server) = control_server {
+        server.finish();
+    }
+    let write_amplification = write_amplification.map(|(device, before)| {
+        let amplification = writeamp::finish(&device, &file_paths, &before, writes.iter().map(|w| w.bytes).sum());
+        (device, amplification)
+    });
+    let churn_summary = match churner {
+        Some(churner) => Some(churner.finish().map_err(|e| format!("cache churn failed: {e}"))?),
+        None => None,
+    };
+    let metadata_summary = match metadata_churner {
+        Some(churner) => Some(churner.finish().map_err(|e| format!("metadata churn failed: {e}"))?),
+        None => None,
+    };
+    let ingest_summary = match ingester {
+        Some((_, ingester)) => Some(ingester.finish().map_err(|e| format!("ingest failed: {e}"))?),
+        None => None,
+    };
+    let soak_totals = match soak {
+        Some((_, flusher)) => {
+            let log_path = flusher.log_path.clone();
+            Some((flusher.finish().map_err(|e| format!("cannot write the soak log: {e}"))?, log_path))
+        }
+        None => None,
+    };
+    let system_samples = sampler.map(sampler::SystemSampler::stop).unwrap_or_default();
+    let writeback_samples = writeback_sampler.map(sampler::SystemSampler::stop);
+    if let (Some(checkpointer), Some(path)) = (checkpointer, &checkpoint_path) {
+        checkpointer.finish()?;
+        println!("💾 Final checkpoint written to {}", path.display());
+    }
+
+    // Phase 4: Analyze and report results
+    println!("\n📊 Performance Results:");
+    match &trim_outcomes[..] {
+        [] => println!("  Trim before run: not requested"),
+        [(_, outcome)] => println!("  Trim before run: {outcome}"),
+        outcomes => {
+            for (dir, outcome) in outcomes {
+                println!("  Trim before run on {}: {outcome}", dir.display());
+            }
+        }
+    }
+    println!(
+        "  Harness overhead: {}ns per operation ({})",
+        calibration.empty_op.as_nanos(),
+        if args.subtract_overhead { "subtracted" } else { "included" }
+    );
+    let mut results_file = results::ResultsFile::new(&run, checkpoint::RunIdentity::from(&args), ctx.elapsed());
+    results_file.rng = run_args.rng;
+    results_file.auto_threads = args.auto_threads.then_some(args.num_threads);
+    results_file.capabilities = Some(capabilities);
+    results_file.trim = trim;
+    results_file.stabilization = stabilization;
+    if run_args.rng == rng::RngKind::Stdrng {
+        results_file.thread_seeds = (0..run_args.num_threads).map(|t| rng::thread_seed(run_args.seed, t)).collect();
+    }
+    match (&prior_progress, &ctx.progress, &soak_totals) {
+        (_, _, Some((totals, log_path))) => {
+            let window = &totals.window;
+            report_histogram_results(&checkpoint::Progress {
+                completed_operations: window.ops(),
+                all: window.reads.clone(),
+                first: window.first.clone(),
+                repeated: window.repeated.clone(),
+            });
+            let summary = |h: &histogram::LatencyHistogram| (!h.is_empty()).then(|| (&Statistics::from_histogram(h)).into());
+            results_file.all = summary(&window.reads);
+            results_file.first = summary(&window.first);
+            results_file.repeated = summary(&window.repeated);
+            results_file.writes = summary(&window.writes);
+            if !window.writes.is_empty() {
+                println!("\n✍️  Writes ({} operations):", window.writes.count());
+                print_statistics(Statistics::from_histogram(&window.writes));
+            }
+            totals.report(log_path);
+        }
+        (Some(prior), Some(progress), _) => {
+            let progress = progress.lock().unwrap();
+            report_histogram_results(&progress);
+            let summary = |h: &histogram::LatencyHistogram| (!h.is_empty()).then(|| (&Statistics::from_histogram(h)).into());
+            results_file.all = summary(&progress.all);
+            results_file.first = summary(&progress.first);
+            results_file.repeated = summary(&progress.repeated);
+            println!(
+                "\n  ({} operations came from the checkpoint; the analyses below cover only the resumed part)",
+                prior.completed_operations
+            );
+            report_zone_statistics(&results);
+            report_periodicity(&results);
+        }
+        _ if results.is_empty() && !writes.is_empty() => {}
+        _ => {
+            analyze_and_report_results(&results);
+            if args.workload == WorkloadKind::Scan {
+                report_scan_throughput(&results);
+            }
+            results_file.size_classes = report_size_classes(&results);
+            if args.file_weights != distribution::FileWeights::Uniform {
+                report_file_hits(&results, &args.file_weights, args.num_files);
+            }
+            if args.trim_warmup > 0.0 || args.trim_percent > 0.0 {
+                results_file.trimmed = report_trimmed(&results, Duration::from_secs_f64(args.trim_warmup), args.trim_percent);
+            }
+            results_file.iops_per_second = report_throughput_stability(&results);
+            [results_file.all, results_file.first, results_file.repeated] = split_summaries(&results);
+        }
+    }
+    if !writes.is_empty() {
+        println!("\n✍️  Writes ({} operations{}):", writes.len(), if args.fsync_writes { ", without the sync" } else { "" });
+        let stats = calculate_statistics(writes.iter().map(|r| &r.latency).collect());
+        results_file.writes = Some((&stats).into());
+        print_statistics(stats);
+    }
+    let syncs: Vec<&Duration> = writes.iter().filter_map(|r| r.sync_latency.as_ref()).collect();
+    if !syncs.is_empty() {
+        println!("\n🔒 Syncs (fdatasync after each write, {} operations):", syncs.len());
+        let stats = calculate_statistics(syncs);
+        results_file.syncs = Some((&stats).into());
+        print_statistics(stats);
+    }
+    if let Some((device, amplification)) = write_amplification {
+        writeamp::report(&device, &amplification);
+        results_file.write_amplification = Some(amplification);
+    }
+    if let Some(summary) = &ingest_summary {
+        ingest::report(summary, &results);
+    }
+    if let Some(summary) = &churn_summary {
+        churn::report(summary);
+    }
+    if let Some(summary) = &metadata_summary {
+        dentries::report(summary, &results);
+    }
+    if args.interval > 0 {
+        results_file.intervals = report_intervals(&results, interval, &system_samples);
+        if args.thread_matrix {
+            results_file.thread_intervals = report_thread_matrix(&results, interval);
+        }
+    }
+    results_file.threads = group_summaries(&results, &writes, |r| r.thread);
+    results_file.files = group_summaries(&results, &writes, |r| r.file);
+    let file_weights = (args.workload_plugin.is_none() && args.workload_script.is_none()).then_some(&args.file_weights);
+    results_file.fairness = report_fairness(&results.iter().chain(&writes).collect::<Vec<_>>(), file_weights, args.num_files, args.num_threads);
+    if let Some(job) = &args.job {
+        results_file.tags = report_tags(&results, &writes, job, results_file.elapsed_secs);
+    }
+    results_file.failed_reads = ctx.failed_reads.load(Ordering::Relaxed);
+    results_file.first_error = ctx.first_error.lock().unwrap().clone();
+    if let Some(samples) = &writeback_samples {
+        report_writeback(&results, samples, WRITEBACK_SAMPLE_INTERVAL);
+    }
+    if args.access_counts {
+        let total_blocks = (0..args.num_files).map(|file| args.file_size_of(file) / args.block_size as u64).sum();
+        report_access_counts(&ctx.read_blocks.lock().unwrap(), total_blocks);
+    }
+    if args.seek_distances {
+        report_seek_distances(results.iter().chain(&writes).collect(), args.block_size as u64);
+    }
+    if let Some(precision) = &ctx.precision {
+        precision.report();
+    }
+    if let Some(faults) = &ctx.faults {
+        faults.lock().unwrap().report(args.fault_batch);
+    }
+    if args.fit_distributions && !results.is_empty() {
+        report_distribution_fit(&results);
+    }
+    if args.inter_arrival {
+        report_inter_arrival(results.iter().chain(&writes).collect());
+    }
+    if let (true, Some(rate)) = (args.open_loop, args.rate) {
+        let slot = Duration::from_secs_f64(args.num_threads as f64 / rate as f64);
+        results_file.lateness = report_lateness(&results.iter().chain(&writes).collect::<Vec<_>>(), slot);
+    }
+    if args.paired_samples > 0 {
+        match paired::run(&args, &file_paths, &clock, args.paired_samples) {
+            Ok(pairs) => paired::report(&pairs),
+            Err(e) => println!("\n⚠️  Paired cold/warm sampling failed: {e}"),
+        }
+    }
+    if args.syscall_baseline {
+        println!("\n🔧 Syscall Baseline:");
+        let scratch_path = format!("{}_syscall.dat", args.file_prefix);
+        match baseline::measure_syscalls(&clock, &scratch_path, args.block_size) {
+            Ok(syscalls) => baseline::print_syscall_baseline(&syscalls, args.block_size),
+            Err(e) => println!("  ⚠️  Syscall baseline failed: {e}"),
+        }
+    }
+
+    results_file.insights = insights::from_results(&results_file, args.block_size);
+    insights::report(&results_file.insights);
+
+    if args.json {
+        let path = run.output_path(&args.output);
+        match results_file.save(&path) {
+            Ok(()) => println!("\n📝 Results written to {}", path.display()),
+            Err(e) => println!("\n⚠️  Cannot write results to {}: {e}", path.display()),
+        }
+    }
+    if !args.out.is_empty() {
+        let operations: Vec<&ReadResult> = results.iter().chain(&writes).collect();
+        let tags = args.job.as_ref().map(jobfile::Job::tags).unwrap_or_default();
+        report::write_all(&args.out, &run, &report::Report { summary: &results_file, operations: &operations, tags: &tags });
+    }
+
+    // Cleanup
+    cleanup_test_files(&args, &file_paths)?;
+
+    if let (Some(redirect), Some(all)) = (&mut redirect, &results_file.all) {
+        let iops = all.count as f64 / results_file.elapsed_secs.max(f64::EPSILON);
+        if args.quiet {
+            redirect.summary(&format!(
+                "ops={} iops={iops:.1} p50_ns={} p99_ns={}",
+                all.count, all.median_ns, all.p99_ns
+            ));
+        } else {
+            redirect.summary(&format!(
+                "✅ {} ops, {iops:.0} IOPS, p50 {}, p99 {}",
+                all.count,
+                units::format_ns(all.median_ns as f64),
+                units::format_ns(all.p99_ns as f64)
+            ));
+        }
+        if let Some(path) = &args.report_file {
+            redirect.summary(&format!("{}Full report in {}", if args.quiet { "" } else { "📄 " }, path.display()));
+        }
+    }
+
+    let failed_reads = ctx.failed_reads.load(Ordering::Relaxed);
+    let mut outcome = status::Outcome::success();
+    outcome.details.push(("run_id", run.id.clone()));
+    if let Some(all) = &results_file.all {
+        outcome.details.push(("ops", all.count.to_string()));
+        outcome.details.push(("iops", format!("{:.1}", all.count as f64 / results_file.elapsed_secs.max(f64::EPSILON))));
+        outcome.details.push(("p50_ns", all.median_ns.to_string()));
+        outcome.details.push(("p99_ns", all.p99_ns.to_string()));
+    }
+    outcome.details.push(("failed_reads", failed_reads.to_string()));
+    let mismatched_reads = ctx.mismatched_reads.load(Ordering::Relaxed);
+    let unverified = args.verify && ctx.verified_reads.load(Ordering::Relaxed) == 0;
+    if args.verify {
+        outcome.details.push(("verified_reads", ctx.verified_reads.load(Ordering::Relaxed).to_string()));
+        outcome.details.push(("mismatched_reads", mismatched_reads.to_string()));
+    }
+    let p99_us = results_file.all.as_ref().map(|all| all.p99_ns as f64 / 1000.0);
+    // Without a budget some failed reads are tolerated, but not a run in which every read failed
+    let over_budget = match args.max_read_errors {
+        Some(budget) => failed_reads > budget,
+        None => failed_reads > 0 && results_file.all.as_ref().is_none_or(|all| all.count == 0),
+    };
+    outcome.status = if status::interrupted() {
+        status::Status::Interrupted
+    } else if over_budget || mismatched_reads > 0 || unverified {
+        status::Status::IoErrors
+    } else if let (Some(sla), Some(p99)) = (args.sla_p99_us, p99_us)
+        && p99 > sla
+    {
+        status::Status::SlaFailed
+    } else {
+        status::Status::Success
+    };
+    Ok(outcome)
+}
+
+fn verify_dataset(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest_path = dataset::manifest_path(args);
+    let manifest = dataset::Manifest::load(&manifest_path)
+        .map_err(|e| format!("cannot load manifest {}: {e}", manifest_path.display()))?;
+    match manifest.checksum_chunk {
+        Some(chunk) => println!(
+            "🔍 Verifying {} files against {} ({chunk}-byte checksums)",
+            manifest.files.len(),
+            manifest_path.display()
+        ),
+        None => {
+            println!("🔍 Verifying {} files against {}", manifest.files.len(), manifest_path.display());
+            println!("⚠️  The manifest has no checksums (create the files with --checksums); comparing fingerprints only");
+        }
+    }
+
+    let checks = dataset::verify(&manifest);
+    let mut failed = 0;
+    for check in &checks {
+        if let Some(error) = &check.error {
+            println!("❌ {}: {error}", check.path);
+        } else if !check.bad_chunks.is_empty() {
+            let bad_bytes: u64 = check.bad_chunks.iter().map(|(start, end)| end - start).sum();
+            println!("❌ {}: {} corrupted chunk(s), {bad_bytes} bytes", check.path, check.bad_chunks.len());
+            for (start, end) in check.bad_chunks.iter().take(5) {
+                println!("    bytes {start}..{end}");
+            }
+        } else {
+            continue;
+        }
+        failed += 1;
+    }
+    if failed > 0 {
+        return Err(format!("{failed} of {} files do not match the manifest", checks.len()).into());
+    }
+    println!("✅ All {} files match the manifest", checks.len());
+    Ok(())
+}
+
+/// The test files of a previous run with the same prefix, if all are present
+/// with the expected size.
+fn existing_test_files(args: &Args) -> Option<Vec<String>> {
+    (0..args.num_files)
+        .map(|i| (dataset::file_path(args, i), args.file_size_of(i)))
+        .map(|(path, size)| match std::fs::metadata(&path) {
+            Ok(meta) if meta.len() == size => Some(path),
+            _ => None,
+        })
+        .collect()
+}
+
+fn create_test_files(args: &Args, zone_size: Option<u64>) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let file_paths: Vec<String> = (0..args.num_files).map(|i| dataset::file_path(args, i)).collect();
+    
+    // Create test data buffer, reused for every chunk written
+    let largest = (0..args.num_files).map(|i| args.file_size_of(i)).max().unwrap_or(0);
+    let test_data = vec![0xAB; CREATE_CHUNK_SIZE.min(largest) as usize];
+    
+    let mut order: Vec<usize> = (0..args.num_files).collect();
+    let mut rng = StdRng::seed_from_u64(args.seed ^ 0xC8EA_7E00);
+    if args.create_order != CreateOrder::Sequential {
+        order.shuffle(&mut rng);
+    }
+    if args.create_procs > 1 {
+        shards::create(args, &file_paths)?;
+    } else if args.create_order == CreateOrder::Interleaved {
+        order = write_interleaved(args, &file_paths, &test_data, &mut rng)?;
+    } else {
+        for &i in &order {
+            dataset::create_parent(&file_paths[i])?;
+            let file_size = args.file_size_of(i);
+            let mut file = File::create(&file_paths[i])?;
+            match args.create_mode {
+                CreateMode::Write => write_test_pattern(&mut file, file_size, &test_data, zone_size)?,
+                CreateMode::Sparse => file.set_len(file_size)?,
+                CreateMode::Fallocate => fsutil::fallocate(&file, file_size)?,
+            }
+            file.sync_all()?;
+        }
+    }
+    let checksum_chunk = args.checksums.then_some(dataset::CHECKSUM_CHUNK);
+    let mut manifest = dataset::Manifest::build(&file_paths, &value_name(&args.create_mode), checksum_chunk)?;
+    if args.create_order != CreateOrder::Sequential {
+        manifest.creation_order = Some(dataset::CreationOrder { mode: value_name(&args.create_order), seed: args.seed, files: order });
+    }
+    manifest.save(&dataset::manifest_path(args))?;
+    
+    Ok(file_paths)
+}
+
+/// Write the test pattern one chunk at a time, picking the file of every
+/// chunk at random, so the files' extents interleave on the device. Returns
+/// the files in the order their first chunk was written.
+fn write_interleaved(args: &Args, file_paths: &[String], chunk: &[u8], rng: &mut StdRng) -> std::io::Result<Vec<usize>> {
+    use std::os::unix::fs::FileExt;
+
+    for path in file_paths {
+        dataset::create_parent(path)?;
+        File::create(path)?;
+    }
+    let mut written = vec![0u64; file_paths.len()];
+    let mut unfinished: Vec<usize> = (0..file_paths.len()).filter(|&i| args.file_size_of(i) > 0).collect();
+    let mut order = Vec::with_capacity(file_paths.len());
+    while !unfinished.is_empty() {
+        let pick = rng.random_range(0..unfinished.len());
+        let i = unfinished[pick];
+        if written[i] == 0 {
+            order.push(i);
+        }
+        let len = (chunk.len() as u64).min(args.file_size_of(i) - written[i]);
+        // Reopened per chunk, so datasets of many files do not run out of descriptors
+        File::options().write(true).open(&file_paths[i])?.write_all_at(&chunk[..len as usize], written[i])?;
+        written[i] += len;
+        if written[i] == args.file_size_of(i) {
+            unfinished.swap_remove(pick);
+        }
+    }
+    order.extend((0..file_paths.len()).filter(|&i| args.file_size_of(i) == 0));
+    for path in file_paths {
+        File::open(path)?.sync_all()?;
+    }
+    Ok(order)
+}
+
+fn write_test_pattern(file: &mut File, size: u64, chunk: &[u8], zone_size: Option<u64>) -> std::io::Result<()> {
+    let mut written = 0u64;
+    while written < size {
+        let mut len = (chunk.len() as u64).min(size - written);
+        if let Some(zone_size) = zone_size {
+            // Fill one zone's worth at a time and flush it before moving on,
+            // so writes stay sequential within each zone
+            len = len.min(zone_size - written % zone_size);
+        }
+        file.write_all(&chunk[..len as usize])?;
+        written += len;
+        if zone_size.is_some_and(|z| written.is_multiple_of(z)) {
+            file.sync_data()?;
+        }
+    }
+    Ok(())
+}
+
+fn run_tests(args: &Args, file_paths: &[String], ctx: &RunContext) -> Result<Vec<ReadResult>, Box<dyn std::error::Error>> {
+    let prefetcher = match args.prefetch_distance {
+        Some(distance) => Some(prefetch::Prefetcher::start(file_paths, distance)?),
+        None => None,
+    };
+    let prefetch = prefetcher.as_ref().map(prefetch::Prefetcher::handle);
+
+    let results = match engine::open(&args.engine, args, file_paths)? {
+        engine::Engine::Sync(engine) => {
+            let engine: Arc<dyn engine::IoEngine> = engine.into();
+            let reader = Arc::clone(&engine);
+            let syncer = Arc::clone(&engine);
+            let raw = args.read_after_write.map(|mode| consistency::RawChecker::start(Arc::clone(&engine), mode));
+            let writer = raw.clone();
+            let checker = raw.clone();
+            let verifier = args.verify.then(|| {
+                let fill = if args.create_mode == CreateMode::Write { 0xAB } else { 0 };
+                let expected = if args.writes() { vec![fill, engine::WRITE_PATTERN] } else { vec![fill] };
+                Arc::new(verify::Verifier::new(Arc::clone(&engine), expected, args.verify_sample.unwrap_or(1.0), args.seed))
+            });
+            let sampler = verifier.clone();
+            let finisher = Arc::clone(&engine);
+            let results = driver::run_readers(
+                args,
+                ctx,
+                file_paths.len(),
+                prefetch,
+                move |op| match (op.kind, &writer) {
+                    (workload::OpKind::Read, _) => reader.read(op.file, op.offset, op.len as usize),
+                    (workload::OpKind::Write, Some(raw)) => raw.write(op),
+                    (workload::OpKind::Write, None) => {
+                        reader.write(op.file, op.offset, &vec![engine::WRITE_PATTERN; op.len as usize])
+                    }
+                },
+                move |op| syncer.sync(op.file),
+                move |op| {
+                    finisher.after(op);
+                    if let Some(raw) = &checker {
+                        raw.after(op);
+                    }
+                    if let Some(verifier) = &sampler {
+                        verifier.after(op);
+                    }
+                },
+            );
+            engine.report();
+            if let Some(verifier) = verifier {
+                let (checked, mismatched) = verifier.finish();
+                ctx.verified_reads.fetch_add(checked, Ordering::Relaxed);
+                ctx.mismatched_reads.fetch_add(mismatched, Ordering::Relaxed);
+            }
+            if let Some(raw) = raw {
+                raw.finish();
+            }
+            if args.whole_file_copies {
+                report_whole_file_copies(engine.as_ref(), args, file_paths.len());
+            }
+            results
+        }
+        engine::Engine::Async(engine) => {
+            if args.whole_file_copies {
+                println!("⚠️  --whole-file-copies needs a synchronous engine; skipping");
+            }
+            let engine: Arc<dyn engine::AsyncEngine> = engine.into();
+            let results = if args.scatter > 1 {
+                driver::run_async_scatter_readers(args, ctx, file_paths.len(), prefetch, Arc::clone(&engine), args.scatter)
+            } else {
+                driver::run_async_readers(args, ctx, file_paths.len(), prefetch, Arc::clone(&engine), args.queue_depth)
+            };
+            engine.report();
+            results
+        }
+    };
+    if args.buffers == BufferKind::Pinned {
+        engine::report_pinned();
+    }
+
+    if let Some(prefetcher) = prefetcher {
+        let stats = prefetcher.finish();
+        println!(
+            "🔮 Prefetcher: {} hints issued, {} failed, {} dropped, {:.1}ms spent hinting",
+            stats.issued,
+            stats.failed,
+            stats.dropped,
+            stats.busy_time.as_secs_f64() * 1000.0
+        );
+    }
+    Ok(results)
+}
+
+/// Per-phase summaries of a sweep, with the insights drawn from its steps.
+type SweepResults = (Vec<results::PhaseSummary>, Vec<insights::Insight>);
+
+/// Measure with a growing working set to find where reads fall out of the page cache.
+fn run_working_set_sweep(
+    args: &Args,
+    file_paths: &[String],
+    clock: timing::Clock,
+    zone_map: Option<Arc<device::ZoneMap>>,
+    dataset_bytes: u64,
+    latency_correction: Duration,
+    control: Option<Arc<control::Control>>,
+) -> Result<SweepResults, Box<dyn std::error::Error>> {
+    let ram = procfs::MemInfo::read()?.total().ok_or("MemTotal missing from /proc/meminfo")?;
+    let block_size = args.block_size as u64;
+    let blocks_per_file = (0..args.num_files).map(|file| args.file_size_of(file) / block_size).max().unwrap_or(0);
+
+    let mut rows = Vec::new();
+    let mut phases = Vec::new();
+    for &percent in &args.ws_sweep {
+        let wanted = (ram as f64 * percent / 100.0) as u64;
+        if wanted > dataset_bytes {
+            println!(
+                "⚠️  Working set {percent}% of RAM ({wanted} bytes) exceeds the dataset ({dataset_bytes} bytes); capping"
+            );
+        }
+        let per_file = wanted.min(dataset_bytes).div_ceil(args.num_files as u64);
+        let working_set_blocks = per_file.div_ceil(block_size).clamp(1, blocks_per_file.max(1));
+
+        println!("🔬 Working set {percent}% of RAM ({} bytes)...", working_set_blocks * block_size * args.num_files as u64);
+        if let Some(control) = &control {
+            control.begin_phase();
+        }
+        let ctx = RunContext {
+            run_start: Instant::now(),
+            start_ticks: clock.now(),
+            clock,
+            zone_map: zone_map.clone(),
+            working_set_blocks: Some(working_set_blocks),
+            read_blocks: Arc::new(Mutex::new(BlockCounts::new())),
+            progress: None,
+            latency_correction,
+            workload: None,
+            failed_reads: Arc::new(AtomicU64::new(0)),
+            first_error: Arc::default(),
+            verified_reads: Arc::new(AtomicU64::new(0)),
+            mismatched_reads: Arc::new(AtomicU64::new(0)),
+            faults: None,
+            precision: None,
+            soak: None,
+            ingest: None,
+            schedules: None,
+            control: control.clone(),
+        };
+        let results = run_tests(args, file_paths, &ctx)?;
+        let elapsed = ctx.elapsed();
+        let first_reads = results.iter().filter(|r| r.is_first_read).count();
+        let stats = calculate_statistics(results.iter().map(|r| &r.latency).collect());
+        phases.push(phase_summary(format!("ws {percent}%"), &args.engine, &results));
+        rows.push((percent, working_set_blocks * block_size * args.num_files as u64, elapsed, first_reads, stats));
+    }
+
+    let points: Vec<insights::SweepPoint> = rows
+        .iter()
+        .map(|(percent, _, elapsed, _, stats)| insights::SweepPoint {
+            value: *percent,
+            iops: stats.count as f64 / elapsed.as_secs_f64(),
+            median: stats.median,
+        })
+        .collect();
+    println!("\n📊 Working Set Sweep (RAM: {ram} bytes):");
+    println!(
+        "  {:>7} {:>14} {:>8} {:>10} {:>10} {:>10} {:>10} {:>7}",
+        "% RAM", "Bytes", "Ops", "IOPS", "Average", "Median", "99th %ile", "First"
+    );
+    for (percent, bytes, elapsed, first_reads, stats) in rows {
+        println!(
+            "  {:>6}% {:>14} {:>8} {:>10.0} {:>10} {:>10} {:>10} {:>6.1}%",
+            percent,
+            bytes,
+            stats.count,
+            stats.count as f64 / elapsed.as_secs_f64(),
+            units::format_latency(stats.avg),
+            units::format_latency(stats.median),
+            units::format_latency(stats.p99),
+            100.0 * first_reads as f64 / stats.count.max(1) as f64
+        );
+    }
+    Ok((phases, insights::from_working_sets(&points)))
+}
+
+/// Probe length of each thread count tried by `--auto-threads`.
+const AUTO_THREADS_PROBE: Duration = Duration::from_secs(1);
+/// Share of the target rate a thread count must reach.
+const AUTO_THREADS_MET: f64 = 0.95;
+
+/// The fewest threads, doubling from one up to `--threads`, that reach `rate`
+/// in a short probe; `--threads` if none does.
+fn find_thread_count(
+    args: &Args,
+    file_paths: &[String],
+    clock: timing::Clock,
+    zone_map: Option<Arc<device::ZoneMap>>,
+    latency_correction: Duration,
+    rate: u64,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut threads = 1;
+    loop {
+        let mut probe_args = args.clone();
+        probe_args.num_threads = threads;
+        // The probe ends by skipping its phase, not by running out of operations
+        probe_args.num_operations = usize::MAX;
+        let control = Arc::new(control::Control::new(Some(rate), threads, false, false));
+        let ctx = RunContext {
+            run_start: Instant::now(),
+            start_ticks: clock.now(),
+            clock,
+            zone_map: zone_map.clone(),
+            working_set_blocks: None,
+            read_blocks: Arc::new(Mutex::new(BlockCounts::new())),
+            progress: None,
+            latency_correction,
+            workload: None,
+            failed_reads: Arc::new(AtomicU64::new(0)),
+            first_error: Arc::default(),
+            verified_reads: Arc::new(AtomicU64::new(0)),
+            mismatched_reads: Arc::new(AtomicU64::new(0)),
+            faults: None,
+            precision: None,
+            soak: None,
+            ingest: None,
+            schedules: None,
+            control: Some(Arc::clone(&control)),
+        };
+        let timer = std::thread::spawn(move || {
+            std::thread::sleep(AUTO_THREADS_PROBE);
+            control.skip_phase();
+        });
+        let results = run_tests(&probe_args, file_paths, &ctx)?;
+        let _ = timer.join();
+        let achieved = results.len() as f64 / ctx.elapsed().as_secs_f64().max(f64::EPSILON);
+        let met = achieved >= rate as f64 * AUTO_THREADS_MET;
+        println!("  {threads:>4} threads: {achieved:.0} operations/s{}", if met { " ✅" } else { "" });
+        if met || threads >= args.num_threads || status::interrupted() {
+            if !met {
+                println!("⚠️  {threads} threads fall short of {rate} operations/s; raise --threads to try more");
+            }
+            return Ok(threads);
+        }
+        threads = (threads * 2).min(args.num_threads);
+    }
+}
+
+/// Read every test file in one engine call and report the throughput.
+fn report_whole_file_copies(engine: &dyn engine::IoEngine, args: &Args, num_files: usize) {
+    let mut durations = Vec::with_capacity(num_files);
+    let mut bytes = 0;
+    let started = Instant::now();
+    for file in 0..num_files {
+        let Ok(len) = usize::try_from(args.file_size_of(file)) else { return };
+        bytes += len as u64;
+        let start = Instant::now();
+        if let Err(e) = engine.read(file, 0, len) {
+            println!("⚠️  Whole-file copy of file {file} failed: {e}");
+            return;
+        }
+        durations.push(start.elapsed());
+    }
+    let elapsed = started.elapsed();
+    let stats = calculate_statistics(durations.iter().collect());
+
+    println!("\n📦 Whole-File Copies ({num_files} files, {bytes} bytes, engine {}):", args.engine);
+    println!(
+        "  Throughput: {:.1} MiB/s",
+        bytes as f64 / elapsed.as_secs_f64() / (1024.0 * 1024.0)
+    );
+    println!("  Per file:   median {}, max {}", units::format_latency(stats.median), units::format_latency(stats.max));
+}
+
+/// Measure the async engine at each queue depth to find where the device saturates.
+fn run_queue_depth_sweep(
+    args: &Args,
+    file_paths: &[String],
+    clock: timing::Clock,
+    zone_map: Option<Arc<device::ZoneMap>>,
+    latency_correction: Duration,
+    control: Option<Arc<control::Control>>,
+) -> Result<SweepResults, Box<dyn std::error::Error>> {
+    let mut rows = Vec::new();
+    let mut phases = Vec::new();
+    for (index, &depth) in args.qd_sweep.iter().enumerate() {
+        // A fresh seed per depth, so blocks cached by earlier depths are not simply re-read
+        let mut depth_args = args.clone();
+        depth_args.queue_depth = depth;
+        depth_args.seed = args.seed.wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+        println!("🔬 Queue depth {depth}...");
+        if let Some(control) = &control {
+            control.begin_phase();
+        }
+        let ctx = RunContext {
+            run_start: Instant::now(),
+            start_ticks: clock.now(),
+            clock,
+            zone_map: zone_map.clone(),
+            working_set_blocks: None,
+            read_blocks: Arc::new(Mutex::new(BlockCounts::new())),
+            progress: None,
+            latency_correction,
+            workload: None,
+            failed_reads: Arc::new(AtomicU64::new(0)),
+            first_error: Arc::default(),
+            verified_reads: Arc::new(AtomicU64::new(0)),
+            mismatched_reads: Arc::new(AtomicU64::new(0)),
+            faults: None,
+            precision: None,
+            soak: None,
+            ingest: None,
+            schedules: None,
+            control: control.clone(),
+        };
+        let results = run_tests(&depth_args, file_paths, &ctx)?;
+        let elapsed = ctx.elapsed();
+        let first_reads = results.iter().filter(|r| r.is_first_read).count();
+        let service: Vec<Duration> = results
+            .iter()
+            .filter_map(|r| r.submit_delay.map(|delay| r.latency.saturating_sub(delay)))
+            .collect();
+        let service = calculate_statistics(service.iter().collect());
+        let stats = calculate_statistics(results.iter().map(|r| &r.latency).collect());
+        phases.push(phase_summary(format!("qd {depth}"), &depth_args.engine, &results));
+        rows.push((depth, elapsed, first_reads, stats, service));
+    }
+
+    let points: Vec<insights::SweepPoint> = rows
+        .iter()
+        .map(|(depth, elapsed, _, stats, _)| insights::SweepPoint {
+            value: *depth as f64,
+            iops: stats.count as f64 / elapsed.as_secs_f64(),
+            median: stats.median,
+        })
+        .collect();
+    println!("\n📊 Queue Depth Sweep (engine {}, {} threads):", args.engine, args.num_threads);
+    println!(
+        "  {:>5} {:>8} {:>10} {:>10} {:>10} {:>10} {:>12} {:>7}",
+        "QD", "Ops", "IOPS", "Average", "Median", "99th %ile", "Device p50", "First"
+    );
+    for (depth, elapsed, first_reads, stats, service) in rows {
+        println!(
+            "  {:>5} {:>8} {:>10.0} {:>10} {:>10} {:>10} {:>12} {:>6.1}%",
+            depth,
+            stats.count,
+            stats.count as f64 / elapsed.as_secs_f64(),
+            units::format_latency(stats.avg),
+            units::format_latency(stats.median),
+            units::format_latency(stats.p99),
+            units::format_latency(service.median),
+            100.0 * first_reads as f64 / stats.count.max(1) as f64
+        );
+    }
+    Ok((phases, insights::from_queue_depths(&points)))
+}
+
+/// All, first and repeated read latency; the sorted first and repeated reads
+/// are merged for all of them.
+fn split_summaries(results: &[ReadResult]) -> [Option<results::LatencySummary>; 3] {
+    let (first, repeated): (Vec<&ReadResult>, Vec<&ReadResult>) = results.iter().partition(|r| r.is_first_read);
+    let first = stats::Samples::new(first.iter().map(|r| r.latency));
+    let repeated = stats::Samples::new(repeated.iter().map(|r| r.latency));
+    let mut all = first.clone();
+    all.merge(&repeated);
+    let summary = |samples: &stats::Samples| (!samples.is_empty()).then(|| (&samples.statistics()).into());
+    [summary(&all), summary(&first), summary(&repeated)]
+}
+
+/// All/first/repeated latency of one phase's results.
+fn phase_summary(phase: String, engine: &str, results: &[ReadResult]) -> results::PhaseSummary {
+    let [all, first, repeated] = split_summaries(results);
+    results::PhaseSummary { phase, engine: engine.to_string(), all, first, repeated }
+}
+
+/// Print the first/repeated split of each phase and write it to the results file.
+fn report_phases(
+    args: &Args,
+    run: &results::RunInfo,
+    phases: Vec<results::PhaseSummary>,
+    insights: Vec<insights::Insight>,
+    trim: &[results::TrimSummary],
+    elapsed: Duration,
+) {
+    println!("\n🆕 First vs Repeated Reads by Phase:");
+    println!(
+        "  {:>10} {:>10} {:>8} {:>10} {:>10} {:>8} {:>10} {:>10}",
+        "Phase", "Engine", "First", "Median", "99th %ile", "Repeated", "Median", "99th %ile"
+    );
+    let columns = |summary: &Option<results::LatencySummary>| match summary {
+        Some(s) => format!("{:>8} {:>10} {:>10}", s.count, units::format_ns(s.median_ns as f64), units::format_ns(s.p99_ns as f64)),
+        None => format!("{:>8} {:>10} {:>10}", 0, "-", "-"),
+    };
+    for phase in &phases {
+        println!("  {:>10} {:>10} {} {}", phase.phase, phase.engine, columns(&phase.first), columns(&phase.repeated));
+    }
+    insights::report(&insights);
+
+    if args.json {
+        let mut results_file = results::ResultsFile::new(run, checkpoint::RunIdentity::from(args), elapsed);
+        results_file.rng = args.rng;
+        results_file.trim = trim.to_vec();
+        results_file.phases = phases;
+        results_file.insights = insights;
+        let path = run.output_path(&args.output);
+        match results_file.save(&path) {
+            Ok(()) => println!("\n📝 Results written to {}", path.display()),
+            Err(e) => println!("\n⚠️  Cannot write results to {}: {e}", path.display()),
+        }
+    }
+}
+
+/// Set up `--device`: the device becomes the only "file", sized in whole blocks.
+fn open_raw_device(args: &mut Args, path: &Path) -> Result<device::BlockDevice, String> {
+    let device = device::block_device_node(path).map_err(|e| format!("--device {}: {e}", path.display()))?;
+    if matches!(args.command, Some(Command::CrashTest { .. })) {
+        return Err("crash-test writes its own test files and cannot run against --device".to_string());
+    }
+    if args.writes() && !args.allow_destructive {
+        return Err(format!(
+            "writes would overwrite data on {}; add --allow-destructive if its contents may be destroyed",
+            path.display()
+        ));
+    }
+    let logical_block_size = device.logical_block_size();
+    if !(args.block_size as u64).is_multiple_of(logical_block_size) {
+        return Err(format!(
+            "--block-size ({}) must be a multiple of the logical block size of {} ({logical_block_size} bytes)",
+            args.block_size,
+            path.display()
+        ));
+    }
+    let physical_block_size = device.physical_block_size();
+    if !(args.block_size as u64).is_multiple_of(physical_block_size) {
+        println!(
+            "⚠️  --block-size ({}) is not a multiple of the physical block size of {} ({physical_block_size} bytes); writes need a read-modify-write",
+            args.block_size,
+            path.display()
+        );
+    }
+    let size = std::fs::File::open(path)
+        .and_then(|file| fsutil::file_len(&file))
+        .map_err(|e| format!("cannot open --device {}: {e}", path.display()))?;
+    args.num_files = 1;
+    args.file_size = size - size % args.block_size as u64;
+    Ok(device)
+}
+
+/// Reject counts of zero, which the rest of the setup would divide by.
+fn reject_zero_counts(args: &Args) -> Result<(), String> {
+    for (value, name) in [(args.num_files, "--num-files"), (args.num_threads, "--num-threads"), (args.block_size, "--block-size")] {
+        if value == 0 {
+            return Err(format!("{name} must be at least 1"));
+        }
+    }
+    Ok(())
+}
+
+/// Reject option combinations that would run no operations or measure nonsense.
+fn validate_config(args: &Args) -> Result<(), String> {
+    if args.pregenerate && args.job.as_ref().is_some_and(jobfile::Job::is_paced) {
+        return Err("--pregenerate draws operations before the run, so job classes with a rate would be paced while drawing, not while issuing; drop one".to_string());
+    }
+    if args.verify && !engine::keeps_data(&args.engine) {
+        return Err(format!("--verify checks the data each read left in memory; the {} engine does not keep it", args.engine));
+    }
+    if !engine::is_async(&args.engine) && (args.queue_depth > 1 || args.submit_batch.is_some() || !args.qd_sweep.is_empty()) {
+        return Err(format!("--queue-depth, --submit-batch and --qd-sweep need an async engine; {} issues one read at a time", args.engine));
+    }
+    if let Some(&depth) = args.qd_sweep.iter().find(|&&d| d == 0 || d > 4096) {
+        return Err(format!("--qd-sweep depth {depth} is outside 1..=4096"));
+    }
+    if let Some(dir) = args.target_dirs.iter().enumerate().find_map(|(i, dir)| args.target_dirs[..i].contains(dir).then_some(dir)) {
+        return Err(format!("--target-dir {} is given twice", dir.display()));
+    }
+    if args.target_dirs.len() > 1 && (args.zone_aware || args.write_amplification) {
+        return Err("--zone-aware and --write-amplification follow a single device; use them with at most one --target-dir".to_string());
+    }
+    if args.scatter > 1 && args.queue_depth > 1 {
+        return Err("--scatter submits each operation's blocks as one batch; it cannot be combined with --queue-depth".to_string());
+    }
+    if args.writes() && (!engine::can_write(&args.engine) || args.poll && args.engine == "standard") {
+        return Err(format!("the {} engine cannot write{}", args.engine, if args.poll { " with --poll" } else { "" }));
+    }
+    let block_size = args.block_size as u64;
+    let (size_option, sizes) = match &args.sizes[..] {
+        [] => ("--file-size", vec![args.file_size]),
+        sizes => ("--file-sizes", sizes.to_vec()),
+    };
+    for file_size in sizes {
+        if block_size > file_size {
+            return Err(format!(
+                "--block-size ({}) exceeds {size_option} ({file_size}), so files hold no complete block; raise {size_option} or lower --block-size",
+                args.block_size
+            ));
+        }
+        if !file_size.is_multiple_of(block_size) && args.workload_plugin.is_none() && args.workload_script.is_none() {
+            return Err(format!(
+                "{size_option} ({file_size}) is not a multiple of --block-size ({}); the last {} bytes of such files would never be read (try {})",
+                args.block_size,
+                file_size % block_size,
+                file_size - file_size % block_size
+            ));
+        }
+    }
+    if args.num_threads > args.num_operations {
+        return Err(format!(
+            "--num-threads ({}) exceeds --num-operations ({}), so {} threads would have nothing to do",
+            args.num_threads,
+            args.num_operations,
+            args.num_threads - args.num_operations
+        ));
+    }
+    if args.writes() && (args.checkpoint.is_some() || args.resume.is_some()) {
+        return Err("checkpoints record reads only; drop --checkpoint/--resume or run without writes".to_string());
+    }
+    if let Some(fraction) = args.inject_corruption
+        && !(0.0..=1.0).contains(&fraction)
+    {
+        return Err(format!("--inject-corruption is a fraction of blocks between 0 and 1, got {fraction}"));
+    }
+    if args.buffers == BufferKind::Pinned && !["standard", "io_uring", "libaio"].contains(&args.engine.as_str()) {
+        return Err(format!("--buffers pinned needs an engine that reads into buffers (standard, io_uring or libaio), not {}", args.engine));
+    }
+    if let Some(fraction) = args.ingest {
+        if !(fraction > 0.0 && fraction < 1.0) {
+            return Err(format!("--ingest is the fraction of each file written before the readers start, between 0 and 1; got {fraction}"));
+        }
+        if args.create_mode != CreateMode::Write {
+            return Err("--ingest writes the files while the readers run; it needs --create-mode write".to_string());
+        }
+    }
+    if args.create_procs > 1 && (args.create_order != CreateOrder::Sequential || args.zone_aware) {
+        return Err("--create-procs creates files in parallel; it cannot follow --create-order or --zone-aware".to_string());
+    }
+    if args.create_order == CreateOrder::Interleaved && (args.create_mode != CreateMode::Write || args.zone_aware) {
+        return Err("--create-order interleaved writes chunks of all files in turn; it needs --create-mode write and no --zone-aware".to_string());
+    }
+    if args.cache_churn_size.is_some_and(|size| size < 1024 * 1024) {
+        return Err("--cache-churn-size must be at least 1M".to_string());
+    }
+    if args.write_amplification && !args.writes() {
+        return Err("--write-amplification needs writes; add --write-percent".to_string());
+    }
+    if args.fsync_writes && (args.engine != "standard" || !args.writes()) {
+        return Err("--fsync-writes syncs after the writes of the standard engine; add --write-percent and use --engine standard".to_string());
+    }
+    if args.msync != msync::MsyncPolicy::None && (args.engine != "mmap" || !args.writes()) {
+        return Err("--msync applies to writes with the mmap engine; add --engine mmap and --write-percent".to_string());
+    }
+    if !(args.target_percentile > 0.0 && args.target_percentile < 100.0) {
+        return Err(format!("--target-percentile must be between 0 and 100, got {}", args.target_percentile));
+    }
+    if !(0.0..50.0).contains(&args.trim_percent) {
+        return Err(format!("--trim-percent must be at least 0 and below 50, got {}", args.trim_percent));
+    }
+    if !(args.trim_warmup >= 0.0 && args.trim_warmup.is_finite()) {
+        return Err(format!("--trim-warmup must be a non-negative number of seconds, got {}", args.trim_warmup));
+    }
+    if args.soak.is_some() && args.out.iter().any(|sink| sink.format == report::Format::Csv) {
+        return Err("--soak keeps no per-operation results for --out csv; use --soak-raw-log".to_string());
+    }
+    if args.inflight.is_some() && args.engine != "threadpool" {
+        return Err("--inflight sizes the worker pool of the threadpool engine; add --engine threadpool".to_string());
+    }
+    if args.job_file.is_some() && args.write_percent > 0 {
+        return Err("--job-file classes decide which operations write; drop --write-percent and the writes of --rw".to_string());
+    }
+    if args.job_file.is_some() && args.workload != WorkloadKind::Point {
+        return Err("--job-file classes set the length of each operation; they apply to --workload point".to_string());
+    }
+    if args.fault_batch > 0 && args.engine != "mmap" {
+        return Err("--fault-batch attributes page faults of mapped reads; add --engine mmap".to_string());
+    }
+    if args.read_after_write.is_some() && !args.writes() {
+        return Err("--read-after-write checks writes; set --write-percent or a --rw mix with writes".to_string());
+    }
+    if args.read_after_write.is_some() && args.block_size < consistency::HEADER_LEN {
+        return Err(format!("--read-after-write stamps each written block and needs --block-size of at least {} bytes", consistency::HEADER_LEN));
+    }
+    if args.randomize_seed && args.resume.is_some() {
+        return Err("--randomize-seed cannot be combined with --resume, which continues with the checkpoint's seed".to_string());
+    }
+    if args.hotspot_move.is_some() && args.distribution.is_uniform() {
+        return Err("--hotspot-move needs a skewed --distribution; a uniform one has no hot spot to move".to_string());
+    }
+    if args.pattern != distribution::AccessPattern::Random
+        && (!args.distribution.is_uniform()
+            || args.hotspot_move.is_some()
+            || args.file_weights != distribution::FileWeights::Uniform)
+    {
+        return Err(format!(
+            "--pattern {} walks the files in a fixed order; --distribution, --file-weights and --hotspot-move only apply to --pattern random",
+            args.pattern
+        ));
+    }
+    if args.workload == WorkloadKind::WriteVerify
+        && (args.device.is_some()
+            || args.reuse_existing
+            || args.soak.is_some()
+            || !args.ws_sweep.is_empty()
+            || !args.qd_sweep.is_empty()
+            || args.checkpoint.is_some()
+            || args.resume.is_some()
+            || args.ingest.is_some())
+    {
+        return Err("--workload write-verify runs its own cycles on fresh test files; drop --device, --reuse-existing, --soak, the sweeps, checkpoints and --ingest".to_string());
+    }
+    if args.workload == WorkloadKind::WriteVerify && args.write_verify_blocks == 0 {
+        return Err("--write-verify-blocks must be at least 1".to_string());
+    }
+    if args.workload == WorkloadKind::Scan && args.engine == "mmap" && args.mmap_segment_size > 0 {
+        return Err("--workload scan reads ranges that may cross mapped segments; drop --mmap-segment-size".to_string());
+    }
+    if args.thread_matrix && args.interval == 0 {
+        return Err("--thread-matrix needs a reporting interval (--interval > 0)".to_string());
+    }
+    if args.sample_system && args.interval == 0 {
+        return Err("--sample-system needs a reporting interval (--interval > 0)".to_string());
+    }
+
+    if args.engine == "mmap" && args.mmap_segment_size == 0 {
+        let dataset_bytes = args.dataset_bytes().unwrap_or(u64::MAX);
+        // 47 bits of user address space on common 64-bit kernels
+        let address_space: u64 = if usize::BITS >= 64 { 1 << 47 } else { 1 << 31 };
+        if dataset_bytes > address_space {
+            return Err(format!(
+                "mapping all files ({dataset_bytes} bytes) exceeds the {address_space}-byte address space; use --mmap-segment-size"
+            ));
+        }
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        if unsafe { libc::getrlimit(libc::RLIMIT_AS, &mut limit) } == 0
+            && limit.rlim_cur != libc::RLIM_INFINITY
+            && dataset_bytes > limit.rlim_cur
+        {
+            return Err(format!(
+                "mapping all files ({dataset_bytes} bytes) exceeds the address-space limit (ulimit -v, {} bytes); use --mmap-segment-size",
+                limit.rlim_cur
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The per-thread seeds, abbreviated for many threads. Only `--rng stdrng`
+/// seeds threads separately; the other generators split one seed into streams.
+fn format_thread_seeds(kind: rng::RngKind, seed: u64, num_threads: usize) -> String {
+    if kind != rng::RngKind::Stdrng {
+        return format!("{kind} stream i of seed {seed} for thread i");
+    }
+    const SHOWN: usize = 8;
+    let mut seeds: Vec<String> = (0..num_threads.min(SHOWN))
+        .map(|thread_id| format!("{thread_id}={}", rng::thread_seed(seed, thread_id)))
+        .collect();
+    if num_threads > SHOWN {
+        seeds.push(format!("… {}={}", num_threads - 1, rng::thread_seed(seed, num_threads - 1)));
+    }
+    seeds.join(", ")
+}
+
+/// The command-line spelling of a value-enum option.
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn page_size() -> usize {
+    static PAGE_SIZE: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+    *PAGE_SIZE.get_or_init(|| {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 { size as usize } else { 4096 }
+    })
+}
+
+fn analyze_and_report_results(results: &[ReadResult]) {
+    if results.is_empty() {
+        println!("❌ No results to analyze");
+        return;
+    }
+    
+    let all_results = results;
+    let first_reads: Vec<_> = results.iter().filter(|r| r.is_first_read).collect();
+    let repeated_reads: Vec<_> = results.iter().filter(|r| !r.is_first_read).collect();
+    
+    println!("\n📈 All Reads ({} operations):", all_results.len());
+    print_statistics(calculate_statistics(all_results.iter().map(|r| &r.latency).collect()));
+    
+    if !first_reads.is_empty() {
+        println!("\n🆕 First Reads ({} operations):", first_reads.len());
+        print_statistics(calculate_statistics(first_reads.iter().map(|r| &r.latency).collect()));
+    }
+    
+    if !repeated_reads.is_empty() {
+        println!("\n🔄 Repeated Reads ({} operations):", repeated_reads.len());
+        print_statistics(calculate_statistics(repeated_reads.iter().map(|r| &r.latency).collect()));
+    }
+
+    report_latency_breakdown(results);
+    report_zone_statistics(results);
+    report_periodicity(results);
+}
+
+/// Split async-engine latencies into time queued in the harness and time in the kernel/device.
+fn report_latency_breakdown(results: &[ReadResult]) {
+    let split: Vec<(Duration, Duration)> = results
+        .iter()
+        .filter_map(|r| r.submit_delay.map(|delay| (delay, r.latency.saturating_sub(delay))))
+        .collect();
+    if split.is_empty() {
+        return;
+    }
+
+    println!("\n⏳ Latency Breakdown ({} operations):", split.len());
+    println!("  Issue → submit (queued in the harness):");
+    print_statistics(calculate_statistics(split.iter().map(|(queued, _)| queued).collect()));
+    println!("  Submit → complete (kernel and device):");
+    print_statistics(calculate_statistics(split.iter().map(|(_, service)| service).collect()));
+}
+
+/// Reads and latencies per file, hottest first, against the configured weights.
+fn report_file_hits(results: &[ReadResult], weights: &distribution::FileWeights, num_files: usize) {
+    const SHOWN: usize = 20;
+    let mut per_file: Vec<Vec<&ReadResult>> = vec![Vec::new(); num_files];
+    for r in results {
+        if let Some(reads) = per_file.get_mut(r.file) {
+            reads.push(r);
+        }
+    }
+    let mut order: Vec<usize> = (0..num_files).collect();
+    order.sort_by_key(|&file| std::cmp::Reverse(per_file[file].len()));
+
+    println!("\n🗂️  Reads per File:");
+    println!(
+        "  {:>6} {:>8} {:>8} {:>9} {:>8} {:>10} {:>10}",
+        "File", "Expected", "Share", "Reads", "First", "Average", "99th %ile"
+    );
+    for &file in order.iter().take(SHOWN) {
+        let reads = &per_file[file];
+        if reads.is_empty() {
+            continue;
+        }
+        let first = reads.iter().filter(|r| r.is_first_read).count();
+        let stats = calculate_statistics(reads.iter().map(|r| &r.latency).collect());
+        println!(
+            "  {file:>6} {:>7.2}% {:>7.2}% {:>9} {first:>8} {:>10} {:>10}",
+            100.0 * weights.share(file, num_files),
+            100.0 * reads.len() as f64 / results.len() as f64,
+            reads.len(),
+            units::format_latency(stats.avg),
+            units::format_latency(stats.p99)
+        );
+    }
+    if num_files > SHOWN {
+        let rest: usize = order.iter().skip(SHOWN).map(|&file| per_file[file].len()).sum();
+        println!("  ({} colder files: {rest} reads)", num_files - SHOWN);
+    }
+}
+
+/// Jain's index of the operations issued per file and per thread, each count
+/// over its intended share, with the least served file and thread. Files go
+/// against `weights`, unless a plugin or script picks them.
+fn report_fairness(
+    ops: &[&ReadResult],
+    weights: Option<&distribution::FileWeights>,
+    num_files: usize,
+    num_threads: usize,
+) -> Option<results::FairnessSummary> {
+    if ops.is_empty() || (num_files < 2 && num_threads < 2) {
+        return None;
+    }
+    let total = ops.len() as f64;
+    let mut per_file = vec![0u64; num_files];
+    let mut per_thread = vec![0u64; num_threads];
+    for r in ops {
+        if let Some(count) = per_file.get_mut(r.file) {
+            *count += 1;
+        }
+        if let Some(count) = per_thread.get_mut(r.thread) {
+            *count += 1;
+        }
+    }
+    let least = |ratios: &[(usize, f64)]| ratios.iter().copied().min_by(|a, b| a.1.total_cmp(&b.1)).unwrap_or((0, 1.0));
+
+    println!("\n⚖️  Scheduling Fairness (Jain's index of issued over intended operations, 1.000 = as intended):");
+    let mut summary = results::FairnessSummary { files: None, files_by_chance: None, threads: 1.0 };
+    if let Some(weights) = weights.filter(|_| num_files > 1) {
+        // Files that should get nothing are left out
+        let shares: Vec<(usize, f64)> = (0..num_files).map(|file| (file, weights.share(file, num_files))).filter(|&(_, share)| share > 0.0).collect();
+        let ratios: Vec<(usize, f64)> = shares.iter().map(|&(file, share)| (file, per_file[file] as f64 / (total * share))).collect();
+        let index = stats::jain_index(&ratios.iter().map(|&(_, ratio)| ratio).collect::<Vec<_>>());
+        // Random selection alone spreads each ratio with variance (1 - share) / (total * share)
+        let noise = shares.iter().map(|&(_, share)| (1.0 - share) / (total * share)).sum::<f64>() / shares.len() as f64;
+        let chance = 1.0 / (1.0 + noise);
+        let (file, ratio) = least(&ratios);
+        println!("  Files:   {index:.3} (random selection alone: {chance:.3}; least served: file {file} at {ratio:.2}× its share)");
+        summary.files = Some(index);
+        summary.files_by_chance = Some(chance);
+    }
+    let expected = total / num_threads as f64;
+    let ratios: Vec<(usize, f64)> = per_thread.iter().enumerate().map(|(thread, &count)| (thread, count as f64 / expected)).collect();
+    summary.threads = stats::jain_index(&ratios.iter().map(|&(_, ratio)| ratio).collect::<Vec<_>>());
+    let (thread, ratio) = least(&ratios);
+    println!("  Threads: {:.3} (least served: thread {thread} at {ratio:.2}× its share)", summary.threads);
+    Some(summary)
+}
+
+/// How far behind their `--open-loop` schedule operations started, and how
+/// many fell a whole `slot` (a thread's gap between operations) behind: then
+/// the threads, not the device, set the pace.
+fn report_lateness(ops: &[&ReadResult], slot: Duration) -> Option<results::LatenessSummary> {
+    let lateness: Vec<&Duration> = ops.iter().filter_map(|r| r.lateness.as_ref()).collect();
+    if lateness.is_empty() {
+        return None;
+    }
+    let late = lateness.iter().filter(|&&&l| l >= slot).count();
+    let late_percent = 100.0 * late as f64 / lateness.len() as f64;
+    println!("\n⏰ Scheduling Lateness (operation start behind its open-loop schedule, slot {}):", units::format_latency(slot));
+    let stats = calculate_statistics(lateness);
+    let summary = results::LatenessSummary { lateness: (&stats).into(), slot_ns: slot.as_nanos(), late_percent };
+    print_statistics(stats);
+    println!("  Not issued on time (a slot or more late): {late} ({late_percent:.2}%)");
+    Some(summary)
+}
+
+/// Throughput of range scans, overall and by scan length.
+fn report_scan_throughput(results: &[ReadResult]) {
+    let bytes: u64 = results.iter().map(|r| r.bytes).sum();
+    let busy: Duration = results.iter().map(|r| r.latency).sum();
+    if results.is_empty() || busy.is_zero() {
+        return;
+    }
+    println!("\n📏 Range Scans ({} operations):", results.len());
+    println!("  Mean length: {} bytes", bytes / results.len() as u64);
+    println!("  Throughput:  {:.1} MB/s (bytes over summed scan latency)", bytes as f64 / busy.as_secs_f64() / 1_000_000.0);
+
+    // Buckets by power of two of the scan length
+    let mut buckets: std::collections::BTreeMap<u32, (u64, Duration)> = std::collections::BTreeMap::new();
+    for r in results {
+        let bucket = buckets.entry(63 - r.bytes.max(1).leading_zeros()).or_default();
+        bucket.0 += r.bytes;
+        bucket.1 += r.latency;
+    }
+    for (log2, (bytes, latency)) in buckets {
+        if !latency.is_zero() {
+            println!(
+                "  {:>10}+ bytes: {:.1} MB/s",
+                1u64 << log2,
+                bytes as f64 / latency.as_secs_f64() / 1_000_000.0
+            );
+        }
+    }
+}
+
+/// Split reads by size class (powers of two of their length) when there is more
+/// than one, since one percentile set over 4 KiB and 1 MiB reads means little.
+/// Classes of `LARGE_TRANSFER` bytes and more are also given per MiB.
+fn report_size_classes(results: &[ReadResult]) -> Vec<results::SizeClassSummary> {
+    let mut classes: BTreeMap<u32, Vec<&ReadResult>> = BTreeMap::new();
+    for result in results {
+        classes.entry(63 - result.bytes.max(1).leading_zeros()).or_default().push(result);
+    }
+    if classes.len() < 2 {
+        return Vec::new();
+    }
+    println!("\n📐 By Operation Size ({} size classes):", classes.len());
+    println!("  {:>16} {:>9} {:>10} {:>10} {:>12} {:>12}", "Size", "Ops", "Median", "99th %ile", "Median/MiB", "99th/MiB");
+    let mut summaries = Vec::with_capacity(classes.len());
+    for (log2, reads) in classes {
+        let stats = calculate_statistics(reads.iter().map(|r| &r.latency).collect());
+        let min_bytes = 1u64 << log2;
+        let per_mib = (min_bytes >= LARGE_TRANSFER).then(|| {
+            let normalized: Vec<Duration> = reads.iter().map(|r| r.latency.div_f64(r.bytes as f64 / (1024.0 * 1024.0))).collect();
+            calculate_statistics(normalized.iter().collect())
+        });
+        println!(
+            "  {:>16} {:>9} {:>10} {:>10} {:>12} {:>12}",
+            format!("{min_bytes}+ bytes"),
+            stats.count,
+            units::format_latency(stats.median),
+            units::format_latency(stats.p99),
+            per_mib.as_ref().map_or("-".to_string(), |s| units::format_latency(s.median)),
+            per_mib.as_ref().map_or("-".to_string(), |s| units::format_latency(s.p99))
+        );
+        summaries.push(results::SizeClassSummary {
+            min_bytes,
+            max_bytes: min_bytes * 2 - 1,
+            latency: (&stats).into(),
+            per_mib: per_mib.as_ref().map(Into::into),
+        });
+    }
+    summaries
+}
+
+/// Statistics per --job-file class, which a composite workload's blended
+/// distribution hides.
+fn report_tags(results: &[ReadResult], writes: &[ReadResult], job: &jobfile::Job, elapsed_secs: f64) -> Vec<results::TagSummary> {
+    let mut latencies: Vec<Vec<&Duration>> = vec![Vec::new(); job.classes.len()];
+    for op in results.iter().chain(writes) {
+        if let Some(tag) = op.tag {
+            latencies[usize::from(tag)].push(&op.latency);
+        }
+    }
+    println!("\n🏷️  By Tag ({} operation classes):", job.classes.len());
+    println!(
+        "  {:<16} {:>6} {:>9} {:>10} {:>10} {:>10} {:>10} {:>10}  p99 objective",
+        "Tag", "Kind", "Ops", "Ops/s", "Average", "Median", "99th %ile", "Max"
+    );
+    job.classes
+        .iter()
+        .zip(latencies)
+        .map(|(class, latencies)| {
+            let slo = class.slo_p99_us.map(|objective| {
+                let limit = Duration::from_secs_f64(objective / 1e6);
+                let within = latencies.iter().filter(|&&&latency| latency <= limit).count();
+                (objective, limit, 100.0 * within as f64 / latencies.len().max(1) as f64)
+            });
+            let stats = calculate_statistics(latencies);
+            let ops_per_sec = stats.count as f64 / elapsed_secs.max(f64::EPSILON);
+            let slo = slo.map(|(p99_us, limit, within_percent)| results::SloSummary { p99_us, met: stats.count > 0 && stats.p99 <= limit, within_percent });
+            println!(
+                "  {:<16} {:>6} {:>9} {:>10} {:>10} {:>10} {:>10} {:>10}  {}",
+                class.tag,
+                if class.kind == workload::OpKind::Write { "write" } else { "read" },
+                stats.count,
+                match class.rate {
+                    Some(rate) => format!("{ops_per_sec:.0}/{rate:.0}"),
+                    None => format!("{ops_per_sec:.0}"),
+                },
+                units::format_latency(stats.avg),
+                units::format_latency(stats.median),
+                units::format_latency(stats.p99),
+                units::format_latency(stats.max),
+                match &slo {
+                    Some(slo) => format!(
+                        "{} {} ({:.2}% within)",
+                        if slo.met { "✅" } else { "❌" },
+                        units::format_latency(Duration::from_secs_f64(slo.p99_us / 1e6)),
+                        slo.within_percent
+                    ),
+                    None => "-".to_string(),
+                }
+            );
+            results::TagSummary {
+                tag: class.tag.clone(),
+                kind: class.kind,
+                bytes: class.len,
+                latency: (stats.count > 0).then(|| (&stats).into()),
+                ops_per_sec,
+                rate: class.rate,
+                slo,
+            }
+        })
+        .collect()
+}
+
+fn report_access_counts(read_blocks: &BlockCounts, total_blocks: u64) {
+    let touched = read_blocks.len() as u64;
+    let reads: u64 = read_blocks.values().sum();
+    println!("\n🎯 Block Access Counts:");
+    println!(
+        "  Coverage:  {touched} of {total_blocks} blocks ({:.2}% of the dataset)",
+        100.0 * touched as f64 / total_blocks.max(1) as f64
+    );
+    if touched == 0 {
+        return;
+    }
+    println!("  Mean:      {:.2} reads per touched block", reads as f64 / touched as f64);
+    if let Some(((file, block), count)) = read_blocks.iter().max_by_key(|(key, count)| (**count, std::cmp::Reverse(**key))) {
+        println!("  Hottest:   file {file} block {block} ({count} reads)");
+    }
+
+    // Buckets 1, 2, 3-4, 5-8, ... by power of two
+    let mut buckets: Vec<u64> = Vec::new();
+    for &count in read_blocks.values() {
+        let bucket = (64 - (count - 1).leading_zeros()) as usize;
+        if bucket >= buckets.len() {
+            buckets.resize(bucket + 1, 0);
+        }
+        buckets[bucket] += 1;
+    }
+    println!("  {:>12} {:>10} {:>8}", "Reads", "Blocks", "Share");
+    for (bucket, &blocks) in buckets.iter().enumerate() {
+        if blocks == 0 {
+            continue;
+        }
+        let (low, high) = if bucket == 0 { (1, 1) } else { ((1u64 << (bucket - 1)) + 1, 1u64 << bucket) };
+        let label = if low == high { low.to_string() } else { format!("{low}-{high}") };
+        println!("  {:>12} {:>10} {:>7.2}%", label, blocks, 100.0 * blocks as f64 / touched as f64);
+    }
+}
+
+/// Fit read latencies to a lognormal and a two-component lognormal mixture;
+/// the mixture's weights are the quantitative hit/miss split.
+fn report_distribution_fit(results: &[ReadResult]) {
+    let latencies: Vec<f64> = results.iter().map(|r| r.latency.as_nanos() as f64).collect();
+    println!("\n📐 Distribution Fit ({} read latencies):", latencies.len());
+    let lognormal = analysis::fit_lognormal(&latencies);
+    if let Some((dist, fit)) = lognormal {
+        println!(
+            "  Lognormal:   median {}, σ {:.3}                        KS {:.4}  BIC {:.0}",
+            units::format_ns(dist.median()),
+            dist.sigma,
+            fit.ks,
+            fit.bic
+        );
+    }
+    let mixture = analysis::fit_mixture(&latencies);
+    match mixture {
+        Some((mix, fit)) => println!(
+            "  Mixture:     {:.1}% median {} σ {:.3}, {:.1}% median {} σ {:.3}  KS {:.4}  BIC {:.0}",
+            mix.fast_weight * 100.0,
+            units::format_ns(mix.fast.median()),
+            mix.fast.sigma,
+            (1.0 - mix.fast_weight) * 100.0,
+            units::format_ns(mix.slow.median()),
+            mix.slow.sigma,
+            fit.ks,
+            fit.bic
+        ),
+        None => println!("  Mixture:     no fit (too few reads, or one component vanished)"),
+    }
+    if let (Some((_, single)), Some((mix, fit))) = (lognormal, mixture) {
+        if fit.bic < single.bic {
+            println!(
+                "  Better fit:  the mixture (ΔBIC {:.0}): {:.1}% of reads look like hits around {}, {:.1}% like misses around {}",
+                single.bic - fit.bic,
+                mix.fast_weight * 100.0,
+                units::format_ns(mix.fast.median()),
+                (1.0 - mix.fast_weight) * 100.0,
+                units::format_ns(mix.slow.median())
+            );
+        } else {
+            println!("  Better fit:  the single lognormal (ΔBIC {:.0}); no separate hit/miss populations", fit.bic - single.bic);
+        }
+    }
+}
+
+/// Statistics of the reads left after dropping the warmup window and the
+/// fastest and slowest `percent`%; the untrimmed statistics are printed above.
+fn report_trimmed(results: &[ReadResult], warmup: Duration, percent: f64) -> Option<results::LatencySummary> {
+    let latencies = stats::Samples::new(results.iter().filter(|r| r.timestamp >= warmup).map(|r| r.latency));
+    let after_warmup = latencies.len();
+    let cut = (latencies.len() as f64 * percent / 100.0) as usize;
+    // Below 50% the two cuts never overlap
+    let trimmed = &latencies.as_sorted()[cut..latencies.len() - cut];
+
+    println!(
+        "\n✂️  Trimmed Reads (excluding the first {}s and the fastest and slowest {percent}%):",
+        warmup.as_secs_f64()
+    );
+    println!(
+        "  Dropped {} reads in the warmup window and {} outliers; {} of {} reads remain",
+        results.len() - after_warmup,
+        after_warmup - trimmed.len(),
+        trimmed.len(),
+        results.len()
+    );
+    if trimmed.is_empty() {
+        println!("⚠️  Nothing left after trimming");
+        return None;
+    }
+    let stats = calculate_statistics(trimmed.iter().collect());
+    let summary = (&stats).into();
+    print_statistics(stats);
+    Some(summary)
+}
+
+/// Reads completed in each full second, as a stability metric next to the mean
+/// IOPS. The last, partial second is left out.
+fn report_throughput_stability(results: &[ReadResult]) -> Option<results::IopsSummary> {
+    let end = results.iter().map(|r| r.timestamp + r.latency).max()?;
+    let seconds = end.as_secs() as usize;
+    if seconds < 2 {
+        return None;
+    }
+    let mut per_second = vec![0u64; seconds];
+    for result in results {
+        if let Some(count) = per_second.get_mut((result.timestamp + result.latency).as_secs() as usize) {
+            *count += 1;
+        }
+    }
+    let mean = per_second.iter().sum::<u64>() as f64 / seconds as f64;
+    let cv = analysis::coefficient_of_variation(&per_second.iter().map(|&c| c as f64).collect::<Vec<_>>());
+    per_second.sort_unstable();
+    let percentile = |p: f64| per_second[((seconds as f64 * p) as usize).min(seconds - 1)];
+    let summary = results::IopsSummary {
+        seconds,
+        mean,
+        min: per_second[0],
+        p5: percentile(0.05),
+        median: percentile(0.5),
+        p95: percentile(0.95),
+        max: per_second[seconds - 1],
+    };
+
+    println!("\n📶 Throughput Stability ({seconds} full seconds):");
+    println!("  Mean:      {:.0} IOPS", summary.mean);
+    println!("  Min:       {} IOPS", summary.min);
+    println!("  5th %ile:  {} IOPS", summary.p5);
+    println!("  Median:    {} IOPS", summary.median);
+    println!("  95th %ile: {} IOPS", summary.p95);
+    println!("  Max:       {} IOPS", summary.max);
+    if let Some(cv) = cv {
+        println!("  Variation: {:.1}% (standard deviation over mean)", cv * 100.0);
+    }
+    Some(summary)
+}
+
+/// Seek distances of the operations in issue order: per thread, and merged across
+/// threads as the device sees them. Distances run from the end of one operation
+/// to the start of the next, so back-to-back sequential operations are 0 apart.
+fn report_seek_distances(mut ops: Vec<&ReadResult>, block_size: u64) {
+    ops.sort_by_key(|r| (r.timestamp, r.thread));
+    let distances = |ops: &[&ReadResult]| {
+        analysis::seek_distances(&ops.iter().map(|r| (r.file, r.offset, r.bytes)).collect::<Vec<_>>())
+    };
+    let mut threads: BTreeMap<usize, Vec<&ReadResult>> = BTreeMap::new();
+    for &op in &ops {
+        threads.entry(op.thread).or_default().push(op);
+    }
+
+    println!("\n📏 Seek Distances (end of one operation to the start of the next):");
+    println!(
+        "  {:>10} {:>9} {:>11} {:>12} {:>10} {:>12} {:>10} {:>12}",
+        "Stream", "Seeks", "Sequential", "Other file", "Backward", "Median", "", "99th %ile"
+    );
+    let row = |label: String, distances: &[Option<i64>]| {
+        let mut same_file: Vec<u64> = distances.iter().flatten().map(|d| d.unsigned_abs()).collect();
+        same_file.sort_unstable();
+        let share = |n: usize| 100.0 * n as f64 / distances.len().max(1) as f64;
+        let sequential = same_file.iter().filter(|&&d| d == 0).count();
+        let backward = distances.iter().flatten().filter(|&&d| d < 0).count();
+        let percentile = |p: f64| same_file.get(((same_file.len() as f64 * p) as usize).min(same_file.len().saturating_sub(1))).copied();
+        let (median, p99) = (percentile(0.5).unwrap_or(0), percentile(0.99).unwrap_or(0));
+        println!(
+            "  {:>10} {:>9} {:>10.1}% {:>11.1}% {:>9.1}% {:>12} {:>10} {:>12} {:>10}",
+            label,
+            distances.len(),
+            share(sequential),
+            share(distances.len() - same_file.len()),
+            share(backward),
+            format!("{median} B"),
+            format!("{} blk", median / block_size),
+            format!("{p99} B"),
+            format!("{} blk", p99 / block_size)
+        );
+    };
+    for (thread, thread_ops) in &threads {
+        row(format!("thread {thread}"), &distances(thread_ops));
+    }
+    let merged = distances(&ops);
+    row("merged".to_string(), &merged);
+
+    // Merged distances in blocks, bucketed 0, 1, 2-3, 4-7, ... by power of two
+    let mut buckets: Vec<(u64, u64)> = Vec::new();
+    for &distance in merged.iter().flatten() {
+        let blocks = distance.unsigned_abs() / block_size;
+        let bucket = (64 - blocks.leading_zeros()) as usize;
+        if bucket >= buckets.len() {
+            buckets.resize(bucket + 1, (0, 0));
+        }
+        if distance < 0 {
+            buckets[bucket].1 += 1;
+        } else {
+            buckets[bucket].0 += 1;
+        }
+    }
+    println!("  Merged distance in blocks:");
+    println!("  {:>14} {:>10} {:>10} {:>8}", "Blocks", "Forward", "Backward", "Share");
+    for (bucket, &(forward, backward)) in buckets.iter().enumerate() {
+        if forward + backward == 0 {
+            continue;
+        }
+        let label = match bucket {
+            0 => "0".to_string(),
+            1 => "1".to_string(),
+            _ => format!("{}-{}", 1u64 << (bucket - 1), (1u64 << bucket) - 1),
+        };
+        println!(
+            "  {:>14} {:>10} {:>10} {:>7.2}%",
+            label,
+            forward,
+            backward,
+            100.0 * (forward + backward) as f64 / merged.len().max(1) as f64
+        );
+    }
+}
+
+/// Gaps between the issue times of consecutive operations: per thread, pooled
+/// over all threads, and for the merged stream of all threads. The coefficient
+/// of variation tells the arrival process apart: near 0 for a fixed rate,
+/// near 1 for Poisson arrivals.
+fn report_inter_arrival(mut ops: Vec<&ReadResult>) {
+    ops.sort_by_key(|r| (r.timestamp, r.thread));
+    let mut threads: BTreeMap<usize, Vec<Duration>> = BTreeMap::new();
+    for op in &ops {
+        threads.entry(op.thread).or_default().push(op.timestamp);
+    }
+    let per_thread: BTreeMap<usize, Vec<Duration>> =
+        threads.iter().map(|(&thread, issued)| (thread, analysis::inter_arrival_gaps(issued))).collect();
+
+    println!("\n⏲️  Inter-Arrival Times (gaps between operation issue times):");
+    println!("  {:>10} {:>9} {:>12} {:>10} {:>10} {:>8}", "Stream", "Gaps", "Rate", "Mean", "Median", "CV");
+    let row = |label: String, gaps: &[Duration]| {
+        if gaps.is_empty() {
+            println!("  {label:>10} {:>9}", 0);
+            return;
+        }
+        let stats = calculate_statistics(gaps.iter().collect());
+        let mean = gaps.iter().map(Duration::as_secs_f64).sum::<f64>() / gaps.len() as f64;
+        let cv = analysis::coefficient_of_variation(&gaps.iter().map(Duration::as_secs_f64).collect::<Vec<_>>());
+        println!(
+            "  {:>10} {:>9} {:>10.0}/s {:>10} {:>10} {:>8}",
+            label,
+            gaps.len(),
+            if mean > 0.0 { 1.0 / mean } else { 0.0 },
+            units::format_latency(stats.avg),
+            units::format_latency(stats.median),
+            cv.map_or("-".to_string(), |cv| format!("{cv:.3}"))
+        );
+    };
+    for (thread, gaps) in &per_thread {
+        row(format!("thread {thread}"), gaps);
+    }
+    let merged = analysis::inter_arrival_gaps(&ops.iter().map(|r| r.timestamp).collect::<Vec<_>>());
+    row("merged".to_string(), &merged);
+
+    let pooled: Vec<&Duration> = per_thread.values().flatten().collect();
+    if !pooled.is_empty() {
+        println!("  Per-thread gaps, all threads:");
+        print_statistics(calculate_statistics(pooled));
+    }
+}
+
+/// Split reads and writes by `key` (thread or file) for the results file.
+fn group_summaries(results: &[ReadResult], writes: &[ReadResult], key: impl Fn(&ReadResult) -> usize) -> Vec<results::GroupSummary> {
+    let groups = results.iter().chain(writes).map(&key).max().map_or(0, |max| max + 1);
+    let mut reads: Vec<Vec<&ReadResult>> = vec![Vec::new(); groups];
+    let mut write_counts = vec![0; groups];
+    for result in results {
+        reads[key(result)].push(result);
+    }
+    for write in writes {
+        write_counts[key(write)] += 1;
+    }
+    reads
+        .into_iter()
+        .zip(write_counts)
+        .enumerate()
+        .map(|(index, (reads, writes))| results::GroupSummary {
+            index,
+            reads: reads.len(),
+            first_reads: reads.iter().filter(|r| r.is_first_read).count(),
+            writes,
+            latency: (!reads.is_empty()).then(|| (&calculate_statistics(reads.iter().map(|r| &r.latency).collect())).into()),
+        })
+        .collect()
+}
+
+fn report_intervals(results: &[ReadResult], interval: Duration, samples: &[sampler::SystemSample]) -> Vec<results::IntervalSummary> {
+    let interval_ns = interval.as_nanos();
+    let last_result = results.iter().map(|r| r.timestamp.as_nanos() / interval_ns).max();
+    let last_sample = samples.iter().map(|s| s.at.as_nanos().div_ceil(interval_ns).saturating_sub(1)).max();
+    let Some(last) = last_result.max(last_sample) else {
+        return Vec::new();
+    };
+
+    let mut per_interval: Vec<Vec<&Duration>> = vec![Vec::new(); last as usize + 1];
+    for result in results {
+        per_interval[(result.timestamp.as_nanos() / interval_ns) as usize].push(&result.latency);
+    }
+    let mut per_interval_samples: Vec<Option<&sampler::SystemSample>> = vec![None; per_interval.len()];
+    for sample in samples {
+        let index = sample.at.as_nanos().div_ceil(interval_ns).saturating_sub(1) as usize;
+        per_interval_samples[index] = Some(sample);
+    }
+
+    println!("\n⏱️  Interval Statistics ({}s intervals):", interval.as_secs());
+    print!("  {:>9} {:>8} {:>10} {:>10} {:>10} {:>10}", "Interval", "Ops", "IOPS", "Average", "Median", "99th %ile");
+    if !samples.is_empty() {
+        print!(" {:>8} {:>10} {:>10} {:>6}", "iowait", "Dirty", "Writeback", "Load");
+    }
+    println!();
+
+    let mut summaries = Vec::with_capacity(per_interval.len());
+    for (index, latencies) in per_interval.into_iter().enumerate() {
+        let ops = latencies.len();
+        let stats = calculate_statistics(latencies);
+        let sample = per_interval_samples[index];
+        summaries.push(results::IntervalSummary {
+            start_secs: index as u64 * interval.as_secs(),
+            ops,
+            iops: ops as f64 / interval.as_secs_f64(),
+            latency: (ops > 0).then(|| (&stats).into()),
+            iowait_percent: sample.and_then(|s| s.iowait_percent),
+            dirty_bytes: sample.and_then(|s| s.dirty_bytes),
+            writeback_bytes: sample.and_then(|s| s.writeback_bytes),
+            load_average: sample.and_then(|s| s.load_average),
+        });
+        print!(
+            "  {:>8}s {:>8} {:>10.0} {:>10} {:>10} {:>10}",
+            index as u64 * interval.as_secs(),
+            ops,
+            ops as f64 / interval.as_secs_f64(),
+            units::format_latency(stats.avg),
+            units::format_latency(stats.median),
+            units::format_latency(stats.p99)
+        );
+        if let Some(sample) = sample {
+            print!(
+                " {:>7}% {:>8}KB {:>8}KB {:>6}",
+                sample.iowait_percent.map_or("-".to_string(), |v| format!("{v:.1}")),
+                sample.dirty_bytes.map_or("-".to_string(), |v| (v / 1024).to_string()),
+                sample.writeback_bytes.map_or("-".to_string(), |v| (v / 1024).to_string()),
+                sample.load_average.map_or("-".to_string(), |v| format!("{v:.2}"))
+            );
+        }
+        println!();
+    }
+    summaries
+}
+
+/// Median and p99 of every thread in every interval, so one thread degrading
+/// stands out. Cells whose median is over twice the interval's median are marked.
+fn report_thread_matrix(results: &[ReadResult], interval: Duration) -> Vec<results::ThreadIntervals> {
+    let interval_ns = interval.as_nanos();
+    let (Some(last_thread), Some(last_interval)) = (
+        results.iter().map(|r| r.thread).max(),
+        results.iter().map(|r| (r.timestamp.as_nanos() / interval_ns) as usize).max(),
+    ) else {
+        return Vec::new();
+    };
+    let mut cells: Vec<Vec<Vec<&Duration>>> = vec![vec![Vec::new(); last_interval + 1]; last_thread + 1];
+    let mut per_interval: Vec<Vec<&Duration>> = vec![Vec::new(); last_interval + 1];
+    for result in results {
+        let index = (result.timestamp.as_nanos() / interval_ns) as usize;
+        cells[result.thread][index].push(&result.latency);
+        per_interval[index].push(&result.latency);
+    }
+    let interval_medians: Vec<Duration> = per_interval.into_iter().map(|latencies| calculate_statistics(latencies).median).collect();
+
+    println!("\n🧵 Thread × Interval (median/p99, * = median over twice the interval's):");
+    print!("  {:>6}", "Thread");
+    for index in 0..=last_interval {
+        print!(" {:>20}", format!("{}s", index as u64 * interval.as_secs()));
+    }
+    println!();
+    let mut rows = Vec::with_capacity(cells.len());
+    for (thread, row) in cells.into_iter().enumerate() {
+        print!("  {thread:>6}");
+        let mut intervals = Vec::with_capacity(row.len());
+        for (index, latencies) in row.into_iter().enumerate() {
+            if latencies.is_empty() {
+                print!(" {:>20}", "-");
+                intervals.push(None);
+                continue;
+            }
+            let ops = latencies.len();
+            let stats = calculate_statistics(latencies);
+            let mark = if stats.median > interval_medians[index] * 2 { "*" } else { "" };
+            print!(" {:>20}", format!("{}{mark}/{}", units::format_latency(stats.median), units::format_latency(stats.p99)));
+            intervals.push(Some(results::MatrixCell { ops, median_ns: stats.median.as_nanos(), p99_ns: stats.p99.as_nanos() }));
+        }
+        println!();
+        rows.push(results::ThreadIntervals { thread, intervals });
+    }
+    rows
+}
+
+/// Relate read latency to the dirty and writeback page counts sampled at the end
+/// of each `window`.
+fn report_writeback(results: &[ReadResult], samples: &[sampler::SystemSample], window: Duration) {
+    let window_ns = window.as_nanos();
+    let mut per_window: Vec<Vec<&Duration>> = vec![Vec::new(); samples.len()];
+    for result in results {
+        let index = (result.timestamp.as_nanos() / window_ns) as usize;
+        if let Some(latencies) = per_window.get_mut(index) {
+            latencies.push(&result.latency);
+        }
+    }
+    // (p99 of the window's reads, dirty bytes, writeback bytes)
+    let windows: Vec<(Duration, u64, u64)> = per_window
+        .into_iter()
+        .zip(samples)
+        .filter(|(latencies, _)| !latencies.is_empty())
+        .filter_map(|(latencies, sample)| {
+            Some((calculate_statistics(latencies).p99, sample.dirty_bytes?, sample.writeback_bytes?))
+        })
+        .collect();
+    if windows.is_empty() {
+        return;
+    }
+
+    println!("\n🧽 Writeback vs Read Latency ({}ms windows):", window.as_millis());
+    let peak_dirty = windows.iter().map(|w| w.1).max().unwrap_or(0);
+    let peak_writeback = windows.iter().map(|w| w.2).max().unwrap_or(0);
+    let active = windows.iter().filter(|w| w.2 > 0).count();
+    println!("  Peak dirty: {}KB, peak writeback: {}KB", peak_dirty / 1024, peak_writeback / 1024);
+    println!("  Windows with writeback in progress: {active} of {}", windows.len());
+
+    let mut during: Vec<&ReadResult> = Vec::new();
+    let mut idle: Vec<&ReadResult> = Vec::new();
+    for result in results {
+        let index = (result.timestamp.as_nanos() / window_ns) as usize;
+        match samples.get(index).and_then(|s| s.writeback_bytes) {
+            Some(bytes) if bytes > 0 => during.push(result),
+            Some(_) => idle.push(result),
+            None => {}
+        }
+    }
+    for (label, group) in [("during writeback", &during), ("without writeback", &idle)] {
+        if !group.is_empty() {
+            let stats = calculate_statistics(group.iter().map(|r| &r.latency).collect());
+            println!(
+                "  Reads {label}: {} ops, median {}, 99th %ile {}",
+                stats.count,
+                units::format_latency(stats.median),
+                units::format_latency(stats.p99)
+            );
+        }
+    }
+
+    let p99s: Vec<f64> = windows.iter().map(|w| w.0.as_nanos() as f64).collect();
+    let dirty: Vec<f64> = windows.iter().map(|w| w.1 as f64).collect();
+    let writeback: Vec<f64> = windows.iter().map(|w| w.2 as f64).collect();
+    let format_r = |r: Option<f64>| r.map_or("n/a (constant)".to_string(), |r| format!("{r:.2}"));
+    println!(
+        "  Correlation with per-window 99th %ile: writeback {}, dirty {}",
+        format_r(analysis::correlation(&writeback, &p99s)),
+        format_r(analysis::correlation(&dirty, &p99s))
+    );
+    if analysis::correlation(&writeback, &p99s).is_some_and(|r| r >= 0.5) {
+        println!("⚠️  Read latency rises with writeback: reads are stalling behind flushed pages");
+    }
+}
+
+fn report_periodicity(results: &[ReadResult]) {
+    let mut ordered: Vec<_> = results.iter().map(|r| (r.timestamp, r.latency)).collect();
+    ordered.sort();
+
+    let series: Vec<f64> = ordered.iter().map(|(_, l)| l.as_nanos() as f64).collect();
+    println!("\n🔁 Latency Autocorrelation (consecutive operations):");
+    let acf = analysis::autocorrelation(&series, &[1, 2, 5, 10, 100, 1000]);
+    let line: Vec<String> = acf.iter().map(|(lag, r)| format!("lag {lag}: {r:.3}")).collect();
+    println!("  {}", line.join(", "));
+
+    let threshold = calculate_statistics(results.iter().map(|r| &r.latency).collect()).p99;
+    match analysis::detect_periodic_spikes(&ordered, threshold) {
+        Some(p) => println!(
+            "  Periodic spikes: every {:.3}s ({} ops above {}, correlation {:.2})",
+            p.period.as_secs_f64(),
+            p.spike_count,
+            units::format_latency(p.spike_threshold),
+            p.strength
+        ),
+        None => println!("  Periodic spikes: none detected"),
+    }
+}
+
+fn report_zone_statistics(results: &[ReadResult]) {
+    let mut per_zone: HashMap<u64, Vec<&Duration>> = HashMap::new();
+    for result in results {
+        if let Some(zone) = result.zone {
+            per_zone.entry(zone).or_default().push(&result.latency);
+        }
+    }
+    if per_zone.is_empty() {
+        return;
+    }
+
+    let mut zones: Vec<_> = per_zone.into_iter().collect();
+    zones.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+
+    println!("\n🧱 Reads by Zone ({} zones touched, busiest first):", zones.len());
+    println!("  {:>10} {:>8} {:>12} {:>12}", "Zone", "Count", "Average", "99th %ile");
+    for (zone, latencies) in zones.into_iter().take(10) {
+        let stats = calculate_statistics(latencies);
+        println!(
+            "  {:>10} {:>8} {:>12} {:>12}",
+            zone,
+            stats.count,
+            units::format_latency(stats.avg),
+            units::format_latency(stats.p99)
+        );
+    }
+}
+
+/// Report All/First/Repeated statistics from aggregated histograms.
+fn report_histogram_results(progress: &checkpoint::Progress) {
+    let sections = [
+        ("📈 All Reads", &progress.all),
+        ("🆕 First Reads", &progress.first),
+        ("🔄 Repeated Reads", &progress.repeated),
+    ];
+    for (title, histogram) in sections {
+        if histogram.is_empty() {
+            continue;
+        }
+        println!("\n{title} ({} operations):", histogram.count());
+        print_statistics(Statistics::from_histogram(histogram));
+    }
+}
+
+/// Pass `result` on, removing the test files first when it is an error.
+fn cleanup_on_error<T, E: Into<Box<dyn std::error::Error>>>(
+    args: &Args,
+    file_paths: &[String],
+    result: Result<T, E>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    result.or_else(|e| {
+        cleanup_test_files(args, file_paths)?;
+        Err(e.into())
+    })
+}
+
+fn cleanup_test_files(args: &Args, file_paths: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if args.device.is_some() {
+        return Ok(());
+    }
+    if args.reuse_existing {
+        if args.writes() {
+            // Writes changed the contents; record them so the next run still verifies
+            let manifest_path = dataset::manifest_path(args);
+            let previous = dataset::Manifest::load(&manifest_path).ok();
+            let checksum_chunk = previous
+                .as_ref()
+                .and_then(|m| m.checksum_chunk)
+                .or(args.checksums.then_some(dataset::CHECKSUM_CHUNK));
+            let mut manifest = dataset::Manifest::build(file_paths, &value_name(&args.create_mode), checksum_chunk)?;
+            manifest.creation_order = previous.and_then(|m| m.creation_order);
+            manifest.save(&manifest_path)?;
+        }
+        println!("\n📦 Kept {} test files for --reuse-existing", file_paths.len());
+        return Ok(());
+    }
+    for file_path in file_paths {
+        if Path::new(file_path).exists() {
+            std::fs::remove_file(file_path)?;
+        }
+    }
+    let manifest_path = dataset::manifest_path(args);
+    if manifest_path.exists() {
+        std::fs::remove_file(manifest_path)?;
+    }
+    dataset::remove_tree(args)?;
+    println!("\n🧹 Cleaned up test files");
     Ok(())
 }
@@ -0,0 +1,273 @@
+//! Memory mappings of the test files, optionally split into lazily created segments.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use memmap2::{MmapOptions, MmapRaw};
+
+/// `mmap(2)` flags Linux only accepts for DAX files: stores are durable once
+/// they leave the CPU caches, without an msync.
+#[cfg(target_os = "linux")]
+const MAP_SHARED_VALIDATE: libc::c_int = 0x03;
+#[cfg(target_os = "linux")]
+const MAP_SYNC: libc::c_int = 0x80000;
+
+/// A mapped segment: a page cache mapping, or a direct mapping of a DAX file.
+pub enum Region {
+    Paged(MmapRaw),
+    #[cfg(target_os = "linux")]
+    Dax { ptr: *mut u8, len: usize },
+}
+
+// The DAX mapping is plain shared memory, like `MmapRaw`
+unsafe impl Send for Region {}
+unsafe impl Sync for Region {}
+
+impl Region {
+    /// Map `len` bytes at `offset` of a DAX file; fails with EOPNOTSUPP if the file is not DAX.
+    #[cfg(target_os = "linux")]
+    fn map_dax(file: &File, offset: u64, len: usize, writable: bool) -> std::io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let (prot, flags) = if writable {
+            (libc::PROT_READ | libc::PROT_WRITE, MAP_SHARED_VALIDATE | MAP_SYNC)
+        } else {
+            (libc::PROT_READ, libc::MAP_SHARED)
+        };
+        let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, prot, flags, file.as_raw_fd(), offset as libc::off_t) };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Region::Dax { ptr: ptr.cast(), len })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn map_dax(_file: &File, _offset: u64, _len: usize, _writable: bool) -> std::io::Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "DAX mappings are only available on Linux"))
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.as_mut_ptr()
+    }
+
+    pub fn as_mut_ptr(&self) -> *mut u8 {
+        match self {
+            Region::Paged(mmap) => mmap.as_mut_ptr(),
+            #[cfg(target_os = "linux")]
+            Region::Dax { ptr, .. } => *ptr,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Region::Paged(mmap) => mmap.len(),
+            #[cfg(target_os = "linux")]
+            Region::Dax { len, .. } => *len,
+        }
+    }
+
+    /// msync a range; on a `MAP_SYNC` mapping this only flushes the CPU caches.
+    pub fn flush_range(&self, offset: usize, len: usize) -> std::io::Result<()> {
+        match self {
+            Region::Paged(mmap) => mmap.flush_range(offset, len),
+            #[cfg(target_os = "linux")]
+            Region::Dax { ptr, .. } => {
+                // msync needs a page-aligned start
+                let start = offset - offset % crate::page_size();
+                let ret = unsafe { libc::msync(ptr.add(start).cast(), len + offset - start, libc::MS_SYNC) };
+                if ret != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Region::Dax { ptr, len } = *self {
+            unsafe { libc::munmap(ptr.cast(), len) };
+        }
+    }
+}
+
+struct Segment {
+    mmap: Arc<Region>,
+    last_use: AtomicU64,
+}
+
+#[derive(Default)]
+struct State {
+    segments: HashMap<(usize, u64), Segment>,
+    mapped_bytes: u64,
+}
+
+/// Counters describing how the mappings were managed during a run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MappingStats {
+    pub created: u64,
+    pub evicted: u64,
+    pub peak_mapped_bytes: u64,
+}
+
+/// Mappings for a set of files.
+///
+/// With a segment size of 0 every file is mapped whole, up front. Otherwise
+/// files are mapped in `segment_size` pieces on first access, and the least
+/// recently used segments are unmapped when `max_mapped` bytes would be
+/// exceeded (0 = no cap). Writable mappings are shared (`MAP_SHARED`), so
+/// stores reach the files through the page cache; with `dax` the files are
+/// mapped directly and loads and stores bypass it.
+pub struct SegmentedMappings {
+    files: Vec<File>,
+    file_sizes: Vec<u64>,
+    segment_size: u64,
+    max_mapped: u64,
+    random_advice: bool,
+    writable: bool,
+    dax: bool,
+    state: RwLock<State>,
+    use_counter: AtomicU64,
+    created: AtomicU64,
+    evicted: AtomicU64,
+    peak_mapped: AtomicU64,
+}
+
+impl SegmentedMappings {
+    /// With `random_advice` every mapping is madvised MADV_RANDOM, disabling readahead.
+    pub fn new(
+        file_paths: &[String],
+        segment_size: u64,
+        max_mapped: u64,
+        random_advice: bool,
+        writable: bool,
+        dax: bool,
+    ) -> std::io::Result<Self> {
+        let files = file_paths
+            .iter()
+            .map(|path| File::options().read(true).write(writable).open(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let file_sizes = files
+            .iter()
+            .map(crate::fsutil::file_len)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mappings = SegmentedMappings {
+            files,
+            file_sizes,
+            segment_size,
+            max_mapped,
+            random_advice,
+            writable,
+            dax,
+            state: RwLock::new(State::default()),
+            use_counter: AtomicU64::new(0),
+            created: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
+            peak_mapped: AtomicU64::new(0),
+        };
+
+        if segment_size == 0 {
+            let mut state = mappings.state.write().unwrap();
+            for file_index in 0..mappings.files.len() {
+                mappings.map_segment(&mut state, file_index, 0)?;
+            }
+        }
+        Ok(mappings)
+    }
+
+    /// The mapping containing `offset` of file `file_index`, and the offset within it.
+    pub fn locate(&self, file_index: usize, offset: u64) -> std::io::Result<(Arc<Region>, usize)> {
+        let segment_start = if self.segment_size == 0 {
+            0
+        } else {
+            offset - offset % self.segment_size
+        };
+        let key = (file_index, segment_start);
+        let tick = self.use_counter.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(segment) = self.state.read().unwrap().segments.get(&key) {
+            segment.last_use.store(tick, Ordering::Relaxed);
+            return Ok((Arc::clone(&segment.mmap), (offset - segment_start) as usize));
+        }
+
+        let mut state = self.state.write().unwrap();
+        // Another thread may have mapped it while we waited for the lock
+        if !state.segments.contains_key(&key) {
+            self.map_segment(&mut state, file_index, segment_start)?;
+        }
+        let segment = &state.segments[&key];
+        segment.last_use.store(tick, Ordering::Relaxed);
+        Ok((Arc::clone(&segment.mmap), (offset - segment_start) as usize))
+    }
+
+    pub fn stats(&self) -> MappingStats {
+        MappingStats {
+            created: self.created.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+            peak_mapped_bytes: self.peak_mapped.load(Ordering::Relaxed),
+        }
+    }
+
+    fn map_segment(&self, state: &mut State, file_index: usize, segment_start: u64) -> std::io::Result<()> {
+        let file_size = self.file_sizes[file_index];
+        let len = if self.segment_size == 0 {
+            file_size
+        } else {
+            self.segment_size.min(file_size - segment_start)
+        };
+
+        if self.max_mapped > 0 {
+            while state.mapped_bytes + len > self.max_mapped && !state.segments.is_empty() {
+                let oldest = state
+                    .segments
+                    .iter()
+                    .min_by_key(|(_, s)| s.last_use.load(Ordering::Relaxed))
+                    .map(|(k, _)| *k)
+                    .expect("segments is not empty");
+                let removed = state.segments.remove(&oldest).expect("key was just found");
+                state.mapped_bytes -= removed.mmap.len() as u64;
+                self.evicted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let map_len = usize::try_from(len).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("cannot map {len} bytes in this address space; use --mmap-segment-size"),
+            )
+        })?;
+        let mmap = if self.dax {
+            // No page cache, so no readahead to turn off
+            Region::map_dax(&self.files[file_index], segment_start, map_len, self.writable)?
+        } else {
+            let mut options = MmapOptions::new();
+            options.offset(segment_start).len(map_len);
+            let mmap = if self.writable {
+                options.map_raw(&self.files[file_index])?
+            } else {
+                options.map_raw_read_only(&self.files[file_index])?
+            };
+            #[cfg(unix)]
+            if self.random_advice {
+                mmap.advise(memmap2::Advice::Random)?;
+            }
+            Region::Paged(mmap)
+        };
+        state.segments.insert(
+            (file_index, segment_start),
+            Segment {
+                mmap: Arc::new(mmap),
+                last_use: AtomicU64::new(0),
+            },
+        );
+        state.mapped_bytes += len;
+        self.created.fetch_add(1, Ordering::Relaxed);
+        self.peak_mapped.fetch_max(state.mapped_bytes, Ordering::Relaxed);
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! When stores to writable mappings are flushed to the files with `msync`.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::mapping::SegmentedMappings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsyncPolicy {
+    /// Leave writeback to the kernel
+    None,
+    /// `msync` each written block before the write returns
+    Block,
+    /// `msync` the blocks written so far after every N writes
+    Every(u64),
+    /// A background thread `msync`s the blocks written since its last pass
+    Background(Duration),
+}
+
+impl MsyncPolicy {
+    /// Parse `none`, `block`, `every:<n>` or `background:<ms>`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec.split_once(':') {
+            None if spec == "none" => Ok(MsyncPolicy::None),
+            None if spec == "block" => Ok(MsyncPolicy::Block),
+            Some(("every", n)) => match n.parse() {
+                Ok(n) if n > 0 => Ok(MsyncPolicy::Every(n)),
+                _ => Err(format!("invalid write count '{n}' in '{spec}'")),
+            },
+            Some(("background", ms)) => match ms.trim_end_matches("ms").parse() {
+                Ok(ms) if ms > 0 => Ok(MsyncPolicy::Background(Duration::from_millis(ms))),
+                _ => Err(format!("invalid interval '{ms}' in '{spec}', expected milliseconds")),
+            },
+            _ => Err(format!("unknown msync policy '{spec}', expected none, block, every:<n> or background:<ms>")),
+        }
+    }
+}
+
+impl std::fmt::Display for MsyncPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MsyncPolicy::None => write!(f, "none"),
+            MsyncPolicy::Block => write!(f, "block"),
+            MsyncPolicy::Every(n) => write!(f, "every:{n}"),
+            MsyncPolicy::Background(interval) => write!(f, "background:{}ms", interval.as_millis()),
+        }
+    }
+}
+
+/// Flushes written ranges according to a policy and times every flush.
+pub struct Syncer {
+    policy: MsyncPolicy,
+    mappings: Arc<SegmentedMappings>,
+    /// Ranges written since the last flush, as `(file, offset, len)`
+    dirty: Mutex<Vec<(usize, u64, usize)>>,
+    writes: AtomicU64,
+    ranges: AtomicU64,
+    errors: AtomicU64,
+    latencies: Mutex<Vec<Duration>>,
+    stop: AtomicBool,
+    background: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Syncer {
+    pub fn start(policy: MsyncPolicy, mappings: Arc<SegmentedMappings>) -> Arc<Self> {
+        let syncer = Arc::new(Syncer {
+            policy,
+            mappings,
+            dirty: Mutex::new(Vec::new()),
+            writes: AtomicU64::new(0),
+            ranges: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            latencies: Mutex::new(Vec::new()),
+            stop: AtomicBool::new(false),
+            background: Mutex::new(None),
+        });
+        if let MsyncPolicy::Background(interval) = policy {
+            let thread_syncer = Arc::clone(&syncer);
+            let handle = std::thread::spawn(move || {
+                let mut next = Instant::now() + interval;
+                while !thread_syncer.stop.load(Ordering::Relaxed) {
+                    // Sleep in short slices so a stop request is noticed quickly
+                    std::thread::sleep(next.saturating_duration_since(Instant::now()).min(Duration::from_millis(50)));
+                    if Instant::now() >= next {
+                        thread_syncer.flush_dirty();
+                        next += interval;
+                    }
+                }
+            });
+            *syncer.background.lock().unwrap() = Some(handle);
+        }
+        syncer
+    }
+
+    /// Account for a completed store of `len` bytes at `offset` of `file`.
+    pub fn written(&self, file: usize, offset: u64, len: usize) -> std::io::Result<()> {
+        let writes = self.writes.fetch_add(1, Ordering::Relaxed) + 1;
+        match self.policy {
+            MsyncPolicy::None => Ok(()),
+            MsyncPolicy::Block => self.flush(&[(file, offset, len)]),
+            MsyncPolicy::Every(n) => {
+                self.dirty.lock().unwrap().push((file, offset, len));
+                if writes.is_multiple_of(n) {
+                    self.flush_dirty();
+                }
+                Ok(())
+            }
+            MsyncPolicy::Background(_) => {
+                self.dirty.lock().unwrap().push((file, offset, len));
+                Ok(())
+            }
+        }
+    }
+
+    fn flush_dirty(&self) {
+        let ranges = std::mem::take(&mut *self.dirty.lock().unwrap());
+        if !ranges.is_empty() && self.flush(&ranges).is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// `msync` the ranges synchronously; the whole batch is timed as one sync.
+    fn flush(&self, ranges: &[(usize, u64, usize)]) -> std::io::Result<()> {
+        let start = Instant::now();
+        for &(file, offset, len) in ranges {
+            let (mmap, local) = self.mappings.locate(file, offset)?;
+            mmap.flush_range(local, len)?;
+        }
+        self.latencies.lock().unwrap().push(start.elapsed());
+        self.ranges.fetch_add(ranges.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Stop the background thread, flush what is still dirty and print the sync statistics.
+    pub fn finish(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.background.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+        if self.policy == MsyncPolicy::None {
+            return;
+        }
+        self.flush_dirty();
+
+        let latencies = std::mem::take(&mut *self.latencies.lock().unwrap());
+        println!(
+            "\n🧷 msync ({}): {} syncs covering {} written blocks",
+            self.policy,
+            latencies.len(),
+            self.ranges.load(Ordering::Relaxed)
+        );
+        let errors = self.errors.load(Ordering::Relaxed);
+        if errors > 0 {
+            println!("⚠️  {errors} msync batches failed");
+        }
+        if !latencies.is_empty() {
+            crate::print_statistics(crate::calculate_statistics(latencies.iter().collect()));
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Sending the full report to a file (or nowhere) while the terminal gets a short summary.
+
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::path::Path;
+
+/// Standard output pointed at a report file or `/dev/null` until dropped.
+pub struct ReportRedirect {
+    /// The original standard output
+    terminal: File,
+}
+
+impl ReportRedirect {
+    /// Send everything printed from now on to `path`, or discard it if `path` is `None`.
+    pub fn start(path: Option<&Path>) -> std::io::Result<Self> {
+        let target = match path {
+            Some(path) => File::create(path)?,
+            None => std::fs::OpenOptions::new().write(true).open("/dev/null")?,
+        };
+        std::io::stdout().flush()?;
+        let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if saved < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let terminal = unsafe { File::from_raw_fd(saved) };
+        if unsafe { libc::dup2(target.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(ReportRedirect { terminal })
+    }
+
+    /// Print a line on the original standard output.
+    pub fn summary(&mut self, line: &str) {
+        let _ = writeln!(self.terminal, "{line}");
+    }
+}
+
+impl Drop for ReportRedirect {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().flush();
+        unsafe { libc::dup2(self.terminal.as_raw_fd(), libc::STDOUT_FILENO) };
+    }
+}
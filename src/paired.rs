@@ -0,0 +1,75 @@
+//! Matched cold/warm samples: evict one block from the page cache, read it
+//! (cold), then read it again (warm), so each pair differs only in caching.
+
+use std::os::unix::fs::FileExt;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{Args, calculate_statistics, fsutil, print_statistics, timing};
+
+/// One sampled block read right after eviction and again right after that.
+pub struct Pair {
+    pub cold: Duration,
+    pub warm: Duration,
+}
+
+/// Take `samples` pairs from random blocks of `file_paths` with `pread`.
+pub fn run(args: &Args, file_paths: &[String], clock: &timing::Clock, samples: usize) -> std::io::Result<Vec<Pair>> {
+    let files = file_paths.iter().map(std::fs::File::open).collect::<std::io::Result<Vec<_>>>()?;
+    // Dirty pages are not dropped by DONTNEED
+    for file in &files {
+        file.sync_data()?;
+    }
+    let block_size = args.block_size as u64;
+    let mut rng = StdRng::seed_from_u64(args.seed ^ 0xC01D_3A53);
+    let mut buffer = vec![0u8; args.block_size];
+    let mut pairs = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let file = rng.random_range(0..files.len());
+        let blocks = args.file_size_of(file) / block_size;
+        if blocks == 0 {
+            continue;
+        }
+        let offset = rng.random_range(0..blocks) * block_size;
+        fsutil::advise_dontneed_range(&files[file], offset, block_size)?;
+        let start = clock.now();
+        files[file].read_exact_at(&mut buffer, offset)?;
+        let cold = clock.between(start, clock.now());
+        let start = clock.now();
+        files[file].read_exact_at(&mut buffer, offset)?;
+        let warm = clock.between(start, clock.now());
+        pairs.push(Pair { cold, warm });
+    }
+    Ok(pairs)
+}
+
+pub fn report(pairs: &[Pair]) {
+    println!("\n🧊 Paired Cold/Warm Samples ({} blocks, evicted with fadvise DONTNEED, then read twice):", pairs.len());
+    if pairs.is_empty() {
+        return;
+    }
+    println!("  Cold reads:");
+    print_statistics(calculate_statistics(pairs.iter().map(|p| &p.cold).collect()));
+    println!("  Warm reads:");
+    print_statistics(calculate_statistics(pairs.iter().map(|p| &p.warm).collect()));
+
+    let differences: Vec<Duration> = pairs.iter().map(|p| p.cold.saturating_sub(p.warm)).collect();
+    println!("  Cold minus warm, per pair:");
+    print_statistics(calculate_statistics(differences.iter().collect()));
+
+    let mut ratios: Vec<f64> = pairs
+        .iter()
+        .map(|p| p.cold.as_secs_f64() / p.warm.as_secs_f64().max(1e-9))
+        .collect();
+    ratios.sort_by(f64::total_cmp);
+    println!("  Median cold/warm ratio: {:.1}x", ratios[ratios.len() / 2]);
+    let not_slower = pairs.iter().filter(|p| p.cold <= p.warm).count();
+    if not_slower * 10 > pairs.len() {
+        println!(
+            "⚠️  {not_slower} of {} cold reads were not slower than their warm read; the eviction may not take effect here (e.g. tmpfs)",
+            pairs.len()
+        );
+    }
+}
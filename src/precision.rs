@@ -0,0 +1,132 @@
+//! `--target-precision`: keep reading until the confidence interval of a
+//! latency percentile is narrow enough, then stop all readers.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Two-sided 95% normal quantile.
+const Z_95: f64 = 1.959_964;
+/// Fewest reads before the interval is trusted at all.
+const MIN_SAMPLES: usize = 100;
+/// Latencies a reader thread collects before handing them over.
+pub const BATCH: usize = 256;
+
+/// Parse a relative precision like `1%` or `0.5%` into a fraction.
+pub fn parse_precision(spec: &str) -> Result<f64, String> {
+    let percent = spec
+        .strip_suffix('%')
+        .ok_or_else(|| format!("'{spec}': expected a percentage such as 1%"))?
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("'{spec}': not a number"))?;
+    if !(percent > 0.0 && percent < 100.0) {
+        return Err(format!("'{spec}': precision must be between 0% and 100%"));
+    }
+    Ok(percent / 100.0)
+}
+
+/// 95% confidence interval of a percentile from order statistics.
+#[derive(Debug, Clone, Copy)]
+pub struct Interval {
+    pub samples: usize,
+    pub estimate: Duration,
+    pub low: Duration,
+    pub high: Duration,
+}
+
+impl Interval {
+    /// Half the interval's width relative to the estimate.
+    pub fn relative_half_width(&self) -> f64 {
+        (self.high - self.low).as_secs_f64() / 2.0 / self.estimate.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+/// Distribution-free confidence interval of quantile `q` of `sorted`: the
+/// order statistics at ranks `nq ± z·sqrt(nq(1-q))`.
+pub fn quantile_interval(sorted: &[Duration], q: f64) -> Option<Interval> {
+    let n = sorted.len();
+    if n < MIN_SAMPLES {
+        return None;
+    }
+    let center = n as f64 * q;
+    let spread = Z_95 * (center * (1.0 - q)).sqrt();
+    let rank = |r: f64| (r.max(0.0) as usize).min(n - 1);
+    Some(Interval {
+        samples: n,
+        estimate: sorted[rank(center)],
+        low: sorted[rank((center - spread).floor())],
+        high: sorted[rank((center + spread).ceil())],
+    })
+}
+
+/// Shared by all reader threads of a run: collects latencies and tells the
+/// readers to stop once the interval is within the bound.
+pub struct PrecisionTarget {
+    /// Largest acceptable half-width of the interval, relative to the estimate
+    pub bound: f64,
+    /// Percentile whose interval is tracked, in (0, 1)
+    pub quantile: f64,
+    latencies: Mutex<Vec<Duration>>,
+    /// Sample count at which the interval is computed next; checks grow geometrically
+    next_check: Mutex<usize>,
+    reached: AtomicBool,
+}
+
+impl PrecisionTarget {
+    pub fn new(bound: f64, quantile: f64) -> Self {
+        PrecisionTarget {
+            bound,
+            quantile,
+            latencies: Mutex::new(Vec::new()),
+            next_check: Mutex::new(MIN_SAMPLES),
+            reached: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the readers should stop.
+    pub fn reached(&self) -> bool {
+        self.reached.load(Ordering::Relaxed)
+    }
+
+    /// Take over a reader's batch of latencies and check the interval when due.
+    pub fn add(&self, batch: &mut Vec<Duration>) {
+        let mut latencies = self.latencies.lock().unwrap();
+        latencies.append(batch);
+        let mut next_check = self.next_check.lock().unwrap();
+        if latencies.len() < *next_check {
+            return;
+        }
+        *next_check = latencies.len() + latencies.len() / 8;
+        let mut sorted = latencies.clone();
+        drop(latencies);
+        sorted.sort_unstable();
+        if quantile_interval(&sorted, self.quantile).is_some_and(|i| i.relative_half_width() <= self.bound) {
+            self.reached.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn report(&self) {
+        let mut sorted = self.latencies.lock().unwrap().clone();
+        sorted.sort_unstable();
+        let label = if self.quantile == 0.5 { "median".to_string() } else { format!("p{}", self.quantile * 100.0) };
+        println!("\n🎯 Target Precision (±{}% on the {label}, 95% confidence):", self.bound * 100.0);
+        let Some(interval) = quantile_interval(&sorted, self.quantile) else {
+            println!("⚠️  Only {} reads; at least {MIN_SAMPLES} are needed for an interval", sorted.len());
+            return;
+        };
+        println!(
+            "  {label}: {}, 95% CI {} to {} (±{:.2}%) after {} reads",
+            crate::units::format_latency(interval.estimate),
+            crate::units::format_latency(interval.low),
+            crate::units::format_latency(interval.high),
+            interval.relative_half_width() * 100.0,
+            interval.samples
+        );
+        if self.reached() {
+            println!("✅ Reached the target precision");
+        } else {
+            println!("⚠️  Target precision not reached; raise --num-operations, the upper limit of the run");
+        }
+    }
+}
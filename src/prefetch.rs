@@ -0,0 +1,103 @@
+//! Application-level readahead: a thread that hints blocks shortly before
+//! the readers get to them.
+
+use std::fs::File;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{self, Receiver, Sender, TrySendError};
+
+use crate::fsutil;
+
+/// Hints waiting beyond this are dropped rather than blocking the readers.
+const HINT_QUEUE_LEN: usize = 4096;
+
+struct Hint {
+    file_index: usize,
+    offset: u64,
+    len: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefetchStats {
+    pub issued: u64,
+    pub failed: u64,
+    pub dropped: u64,
+    pub busy_time: Duration,
+}
+
+/// The readers' side of the prefetcher.
+#[derive(Clone)]
+pub struct PrefetchHandle {
+    sender: Sender<Hint>,
+    dropped: Arc<AtomicU64>,
+    distance: usize,
+}
+
+impl PrefetchHandle {
+    /// How many operations ahead of its reads a reader should hint.
+    pub fn distance(&self) -> usize {
+        self.distance
+    }
+
+    /// Queue a WILLNEED hint without ever blocking the caller.
+    pub fn hint(&self, file_index: usize, offset: u64, len: u64) {
+        let hint = Hint {
+            file_index,
+            offset,
+            len,
+        };
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(hint) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub struct Prefetcher {
+    handle: PrefetchHandle,
+    thread: JoinHandle<PrefetchStats>,
+}
+
+impl Prefetcher {
+    pub fn start(file_paths: &[String], distance: usize) -> std::io::Result<Self> {
+        let files = file_paths.iter().map(File::open).collect::<Result<Vec<_>, _>>()?;
+        let (sender, receiver) = channel::bounded(HINT_QUEUE_LEN);
+        let thread = std::thread::spawn(move || serve_hints(&files, receiver));
+        Ok(Prefetcher {
+            handle: PrefetchHandle {
+                sender,
+                dropped: Arc::new(AtomicU64::new(0)),
+                distance,
+            },
+            thread,
+        })
+    }
+
+    pub fn handle(&self) -> PrefetchHandle {
+        self.handle.clone()
+    }
+
+    /// Wait for outstanding hints once all reader handles are gone.
+    pub fn finish(self) -> PrefetchStats {
+        let dropped = self.handle.dropped.load(Ordering::Relaxed);
+        drop(self.handle);
+        let mut stats = self.thread.join().unwrap_or_default();
+        stats.dropped = dropped;
+        stats
+    }
+}
+
+fn serve_hints(files: &[File], receiver: Receiver<Hint>) -> PrefetchStats {
+    let mut stats = PrefetchStats::default();
+    for hint in receiver {
+        let start = Instant::now();
+        match fsutil::advise_willneed(&files[hint.file_index], hint.offset, hint.len) {
+            Ok(()) => stats.issued += 1,
+            Err(_) => stats.failed += 1,
+        }
+        stats.busy_time += start.elapsed();
+    }
+    stats
+}
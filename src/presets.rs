@@ -0,0 +1,77 @@
+//! Bundled workload presets: named sets of options modelled on common storage
+//! workloads, for `presets list|show|run`.
+
+use std::ffi::OsString;
+
+/// A named configuration, expanded to command-line options.
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Options as on the command line, separated by spaces
+    pub args: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "cold-random-read",
+        description: "4 KiB random reads that miss the page cache: O_DIRECT libaio at queue depth 32, readahead off",
+        args: "--rw randread -b 4096 -f 16 -s 268435456 -t 4 --engine libaio --queue-depth 32 --readahead 0 -n 200000",
+    },
+    Preset {
+        name: "oltp-mixed",
+        description: "8 KiB random reads and writes (70% reads) skewed 80/20 onto a hot set, as database buffer pool misses",
+        args: "--rw randrw70 -b 8192 -f 8 -s 268435456 -t 16 --distribution pareto:1.16 -n 200000",
+    },
+    Preset {
+        name: "wal-commit",
+        description: "One thread appending 4 KiB blocks, each followed by fdatasync, as a write-ahead log commits",
+        args: "--rw write -b 4096 -f 1 -s 67108864 -t 1 --fsync-writes -n 10000",
+    },
+    Preset {
+        name: "compaction",
+        description: "Sequential 2-8 MiB scans from two threads, half of them rewrites, as LSM compaction merges sorted runs",
+        args: "--rw rw50 --workload scan --scan-length 2MB..8MB -f 8 -s 268435456 -t 2 -n 2000",
+    },
+    Preset {
+        name: "full-scan",
+        description: "One thread reading 4 GiB front to back in 1 MiB blocks with readahead, as a backup or analytics scan",
+        args: "--rw read -b 1048576 -f 4 -s 1073741824 -t 1 -n 4096",
+    },
+];
+
+pub fn find(name: &str) -> Result<&'static Preset, String> {
+    PRESETS.iter().find(|preset| preset.name == name).ok_or_else(|| {
+        let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+        format!("unknown preset '{name}'; available: {}", names.join(", "))
+    })
+}
+
+pub fn list() {
+    println!("📦 Workload presets (`presets show <name>` prints the options, `presets run <name>` runs them):");
+    for preset in PRESETS {
+        println!("  {:<18} {}", preset.name, preset.description);
+    }
+}
+
+/// Print the preset as a command line to copy and edit.
+pub fn show(preset: &Preset) {
+    let binary = std::env::args().next().unwrap_or_else(|| "random_io_tester".to_string());
+    println!("# {}: {}", preset.name, preset.description);
+    println!("{binary} {}", preset.args);
+}
+
+/// The command line of `presets run`: the preset's options followed by the
+/// user's own, so options given on the command line override the preset's.
+/// `argv` is the full command line.
+pub fn command_line(preset: &Preset, argv: &[OsString]) -> Vec<OsString> {
+    let subcommand = argv.iter().position(|arg| arg == "presets").unwrap_or(argv.len());
+    let run = argv[subcommand..].iter().position(|arg| arg == "run").map_or(argv.len(), |i| subcommand + i);
+    let name = argv[run..].iter().position(|arg| arg == preset.name).map_or(argv.len(), |i| run + i);
+    let mut line: Vec<OsString> = argv.iter().take(1).cloned().collect();
+    line.extend(preset.args.split_whitespace().map(OsString::from));
+    // Everything but the subcommand and the preset name, wherever clap allowed the user's options
+    line.extend(argv.iter().take(subcommand).skip(1).cloned());
+    line.extend(argv.iter().take(name).skip(run + 1).cloned());
+    line.extend(argv.iter().skip(name + 1).cloned());
+    line
+}
@@ -0,0 +1,77 @@
+//! Readers for the handful of `/proc` files the tester cares about.
+
+use std::collections::HashMap;
+
+/// Parsed `/proc/meminfo`, values in bytes.
+#[derive(Debug, Clone, Default)]
+pub struct MemInfo {
+    fields: HashMap<String, u64>,
+}
+
+impl MemInfo {
+    pub fn read() -> std::io::Result<Self> {
+        let text = std::fs::read_to_string("/proc/meminfo")?;
+        let fields = text
+            .lines()
+            .filter_map(|line| {
+                let (key, rest) = line.split_once(':')?;
+                let mut parts = rest.split_whitespace();
+                let value: u64 = parts.next()?.parse().ok()?;
+                let scale = if parts.next() == Some("kB") { 1024 } else { 1 };
+                Some((key.to_string(), value * scale))
+            })
+            .collect();
+        Ok(MemInfo { fields })
+    }
+
+    pub fn get(&self, key: &str) -> Option<u64> {
+        self.fields.get(key).copied()
+    }
+
+    pub fn total(&self) -> Option<u64> {
+        self.get("MemTotal")
+    }
+}
+
+/// Aggregate CPU time counters from the first line of `/proc/stat`, in ticks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimes {
+    pub iowait: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    pub fn read() -> std::io::Result<Self> {
+        let text = std::fs::read_to_string("/proc/stat")?;
+        let line = text.lines().find(|l| l.starts_with("cpu ")).unwrap_or("");
+        let values: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        Ok(CpuTimes {
+            iowait: values.get(4).copied().unwrap_or(0),
+            // user nice system idle iowait irq softirq steal; guest time is already in user
+            total: values.iter().take(8).sum(),
+        })
+    }
+
+    /// Share of CPU time spent in iowait between `earlier` and `self`, in percent.
+    pub fn iowait_percent_since(&self, earlier: &CpuTimes) -> Option<f64> {
+        let total = self.total.checked_sub(earlier.total)?;
+        if total == 0 {
+            return None;
+        }
+        let iowait = self.iowait.saturating_sub(earlier.iowait);
+        Some(100.0 * iowait as f64 / total as f64)
+    }
+}
+
+/// One-minute load average from `/proc/loadavg`.
+pub fn load_average() -> std::io::Result<f64> {
+    let text = std::fs::read_to_string("/proc/loadavg")?;
+    text.split_whitespace()
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "malformed /proc/loadavg"))
+}
@@ -0,0 +1,161 @@
+//! Output sinks selected with `--out FORMAT=TARGET`, so one run can produce the
+//! human report and machine-readable files at the same time.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::ReadResult;
+use crate::results::{ResultsFile, RunInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The human-readable report
+    Text,
+    /// The `--json` results summary
+    Json,
+    /// One row per operation
+    Csv,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+/// Where a sink writes: standard output (`-`) or a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Stdout,
+    /// File name; {host}, {ts} and {id} are replaced as in --output
+    File(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkSpec {
+    pub format: Format,
+    pub target: Target,
+}
+
+/// Parse `text=-`, `json=results.json` or `csv=ops.csv`.
+pub fn parse_sink(spec: &str) -> Result<SinkSpec, String> {
+    let (format, target) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("'{spec}': expected FORMAT=TARGET, e.g. json=results.json"))?;
+    let format = match format {
+        "text" => Format::Text,
+        "json" => Format::Json,
+        "csv" => Format::Csv,
+        _ => return Err(format!("'{spec}': unknown format '{format}', expected text, json or csv")),
+    };
+    let target = match target {
+        "" => return Err(format!("'{spec}': missing target (a file name, or - for standard output)")),
+        "-" => Target::Stdout,
+        path => Target::File(path.to_string()),
+    };
+    Ok(SinkSpec { format, target })
+}
+
+/// The file the text report goes to, if a sink asks for one; `--report-file` does the same.
+pub fn text_file(sinks: &[SinkSpec]) -> Result<Option<PathBuf>, String> {
+    let mut text = sinks.iter().filter(|sink| sink.format == Format::Text);
+    let first = text.next();
+    if text.next().is_some() {
+        return Err("only one text output is supported".to_string());
+    }
+    Ok(match first.map(|sink| &sink.target) {
+        Some(Target::File(path)) => Some(PathBuf::from(path)),
+        _ => None,
+    })
+}
+
+/// Everything a machine-readable sink may write.
+pub struct Report<'a> {
+    pub summary: &'a ResultsFile,
+    /// Reads and writes of the measured phase
+    pub operations: &'a [&'a ReadResult],
+    /// --job-file tags by index, for the csv `tag` column; empty without a job file
+    pub tags: &'a [String],
+}
+
+/// Writes a finished run in one format.
+pub trait Sink {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+struct JsonSink;
+
+impl Sink for JsonSink {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, report.summary)?;
+        writeln!(out)
+    }
+}
+
+/// Columns of the per-operation CSV, shared with the `--soak-raw-log`.
+pub const CSV_HEADER: &str = "timestamp_ns,latency_ns,thread,file,offset,bytes,kind,first_read";
+
+/// One CSV row without the line break.
+pub fn csv_row(op: &ReadResult) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{}",
+        op.timestamp.as_nanos(),
+        op.latency.as_nanos(),
+        op.thread,
+        op.file,
+        op.offset,
+        op.bytes,
+        if op.is_write { "write" } else { "read" },
+        op.is_first_read
+    )
+}
+
+struct CsvSink;
+
+impl Sink for CsvSink {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()> {
+        if report.tags.is_empty() {
+            writeln!(out, "{CSV_HEADER}")?;
+            for op in report.operations {
+                writeln!(out, "{}", csv_row(op))?;
+            }
+            return Ok(());
+        }
+        writeln!(out, "{CSV_HEADER},tag")?;
+        for op in report.operations {
+            let tag = op.tag.and_then(|tag| report.tags.get(usize::from(tag))).map_or("", String::as_str);
+            writeln!(out, "{},{tag}", csv_row(op))?;
+        }
+        Ok(())
+    }
+}
+
+/// Write `report` to every json and csv sink; text sinks are the report itself.
+pub fn write_all(sinks: &[SinkSpec], run: &RunInfo, report: &Report) {
+    for spec in sinks {
+        let sink: Box<dyn Sink> = match spec.format {
+            Format::Text => continue,
+            Format::Json => Box::new(JsonSink),
+            Format::Csv => Box::new(CsvSink),
+        };
+        let written = match &spec.target {
+            Target::Stdout => sink.write(report, &mut std::io::stdout().lock()).map(|()| "standard output".to_string()),
+            Target::File(pattern) => {
+                let path = run.output_path(pattern);
+                std::fs::File::create(&path)
+                    .map(std::io::BufWriter::new)
+                    .and_then(|mut file| sink.write(report, &mut file).and_then(|()| file.flush()))
+                    .map(|()| path.display().to_string())
+                    .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+            }
+        };
+        match written {
+            Ok(target) => println!("📝 {} output written to {target}", spec.format.name()),
+            Err(e) => println!("⚠️  Cannot write {} output: {e}", spec.format.name()),
+        }
+    }
+}
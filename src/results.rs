@@ -0,0 +1,385 @@
+//! Run identification and the machine-readable results file.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::stats::Statistics;
+use crate::checkpoint::RunIdentity;
+use crate::fsutil::TrimOutcome;
+
+/// Identifies one run across its console output, checkpoints and results file.
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+    pub id: String,
+    pub host: String,
+    pub started: SystemTime,
+}
+
+impl RunInfo {
+    /// Start a run, keeping `id` when continuing an earlier one.
+    pub fn new(id: Option<String>) -> Self {
+        RunInfo {
+            id: id.unwrap_or_else(new_run_id),
+            host: hostname(),
+            started: SystemTime::now(),
+        }
+    }
+
+    /// Compact UTC start time for file names, e.g. `20261016T093000Z`.
+    pub fn timestamp(&self) -> String {
+        self.rfc3339().replace(['-', ':'], "")
+    }
+
+    /// UTC start time, e.g. `2026-10-16T09:30:00Z`.
+    pub fn rfc3339(&self) -> String {
+        let secs = self.started.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let rem = secs % 86_400;
+        format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", rem / 3600, rem / 60 % 60, rem % 60)
+    }
+
+    /// Expand `{host}`, `{ts}` and `{id}` in an output file name pattern.
+    pub fn output_path(&self, pattern: &str) -> PathBuf {
+        let host: String = self
+            .host
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        PathBuf::from(
+            pattern
+                .replace("{host}", &host)
+                .replace("{ts}", &self.timestamp())
+                .replace("{id}", &self.id),
+        )
+    }
+}
+
+/// A random (version 4) UUID.
+fn new_run_id() -> String {
+    let mut bits: u128 = rand::random();
+    bits = (bits & !(0xF << 76)) | (0x4 << 76);
+    bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+    let hex = format!("{bits:032x}");
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Latency statistics in nanoseconds.
+#[derive(Debug, Serialize)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub avg_ns: u128,
+    pub median_ns: u128,
+    pub p90_ns: u128,
+    pub p95_ns: u128,
+    pub p99_ns: u128,
+    pub min_ns: u128,
+    pub max_ns: u128,
+}
+
+impl From<&Statistics> for LatencySummary {
+    fn from(stats: &Statistics) -> Self {
+        LatencySummary {
+            count: stats.count,
+            avg_ns: stats.avg.as_nanos(),
+            median_ns: stats.median.as_nanos(),
+            p90_ns: stats.p90.as_nanos(),
+            p95_ns: stats.p95.as_nanos(),
+            p99_ns: stats.p99.as_nanos(),
+            min_ns: stats.min.as_nanos(),
+            max_ns: stats.max.as_nanos(),
+        }
+    }
+}
+
+/// One `--interval` row, with the system sample taken at its end (`--sample-system`).
+#[derive(Debug, Serialize)]
+pub struct IntervalSummary {
+    pub start_secs: u64,
+    pub ops: usize,
+    pub iops: f64,
+    pub latency: Option<LatencySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iowait_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dirty_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub writeback_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_average: Option<f64>,
+}
+
+/// Reads of one thread in one interval (`--thread-matrix`).
+#[derive(Debug, Serialize)]
+pub struct MatrixCell {
+    pub ops: usize,
+    pub median_ns: u128,
+    pub p99_ns: u128,
+}
+
+/// One row of the thread × interval matrix; `None` where the thread read nothing.
+#[derive(Debug, Serialize)]
+pub struct ThreadIntervals {
+    pub thread: usize,
+    pub intervals: Vec<Option<MatrixCell>>,
+}
+
+/// Reads of one size class, from `min_bytes` to `max_bytes` long.
+#[derive(Debug, Serialize)]
+pub struct SizeClassSummary {
+    pub min_bytes: u64,
+    pub max_bytes: u64,
+    pub latency: LatencySummary,
+    /// Latency per MiB transferred, for large reads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_mib: Option<LatencySummary>,
+}
+
+/// Operations of one --job-file class.
+#[derive(Debug, Serialize)]
+pub struct TagSummary {
+    pub tag: String,
+    pub kind: crate::workload::OpKind,
+    pub bytes: u64,
+    pub latency: Option<LatencySummary>,
+    pub ops_per_sec: f64,
+    /// The class's `rate` cap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo: Option<SloSummary>,
+}
+
+/// How a job class fared against its p99 objective.
+#[derive(Debug, Serialize)]
+pub struct SloSummary {
+    pub p99_us: f64,
+    pub met: bool,
+    /// Share of the class's operations within the objective
+    pub within_percent: f64,
+}
+
+/// Operations of one reader thread or one file; `latency` covers the reads.
+#[derive(Debug, Serialize)]
+pub struct GroupSummary {
+    pub index: usize,
+    pub reads: usize,
+    pub first_reads: usize,
+    pub writes: usize,
+    pub latency: Option<LatencySummary>,
+}
+
+/// Distribution of the reads completed in each full second of the run.
+#[derive(Debug, Serialize)]
+pub struct IopsSummary {
+    pub seconds: usize,
+    pub mean: f64,
+    pub min: u64,
+    pub p5: u64,
+    pub median: u64,
+    pub p95: u64,
+    pub max: u64,
+}
+
+/// How far behind their `--open-loop` schedule operations started.
+#[derive(Debug, Serialize)]
+pub struct LatenessSummary {
+    pub lateness: LatencySummary,
+    /// Gap between a thread's scheduled operations at the configured rate
+    pub slot_ns: u128,
+    /// Share of operations that started a full slot or more late
+    pub late_percent: f64,
+}
+
+/// Jain's fairness index of the operations issued per file and per thread,
+/// each count divided by its intended share; 1.0 is exactly as intended.
+#[derive(Debug, Serialize)]
+pub struct FairnessSummary {
+    /// Against `--file-weights`; absent when a plugin or script picks the files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<f64>,
+    /// What random file selection alone gives for this many operations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_by_chance: Option<f64>,
+    /// Against an equal share per thread
+    pub threads: f64,
+}
+
+/// First/repeated split of one measured phase, e.g. one step of a sweep.
+#[derive(Debug, Serialize)]
+pub struct PhaseSummary {
+    pub phase: String,
+    pub engine: String,
+    pub all: Option<LatencySummary>,
+    pub first: Option<LatencySummary>,
+    pub repeated: Option<LatencySummary>,
+}
+
+/// Whether `--trim before` trimmed the free space of a directory holding test files.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrimSummary {
+    pub dir: PathBuf,
+    /// `done`, `failed` or `skipped` (not requested)
+    pub status: &'static str,
+    /// How it was trimmed, or why it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl TrimSummary {
+    /// The trim of `dir`; `outcome` is `None` when no trim was requested.
+    pub fn new(dir: &Path, outcome: Option<&TrimOutcome>) -> Self {
+        let status = match outcome {
+            None => "skipped",
+            Some(TrimOutcome::Failed { .. }) => "failed",
+            Some(_) => "done",
+        };
+        TrimSummary { dir: dir.to_path_buf(), status, detail: outcome.map(ToString::to_string) }
+    }
+}
+
+/// Contents of the `--json` results file.
+#[derive(Debug, Serialize)]
+pub struct ResultsFile {
+    pub run_id: String,
+    pub host: String,
+    pub started_at: String,
+    pub elapsed_secs: f64,
+    pub command_line: Vec<String>,
+    pub config: RunIdentity,
+    /// Generator of the reader threads' random streams
+    pub rng: crate::rng::RngKind,
+    /// Seeds of the reader threads, in thread order (`--rng stdrng` only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thread_seeds: Vec<u64>,
+    /// What the platform and the target file system support (engines, O_DIRECT, fallocate modes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<crate::capability::Capabilities>,
+    /// Free-space trim before the run, per directory holding test files
+    pub trim: Vec<TrimSummary>,
+    /// How long `--stabilize` waited and whether the device settled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stabilization: Option<crate::stabilize::Outcome>,
+    pub all: Option<LatencySummary>,
+    pub first: Option<LatencySummary>,
+    pub repeated: Option<LatencySummary>,
+    pub writes: Option<LatencySummary>,
+    /// fdatasync after each write, apart from the writes (`--fsync-writes`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub syncs: Option<LatencySummary>,
+    /// Bytes written by the benchmark, the kernel and the device (`--write-amplification`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_amplification: Option<crate::writeamp::WriteAmplification>,
+    /// Reads without the warmup window and the outliers (`--trim-warmup`, `--trim-percent`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trimmed: Option<LatencySummary>,
+    /// Per-second read IOPS, for runs of at least two full seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iops_per_second: Option<IopsSummary>,
+    /// Reads that failed and were left out of the statistics
+    pub failed_reads: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+    /// Per-interval statistics (`--interval`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub intervals: Vec<IntervalSummary>,
+    /// Per reader thread, indexed by thread
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub threads: Vec<GroupSummary>,
+    /// Reads split by size class, when they are of different sizes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub size_classes: Vec<SizeClassSummary>,
+    /// Per --job-file class, reads and writes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<TagSummary>,
+    /// Read latency per thread and interval (`--thread-matrix`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thread_intervals: Vec<ThreadIntervals>,
+    /// Per file, indexed by file
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<GroupSummary>,
+    /// Thread count `--auto-threads` settled on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_threads: Option<usize>,
+    /// Start of operations behind their `--open-loop` schedule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lateness: Option<LatenessSummary>,
+    /// Operations issued per file and thread against the intended split
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fairness: Option<FairnessSummary>,
+    /// Per-phase splits of multi-phase runs (`--ws-sweep`, `--qd-sweep`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<PhaseSummary>,
+    /// Tuning suggestions drawn from the measurements
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub insights: Vec<crate::insights::Insight>,
+}
+
+impl ResultsFile {
+    pub fn new(run: &RunInfo, config: RunIdentity, elapsed: Duration) -> Self {
+        ResultsFile {
+            run_id: run.id.clone(),
+            host: run.host.clone(),
+            started_at: run.rfc3339(),
+            elapsed_secs: elapsed.as_secs_f64(),
+            command_line: std::env::args().collect(),
+            config,
+            rng: crate::rng::RngKind::Stdrng,
+            thread_seeds: Vec::new(),
+            capabilities: None,
+            trim: Vec::new(),
+            stabilization: None,
+            all: None,
+            first: None,
+            repeated: None,
+            writes: None,
+            syncs: None,
+            write_amplification: None,
+            trimmed: None,
+            iops_per_second: None,
+            failed_reads: 0,
+            first_error: None,
+            intervals: Vec::new(),
+            threads: Vec::new(),
+            thread_intervals: Vec::new(),
+            size_classes: Vec::new(),
+            tags: Vec::new(),
+            files: Vec::new(),
+            fairness: None,
+            auto_threads: None,
+            lateness: None,
+            phases: Vec::new(),
+            insights: Vec::new(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
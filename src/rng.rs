@@ -0,0 +1,269 @@
+//! Random number streams for the reader threads: one independent stream per thread.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// Generator behind every thread's stream.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RngKind {
+    /// ChaCha12, seeded per thread with a SplitMix64-derived seed
+    #[default]
+    Stdrng,
+    /// Xoshiro256++; thread i starts 2^128 steps after thread i-1 (jump-ahead)
+    Xoshiro,
+    /// Philox4x32-10, counter-based; the thread index selects the counter range
+    Philox,
+}
+
+impl std::fmt::Display for RngKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RngKind::Stdrng => "stdrng",
+            RngKind::Xoshiro => "xoshiro",
+            RngKind::Philox => "philox",
+        })
+    }
+}
+
+/// One step of SplitMix64, the usual way to expand a seed into well-mixed words.
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The seed of reader `thread_id`, mixed so neighbouring threads get unrelated
+/// seeds; it does not depend on the number of threads.
+pub fn thread_seed(seed: u64, thread_id: usize) -> u64 {
+    let mut state = seed ^ splitmix64(&mut (thread_id as u64));
+    splitmix64(&mut state)
+}
+
+/// A thread's random stream.
+#[derive(Debug, Clone)]
+pub enum StreamRng {
+    Std(Box<StdRng>),
+    Xoshiro(Xoshiro256pp),
+    Philox(Philox4x32),
+}
+
+impl StreamRng {
+    /// Stream `thread_id` of `seed` for the given generator.
+    pub fn new(kind: RngKind, seed: u64, thread_id: usize) -> Self {
+        match kind {
+            RngKind::Stdrng => StreamRng::Std(Box::new(StdRng::seed_from_u64(thread_seed(seed, thread_id)))),
+            RngKind::Xoshiro => {
+                let mut rng = Xoshiro256pp::new(seed);
+                for _ in 0..thread_id {
+                    rng.jump();
+                }
+                StreamRng::Xoshiro(rng)
+            }
+            RngKind::Philox => StreamRng::Philox(Philox4x32::new(seed, thread_id as u64)),
+        }
+    }
+}
+
+impl RngCore for StreamRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            StreamRng::Std(rng) => rng.next_u64(),
+            StreamRng::Xoshiro(rng) => rng.next(),
+            StreamRng::Philox(rng) => rng.next(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand::rand_core::impls::fill_bytes_via_next(self, dest);
+    }
+}
+
+/// Xoshiro256++ (Blackman and Vigna).
+#[derive(Debug, Clone)]
+pub struct Xoshiro256pp {
+    s: [u64; 4],
+}
+
+impl Xoshiro256pp {
+    const JUMP: [u64; 4] = [0x180E_C6D3_3CFD_0ABA, 0xD5A6_1266_F0C9_392C, 0xA958_2618_E03F_C9AA, 0x39AB_DC45_29B1_661C];
+
+    fn new(seed: u64) -> Self {
+        let mut state = seed;
+        Xoshiro256pp {
+            s: std::array::from_fn(|_| splitmix64(&mut state)),
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Advance by 2^128 steps, the start of the next non-overlapping stream.
+    fn jump(&mut self) {
+        let mut jumped = [0u64; 4];
+        for word in Self::JUMP {
+            for bit in 0..64 {
+                if word & (1 << bit) != 0 {
+                    for (j, s) in jumped.iter_mut().zip(self.s) {
+                        *j ^= s;
+                    }
+                }
+                self.next();
+            }
+        }
+        self.s = jumped;
+    }
+}
+
+/// Philox4x32-10 (Salmon et al.): block `n` of stream `stream` is the encryption
+/// of the counter `(n, stream)` under the seed.
+#[derive(Debug, Clone)]
+pub struct Philox4x32 {
+    key: [u32; 2],
+    block: u64,
+    stream: u64,
+    output: [u32; 4],
+    /// Next unused word of `output`
+    used: usize,
+}
+
+impl Philox4x32 {
+    fn new(seed: u64, stream: u64) -> Self {
+        Philox4x32 {
+            key: [seed as u32, (seed >> 32) as u32],
+            block: 0,
+            stream,
+            output: [0; 4],
+            used: 4,
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.used == 4 {
+            self.output = self.encrypt();
+            self.block += 1;
+            self.used = 0;
+        }
+        self.used += 1;
+        self.output[self.used - 1]
+    }
+
+    fn next(&mut self) -> u64 {
+        u64::from(self.next_u32()) | (u64::from(self.next_u32()) << 32)
+    }
+
+    fn encrypt(&self) -> [u32; 4] {
+        let mut c = [self.block as u32, (self.block >> 32) as u32, self.stream as u32, (self.stream >> 32) as u32];
+        let mut k = self.key;
+        for _ in 0..10 {
+            let p0 = u64::from(0xD251_1F53u32) * u64::from(c[0]);
+            let p1 = u64::from(0xCD9E_8D57u32) * u64::from(c[2]);
+            c = [
+                (p1 >> 32) as u32 ^ c[1] ^ k[0],
+                p1 as u32,
+                (p0 >> 32) as u32 ^ c[3] ^ k[1],
+                p0 as u32,
+            ];
+            k[0] = k[0].wrapping_add(0x9E37_79B9);
+            k[1] = k[1].wrapping_add(0xBB67_AE85);
+        }
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn philox(counter: [u32; 4], key: [u32; 2]) -> Philox4x32 {
+        Philox4x32 {
+            key,
+            block: u64::from(counter[0]) | u64::from(counter[1]) << 32,
+            stream: u64::from(counter[2]) | u64::from(counter[3]) << 32,
+            output: [0; 4],
+            used: 4,
+        }
+    }
+
+    #[test]
+    fn xoshiro_matches_the_reference_outputs() {
+        let mut rng = Xoshiro256pp { s: [1, 2, 3, 4] };
+        let outputs: Vec<u64> = (0..10).map(|_| rng.next()).collect();
+        assert_eq!(
+            outputs,
+            [
+                41943041,
+                58720359,
+                3588806011781223,
+                3591011842654386,
+                9228616714210784205,
+                9973669472204895162,
+                14011001112246962877,
+                12406186145184390807,
+                15849039046786891736,
+                10450023813501588000,
+            ]
+        );
+    }
+
+    #[test]
+    fn xoshiro_jump_matches_the_reference_jump() {
+        // State after the reference implementation's jump() from {1, 2, 3, 4}
+        let mut rng = Xoshiro256pp { s: [1, 2, 3, 4] };
+        rng.jump();
+        assert_eq!(rng.s, [0x8C7A_1539_56B5_F3D1, 0x701F_1A71_3401_D85E, 0x6527_F66A_6546_9085, 0x8386_B786_C440_8050]);
+        assert_eq!([rng.next(), rng.next()], [17043750140134683703, 2364973248208838314]);
+    }
+
+    #[test]
+    fn xoshiro_streams_are_jumps_of_the_seed() {
+        let mut jumped = Xoshiro256pp::new(7);
+        jumped.jump();
+        jumped.jump();
+        let mut stream = StreamRng::new(RngKind::Xoshiro, 7, 2);
+        assert_eq!(stream.next_u64(), jumped.next());
+    }
+
+    #[test]
+    fn philox_matches_the_random123_known_answers() {
+        let cases = [
+            ([0, 0, 0, 0], [0, 0], [0x6627_E8D5, 0xE169_C58D, 0xBC57_AC4C, 0x9B00_DBD8]),
+            ([u32::MAX; 4], [u32::MAX; 2], [0x408F_276D, 0x41C8_3B0E, 0xA20B_C7C6, 0x6D54_51FD]),
+            (
+                [0x243F_6A88, 0x85A3_08D3, 0x1319_8A2E, 0x0370_7344],
+                [0xA409_3822, 0x299F_31D0],
+                [0xD16C_FE09, 0x94FD_CCEB, 0x5001_E420, 0x2412_6EA1],
+            ),
+        ];
+        for (counter, key, expected) in cases {
+            assert_eq!(philox(counter, key).encrypt(), expected, "counter {counter:x?}, key {key:x?}");
+        }
+    }
+
+    #[test]
+    fn philox_counts_blocks_within_the_thread_stream() {
+        let mut rng = Philox4x32::new(0, 0);
+        assert_eq!([rng.next(), rng.next()], [0xE169_C58D_6627_E8D5, 0x9B00_DBD8_BC57_AC4C]);
+        // The next block is counter 1 of the same stream
+        let second = philox([1, 0, 0, 0], [0, 0]).encrypt();
+        assert_eq!(rng.next(), u64::from(second[0]) | u64::from(second[1]) << 32);
+        let mut stream = StreamRng::new(RngKind::Philox, 0, 3);
+        assert_eq!(stream.next_u64(), philox([0, 0, 3, 0], [0, 0]).next());
+    }
+}
@@ -0,0 +1,77 @@
+//! Isolation of concurrent runs sharing a directory: every run names its files
+//! after its run ID and leaves a marker while it is alive, so other runs can
+//! see it and refuse to overwrite or delete its files.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+const MARKER_PREFIX: &str = ".random_io_tester_run_";
+
+/// `--file-prefix` of a run: the given prefix followed by the first part of its run ID.
+pub fn namespaced_prefix(prefix: &str, run_id: &str) -> String {
+    let short: String = run_id.chars().take(8).collect();
+    format!("{prefix}_{short}")
+}
+
+/// Contents of a run's marker file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOwner {
+    pub run_id: String,
+    pub host: String,
+    pub pid: u32,
+    /// Prefix of the files the run creates and deletes
+    pub file_prefix: String,
+}
+
+impl RunOwner {
+    /// Whether the run's process still exists; runs on other hosts sharing the
+    /// directory over a network file system cannot be checked and count as alive.
+    fn alive(&self, host: &str) -> bool {
+        if self.host != host {
+            return true;
+        }
+        let Ok(pid) = libc::pid_t::try_from(self.pid) else { return false };
+        let signalled = unsafe { libc::kill(pid, 0) } == 0;
+        signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+}
+
+/// Live runs with a marker in `dir`, other than the calling process. Markers of
+/// runs that died without cleaning up are removed.
+pub fn live_runs(dir: &Path, host: &str) -> Vec<RunOwner> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(MARKER_PREFIX))
+        .filter_map(|entry| {
+            let owner: RunOwner = serde_json::from_slice(&std::fs::read(entry.path()).ok()?).ok()?;
+            if owner.pid == std::process::id() && owner.host == host {
+                return None;
+            }
+            if !owner.alive(host) {
+                let _ = std::fs::remove_file(entry.path());
+                return None;
+            }
+            Some(owner)
+        })
+        .collect()
+}
+
+/// A run's marker; removed when dropped.
+pub struct Marker {
+    path: PathBuf,
+}
+
+/// Announce the calling run in `dir`.
+pub fn claim(dir: &Path, owner: &RunOwner) -> std::io::Result<Marker> {
+    let path = dir.join(format!("{MARKER_PREFIX}{}.json", owner.run_id));
+    std::fs::write(&path, serde_json::to_vec_pretty(owner)?)?;
+    Ok(Marker { path })
+}
+
+impl Drop for Marker {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,77 @@
+//! Background thread recording system state at each reporting interval.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::procfs::{self, CpuTimes, MemInfo};
+
+/// System state at the end of one reporting interval.
+#[derive(Debug, Clone)]
+pub struct SystemSample {
+    /// End of the interval, relative to the start of the measured phase
+    pub at: Duration,
+    /// CPU iowait over the interval, in percent
+    pub iowait_percent: Option<f64>,
+    pub dirty_bytes: Option<u64>,
+    pub writeback_bytes: Option<u64>,
+    pub load_average: Option<f64>,
+}
+
+pub struct SystemSampler {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Vec<SystemSample>>,
+}
+
+impl SystemSampler {
+    /// Start sampling at `run_start + k * interval` for k = 1, 2, ...
+    pub fn start(run_start: Instant, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = Arc::clone(&stop);
+
+        let handle = std::thread::spawn(move || {
+            let mut samples = Vec::new();
+            let mut previous_cpu = CpuTimes::read().ok();
+            let mut next = interval;
+
+            loop {
+                // Sleep in short slices so a stop request is noticed quickly
+                while run_start.elapsed() < next && !stop_clone.load(Ordering::Relaxed) {
+                    let remaining = next.saturating_sub(run_start.elapsed());
+                    std::thread::sleep(remaining.min(Duration::from_millis(50)));
+                }
+                let stopping = stop_clone.load(Ordering::Relaxed);
+                let at = if stopping { run_start.elapsed() } else { next };
+
+                let cpu = CpuTimes::read().ok();
+                let meminfo = MemInfo::read().ok();
+                samples.push(SystemSample {
+                    at,
+                    iowait_percent: match (&cpu, &previous_cpu) {
+                        (Some(now), Some(before)) => now.iowait_percent_since(before),
+                        _ => None,
+                    },
+                    dirty_bytes: meminfo.as_ref().and_then(|m| m.get("Dirty")),
+                    writeback_bytes: meminfo.as_ref().and_then(|m| m.get("Writeback")),
+                    load_average: procfs::load_average().ok(),
+                });
+                previous_cpu = cpu;
+
+                if stopping {
+                    break;
+                }
+                next += interval;
+            }
+            samples
+        });
+
+        SystemSampler { stop, handle }
+    }
+
+    /// Take a final sample for the partial last interval and return all samples.
+    pub fn stop(self) -> Vec<SystemSample> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().unwrap_or_default()
+    }
+}
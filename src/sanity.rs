@@ -0,0 +1,74 @@
+//! Startup checks for environments that make the measurements misleading.
+
+use std::path::{Path, PathBuf};
+
+use crate::fsutil;
+use crate::procfs::MemInfo;
+
+/// Inspect the filesystems of `target_dirs` and the dataset size and describe
+/// every condition that would invalidate the results.
+pub fn check_environment(target_dirs: &[PathBuf], dataset_bytes: u64) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for target_dir in target_dirs {
+        check_filesystem(target_dir, &mut warnings);
+    }
+
+    if let Some(total) = MemInfo::read().ok().and_then(|m| m.total())
+        && dataset_bytes < total
+    {
+        warnings.push(format!(
+            "dataset ({dataset_bytes} bytes) is smaller than RAM ({total} bytes): \
+             \"first reads\" are likely served from the page cache, not cold storage"
+        ));
+    }
+
+    warnings
+}
+
+fn check_filesystem(target_dir: &Path, warnings: &mut Vec<String>) {
+    match fsutil::mount_info_for(target_dir) {
+        Ok(mount) => {
+            match mount.fs_type.as_str() {
+                "tmpfs" | "ramfs" => warnings.push(format!(
+                    "{} is on {}: reads come from RAM, not from a storage device",
+                    target_dir.display(),
+                    mount.fs_type
+                )),
+                "overlay" => warnings.push(format!(
+                    "{} is on overlayfs: results depend on the (unknown) upper/lower layers",
+                    target_dir.display()
+                )),
+                _ => {}
+            }
+
+            let atime_disabled = ["noatime", "relatime", "lazytime"]
+                .iter()
+                .any(|o| mount.has_option(o));
+            if !atime_disabled {
+                warnings.push(format!(
+                    "{} is mounted with strict atime: every read also dirties inode metadata",
+                    mount.mount_point.display()
+                ));
+            }
+
+            if mount.fs_type == "btrfs"
+                && (mount.has_option("compress") || mount.has_option("compress-force"))
+            {
+                warnings.push(format!(
+                    "{} is a compressed btrfs mount: the constant test pattern compresses to almost nothing",
+                    mount.mount_point.display()
+                ));
+            }
+            if mount.fs_type == "zfs" {
+                warnings.push(format!(
+                    "{} is on ZFS: the ARC and dataset compression bypass the page cache semantics measured here",
+                    mount.mount_point.display()
+                ));
+            }
+        }
+        Err(e) => warnings.push(format!(
+            "cannot determine the filesystem of {}: {e}",
+            target_dir.display()
+        )),
+    }
+}
@@ -0,0 +1,100 @@
+//! A JSON schema of the run configuration, derived from the command-line definition.
+
+use clap::builder::ValueParser;
+use clap::{Arg, ArgAction, Command};
+use serde_json::{Map, Value, json};
+
+/// Schema of an object with one property per option, keyed by its long name.
+pub fn options_schema(cmd: &Command) -> Value {
+    let mut properties = Map::new();
+    for arg in cmd.get_arguments() {
+        let Some(long) = arg.get_long() else { continue };
+        if matches!(arg.get_action(), ArgAction::Help | ArgAction::Version) {
+            continue;
+        }
+        properties.insert(long.to_string(), property(arg));
+    }
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{} options", cmd.get_name()),
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties,
+    })
+}
+
+fn property(arg: &Arg) -> Value {
+    let mut schema = Map::new();
+    if let Some(help) = arg.get_help() {
+        schema.insert("description".into(), help.to_string().into());
+    }
+    let item = value_schema(arg);
+    let defaults: Vec<Value> = arg
+        .get_default_values()
+        .iter()
+        .map(|v| typed(&item, &v.to_string_lossy()))
+        .collect();
+    match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse => {
+            schema.insert("type".into(), "boolean".into());
+            schema.insert("default".into(), matches!(arg.get_action(), ArgAction::SetFalse).into());
+        }
+        ArgAction::Count => {
+            schema.insert("type".into(), "integer".into());
+            schema.insert("minimum".into(), 0.into());
+        }
+        ArgAction::Append => {
+            schema.insert("type".into(), "array".into());
+            schema.insert("items".into(), item);
+            if !defaults.is_empty() {
+                schema.insert("default".into(), defaults.into());
+            }
+        }
+        _ => {
+            schema.extend(item.as_object().cloned().unwrap_or_default());
+            if let Some(default) = defaults.into_iter().next() {
+                schema.insert("default".into(), default);
+            }
+        }
+    }
+    Value::Object(schema)
+}
+
+/// Schema of a single value, from its parser's output type or its fixed choices.
+fn value_schema(arg: &Arg) -> Value {
+    let choices: Vec<Value> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().into())
+        .collect();
+    if !choices.is_empty() {
+        return json!({ "type": "string", "enum": choices });
+    }
+    let parser = arg.get_value_parser();
+    let is = |other: ValueParser| parser.type_id() == other.type_id();
+    if is(clap::value_parser!(u64).into())
+        || is(clap::value_parser!(usize).into())
+        || is(clap::value_parser!(u32).into())
+        || is(clap::value_parser!(i64).into())
+    {
+        let mut schema = json!({ "type": "integer" });
+        if is(clap::value_parser!(u64).into()) || is(clap::value_parser!(usize).into()) || is(clap::value_parser!(u32).into()) {
+            schema["minimum"] = 0.into();
+        }
+        schema
+    } else if is(clap::value_parser!(f64).into()) {
+        json!({ "type": "number" })
+    } else {
+        json!({ "type": "string" })
+    }
+}
+
+/// A default value in the JSON type of its schema.
+fn typed(schema: &Value, raw: &str) -> Value {
+    match schema["type"].as_str() {
+        Some("integer") => raw.parse::<i64>().map_or_else(|_| raw.into(), Value::from),
+        Some("number") => raw.parse::<f64>().map_or_else(|_| raw.into(), Value::from),
+        _ => raw.into(),
+    }
+}
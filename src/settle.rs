@@ -0,0 +1,109 @@
+//! `--settle`: explicit steps between file creation and the measurement, so
+//! the run does not start while creation writeback is still in flight.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{procfs, workload};
+
+/// Dirty and writeback memory `drop-dirty` waits for when no threshold is given.
+const DEFAULT_DIRTY_THRESHOLD: u64 = 1024 * 1024;
+/// Longest `drop-dirty` wait before giving up with a warning.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(600);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// syncfs(2) on the file system holding the test files
+    Syncfs,
+    /// Wait until dirty plus writeback memory is at most this many bytes
+    DropDirty(u64),
+    Sleep(Duration),
+}
+
+/// Parse one step: `syncfs`, `drop-dirty[=SIZE]` or `sleep=SECS[s]`.
+pub fn parse_step(spec: &str) -> Result<Step, String> {
+    let (name, value) = match spec.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (spec, None),
+    };
+    match (name, value) {
+        ("syncfs", None) => Ok(Step::Syncfs),
+        ("drop-dirty", None) => Ok(Step::DropDirty(DEFAULT_DIRTY_THRESHOLD)),
+        ("drop-dirty", Some(size)) => workload::parse_size(size).map(Step::DropDirty),
+        ("sleep", Some(secs)) => secs
+            .strip_suffix('s')
+            .unwrap_or(secs)
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+            .map(|secs| Step::Sleep(Duration::from_secs_f64(secs)))
+            .ok_or_else(|| format!("'{spec}': expected sleep=SECONDS, e.g. sleep=5s")),
+        ("sleep", None) => Err(format!("'{spec}': sleep needs a duration, e.g. sleep=5s")),
+        _ => Err(format!("'{spec}': unknown step; expected syncfs, drop-dirty[=SIZE] or sleep=Ns")),
+    }
+}
+
+fn unwritten_bytes() -> Option<u64> {
+    let meminfo = procfs::MemInfo::read().ok()?;
+    Some(meminfo.get("Dirty")? + meminfo.get("Writeback")?)
+}
+
+#[cfg(target_os = "linux")]
+fn syncfs(dir: &Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let dir = std::fs::File::open(dir)?;
+    if unsafe { libc::syncfs(dir.as_raw_fd()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn syncfs(_dir: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "syncfs is only available on Linux"))
+}
+
+/// Run `steps` in order against the file systems holding `dirs`.
+pub fn run(steps: &[Step], dirs: &[PathBuf]) -> std::io::Result<()> {
+    for step in steps {
+        let start = Instant::now();
+        match step {
+            Step::Syncfs => {
+                for dir in dirs {
+                    let start = Instant::now();
+                    syncfs(dir)?;
+                    println!("🧽 Settle: syncfs on {} took {:.2}s", dir.display(), start.elapsed().as_secs_f64());
+                }
+            }
+            Step::DropDirty(threshold) => {
+                let Some(mut unwritten) = unwritten_bytes() else {
+                    println!("⚠️  Settle: /proc/meminfo has no Dirty/Writeback counters; skipping drop-dirty");
+                    continue;
+                };
+                let initial = unwritten;
+                while unwritten > *threshold && start.elapsed() < DRAIN_TIMEOUT && !crate::status::interrupted() {
+                    std::thread::sleep(POLL_INTERVAL);
+                    unwritten = unwritten_bytes().unwrap_or(0);
+                }
+                if unwritten > *threshold {
+                    println!(
+                        "⚠️  Settle: dirty and writeback memory still {unwritten} bytes after {:.0}s (threshold {threshold})",
+                        start.elapsed().as_secs_f64()
+                    );
+                } else {
+                    println!(
+                        "🧽 Settle: dirty and writeback memory drained from {initial} to {unwritten} bytes in {:.2}s",
+                        start.elapsed().as_secs_f64()
+                    );
+                }
+            }
+            Step::Sleep(duration) => {
+                println!("⏳ Settle: sleeping {:.1}s...", duration.as_secs_f64());
+                std::thread::sleep(*duration);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! `--create-procs`: create the test files from several processes at once, for
+//! datasets too large to write from one. The files are shared out by size over
+//! `create-shard` child processes, each kept to its own `--target-dir` when
+//! several are given, and told its files on stdin; they report every chunk
+//! written on stdout, from which the parent prints the progress of the whole
+//! dataset.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{Args, CREATE_CHUNK_SIZE, CreateMode, dataset, fsutil, value_name};
+
+/// How often the parent prints the progress of all shards.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Create `file_paths` from `args.create_procs` child processes.
+pub fn create(args: &Args, file_paths: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let procs = args.create_procs.min(file_paths.len()).max(1);
+    // With --target-dir, each shard writes into one directory, or with fewer
+    // shards than directories, into every procs-th one
+    let dirs = args.target_dirs.len().max(1);
+    let serves = |shard: usize, file: usize| {
+        let dir = file % dirs;
+        if procs >= dirs { shard % dirs == dir } else { dir % procs == shard }
+    };
+    // Largest files first, each to the shard with the fewest bytes so far
+    let mut order: Vec<usize> = (0..file_paths.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(args.file_size_of(i)));
+    let mut shards: Vec<(u64, Vec<usize>)> = vec![(0, Vec::new()); procs];
+    for i in order {
+        let lightest = shards
+            .iter_mut()
+            .enumerate()
+            .filter(|(shard, _)| serves(*shard, i))
+            .map(|(_, shard)| shard)
+            .min_by_key(|(bytes, _)| *bytes)
+            .expect("every directory has a shard");
+        lightest.0 += args.file_size_of(i);
+        lightest.1.push(i);
+    }
+    let total: u64 = shards.iter().map(|(bytes, _)| bytes).sum();
+    println!("📝 Creating {} files ({:.1} GiB) from {procs} processes...", file_paths.len(), total as f64 / (1u64 << 30) as f64);
+
+    let exe = std::env::current_exe()?;
+    let written = Arc::new(AtomicU64::new(0));
+    let mut children = Vec::with_capacity(procs);
+    for (_, files) in &shards {
+        let mut child = Command::new(&exe)
+            .arg("create-shard")
+            .arg("--mode")
+            .arg(value_name(&args.create_mode))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().ok_or("create shard has no stdin")?;
+        for &i in files {
+            writeln!(stdin, "{} {}", args.file_size_of(i), file_paths[i])?;
+        }
+        drop(stdin);
+        let stdout = child.stdout.take().ok_or("create shard has no stdout")?;
+        let stderr = child.stderr.take().ok_or("create shard has no stderr")?;
+        let counter = Arc::clone(&written);
+        let reader = std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Ok(bytes) = line.trim().parse::<u64>() {
+                    counter.fetch_add(bytes, Ordering::Relaxed);
+                }
+            }
+        });
+        // Pass errors on, but not the status line every child ends with
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if !line.starts_with("status=") {
+                    eprintln!("  shard: {line}");
+                }
+            }
+        });
+        children.push((child, reader));
+    }
+
+    let start = Instant::now();
+    let mut last_progress = start;
+    let mut statuses = vec![None; children.len()];
+    while statuses.iter().any(Option::is_none) {
+        std::thread::sleep(Duration::from_millis(100));
+        for ((child, _), status) in children.iter_mut().zip(&mut statuses) {
+            if status.is_none() {
+                *status = child.try_wait()?;
+            }
+        }
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let done = written.load(Ordering::Relaxed);
+            println!(
+                "  {:.1} of {:.1} GiB ({:.0}%), {:.0} MiB/s",
+                done as f64 / (1u64 << 30) as f64,
+                total as f64 / (1u64 << 30) as f64,
+                100.0 * done as f64 / total.max(1) as f64,
+                done as f64 / (1u64 << 20) as f64 / start.elapsed().as_secs_f64().max(f64::EPSILON)
+            );
+        }
+    }
+    let mut failed = Vec::new();
+    for (shard, ((_, reader), status)) in children.into_iter().zip(statuses).enumerate() {
+        let _ = reader.join();
+        if let Some(status) = status.filter(|status| !status.success()) {
+            failed.push(format!("shard {shard} ({status})"));
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("creating the test files failed in {}", failed.join(", ")).into());
+    }
+    println!(
+        "  {:.1} GiB in {:.1}s ({:.0} MiB/s over {procs} processes)",
+        total as f64 / (1u64 << 30) as f64,
+        start.elapsed().as_secs_f64(),
+        total as f64 / (1u64 << 20) as f64 / start.elapsed().as_secs_f64().max(f64::EPSILON)
+    );
+    Ok(())
+}
+
+/// The `create-shard` child: create the files listed on stdin as `<size> <path>`
+/// lines, printing the bytes of every chunk written.
+pub fn run_shard(mode: CreateMode) -> Result<(), Box<dyn std::error::Error>> {
+    let chunk = vec![0xAB; CREATE_CHUNK_SIZE as usize];
+    let mut out = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        let (size, path) = line.split_once(' ').ok_or_else(|| format!("bad shard line '{line}'"))?;
+        let size: u64 = size.parse()?;
+        dataset::create_parent(path)?;
+        let mut file = File::create(path)?;
+        match mode {
+            CreateMode::Write => {
+                let mut done = 0u64;
+                while done < size {
+                    let len = (chunk.len() as u64).min(size - done);
+                    file.write_all(&chunk[..len as usize])?;
+                    done += len;
+                    writeln!(out, "{len}")?;
+                    out.flush()?;
+                }
+            }
+            CreateMode::Sparse => file.set_len(size)?,
+            CreateMode::Fallocate => fsutil::fallocate(&file, size)?,
+        }
+        file.sync_all()?;
+        if mode != CreateMode::Write {
+            writeln!(out, "{size}")?;
+        }
+    }
+    Ok(())
+}
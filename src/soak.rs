@@ -0,0 +1,332 @@
+//! `--soak`: multi-day runs in bounded memory. Readers fold their operations
+//! into histograms instead of keeping them; a flusher thread closes one
+//! interval at a time and appends its summary to a rotating log on disk.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::histogram::LatencyHistogram;
+use crate::results::LatencySummary;
+use crate::stats::Statistics;
+use crate::{ReadResult, report};
+
+/// Operations a reader folds in before handing them to the current interval.
+pub const BATCH: u64 = 256;
+/// Rotated log files kept next to the current one (`.1` is the newest).
+const KEEP_ROTATED: usize = 4;
+/// How often the flusher wakes up to check for the end of an interval or the run.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Latencies of an interval, or of the whole run.
+#[derive(Debug, Clone, Default)]
+pub struct Window {
+    pub reads: LatencyHistogram,
+    pub first: LatencyHistogram,
+    pub repeated: LatencyHistogram,
+    pub writes: LatencyHistogram,
+    pub failed: u64,
+}
+
+impl Window {
+    pub fn ops(&self) -> u64 {
+        self.reads.count() + self.writes.count() + self.failed
+    }
+
+    fn merge(&mut self, other: &Window) {
+        self.reads.merge(&other.reads);
+        self.first.merge(&other.first);
+        self.repeated.merge(&other.repeated);
+        self.writes.merge(&other.writes);
+        self.failed += other.failed;
+    }
+}
+
+/// A reader thread's operations since its last hand-over.
+#[derive(Default)]
+pub struct Batch {
+    window: Window,
+    /// `--soak-raw-log` rows
+    raw: String,
+}
+
+impl Batch {
+    /// Count one operation; `None` for an operation that failed.
+    pub fn record(&mut self, result: Option<&ReadResult>, raw_log: bool) {
+        let Some(op) = result else {
+            self.window.failed += 1;
+            return;
+        };
+        if op.is_write {
+            self.window.writes.record(op.latency);
+        } else {
+            self.window.reads.record(op.latency);
+            if op.is_first_read {
+                self.window.first.record(op.latency);
+            } else {
+                self.window.repeated.record(op.latency);
+            }
+        }
+        if raw_log {
+            self.raw.push_str(&report::csv_row(op));
+            self.raw.push('\n');
+        }
+    }
+
+    pub fn ops(&self) -> u64 {
+        self.window.ops()
+    }
+}
+
+/// A line-oriented log that starts a new file once the current one exceeds
+/// its size limit, keeping the last [`KEEP_ROTATED`] files.
+struct RotatingLog {
+    path: PathBuf,
+    /// Written at the top of every file
+    header: Option<&'static str>,
+    file: BufWriter<File>,
+    written: u64,
+    limit: u64,
+}
+
+impl RotatingLog {
+    fn create(path: PathBuf, header: Option<&'static str>, limit: u64) -> std::io::Result<Self> {
+        let mut log = RotatingLog {
+            file: BufWriter::new(File::create(&path)?),
+            path,
+            header,
+            written: 0,
+            limit,
+        };
+        log.write_header()?;
+        Ok(log)
+    }
+
+    fn write_header(&mut self) -> std::io::Result<()> {
+        if let Some(header) = self.header {
+            writeln!(self.file, "{header}")?;
+            self.written += header.len() as u64 + 1;
+        }
+        Ok(())
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        name.into()
+    }
+
+    fn write(&mut self, text: &str) -> std::io::Result<()> {
+        if self.written > 0 && self.written + text.len() as u64 > self.limit {
+            self.file.flush()?;
+            for n in (1..KEEP_ROTATED).rev() {
+                let _ = std::fs::rename(self.rotated(n), self.rotated(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+            self.file = BufWriter::new(File::create(&self.path)?);
+            self.written = 0;
+            self.write_header()?;
+        }
+        self.file.write_all(text.as_bytes())?;
+        self.written += text.len() as u64;
+        Ok(())
+    }
+}
+
+/// One line of the interval log.
+#[derive(Debug, Serialize)]
+struct IntervalLine {
+    start_secs: f64,
+    secs: f64,
+    reads: u64,
+    writes: u64,
+    failed: u64,
+    iops: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    read_latency: Option<LatencySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    write_latency: Option<LatencySummary>,
+}
+
+fn summary(histogram: &LatencyHistogram) -> Option<LatencySummary> {
+    (!histogram.is_empty()).then(|| (&Statistics::from_histogram(histogram)).into())
+}
+
+/// Shared by the reader threads and the flusher.
+pub struct Soak {
+    start: Instant,
+    /// End of the run; `None` runs until interrupted
+    end: Option<Instant>,
+    current: Mutex<Window>,
+    raw_log: Option<Mutex<RotatingLog>>,
+    stopped: AtomicBool,
+}
+
+impl Soak {
+    /// Whether the readers should stop: the soak is over, interrupted or its flusher failed.
+    pub fn finished(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed) || self.end.is_some_and(|end| Instant::now() >= end)
+    }
+
+    pub fn raw_log(&self) -> bool {
+        self.raw_log.is_some()
+    }
+
+    /// Take over a reader's batch.
+    pub fn add(&self, batch: &mut Batch) {
+        let batch = std::mem::take(batch);
+        self.current.lock().unwrap().merge(&batch.window);
+        if let Some(raw_log) = &self.raw_log
+            && let Err(e) = raw_log.lock().unwrap().write(&batch.raw)
+        {
+            eprintln!("⚠️  Cannot write the raw soak log: {e}");
+        }
+    }
+}
+
+/// What the whole soak run measured.
+pub struct Totals {
+    pub window: Window,
+    pub intervals: u64,
+    /// Lowest, mean and highest IOPS of the completed intervals
+    pub iops: (f64, f64, f64),
+    /// Highest read p99 of any interval, with the interval's start
+    pub worst_p99: Option<(Duration, Duration)>,
+}
+
+type FlusherState = (Totals, RotatingLog, Instant);
+
+/// The flusher thread of a running soak.
+pub struct Flusher {
+    soak: Arc<Soak>,
+    handle: JoinHandle<std::io::Result<FlusherState>>,
+    pub log_path: PathBuf,
+}
+
+/// Start the flusher: every `interval` it closes the current window and appends
+/// its summary to `log_path`; `duration` of `None` runs until interrupted.
+pub fn start(
+    duration: Option<Duration>,
+    interval: Duration,
+    log_path: PathBuf,
+    raw_log_path: Option<PathBuf>,
+    rotate_bytes: u64,
+) -> std::io::Result<(Arc<Soak>, Flusher)> {
+    let mut log = RotatingLog::create(log_path.clone(), None, rotate_bytes)?;
+    let raw_log = raw_log_path
+        .map(|path| RotatingLog::create(path, Some(report::CSV_HEADER), rotate_bytes).map(Mutex::new))
+        .transpose()?;
+    let start = Instant::now();
+    let soak = Arc::new(Soak {
+        start,
+        end: duration.map(|d| start + d),
+        current: Mutex::new(Window::default()),
+        raw_log,
+        stopped: AtomicBool::new(false),
+    });
+    let flusher_soak = Arc::clone(&soak);
+    let handle = std::thread::spawn(move || {
+        let soak = flusher_soak;
+        let mut totals = Totals { window: Window::default(), intervals: 0, iops: (f64::MAX, 0.0, 0.0), worst_p99: None };
+        let mut interval_start = soak.start;
+        // The last, partial interval is closed by `Flusher::finish` once the readers are done
+        while !soak.finished() {
+            std::thread::sleep(TICK);
+            let now = Instant::now();
+            if crate::status::interrupted() {
+                soak.stopped.store(true, Ordering::Relaxed);
+            } else if now.duration_since(interval_start) >= interval || crate::status::take_report_request() {
+                if let Err(e) = close_interval(&soak, &mut log, &mut totals, interval_start, now) {
+                    // Nothing else would stop the readers of an unbounded soak
+                    soak.stopped.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+                interval_start = now;
+            }
+        }
+        Ok((totals, log, interval_start))
+    });
+    Ok((Arc::clone(&soak), Flusher { soak, handle, log_path }))
+}
+
+/// Fold the current window into `totals`, log it and print a one-line summary.
+fn close_interval(soak: &Soak, log: &mut RotatingLog, totals: &mut Totals, start: Instant, end: Instant) -> std::io::Result<()> {
+    let window = std::mem::take(&mut *soak.current.lock().unwrap());
+    let secs = end.duration_since(start).as_secs_f64();
+    let offset = start.duration_since(soak.start);
+    let iops = window.ops() as f64 / secs.max(f64::EPSILON);
+    let line = IntervalLine {
+        start_secs: offset.as_secs_f64(),
+        secs,
+        reads: window.reads.count(),
+        writes: window.writes.count(),
+        failed: window.failed,
+        iops,
+        read_latency: summary(&window.reads),
+        write_latency: summary(&window.writes),
+    };
+    log.write(&format!("{}\n", serde_json::to_string(&line)?))?;
+    log.file.flush()?;
+
+    let p99 = (!window.reads.is_empty()).then(|| window.reads.quantile(0.99));
+    println!(
+        "🕰️  [{:>8.0}s] {:>10.1} IOPS, {} reads, {} writes, {} failed{}",
+        offset.as_secs_f64(),
+        iops,
+        window.reads.count(),
+        window.writes.count(),
+        window.failed,
+        p99.map(|p99| format!(", read p99 {}", crate::units::format_latency(p99))).unwrap_or_default()
+    );
+    totals.intervals += 1;
+    let (min, mean, max) = &mut totals.iops;
+    *min = min.min(iops);
+    *mean += (iops - *mean) / totals.intervals as f64;
+    *max = max.max(iops);
+    if let Some(p99) = p99
+        && totals.worst_p99.is_none_or(|(worst, _)| p99 > worst)
+    {
+        totals.worst_p99 = Some((p99, offset));
+    }
+    totals.window.merge(&window);
+    Ok(())
+}
+
+impl Flusher {
+    /// Stop the flusher once the readers are done, close the last interval and return the totals.
+    pub fn finish(self) -> std::io::Result<Totals> {
+        self.soak.stopped.store(true, Ordering::Relaxed);
+        let (mut totals, mut log, interval_start) = self.handle.join().expect("soak flusher panicked")?;
+        let now = Instant::now();
+        if self.soak.current.lock().unwrap().ops() > 0 {
+            close_interval(&self.soak, &mut log, &mut totals, interval_start, now)?;
+        }
+        if let Some(raw_log) = &self.soak.raw_log {
+            raw_log.lock().unwrap().file.flush()?;
+        }
+        Ok(totals)
+    }
+}
+
+impl Totals {
+    pub fn report(&self, log_path: &std::path::Path) {
+        println!("\n🛁 Soak Summary ({} intervals, summaries in {}):", self.intervals, log_path.display());
+        if self.intervals == 0 {
+            return;
+        }
+        let (min, mean, max) = self.iops;
+        println!("  IOPS per interval: min {min:.1}, mean {mean:.1}, max {max:.1}");
+        if let Some((p99, at)) = self.worst_p99 {
+            println!("  Worst interval read p99: {} (interval starting at {}s)", crate::units::format_latency(p99), at.as_secs());
+        }
+        if self.window.failed > 0 {
+            println!("⚠️  {} operations failed", self.window.failed);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! `--stabilize`: instead of sleeping for a fixed `--wait-time`, probe the
+//! device until its temperature, its read latency and the kernel's writeback
+//! have settled, so the measured phase does not start while the device is
+//! still busy with the file creation.
+
+use std::os::unix::fs::FileExt;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{device, fsutil, procfs};
+
+/// Reads per probe; their median is one latency sample.
+const PROBE_READS: usize = 32;
+/// Size and alignment of a probe read, which bypasses the page cache where possible.
+const PROBE_LEN: usize = 4096;
+/// Consecutive samples that must agree before the device counts as stable.
+const WINDOW: usize = 5;
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Largest spread of the window's latency medians, relative to their median
+const LATENCY_TOLERANCE: f64 = 0.25;
+/// Largest temperature change over the window, in °C
+const TEMPERATURE_TOLERANCE: f64 = 1.0;
+/// Dirty and writeback memory is settled once it varies less than this over the window
+const WRITEBACK_TOLERANCE_BYTES: u64 = 1024 * 1024;
+
+struct Sample {
+    latency: Duration,
+    temperature: Option<f64>,
+    /// Dirty plus writeback memory
+    unwritten: Option<u64>,
+}
+
+/// How the wait ended.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Outcome {
+    pub waited_secs: f64,
+    pub stable: bool,
+}
+
+/// Reads random 4 KiB blocks of the test files, with O_DIRECT if the file
+/// system supports it and otherwise evicting each block before reading it.
+struct Prober {
+    files: Vec<(std::fs::File, u64)>,
+    direct: bool,
+    buffer: Vec<u8>,
+    rng: StdRng,
+}
+
+impl Prober {
+    fn open(file_paths: &[String], seed: u64) -> std::io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let open = |flags: i32| -> std::io::Result<Vec<(std::fs::File, u64)>> {
+            file_paths
+                .iter()
+                .map(|path| {
+                    let file = std::fs::OpenOptions::new().read(true).custom_flags(flags).open(path)?;
+                    let blocks = fsutil::file_len(&file)? / PROBE_LEN as u64;
+                    Ok((file, blocks))
+                })
+                // Files smaller than one probe read are skipped
+                .filter(|file| !matches!(file, Ok((_, 0))))
+                .collect()
+        };
+        let rng = StdRng::seed_from_u64(seed ^ 0x57AB_1E00);
+        // Some file systems accept O_DIRECT at open and reject the reads
+        if let Ok(files) = open(libc::O_DIRECT) {
+            let mut prober = Prober { files, direct: true, buffer: vec![0; 2 * PROBE_LEN], rng: rng.clone() };
+            if prober.probe().is_ok() {
+                return Ok(prober);
+            }
+        }
+        Ok(Prober { files: open(0)?, direct: false, buffer: vec![0; 2 * PROBE_LEN], rng })
+    }
+
+    /// Median latency of one burst of probe reads.
+    fn probe(&mut self) -> std::io::Result<Option<Duration>> {
+        if self.files.is_empty() {
+            return Ok(None);
+        }
+        let aligned = self.buffer.as_ptr().align_offset(PROBE_LEN);
+        let mut latencies = Vec::with_capacity(PROBE_READS);
+        for _ in 0..PROBE_READS {
+            let (file, blocks) = &self.files[self.rng.random_range(0..self.files.len())];
+            let offset = self.rng.random_range(0..*blocks) * PROBE_LEN as u64;
+            if !self.direct {
+                fsutil::advise_dontneed_range(file, offset, PROBE_LEN as u64)?;
+            }
+            let start = Instant::now();
+            file.read_exact_at(&mut self.buffer[aligned..aligned + PROBE_LEN], offset)?;
+            latencies.push(start.elapsed());
+        }
+        latencies.sort_unstable();
+        Ok(Some(latencies[PROBE_READS / 2]))
+    }
+}
+
+/// Whether the last [`WINDOW`] samples agree within the tolerances.
+fn settled(window: &[Sample]) -> bool {
+    if window.len() < WINDOW {
+        return false;
+    }
+    let mut latencies: Vec<Duration> = window.iter().map(|s| s.latency).collect();
+    latencies.sort_unstable();
+    let median = latencies[WINDOW / 2].as_secs_f64().max(f64::MIN_POSITIVE);
+    let latency_spread = (latencies[WINDOW - 1] - latencies[0]).as_secs_f64() / median;
+    let spread = |values: Vec<f64>| values.iter().copied().fold(f64::MIN, f64::max) - values.iter().copied().fold(f64::MAX, f64::min);
+    let temperatures: Option<Vec<f64>> = window.iter().map(|s| s.temperature).collect();
+    let unwritten: Option<Vec<f64>> = window.iter().map(|s| s.unwritten.map(|b| b as f64)).collect();
+    latency_spread <= LATENCY_TOLERANCE
+        && temperatures.is_none_or(|t| spread(t) <= TEMPERATURE_TOLERANCE)
+        && unwritten.is_none_or(|u| spread(u) <= WRITEBACK_TOLERANCE_BYTES as f64)
+}
+
+/// Probe every second until the device is stable or `timeout` has passed.
+pub fn wait(file_paths: &[String], block_device: Option<&device::BlockDevice>, seed: u64, timeout: Duration) -> std::io::Result<Outcome> {
+    let mut prober = Prober::open(file_paths, seed)?;
+    let temperature = || block_device.and_then(device::BlockDevice::temperature);
+    println!(
+        "⏳ Waiting for the device to stabilize (up to {}s; probe reads {}{}):",
+        timeout.as_secs(),
+        if prober.direct { "with O_DIRECT" } else { "after evicting each block" },
+        if temperature().is_some() { ", temperature" } else { "" }
+    );
+    let start = Instant::now();
+    let mut window: Vec<Sample> = Vec::with_capacity(WINDOW + 1);
+    loop {
+        let Some(latency) = prober.probe()? else {
+            println!("⚠️  Test files are smaller than one probe read; not waiting");
+            return Ok(Outcome { waited_secs: start.elapsed().as_secs_f64(), stable: false });
+        };
+        let meminfo = procfs::MemInfo::read().ok();
+        let sample = Sample {
+            latency,
+            temperature: temperature(),
+            unwritten: meminfo.and_then(|m| Some(m.get("Dirty")? + m.get("Writeback")?)),
+        };
+        println!(
+            "  [{:>4}s] probe median {}{}{}",
+            start.elapsed().as_secs(),
+            crate::units::format_latency(sample.latency),
+            sample.temperature.map(|t| format!(", {t:.1}°C")).unwrap_or_default(),
+            sample.unwritten.map(|b| format!(", {:.1} MiB dirty or under writeback", b as f64 / 1048576.0)).unwrap_or_default()
+        );
+        window.push(sample);
+        if window.len() > WINDOW {
+            window.remove(0);
+        }
+        if settled(&window) {
+            return Ok(Outcome { waited_secs: start.elapsed().as_secs_f64(), stable: true });
+        }
+        if start.elapsed() >= timeout || crate::status::interrupted() {
+            return Ok(Outcome { waited_secs: start.elapsed().as_secs_f64(), stable: false });
+        }
+        std::thread::sleep(SAMPLE_INTERVAL);
+    }
+}
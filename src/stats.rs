@@ -0,0 +1,272 @@
+//! Latency statistics: exact quantiles of sorted samples, summaries of
+//! histograms, and the summary block printed throughout the report. The module
+//! depends only on `histogram` and `units`, so it can be lifted into other
+//! measurement code as is.
+
+use std::time::Duration;
+
+use crate::histogram::LatencyHistogram;
+use crate::units;
+
+/// Summary of a set of latencies; all zero when there were none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub count: usize,
+    /// Mean, rounded to the nearest nanosecond
+    pub avg: Duration,
+    pub median: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Statistics {
+    /// Summary of a histogram; quantiles carry its ~1% bucket error.
+    pub fn from_histogram(histogram: &LatencyHistogram) -> Self {
+        Statistics {
+            count: histogram.count() as usize,
+            avg: histogram.mean(),
+            median: histogram.quantile(0.50),
+            p90: histogram.quantile(0.90),
+            p95: histogram.quantile(0.95),
+            p99: histogram.quantile(0.99),
+            min: histogram.min(),
+            max: histogram.max(),
+        }
+    }
+}
+
+/// Latencies kept in ascending order, for exact quantiles.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Samples {
+    sorted: Vec<Duration>,
+}
+
+impl Samples {
+    pub fn new(latencies: impl IntoIterator<Item = Duration>) -> Self {
+        let mut sorted: Vec<Duration> = latencies.into_iter().collect();
+        sorted.sort_unstable();
+        Samples { sorted }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    pub fn as_sorted(&self) -> &[Duration] {
+        &self.sorted
+    }
+
+    /// Add `other`'s samples, keeping the order.
+    pub fn merge(&mut self, other: &Samples) {
+        let mut merged = Vec::with_capacity(self.sorted.len() + other.sorted.len());
+        let (mut mine, mut theirs) = (self.sorted.iter().peekable(), other.sorted.iter().peekable());
+        while let (Some(&&a), Some(&&b)) = (mine.peek(), theirs.peek()) {
+            if a <= b {
+                merged.push(a);
+                mine.next();
+            } else {
+                merged.push(b);
+                theirs.next();
+            }
+        }
+        merged.extend(mine);
+        merged.extend(theirs);
+        self.sorted = merged;
+    }
+
+    pub fn mean(&self) -> Duration {
+        mean(&self.sorted)
+    }
+
+    pub fn quantile(&self, q: f64) -> Duration {
+        quantile(&self.sorted, q)
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        if self.is_empty() {
+            return Statistics::default();
+        }
+        let (min, max) = (self.sorted[0], self.sorted[self.len() - 1]);
+        Statistics {
+            count: self.sorted.len(),
+            avg: self.mean(),
+            median: self.quantile(0.50),
+            p90: self.quantile(0.90),
+            p95: self.quantile(0.95),
+            p99: self.quantile(0.99),
+            min,
+            max,
+        }
+    }
+}
+
+/// Quantile `q` (0.0..=1.0) of ascending `sorted`, interpolating linearly
+/// between the two nearest samples (numpy's default); zero when empty.
+pub fn quantile(sorted: &[Duration], q: f64) -> Duration {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return Duration::ZERO;
+    };
+    let position = q.clamp(0.0, 1.0) * last as f64;
+    let below = position.floor() as usize;
+    let Some(&above) = sorted.get(below + 1) else {
+        return sorted[last];
+    };
+    let (low, high) = (sorted[below].as_nanos(), above.as_nanos());
+    let offset = ((high - low) as f64 * (position - below as f64)).round() as u128;
+    from_nanos(low + offset)
+}
+
+/// Mean rounded to the nearest nanosecond; zero when empty.
+pub fn mean(latencies: &[Duration]) -> Duration {
+    if latencies.is_empty() {
+        return Duration::ZERO;
+    }
+    let count = latencies.len() as u128;
+    let sum: u128 = latencies.iter().map(Duration::as_nanos).sum();
+    from_nanos((sum + count / 2) / count)
+}
+
+fn from_nanos(nanos: u128) -> Duration {
+    Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+}
+
+/// Summary of `latencies`, in any order.
+pub fn calculate_statistics(latencies: Vec<&Duration>) -> Statistics {
+    Samples::new(latencies.into_iter().copied()).statistics()
+}
+
+/// Jain's fairness index of `shares`: 1 when all are equal, 1/n when one gets everything.
+pub fn jain_index(shares: &[f64]) -> f64 {
+    let sum: f64 = shares.iter().sum();
+    let squares: f64 = shares.iter().map(|x| x * x).sum();
+    if squares == 0.0 {
+        return 1.0;
+    }
+    sum * sum / (shares.len() as f64 * squares)
+}
+
+pub fn print_statistics(stats: Statistics) {
+    println!("  Count:     {}", stats.count);
+    println!("  Average:   {}", units::format_latency(stats.avg));
+    println!("  Median:    {}", units::format_latency(stats.median));
+    println!("  90th %ile: {}", units::format_latency(stats.p90));
+    println!("  95th %ile: {}", units::format_latency(stats.p95));
+    println!("  99th %ile: {}", units::format_latency(stats.p99));
+    println!("  Min:       {}", units::format_latency(stats.min));
+    println!("  Max:       {}", units::format_latency(stats.max));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ns(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_nanos(v)).collect()
+    }
+
+    #[test]
+    fn empty_statistics_are_zero() {
+        assert_eq!(calculate_statistics(Vec::new()), Statistics::default());
+        assert_eq!(quantile(&[], 0.5), Duration::ZERO);
+        assert_eq!(mean(&[]), Duration::ZERO);
+    }
+
+    #[test]
+    fn single_sample_is_every_quantile() {
+        let stats = Samples::new(ns(&[42])).statistics();
+        assert_eq!(stats.count, 1);
+        for value in [stats.avg, stats.median, stats.p90, stats.p95, stats.p99, stats.min, stats.max] {
+            assert_eq!(value, Duration::from_nanos(42));
+        }
+    }
+
+    #[test]
+    fn quantiles_interpolate_between_samples() {
+        let sorted = ns(&[10, 20, 30, 40]);
+        assert_eq!(quantile(&sorted, 0.0), Duration::from_nanos(10));
+        assert_eq!(quantile(&sorted, 0.5), Duration::from_nanos(25));
+        assert_eq!(quantile(&sorted, 0.9), Duration::from_nanos(37));
+        assert_eq!(quantile(&sorted, 1.0), Duration::from_nanos(40));
+    }
+
+    #[test]
+    fn quantiles_outside_the_range_are_clamped() {
+        let sorted = ns(&[1, 2, 3]);
+        assert_eq!(quantile(&sorted, -1.0), Duration::from_nanos(1));
+        assert_eq!(quantile(&sorted, 2.0), Duration::from_nanos(3));
+    }
+
+    #[test]
+    fn p99_of_a_hundred_samples_is_not_the_maximum() {
+        let stats = Samples::new(ns(&(1..=100).collect::<Vec<_>>())).statistics();
+        assert_eq!(stats.median, Duration::from_nanos(51));
+        assert_eq!(stats.p99, Duration::from_nanos(99));
+        assert_eq!(stats.max, Duration::from_nanos(100));
+    }
+
+    #[test]
+    fn mean_rounds_to_nearest() {
+        assert_eq!(mean(&ns(&[1, 2])), Duration::from_nanos(2));
+        assert_eq!(mean(&ns(&[1, 1, 2])), Duration::from_nanos(1));
+        assert_eq!(mean(&ns(&[3, 4, 4])), Duration::from_nanos(4));
+    }
+
+    #[test]
+    fn mean_does_not_overflow() {
+        let huge = vec![Duration::from_secs(u64::MAX / 2_000_000_000); 4];
+        assert_eq!(mean(&huge), huge[0]);
+    }
+
+    #[test]
+    fn input_order_does_not_matter() {
+        let values = ns(&[5, 1, 4, 2, 3]);
+        let shuffled = calculate_statistics(values.iter().collect());
+        assert_eq!(shuffled, Samples::new(ns(&[1, 2, 3, 4, 5])).statistics());
+        assert_eq!(shuffled.median, Duration::from_nanos(3));
+    }
+
+    #[test]
+    fn merge_matches_the_combined_samples() {
+        let mut merged = Samples::new(ns(&[1, 5, 9]));
+        merged.merge(&Samples::new(ns(&[2, 5, 10, 11])));
+        assert_eq!(merged, Samples::new(ns(&[1, 2, 5, 5, 9, 10, 11])));
+        merged.merge(&Samples::default());
+        assert_eq!(merged.len(), 7);
+    }
+
+    #[test]
+    fn jain_index_spans_one_over_n_to_one() {
+        assert_eq!(jain_index(&[3.0, 3.0, 3.0, 3.0]), 1.0);
+        assert_eq!(jain_index(&[0.0, 0.0, 8.0, 0.0]), 0.25);
+        assert!((jain_index(&[1.0, 2.0]) - 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn histogram_mean_rounds_like_the_exact_mean() {
+        for values in [ns(&[1, 2]), ns(&[1, 1, 2]), ns(&[3, 4, 4]), ns(&[10, 11, 11, 11])] {
+            let mut histogram = LatencyHistogram::default();
+            values.iter().for_each(|&v| histogram.record(v));
+            assert_eq!(histogram.mean(), mean(&values), "{values:?}");
+        }
+    }
+
+    #[test]
+    fn histogram_summary_is_close_to_the_exact_one() {
+        let values: Vec<Duration> = (1..=10_000).map(|v| Duration::from_nanos(v * 37)).collect();
+        let mut histogram = LatencyHistogram::default();
+        values.iter().for_each(|&v| histogram.record(v));
+        let (exact, approximate) = (Samples::new(values).statistics(), Statistics::from_histogram(&histogram));
+        assert_eq!(approximate.count, exact.count);
+        assert_eq!((approximate.min, approximate.max), (exact.min, exact.max));
+        for (a, e) in [(approximate.median, exact.median), (approximate.p99, exact.p99)] {
+            assert!((a.as_secs_f64() / e.as_secs_f64() - 1.0).abs() < 0.01, "{a:?} vs {e:?}");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Exit codes and the final machine-readable status line.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How a run ended; each outcome has its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    /// The run failed for a reason other than the ones below
+    Error,
+    /// Invalid options or a mismatched checkpoint; nothing was measured
+    ConfigError,
+    /// More reads failed than `--max-read-errors` allows, or all of them
+    IoErrors,
+    /// The run completed but missed `--sla-p99-us`
+    SlaFailed,
+    /// Stopped by SIGINT or SIGTERM; results cover the reads done so far
+    Interrupted,
+}
+
+impl Status {
+    pub fn code(self) -> i32 {
+        match self {
+            Status::Success => 0,
+            Status::Error => 1,
+            Status::ConfigError => 2,
+            Status::IoErrors => 3,
+            Status::SlaFailed => 4,
+            Status::Interrupted => 130,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Success => "ok",
+            Status::Error => "error",
+            Status::ConfigError => "config_error",
+            Status::IoErrors => "io_errors",
+            Status::SlaFailed => "sla_failed",
+            Status::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// The status of a finished run with `key=value` details for the status line.
+pub struct Outcome {
+    pub status: Status,
+    pub details: Vec<(&'static str, String)>,
+}
+
+impl Outcome {
+    pub fn success() -> Self {
+        Outcome {
+            status: Status::Success,
+            details: Vec::new(),
+        }
+    }
+
+    /// Print `status=… exit=… key=value…` as the last line on stderr.
+    pub fn emit(&self) {
+        let mut line = format!("status={} exit={}", self.status.name(), self.status.code());
+        for (key, value) in &self.details {
+            line.push_str(&format!(" {key}={value}"));
+        }
+        eprintln!("{line}");
+    }
+}
+
+/// An error in the options, reported with exit code 2.
+#[derive(Debug)]
+pub struct ConfigError(pub String);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Make SIGINT and SIGTERM stop the readers instead of killing the process, so
+/// the reads done so far are reported and the test files removed.
+pub fn install_interrupt_handler() {
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// Whether SIGINT or SIGTERM has been received.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+static REPORT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_hangup(_: libc::c_int) {
+    REPORT_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Make SIGHUP close the current `--soak` interval right away, like the
+/// control socket's `report`.
+pub fn install_report_handler() {
+    let handler = on_hangup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGHUP, handler);
+    }
+}
+
+/// Ask for the current soak interval to be closed now.
+pub fn request_report() {
+    REPORT_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Whether a report was requested since the last call.
+pub fn take_report_request() -> bool {
+    REPORT_REQUESTED.swap(false, Ordering::Relaxed)
+}
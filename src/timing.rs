@@ -0,0 +1,157 @@
+//! Clock sources for latency measurement and calibration of the timing harness.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+const CALIBRATION_ROUNDS: usize = 100_000;
+
+/// How long the TSC is compared against the monotonic clock to derive its rate.
+const TSC_CALIBRATION_TIME: Duration = Duration::from_millis(50);
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// clock_gettime(CLOCK_MONOTONIC) via std::time::Instant
+    Monotonic,
+    /// x86 time-stamp counter (rdtsc), calibrated against the monotonic clock
+    Tsc,
+}
+
+impl std::fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClockSource::Monotonic => write!(f, "monotonic"),
+            ClockSource::Tsc => write!(f, "tsc"),
+        }
+    }
+}
+
+/// A clock producing raw tick values that can be converted into durations.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    source: ClockSource,
+    epoch: Instant,
+    ns_per_tick: f64,
+}
+
+impl Clock {
+    /// Create the requested clock, falling back to the monotonic clock where
+    /// the TSC is not available.
+    pub fn new(requested: ClockSource) -> Self {
+        let epoch = Instant::now();
+        match requested {
+            ClockSource::Tsc if tsc_available() => Clock {
+                source: ClockSource::Tsc,
+                epoch,
+                ns_per_tick: calibrate_tsc(),
+            },
+            _ => Clock {
+                source: ClockSource::Monotonic,
+                epoch,
+                ns_per_tick: 1.0,
+            },
+        }
+    }
+
+    /// The clock that is actually in use.
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    pub fn ns_per_tick(&self) -> f64 {
+        self.ns_per_tick
+    }
+
+    #[inline]
+    pub fn now(&self) -> u64 {
+        match self.source {
+            ClockSource::Tsc => read_tsc(),
+            ClockSource::Monotonic => self.epoch.elapsed().as_nanos() as u64,
+        }
+    }
+
+    /// Convert a tick difference into a duration.
+    #[inline]
+    pub fn ticks_to_duration(&self, ticks: u64) -> Duration {
+        Duration::from_nanos((ticks as f64 * self.ns_per_tick) as u64)
+    }
+
+    /// Duration between two readings of this clock.
+    #[inline]
+    pub fn between(&self, start: u64, end: u64) -> Duration {
+        self.ticks_to_duration(end.saturating_sub(start))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn tsc_available() -> bool {
+    // Without an invariant TSC the tick rate changes with frequency scaling
+    std::fs::read_to_string("/proc/cpuinfo")
+        .map(|info| info.contains(" constant_tsc") && info.contains(" nonstop_tsc"))
+        .unwrap_or(true)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn tsc_available() -> bool {
+    false
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn read_tsc() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[inline]
+fn read_tsc() -> u64 {
+    unreachable!("the TSC clock is only selected on x86_64")
+}
+
+fn calibrate_tsc() -> f64 {
+    let start = Instant::now();
+    let start_ticks = read_tsc();
+    while start.elapsed() < TSC_CALIBRATION_TIME {
+        std::hint::spin_loop();
+    }
+    let end_ticks = read_tsc();
+    let elapsed = start.elapsed();
+    elapsed.as_nanos() as f64 / end_ticks.saturating_sub(start_ticks).max(1) as f64
+}
+
+/// Cost of the measurement machinery, as seen by the reader threads.
+#[derive(Debug, Clone, Copy)]
+pub struct Calibration {
+    /// Median gap between two back-to-back clock reads
+    pub clock_overhead: Duration,
+    /// Median latency recorded for an operation that does nothing
+    pub empty_op: Duration,
+}
+
+/// Time back-to-back clock reads and empty timed operations.
+pub fn calibrate(clock: &Clock) -> Calibration {
+    let mut clock_gaps = Vec::with_capacity(CALIBRATION_ROUNDS);
+    for _ in 0..CALIBRATION_ROUNDS {
+        let a = clock.now();
+        let b = clock.now();
+        clock_gaps.push(clock.between(a, b));
+    }
+
+    let mut empty_ops = Vec::with_capacity(CALIBRATION_ROUNDS);
+    for _ in 0..CALIBRATION_ROUNDS {
+        let start = clock.now();
+        black_box(());
+        empty_ops.push(clock.between(start, clock.now()));
+    }
+
+    Calibration {
+        clock_overhead: median(&mut clock_gaps),
+        empty_op: median(&mut empty_ops),
+    }
+}
+
+fn median(samples: &mut [Duration]) -> Duration {
+    samples.sort_unstable();
+    samples[samples.len() / 2]
+}
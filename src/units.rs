@@ -0,0 +1,44 @@
+//! Latencies printed in the unit chosen with `--time-unit`.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// Whole nanoseconds
+    Ns,
+    /// Microseconds with three decimals
+    Us,
+    /// Milliseconds with three decimals
+    Ms,
+    /// The largest unit in which the value is at least 1
+    Auto,
+}
+
+static TIME_UNIT: OnceLock<TimeUnit> = OnceLock::new();
+
+/// Choose the unit for the rest of the process; only the first call counts.
+pub fn set_time_unit(unit: TimeUnit) {
+    let _ = TIME_UNIT.set(unit);
+}
+
+/// `latency` in the chosen unit, with its suffix.
+pub fn format_latency(latency: Duration) -> String {
+    let ns = latency.as_nanos();
+    match TIME_UNIT.get().copied().unwrap_or(TimeUnit::Auto) {
+        TimeUnit::Ns => format!("{ns}ns"),
+        TimeUnit::Us => format!("{:.3}μs", ns as f64 / 1e3),
+        TimeUnit::Ms => format!("{:.3}ms", ns as f64 / 1e6),
+        TimeUnit::Auto if ns < 1_000 => format!("{ns}ns"),
+        TimeUnit::Auto if ns < 1_000_000 => format!("{:.2}μs", ns as f64 / 1e3),
+        TimeUnit::Auto if ns < 1_000_000_000 => format!("{:.2}ms", ns as f64 / 1e6),
+        TimeUnit::Auto => format!("{:.3}s", latency.as_secs_f64()),
+    }
+}
+
+/// Like [`format_latency`] for a nanosecond count.
+pub fn format_ns(ns: f64) -> String {
+    format_latency(Duration::from_nanos(ns.max(0.0) as u64))
+}
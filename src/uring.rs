@@ -0,0 +1,265 @@
+//! A minimal io_uring ring over raw syscalls: enough to queue reads and writes and reap completions.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x800_0000;
+const IORING_OFF_SQES: libc::off_t = 0x1000_0000;
+const IORING_ENTER_GETEVENTS: u32 = 1;
+const IORING_OP_READ: u8 = 22;
+const IORING_OP_WRITE: u8 = 23;
+/// Busy-poll for completions instead of waiting for interrupts; needs O_DIRECT files
+pub const IORING_SETUP_IOPOLL: u32 = 1;
+
+#[repr(C)]
+#[derive(Default)]
+struct SqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Sqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    rw_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+#[repr(C)]
+struct Cqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+/// A shared memory region mapped from the ring fd.
+struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mapping {
+    fn new(fd: RawFd, len: usize, offset: libc::off_t) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd,
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping { ptr: ptr.cast(), len })
+    }
+
+    fn at<T>(&self, offset: u32) -> *mut T {
+        unsafe { self.ptr.add(offset as usize).cast() }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.cast(), self.len) };
+    }
+}
+
+/// One io_uring instance, used by a single thread.
+pub struct Ring {
+    // Mappings are declared before the fd so they are unmapped before it is closed
+    sq_ring: Mapping,
+    cq_ring: Mapping,
+    sqes: Mapping,
+    fd: OwnedFd,
+    sq_mask: u32,
+    sq_entries: u32,
+    cq_mask: u32,
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+    /// Entries written to the SQ but not yet handed to the kernel
+    pending: u32,
+}
+
+// The raw pointers refer to memory owned by the ring, which moves with it
+unsafe impl Send for Ring {}
+
+impl Ring {
+    pub fn new(entries: u32, flags: u32) -> io::Result<Self> {
+        let mut params = Params {
+            flags,
+            ..Params::default()
+        };
+        let fd = unsafe { libc::syscall(libc::SYS_io_uring_setup, entries, &mut params as *mut Params) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+        let raw = fd.as_raw_fd();
+
+        let sq_ring = Mapping::new(raw, params.sq_off.array as usize + params.sq_entries as usize * 4, IORING_OFF_SQ_RING)?;
+        let cq_ring = Mapping::new(
+            raw,
+            params.cq_off.cqes as usize + params.cq_entries as usize * std::mem::size_of::<Cqe>(),
+            IORING_OFF_CQ_RING,
+        )?;
+        let sqes = Mapping::new(raw, params.sq_entries as usize * std::mem::size_of::<Sqe>(), IORING_OFF_SQES)?;
+
+        let sq_mask = unsafe { *sq_ring.at::<u32>(params.sq_off.ring_mask) };
+        let cq_mask = unsafe { *cq_ring.at::<u32>(params.cq_off.ring_mask) };
+        Ok(Ring {
+            sq_ring,
+            cq_ring,
+            sqes,
+            fd,
+            sq_mask,
+            sq_entries: params.sq_entries,
+            cq_mask,
+            sq_off: params.sq_off,
+            cq_off: params.cq_off,
+            pending: 0,
+        })
+    }
+
+    fn atomic(mapping: &Mapping, offset: u32) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(mapping.at::<u32>(offset)) }
+    }
+
+    /// Queue a read of `len` bytes at `offset` of `fd` into `buf`.
+    ///
+    /// # Safety
+    /// `buf` must stay valid and unaliased until the completion for `user_data` is reaped.
+    pub unsafe fn push_read(&mut self, fd: RawFd, buf: *mut u8, len: u32, offset: u64, user_data: u64) -> io::Result<()> {
+        unsafe { self.push(IORING_OP_READ, fd, buf, len, offset, user_data) }
+    }
+
+    /// Queue a write of `len` bytes from `buf` at `offset` of `fd`.
+    ///
+    /// # Safety
+    /// `buf` must stay valid and unmodified until the completion for `user_data` is reaped.
+    pub unsafe fn push_write(&mut self, fd: RawFd, buf: *const u8, len: u32, offset: u64, user_data: u64) -> io::Result<()> {
+        unsafe { self.push(IORING_OP_WRITE, fd, buf.cast_mut(), len, offset, user_data) }
+    }
+
+    unsafe fn push(&mut self, opcode: u8, fd: RawFd, buf: *mut u8, len: u32, offset: u64, user_data: u64) -> io::Result<()> {
+        let head = Self::atomic(&self.sq_ring, self.sq_off.head).load(Ordering::Acquire);
+        let tail = Self::atomic(&self.sq_ring, self.sq_off.tail).load(Ordering::Relaxed);
+        if tail.wrapping_sub(head) >= self.sq_entries {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "submission queue full"));
+        }
+        let index = tail & self.sq_mask;
+        unsafe {
+            self.sqes.at::<Sqe>(index * std::mem::size_of::<Sqe>() as u32).write(Sqe {
+                opcode,
+                fd,
+                off: offset,
+                addr: buf as u64,
+                len,
+                user_data,
+                ..Sqe::default()
+            });
+            *self.sq_ring.at::<u32>(self.sq_off.array + index * 4) = index;
+        }
+        Self::atomic(&self.sq_ring, self.sq_off.tail).store(tail.wrapping_add(1), Ordering::Release);
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Hand queued entries to the kernel and wait until `min_complete` completions are available.
+    pub fn submit_and_wait(&mut self, min_complete: u32) -> io::Result<u32> {
+        let flags = if min_complete > 0 { IORING_ENTER_GETEVENTS } else { 0 };
+        let submitted = loop {
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.fd.as_raw_fd(),
+                    self.pending,
+                    min_complete,
+                    flags,
+                    std::ptr::null::<libc::sigset_t>(),
+                    0usize,
+                )
+            };
+            if ret >= 0 {
+                break ret as u32;
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        };
+        self.pending -= submitted.min(self.pending);
+        Ok(submitted)
+    }
+
+    /// Pop all available completions as `(user_data, result)`.
+    pub fn reap(&mut self, mut on_complete: impl FnMut(u64, io::Result<u32>)) {
+        let head_atomic = Self::atomic(&self.cq_ring, self.cq_off.head);
+        let mut head = head_atomic.load(Ordering::Relaxed);
+        let tail = Self::atomic(&self.cq_ring, self.cq_off.tail).load(Ordering::Acquire);
+        while head != tail {
+            let index = head & self.cq_mask;
+            let cqe = unsafe { self.cq_ring.at::<Cqe>(self.cq_off.cqes + index * std::mem::size_of::<Cqe>() as u32).read() };
+            let result = if cqe.res < 0 {
+                Err(io::Error::from_raw_os_error(-cqe.res))
+            } else {
+                Ok(cqe.res as u32)
+            };
+            on_complete(cqe.user_data, result);
+            head = head.wrapping_add(1);
+        }
+        head_atomic.store(head, Ordering::Release);
+    }
+}
@@ -0,0 +1,89 @@
+//! `--verify`: check that reads return what the test files hold, the creation
+//! fill or, where the workload writes, the write pattern. The data the measured
+//! read left in memory is checked once its latency is taken, so checking stays
+//! out of the latency; `--verify-sample` checks only a seeded share of the reads.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::engine::IoEngine;
+use crate::rng::splitmix64;
+use crate::workload::{OpKind, Operation};
+
+/// Parse a share of reads like `1%` or `0.01`.
+pub fn parse_sample(spec: &str) -> Result<f64, String> {
+    let fraction = match spec.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => spec.trim().parse::<f64>(),
+    }
+    .map_err(|_| format!("'{spec}': expected a percentage such as 1% or a fraction such as 0.01"))?;
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err(format!("'{spec}': the sample must be above 0% and at most 100%"));
+    }
+    Ok(fraction)
+}
+
+/// Checks the contents of a sample of the reads; shared by all reader threads.
+pub struct Verifier {
+    engine: Arc<dyn IoEngine>,
+    /// Bytes the files may hold
+    expected: Vec<u8>,
+    sample: f64,
+    seed: u64,
+    reads: AtomicU64,
+    checked: AtomicU64,
+    mismatched: AtomicU64,
+    first_mismatch: Mutex<Option<String>>,
+}
+
+impl Verifier {
+    pub fn new(engine: Arc<dyn IoEngine>, expected: Vec<u8>, sample: f64, seed: u64) -> Self {
+        Verifier {
+            engine,
+            expected,
+            sample,
+            seed,
+            reads: AtomicU64::new(0),
+            checked: AtomicU64::new(0),
+            mismatched: AtomicU64::new(0),
+            first_mismatch: Mutex::default(),
+        }
+    }
+
+    /// Check `op` if it is a read drawn into the sample.
+    pub fn after(&self, op: &Operation) {
+        if op.kind != OpKind::Read {
+            return;
+        }
+        let mut state = self.seed ^ self.reads.fetch_add(1, Ordering::Relaxed);
+        if splitmix64(&mut state) as f64 >= self.sample * u64::MAX as f64 {
+            return;
+        }
+        self.engine.last_read(op, &mut |data| {
+            self.checked.fetch_add(1, Ordering::Relaxed);
+            if let Some(at) = data.iter().position(|byte| !self.expected.contains(byte)) {
+                self.mismatched.fetch_add(1, Ordering::Relaxed);
+                self.first_mismatch.lock().unwrap().get_or_insert_with(|| format!("file {} offset {}: byte {:#04x}", op.file, op.offset + at as u64, data[at]));
+            }
+        });
+    }
+
+    /// Print what was checked and return the numbers of checked and mismatched reads.
+    pub fn finish(&self) -> (u64, u64) {
+        let reads = self.reads.load(Ordering::Relaxed);
+        let checked = self.checked.load(Ordering::Relaxed);
+        let mismatched = self.mismatched.load(Ordering::Relaxed);
+        println!(
+            "\n🔎 Verified {checked} of {reads} reads ({:.1}%): {}",
+            100.0 * checked as f64 / reads.max(1) as f64,
+            if mismatched == 0 { "all as expected".to_string() } else { format!("{mismatched} with unexpected contents") }
+        );
+        if let Some(first) = self.first_mismatch.lock().unwrap().as_ref() {
+            println!("  ❌ First mismatch at {first}");
+        }
+        if checked == 0 {
+            println!("  ❌ No read was checked; raise --verify-sample or run more operations");
+        }
+        (checked, mismatched)
+    }
+}
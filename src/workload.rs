@@ -0,0 +1,516 @@
+//! Pluggable generators of read operations, from WASM modules or scripts.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Kind of I/O a generator asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpKind {
+    Read,
+    Write,
+}
+
+/// One operation requested by a generator.
+#[derive(Debug, Clone, Copy)]
+pub struct Operation {
+    pub kind: OpKind,
+    pub file: usize,
+    pub offset: u64,
+    pub len: u64,
+    /// Index of the operation's class in the --job-file
+    pub tag: Option<u16>,
+    /// When the operation should start under `--open-loop`, relative to the start of the measured phase
+    pub intended: Option<Duration>,
+}
+
+impl Operation {
+    /// Reject operations outside the test files.
+    pub fn check(&self, num_files: usize, file_size: u64) -> Result<(), String> {
+        if self.file >= num_files {
+            return Err(format!("file {} out of range (have {num_files})", self.file));
+        }
+        if self.len == 0 || self.offset.checked_add(self.len).is_none_or(|end| end > file_size) {
+            return Err(format!("range {}+{} outside file of {file_size} bytes", self.offset, self.len));
+        }
+        Ok(())
+    }
+}
+
+/// An fio-style `--rw` operation mix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RwMix {
+    pub pattern: crate::distribution::AccessPattern,
+    pub write_percent: u8,
+}
+
+impl RwMix {
+    /// Parse `read`, `write`, `rw[NN]`, `randread`, `randwrite` or `randrw[NN]`, where
+    /// `NN` is the percentage of reads (default 50), as in fio's `rwmixread`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        use crate::distribution::AccessPattern;
+
+        let (pattern, mix) = match spec.strip_prefix("rand") {
+            Some(rest) => (AccessPattern::Random, rest),
+            None => (AccessPattern::Stride(1), spec),
+        };
+        let write_percent = match mix {
+            "read" => 0,
+            "write" => 100,
+            _ => {
+                let read_percent = mix
+                    .strip_prefix("rw")
+                    .or_else(|| mix.strip_prefix("readwrite"))
+                    .map(|n| if n.is_empty() { Ok(50) } else { n.parse::<u8>() })
+                    .and_then(Result::ok)
+                    .filter(|&n| n <= 100)
+                    .ok_or_else(|| {
+                        format!("unknown mix '{spec}' (expected read, write, rw[NN], randread, randwrite or randrw[NN] with NN% reads)")
+                    })?;
+                100 - read_percent
+            }
+        };
+        Ok(RwMix { pattern, write_percent })
+    }
+}
+
+/// Lengths of `--workload scan` operations, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanLength {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl ScanLength {
+    /// Parse a size such as `4MB` or a range such as `1MB..16MB`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (min, max) = spec.split_once("..").unwrap_or((spec, spec));
+        let (min, max) = (parse_size(min)?, parse_size(max)?);
+        if min == 0 || min > max {
+            return Err(format!("'{spec}': expected a positive length or a range min..max with min <= max"));
+        }
+        Ok(ScanLength { min, max })
+    }
+
+    /// Draw a length uniformly from the range, rounded down to whole blocks (at least one).
+    pub fn sample(&self, rng: &mut impl rand::Rng, block_size: u64) -> u64 {
+        let len = rng.random_range(self.min..=self.max);
+        (len - len % block_size).max(block_size)
+    }
+}
+
+impl std::fmt::Display for ScanLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.min == self.max {
+            write!(f, "{} bytes", self.min)
+        } else {
+            write!(f, "{}..{} bytes", self.min, self.max)
+        }
+    }
+}
+
+/// Sizes of the test files when they differ (`--file-sizes`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileSizes {
+    /// Sizes assigned to the files in turn, repeating the list as needed
+    List(Vec<u64>),
+    /// Sizes drawn uniformly from a range, reproducibly from the seed
+    Uniform { min: u64, max: u64 },
+}
+
+impl FileSizes {
+    /// Parse a list such as `64M,256M,1G` or a range `uniform:64M..1G`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if let Some(range) = spec.strip_prefix("uniform:") {
+            let (min, max) = range
+                .split_once("..")
+                .ok_or_else(|| format!("'{spec}': expected uniform:<min>..<max>"))?;
+            let (min, max) = (parse_size(min)?, parse_size(max)?);
+            if min == 0 || min > max {
+                return Err(format!("'{spec}': expected 0 < min <= max"));
+            }
+            return Ok(FileSizes::Uniform { min, max });
+        }
+        let sizes = spec.split(',').map(parse_size).collect::<Result<Vec<_>, _>>()?;
+        if sizes.contains(&0) {
+            return Err(format!("'{spec}': file sizes must be positive"));
+        }
+        Ok(FileSizes::List(sizes))
+    }
+
+    /// The size of each of `num_files` files. Drawn sizes are whole blocks.
+    pub fn resolve(&self, num_files: usize, seed: u64, block_size: u64) -> Vec<u64> {
+        match self {
+            FileSizes::List(sizes) => sizes.iter().copied().cycle().take(num_files).collect(),
+            FileSizes::Uniform { min, max } => {
+                use rand::{Rng, SeedableRng};
+                let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+                (0..num_files)
+                    .map(|_| {
+                        let size = rng.random_range(*min..=*max);
+                        (size - size % block_size).max(block_size)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FileSizes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileSizes::List(sizes) => {
+                let sizes: Vec<String> = sizes.iter().map(u64::to_string).collect();
+                write!(f, "{} bytes in turn", sizes.join(", "))
+            }
+            FileSizes::Uniform { min, max } => write!(f, "uniform {min}..{max} bytes"),
+        }
+    }
+}
+
+/// Parse a byte count with an optional `K`, `M`, `G` or `T` suffix (powers of 1024; `KB`, `KiB` also accepted).
+pub fn parse_size(spec: &str) -> Result<u64, String> {
+    let spec = spec.trim();
+    let digits = spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len());
+    let (number, suffix) = spec.split_at(digits);
+    let number: u64 = number.parse().map_err(|_| format!("'{spec}' is not a size"))?;
+    let shift = match suffix.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        "T" | "TB" | "TIB" => 40,
+        other => return Err(format!("'{spec}': unknown size suffix '{other}'")),
+    };
+    number.checked_mul(1 << shift).ok_or_else(|| format!("'{spec}' is too large"))
+}
+
+/// Produces one thread's stream of operations.
+pub trait WorkloadGenerator: Send {
+    /// Next operation, or `None` when the generator has no more work.
+    fn next_op(&mut self) -> Result<Option<Operation>, String>;
+}
+
+/// Creates a generator for every reader thread.
+pub trait WorkloadSource: Send + Sync {
+    fn generator(&self, thread_id: usize, seed: u64) -> Result<Box<dyn WorkloadGenerator>, String>;
+}
+
+/// Load a workload plugin; needs the `wasm-plugins` feature.
+pub fn load_plugin(path: &Path, num_files: usize, file_size: u64) -> Result<Arc<dyn WorkloadSource>, Box<dyn std::error::Error>> {
+    #[cfg(feature = "wasm-plugins")]
+    {
+        Ok(Arc::new(wasm::WasmWorkload::load(path, num_files, file_size)?))
+    }
+    #[cfg(not(feature = "wasm-plugins"))]
+    {
+        let _ = (num_files, file_size);
+        Err(format!(
+            "cannot load {}: built without WASM support (rebuild with --features wasm-plugins)",
+            path.display()
+        )
+        .into())
+    }
+}
+
+/// Load a Rhai script computing offsets; needs the `scripting` feature.
+pub fn load_script(path: &Path, num_files: usize, file_size: u64, block_size: u64) -> Result<Arc<dyn WorkloadSource>, Box<dyn std::error::Error>> {
+    #[cfg(feature = "scripting")]
+    {
+        Ok(Arc::new(script::ScriptWorkload::load(path, num_files, file_size, block_size)?))
+    }
+    #[cfg(not(feature = "scripting"))]
+    {
+        let _ = (num_files, file_size, block_size);
+        Err(format!(
+            "cannot load {}: built without scripting support (rebuild with --features scripting)",
+            path.display()
+        )
+        .into())
+    }
+}
+
+/// WASM modules as workload generators.
+///
+/// A module exports `next_op() -> i32`, returning 0 for a read or -1 when done,
+/// and the getters `op_file() -> i32`, `op_offset() -> i64`, `op_len() -> i64`
+/// describing the operation just returned. An optional
+/// `init(thread_id: i32, seed: i64, num_files: i32, file_size: i64)` runs once
+/// per thread. Every thread gets its own instance; modules may not import anything.
+#[cfg(feature = "wasm-plugins")]
+mod wasm {
+    use std::path::Path;
+
+    use wasmtime::{Engine, Instance, Module, Store, TypedFunc};
+
+    use super::{OpKind, Operation, WorkloadGenerator, WorkloadSource};
+
+    pub struct WasmWorkload {
+        engine: Engine,
+        module: Module,
+        num_files: usize,
+        file_size: u64,
+    }
+
+    impl WasmWorkload {
+        pub fn load(path: &Path, num_files: usize, file_size: u64) -> Result<Self, Box<dyn std::error::Error>> {
+            let engine = Engine::default();
+            let module = Module::from_file(&engine, path).map_err(|e| format!("cannot load {}: {e}", path.display()))?;
+            Ok(WasmWorkload {
+                engine,
+                module,
+                num_files,
+                file_size,
+            })
+        }
+    }
+
+    impl WorkloadSource for WasmWorkload {
+        fn generator(&self, thread_id: usize, seed: u64) -> Result<Box<dyn WorkloadGenerator>, String> {
+            let mut store = Store::new(&self.engine, ());
+            let instance = Instance::new(&mut store, &self.module, &[]).map_err(|e| e.to_string())?;
+
+            if let Ok(init) = instance.get_typed_func::<(i32, i64, i32, i64), ()>(&mut store, "init") {
+                init.call(&mut store, (thread_id as i32, seed as i64, self.num_files as i32, self.file_size as i64))
+                    .map_err(|e| format!("init: {e}"))?;
+            }
+
+            let next_op = instance
+                .get_typed_func::<(), i32>(&mut store, "next_op")
+                .map_err(|e| format!("next_op: {e}"))?;
+            let op_file = instance
+                .get_typed_func::<(), i32>(&mut store, "op_file")
+                .map_err(|e| format!("op_file: {e}"))?;
+            let op_offset = instance
+                .get_typed_func::<(), i64>(&mut store, "op_offset")
+                .map_err(|e| format!("op_offset: {e}"))?;
+            let op_len = instance
+                .get_typed_func::<(), i64>(&mut store, "op_len")
+                .map_err(|e| format!("op_len: {e}"))?;
+
+            Ok(Box::new(WasmGenerator {
+                store,
+                next_op,
+                op_file,
+                op_offset,
+                op_len,
+            }))
+        }
+    }
+
+    struct WasmGenerator {
+        store: Store<()>,
+        next_op: TypedFunc<(), i32>,
+        op_file: TypedFunc<(), i32>,
+        op_offset: TypedFunc<(), i64>,
+        op_len: TypedFunc<(), i64>,
+    }
+
+    impl WorkloadGenerator for WasmGenerator {
+        fn next_op(&mut self) -> Result<Option<Operation>, String> {
+            let kind = match self.next_op.call(&mut self.store, ()).map_err(|e| e.to_string())? {
+                -1 => return Ok(None),
+                0 => OpKind::Read,
+                other => return Err(format!("unknown operation kind {other}")),
+            };
+            let file = self.op_file.call(&mut self.store, ()).map_err(|e| e.to_string())?;
+            let offset = self.op_offset.call(&mut self.store, ()).map_err(|e| e.to_string())?;
+            let len = self.op_len.call(&mut self.store, ()).map_err(|e| e.to_string())?;
+            if file < 0 || offset < 0 || len < 0 {
+                return Err(format!("negative operation field (file {file}, offset {offset}, len {len})"));
+            }
+            Ok(Some(Operation {
+                kind,
+                file: file as usize,
+                offset: offset as u64,
+                len: len as u64,
+                tag: None,
+                intended: None,
+            }))
+        }
+    }
+}
+
+/// Rhai scripts as workload generators.
+///
+/// A script defines `fn next_offset(op_index, thread_id)` returning a byte
+/// position within the whole dataset (file `pos / file_size`, offset
+/// `pos % file_size`), or a negative number when the thread is done. Every read
+/// is `block_size` bytes. Scripts can call `num_files()`, `file_size()` and
+/// `block_size()`, and draw from the thread's seeded RNG with `rand_int(lo, hi)`
+/// (inclusive bounds) and `rand_float()`.
+#[cfg(feature = "scripting")]
+mod script {
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use rhai::{AST, Engine, Scope};
+
+    use super::{OpKind, Operation, WorkloadGenerator, WorkloadSource};
+
+    pub struct ScriptWorkload {
+        ast: AST,
+        num_files: usize,
+        file_size: u64,
+        block_size: u64,
+    }
+
+    impl ScriptWorkload {
+        pub fn load(path: &Path, num_files: usize, file_size: u64, block_size: u64) -> Result<Self, Box<dyn std::error::Error>> {
+            let ast = Engine::new()
+                .compile_file(path.to_path_buf())
+                .map_err(|e| format!("cannot load {}: {e}", path.display()))?;
+            if !ast.iter_functions().any(|f| f.name == "next_offset" && f.params.len() == 2) {
+                return Err(format!("{} does not define next_offset(op_index, thread_id)", path.display()).into());
+            }
+            Ok(ScriptWorkload {
+                ast,
+                num_files,
+                file_size,
+                block_size,
+            })
+        }
+    }
+
+    impl WorkloadSource for ScriptWorkload {
+        fn generator(&self, thread_id: usize, seed: u64) -> Result<Box<dyn WorkloadGenerator>, String> {
+            let rng = Arc::new(Mutex::new(StdRng::seed_from_u64(seed)));
+            let mut engine = Engine::new();
+            let int_rng = Arc::clone(&rng);
+            engine.register_fn("rand_int", move |lo: i64, hi: i64| -> i64 {
+                if hi < lo { lo } else { int_rng.lock().unwrap().random_range(lo..=hi) }
+            });
+            let float_rng = Arc::clone(&rng);
+            engine.register_fn("rand_float", move || -> f64 { float_rng.lock().unwrap().random() });
+
+            let (num_files, file_size, block_size) = (self.num_files as i64, self.file_size as i64, self.block_size as i64);
+            engine.register_fn("num_files", move || num_files);
+            engine.register_fn("file_size", move || file_size);
+            engine.register_fn("block_size", move || block_size);
+
+            Ok(Box::new(ScriptGenerator {
+                engine,
+                ast: self.ast.clone(),
+                scope: Scope::new(),
+                thread_id: thread_id as i64,
+                op_index: 0,
+                file_size: self.file_size,
+                block_size: self.block_size,
+            }))
+        }
+    }
+
+    struct ScriptGenerator {
+        engine: Engine,
+        ast: AST,
+        scope: Scope<'static>,
+        thread_id: i64,
+        op_index: i64,
+        file_size: u64,
+        block_size: u64,
+    }
+
+    impl WorkloadGenerator for ScriptGenerator {
+        fn next_op(&mut self) -> Result<Option<Operation>, String> {
+            let position: i64 = self
+                .engine
+                .call_fn(&mut self.scope, &self.ast, "next_offset", (self.op_index, self.thread_id))
+                .map_err(|e| e.to_string())?;
+            self.op_index += 1;
+            if position < 0 {
+                return Ok(None);
+            }
+            let position = position as u64;
+            Ok(Some(Operation {
+                kind: OpKind::Read,
+                file: (position / self.file_size) as usize,
+                offset: position % self.file_size,
+                len: self.block_size,
+                tag: None,
+                intended: None,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::AccessPattern;
+
+    #[test]
+    fn sizes_take_binary_suffixes() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size(" 64 kib "), Ok(64 << 10));
+        assert_eq!(parse_size("16MB"), Ok(16 << 20));
+        assert_eq!(parse_size("2G"), Ok(2 << 30));
+        assert_eq!(parse_size("3TiB"), Ok(3 << 40));
+        assert_eq!(parse_size("0"), Ok(0));
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected() {
+        for spec in ["", "K", "4X", "4KX", "-1", "1.5G", "99999999999999999999"] {
+            assert!(parse_size(spec).is_err(), "{spec}");
+        }
+        assert_eq!(parse_size("16777215T"), Ok(16_777_215 << 40));
+        assert!(parse_size("16777216T").unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn rw_mixes_map_to_pattern_and_write_percent() {
+        let mix = |spec| RwMix::parse(spec).map(|m| (m.pattern, m.write_percent));
+        assert_eq!(mix("read"), Ok((AccessPattern::Stride(1), 0)));
+        assert_eq!(mix("write"), Ok((AccessPattern::Stride(1), 100)));
+        assert_eq!(mix("rw"), Ok((AccessPattern::Stride(1), 50)));
+        assert_eq!(mix("readwrite"), Ok((AccessPattern::Stride(1), 50)));
+        assert_eq!(mix("randread"), Ok((AccessPattern::Random, 0)));
+        assert_eq!(mix("randwrite"), Ok((AccessPattern::Random, 100)));
+        assert_eq!(mix("randrw70"), Ok((AccessPattern::Random, 30)));
+        assert_eq!(mix("randrw0"), Ok((AccessPattern::Random, 100)));
+        assert_eq!(mix("rw100"), Ok((AccessPattern::Stride(1), 0)));
+        for spec in ["", "rand", "randrw101", "rw-1", "rwx", "trim"] {
+            assert!(RwMix::parse(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn scan_lengths_are_a_size_or_a_range() {
+        assert_eq!(ScanLength::parse("4M"), Ok(ScanLength { min: 4 << 20, max: 4 << 20 }));
+        assert_eq!(ScanLength::parse("1M..16M"), Ok(ScanLength { min: 1 << 20, max: 16 << 20 }));
+        for spec in ["0", "0..4K", "16M..1M", "1M..", "..1M"] {
+            assert!(ScanLength::parse(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn scan_lengths_are_whole_blocks() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let length = ScanLength { min: 1000, max: 1000 };
+        assert_eq!(length.sample(&mut rng, 4096), 4096);
+        assert_eq!(ScanLength { min: 10_000, max: 10_000 }.sample(&mut rng, 4096), 8192);
+    }
+
+    #[test]
+    fn file_sizes_are_a_list_or_a_uniform_range() {
+        assert_eq!(FileSizes::parse("64M,1G"), Ok(FileSizes::List(vec![64 << 20, 1 << 30])));
+        assert_eq!(FileSizes::parse("uniform:1M..2M"), Ok(FileSizes::Uniform { min: 1 << 20, max: 2 << 20 }));
+        for spec in ["", "64M,", "64M,0", "uniform:1M", "uniform:0..1M", "uniform:2M..1M"] {
+            assert!(FileSizes::parse(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn file_sizes_resolve_per_file() {
+        assert_eq!(FileSizes::List(vec![1, 2]).resolve(5, 0, 1), [1, 2, 1, 2, 1]);
+        let sizes = FileSizes::Uniform { min: 5000, max: 50_000 }.resolve(100, 7, 4096);
+        assert!(sizes.iter().all(|&size| size % 4096 == 0 && (4096..=50_000).contains(&size)));
+        assert_eq!(sizes, FileSizes::Uniform { min: 5000, max: 50_000 }.resolve(100, 7, 4096));
+    }
+}
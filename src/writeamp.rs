@@ -0,0 +1,70 @@
+//! `--write-amplification`: relate the bytes the benchmark wrote to what the
+//! kernel sent to the disk and what an NVMe controller counted, from counters
+//! read before and after the run.
+
+use serde::Serialize;
+
+use crate::device::BlockDevice;
+
+/// Lifetime write counters of a disk at one point in time.
+pub struct Counters {
+    sectors: Option<u64>,
+    nvme_bytes: Option<u128>,
+}
+
+/// Read the counters; the NVMe error explains a missing controller count.
+pub fn read(device: &BlockDevice) -> (Counters, Option<String>) {
+    let nvme = device.nvme_bytes_written();
+    let error = nvme.as_ref().err().map(ToString::to_string);
+    (Counters { sectors: device.sectors_written(), nvme_bytes: nvme.ok() }, error)
+}
+
+/// Bytes written during the run at each level.
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteAmplification {
+    /// Written by the benchmark's own write operations
+    pub benchmark_bytes: u64,
+    /// Sent to the disk by the kernel, including file system metadata, the journal and other processes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kernel_bytes: Option<u64>,
+    /// Counted by the NVMe controller, in steps of 512,000 bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_bytes: Option<u64>,
+}
+
+/// Sync the test files so their writes reach the disk, then compare the counters with `before`.
+pub fn finish(device: &BlockDevice, file_paths: &[String], before: &Counters, benchmark_bytes: u64) -> WriteAmplification {
+    for path in file_paths {
+        if let Err(e) = std::fs::File::options().write(true).open(path).and_then(|file| file.sync_data()) {
+            eprintln!("⚠️  Cannot sync {path} before reading the write counters: {e}");
+        }
+    }
+    let (after, _) = read(device);
+    WriteAmplification {
+        benchmark_bytes,
+        kernel_bytes: before.sectors.zip(after.sectors).map(|(b, a)| a.saturating_sub(b) * 512),
+        device_bytes: before.nvme_bytes.zip(after.nvme_bytes).map(|(b, a)| u64::try_from(a.saturating_sub(b)).unwrap_or(u64::MAX)),
+    }
+}
+
+pub fn report(device: &BlockDevice, wa: &WriteAmplification) {
+    let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    let factor = |bytes: u64| bytes as f64 / wa.benchmark_bytes.max(1) as f64;
+    println!("\n🧮 Write Amplification ({}):", device.name);
+    println!("  Written by the benchmark: {:>10.1} MiB", mib(wa.benchmark_bytes));
+    if let Some(bytes) = wa.kernel_bytes {
+        println!("  Sent to the disk:         {:>10.1} MiB (×{:.2}, kernel block layer)", mib(bytes), factor(bytes));
+    }
+    match wa.device_bytes {
+        Some(bytes) => {
+            println!("  Counted by the device:    {:>10.1} MiB (×{:.2}, NVMe data units written)", mib(bytes), factor(bytes));
+            if bytes == 0 && wa.kernel_bytes.is_some_and(|b| b > 0) {
+                println!("⚠️  The controller counts in steps of 512,000 bytes and may update lazily; write more for a usable figure");
+            }
+        }
+        None => println!("  Counted by the device:    unavailable (NVMe SMART log not readable)"),
+    }
+    if wa.benchmark_bytes == 0 {
+        println!("⚠️  The benchmark wrote nothing; the factors are meaningless");
+    }
+}
@@ -0,0 +1,137 @@
+//! `--workload write-verify`: the acceptance test for new storage. Each cycle
+//! writes random blocks with a pattern unique to the cycle and block, syncs the
+//! files, drops them from the page cache, reads the blocks back and compares.
+
+use std::os::unix::fs::FileExt;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{SeedableRng, seq::index};
+
+use crate::{Args, calculate_statistics, fsutil, print_statistics, rng, timing};
+
+/// Where the first corrupted block was found.
+pub struct Mismatch {
+    pub cycle: u64,
+    pub file: usize,
+    pub offset: u64,
+    /// First differing byte within the block
+    pub byte: usize,
+}
+
+/// What the cycles wrote, synced and read back.
+#[derive(Default)]
+pub struct Summary {
+    pub cycles: u64,
+    pub writes: Vec<Duration>,
+    /// One per file synced in each cycle
+    pub syncs: Vec<Duration>,
+    pub reads: Vec<Duration>,
+    pub bytes: u64,
+    pub mismatches: u64,
+    pub first_mismatch: Option<Mismatch>,
+    pub elapsed: Duration,
+}
+
+/// Fill `block` with the pattern of `block_index` in `cycle`: a word stream seeded by both.
+fn fill(block: &mut [u8], seed: u64, cycle: u64, block_index: u64) {
+    let mut state = seed ^ cycle.rotate_left(32) ^ block_index;
+    for chunk in block.chunks_mut(8) {
+        let word = rng::splitmix64(&mut state).to_le_bytes();
+        chunk.copy_from_slice(&word[..chunk.len()]);
+    }
+}
+
+/// Run cycles of `blocks_per_cycle` distinct blocks until `duration` has passed.
+pub fn run(args: &Args, file_paths: &[String], clock: &timing::Clock, duration: Duration, blocks_per_cycle: usize) -> std::io::Result<Summary> {
+    let files = file_paths
+        .iter()
+        .map(|path| std::fs::File::options().read(true).write(true).open(path))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    // Without readahead every read back goes to the device, not just the first of a run
+    for file in &files {
+        fsutil::advise_random(file)?;
+    }
+    let block_size = args.block_size as u64;
+    // Global block index of each file's first block
+    let mut starts = Vec::with_capacity(files.len() + 1);
+    starts.push(0u64);
+    for file in 0..files.len() {
+        starts.push(starts[file] + args.file_size_of(file) / block_size);
+    }
+    let total_blocks = starts[files.len()];
+    let per_cycle = blocks_per_cycle.min(total_blocks as usize);
+    let locate = |global: u64| {
+        let file = starts.partition_point(|&start| start <= global) - 1;
+        (file, (global - starts[file]) * block_size)
+    };
+
+    let mut rng = StdRng::seed_from_u64(args.seed ^ 0x3E21_F1E5);
+    let mut written = vec![0u8; args.block_size];
+    let mut read = vec![0u8; args.block_size];
+    let mut summary = Summary::default();
+    let started = Instant::now();
+    while started.elapsed() < duration && per_cycle > 0 && !crate::status::interrupted() {
+        let cycle = summary.cycles;
+        let mut blocks: Vec<u64> = index::sample(&mut rng, total_blocks as usize, per_cycle).into_iter().map(|b| b as u64).collect();
+        blocks.sort_unstable();
+        let mut touched = vec![false; files.len()];
+        for &global in &blocks {
+            let (file, offset) = locate(global);
+            fill(&mut written, args.seed, cycle, global);
+            let start = clock.now();
+            files[file].write_all_at(&written, offset)?;
+            summary.writes.push(clock.between(start, clock.now()));
+            touched[file] = true;
+        }
+        for (file, _) in files.iter().zip(&touched).filter(|(_, touched)| **touched) {
+            let start = clock.now();
+            file.sync_data()?;
+            summary.syncs.push(clock.between(start, clock.now()));
+            fsutil::advise_dontneed(file)?;
+        }
+        for &global in &blocks {
+            let (file, offset) = locate(global);
+            let start = clock.now();
+            files[file].read_exact_at(&mut read, offset)?;
+            summary.reads.push(clock.between(start, clock.now()));
+            fill(&mut written, args.seed, cycle, global);
+            if let Some(byte) = written.iter().zip(&read).position(|(w, r)| w != r) {
+                summary.mismatches += 1;
+                summary.first_mismatch.get_or_insert(Mismatch { cycle, file, offset, byte });
+            }
+        }
+        summary.bytes += blocks.len() as u64 * block_size;
+        summary.cycles += 1;
+    }
+    summary.elapsed = started.elapsed();
+    Ok(summary)
+}
+
+pub fn report(summary: &Summary, file_paths: &[String]) {
+    let mib = summary.bytes as f64 / (1024.0 * 1024.0);
+    println!(
+        "\n🔁 Write-Verify: {} cycles, {mib:.1} MiB written and read back in {:.1}s",
+        summary.cycles,
+        summary.elapsed.as_secs_f64()
+    );
+    for (label, latencies) in [("Writes", &summary.writes), ("Syncs (fdatasync per file and cycle)", &summary.syncs), ("Reads (after dropping the cache)", &summary.reads)] {
+        if latencies.is_empty() {
+            continue;
+        }
+        println!("\n  {label}:");
+        print_statistics(calculate_statistics(latencies.iter().collect()));
+    }
+    match &summary.first_mismatch {
+        None => println!("\n✅ All {} blocks read back as written", summary.reads.len()),
+        Some(first) => println!(
+            "\n❌ {} of {} blocks read back differently; first in cycle {} at {} offset {} (byte {} of the block)",
+            summary.mismatches,
+            summary.reads.len(),
+            first.cycle,
+            file_paths[first.file],
+            first.offset,
+            first.byte
+        ),
+    }
+}